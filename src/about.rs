@@ -0,0 +1,127 @@
+/// Describes how this build of Stepper was configured
+///
+/// Returned by [`about`]. Firmware can report this through its own diagnostic
+/// channels, to make it possible to reason about the motion stack of deployed
+/// builds.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct About {
+    /// The version of Stepper
+    pub version: &'static str,
+
+    /// The Cargo features of Stepper that are enabled
+    pub features: &'static [&'static str],
+
+    /// The drivers that are compiled in
+    pub drivers: &'static [&'static str],
+}
+
+/// Returns the version, enabled features, and compiled-in drivers of Stepper
+pub const fn about() -> About {
+    About {
+        version: env!("CARGO_PKG_VERSION"),
+        features: &[
+            #[cfg(feature = "a3967")]
+            "a3967",
+            #[cfg(feature = "a4988")]
+            "a4988",
+            #[cfg(feature = "amis30543")]
+            "amis30543",
+            #[cfg(feature = "async")]
+            "async",
+            #[cfg(feature = "dq542ma")]
+            "dq542ma",
+            #[cfg(feature = "drv8426")]
+            "drv8426",
+            #[cfg(feature = "drv8825")]
+            "drv8825",
+            #[cfg(feature = "drv8834")]
+            "drv8834",
+            #[cfg(feature = "drv8880")]
+            "drv8880",
+            #[cfg(feature = "generic")]
+            "generic",
+            #[cfg(feature = "l298n")]
+            "l298n",
+            #[cfg(feature = "l6470")]
+            "l6470",
+            #[cfg(feature = "mp6500")]
+            "mp6500",
+            #[cfg(feature = "powerstep01")]
+            "powerstep01",
+            #[cfg(feature = "stspin220")]
+            "stspin220",
+            #[cfg(feature = "tb6600")]
+            "tb6600",
+            #[cfg(feature = "tb67s128ftg")]
+            "tb67s128ftg",
+            #[cfg(feature = "tmc2130")]
+            "tmc2130",
+            #[cfg(feature = "tmc2209")]
+            "tmc2209",
+            #[cfg(feature = "tmc2660")]
+            "tmc2660",
+            #[cfg(feature = "tmc4361a")]
+            "tmc4361a",
+            #[cfg(feature = "tmc5072")]
+            "tmc5072",
+            #[cfg(feature = "tmc5160")]
+            "tmc5160",
+            #[cfg(feature = "uln2003")]
+            "uln2003",
+        ],
+        drivers: &[
+            #[cfg(feature = "a3967")]
+            "A3967",
+            #[cfg(feature = "a4988")]
+            "A4988",
+            #[cfg(feature = "amis30543")]
+            "AMIS30543",
+            #[cfg(feature = "generic")]
+            "DM542",
+            #[cfg(feature = "generic")]
+            "DM556",
+            #[cfg(feature = "generic")]
+            "DM860",
+            #[cfg(feature = "generic")]
+            "DQ542MA",
+            #[cfg(feature = "drv8426")]
+            "DRV8426",
+            #[cfg(feature = "drv8825")]
+            "DRV8825",
+            #[cfg(feature = "drv8834")]
+            "DRV8834",
+            #[cfg(feature = "drv8880")]
+            "DRV8880",
+            #[cfg(feature = "generic")]
+            "GenericStepDir",
+            #[cfg(feature = "l298n")]
+            "L298N",
+            #[cfg(feature = "l6470")]
+            "L6470",
+            #[cfg(feature = "mp6500")]
+            "MP6500",
+            #[cfg(feature = "powerstep01")]
+            "PowerSTEP01",
+            #[cfg(feature = "stspin220")]
+            "STSPIN220",
+            #[cfg(feature = "tb6600")]
+            "TB6600",
+            #[cfg(feature = "tb67s128ftg")]
+            "TB67S128FTG",
+            #[cfg(feature = "tmc2130")]
+            "TMC2130",
+            #[cfg(feature = "tmc2209")]
+            "TMC2209",
+            #[cfg(feature = "tmc2660")]
+            "TMC2660",
+            #[cfg(feature = "tmc4361a")]
+            "TMC4361A",
+            #[cfg(feature = "tmc5072")]
+            "TMC5072",
+            #[cfg(feature = "tmc5160")]
+            "TMC5160",
+            #[cfg(feature = "uln2003")]
+            "ULN2003",
+        ],
+    }
+}
@@ -1,20 +1,26 @@
 //! Compatibility code to help use Stepper on more platforms
 
 use core::fmt;
-use embedded_hal::digital::OutputPin;
+use embedded_hal::digital::{InputPin, OutputPin};
 use embedded_hal::digital::{Error, ErrorKind, ErrorType};
+use embedded_hal_stable::digital::v2::InputPin as StableInputPin;
 use embedded_hal_stable::digital::v2::OutputPin as StableOutputPin;
+use embedded_hal_stable::timer::CountDown;
+use fugit::{TimerDurationU32 as TimerDuration, TimerInstantU32 as TimerInstant};
+
+#[cfg(feature = "embedded-time")]
+pub mod time;
 
 /// Wrapper around a pin
 ///
 /// Provides an implementation of [`embedded_hal::digital::OutputPin`]
-/// (that is, the `OutputPin` from the latest alpha version of `embedded-hal`)
-/// for all types that implement `OutputPin` from the latest stable version of
-/// `embedded-hal`.
+/// (that is, the `OutputPin` from `embedded-hal` 1.0) for all types that
+/// implement `OutputPin` from `embedded-hal` 0.2.
 pub struct Pin<T>(pub T);
 
 /// Wrapper for error compatibility
-#[derive(Debug)]
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct CompatError<T>(pub T);
 
 impl<T> Error for CompatError<T>
@@ -46,3 +52,188 @@ where
         self.0.set_high().map_err(CompatError)
     }
 }
+
+/// Wrapper around an input pin
+///
+/// Provides an implementation of [`embedded_hal::digital::InputPin`] (that is,
+/// the `InputPin` from `embedded-hal` 1.0) for all types that implement
+/// `InputPin` from `embedded-hal` 0.2. This is required for fault outputs,
+/// limit switches, and other inputs.
+pub struct Input<T>(pub T);
+
+impl<T> ErrorType for Input<T>
+where
+    T: StableInputPin,
+    T::Error: fmt::Debug,
+{
+    type Error = CompatError<T::Error>;
+}
+
+impl<T> InputPin for Input<T>
+where
+    T: StableInputPin,
+    T::Error: fmt::Debug,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_high().map_err(CompatError)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_low().map_err(CompatError)
+    }
+}
+
+/// Wrapper around a timer
+///
+/// Provides an implementation of [`fugit_timer::Timer`] for all types that
+/// implement `CountDown` from `embedded-hal` 0.2, as long as their unit of
+/// time can be created from a [`TimerDuration`] with the given frequency.
+///
+/// `CountDown` can't report the current time. The instant returned by
+/// [`fugit_timer::Timer::now`] only advances, once a count down has finished,
+/// by the duration of that count down.
+///
+/// [`TimerDuration`]: fugit::TimerDurationU32
+pub struct Timer<T, const TIMER_HZ: u32> {
+    timer: T,
+    now: TimerInstant<TIMER_HZ>,
+    running: Option<TimerDuration<TIMER_HZ>>,
+}
+
+impl<T, const TIMER_HZ: u32> Timer<T, TIMER_HZ> {
+    /// Create a new instance of `Timer`
+    pub fn new(timer: T) -> Self {
+        Self {
+            timer,
+            now: TimerInstant::from_ticks(0),
+            running: None,
+        }
+    }
+
+    /// Release the wrapped timer
+    pub fn release(self) -> T {
+        self.timer
+    }
+}
+
+impl<T, const TIMER_HZ: u32> fugit_timer::Timer<TIMER_HZ> for Timer<T, TIMER_HZ>
+where
+    T: CountDown,
+    TimerDuration<TIMER_HZ>: Into<T::Time>,
+{
+    type Error = NotRunningError;
+
+    fn now(&mut self) -> TimerInstant<TIMER_HZ> {
+        self.now
+    }
+
+    fn start(
+        &mut self,
+        duration: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        self.timer.start(duration);
+        self.running = Some(duration);
+        Ok(())
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        // `CountDown` can't be canceled. Just stop waiting for it, and let the
+        // next call to `start` restart it.
+        self.running.take().map(|_| ()).ok_or(NotRunningError)
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        let duration = self.running.ok_or(NotRunningError)?;
+
+        match self.timer.wait() {
+            Ok(()) => {
+                self.now += duration;
+                self.running = None;
+                Ok(())
+            }
+            Err(nb::Error::WouldBlock) => Err(nb::Error::WouldBlock),
+            Err(nb::Error::Other(void)) => match void {},
+        }
+    }
+}
+
+/// The timer wasn't running
+///
+/// Returned by the [`fugit_timer::Timer`] implementation of [`Timer`], when
+/// waiting for, or canceling a timer that hasn't been started.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NotRunningError;
+
+impl fmt::Display for NotRunningError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "timer not running")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal_stable::timer::CountDown;
+    use fugit::TimerDurationU32 as TimerDuration;
+    use fugit_timer::Timer as _;
+
+    use super::{CompatError, Input, NotRunningError, Timer};
+
+    #[test]
+    fn input_should_map_level_and_errors() {
+        use embedded_hal::digital::InputPin as _;
+
+        assert_eq!(Input(Level(Ok(true))).is_high(), Ok(true));
+        assert_eq!(Input(Level(Ok(true))).is_low(), Ok(false));
+        assert_eq!(Input(Level(Err(()))).is_high(), Err(CompatError(())));
+    }
+
+    #[test]
+    fn timer_should_wait_for_count_down() {
+        let mut timer = Timer::<_, 1_000_000>::new(Ticks(0));
+
+        timer.start(TimerDuration::from_ticks(2)).unwrap();
+        assert_eq!(timer.wait(), Err(nb::Error::WouldBlock));
+        assert_eq!(timer.wait(), Ok(()));
+        assert_eq!(timer.now().ticks(), 2);
+
+        assert_eq!(timer.wait(), Err(nb::Error::Other(NotRunningError)));
+        assert_eq!(timer.cancel(), Err(NotRunningError));
+    }
+
+    struct Level(Result<bool, ()>);
+
+    impl embedded_hal_stable::digital::v2::InputPin for Level {
+        type Error = ();
+
+        fn is_high(&self) -> Result<bool, Self::Error> {
+            self.0
+        }
+
+        fn is_low(&self) -> Result<bool, Self::Error> {
+            self.0.map(|high| !high)
+        }
+    }
+
+    struct Ticks(u32);
+
+    impl CountDown for Ticks {
+        type Time = TimerDuration<1_000_000>;
+
+        fn start<T>(&mut self, count: T)
+        where
+            T: Into<Self::Time>,
+        {
+            self.0 = count.into().ticks();
+        }
+
+        fn wait(&mut self) -> nb::Result<(), void::Void> {
+            self.0 -= 1;
+            if self.0 == 0 {
+                Ok(())
+            } else {
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Conversions between `embedded-time` and `fugit`
+//!
+//! Only available, if the `embedded-time` feature is enabled. Stepper uses
+//! [fugit] for all durations and rates, while some HALs and applications still
+//! use [embedded-time]. The traits in this module convert between the
+//! equivalent types of both crates, so durations and rates can be passed
+//! between them without any manual tick math.
+//!
+//! All conversions are lossless: Both sides use the same unit and the same
+//! integer type, so only the wrapper type changes.
+//!
+//! ``` rust
+//! use embedded_time::duration::Microseconds;
+//! use stepper::compat::time::{ToEmbeddedTime as _, ToFugit as _};
+//!
+//! let duration = Microseconds(500_u32).to_fugit();
+//! assert_eq!(duration, fugit::MicrosDurationU32::from_ticks(500));
+//! assert_eq!(duration.to_embedded_time(), Microseconds(500_u32));
+//! ```
+//!
+//! [fugit]: https://crates.io/crates/fugit
+//! [embedded-time]: https://crates.io/crates/embedded-time
+
+use embedded_time::{duration, rate};
+
+/// Convert an `embedded-time` type into the equivalent `fugit` type
+pub trait ToFugit {
+    /// The equivalent `fugit` type
+    type Fugit;
+
+    /// Convert into the equivalent `fugit` type
+    fn to_fugit(self) -> Self::Fugit;
+}
+
+/// Convert a `fugit` type into the equivalent `embedded-time` type
+pub trait ToEmbeddedTime {
+    /// The equivalent `embedded-time` type
+    type EmbeddedTime;
+
+    /// Convert into the equivalent `embedded-time` type
+    fn to_embedded_time(self) -> Self::EmbeddedTime;
+}
+
+macro_rules! conversions {
+    (
+        $(
+            $embedded_time:ident<$int:ty> <=> $fugit:ty,
+            from_fugit: $from_fugit:ident,
+            to_fugit: $to_fugit:ident;
+        )*
+    ) => {
+        $(
+            impl ToFugit for $embedded_time<$int> {
+                type Fugit = $fugit;
+
+                fn to_fugit(self) -> Self::Fugit {
+                    <$fugit>::$to_fugit(self.0)
+                }
+            }
+
+            impl ToEmbeddedTime for $fugit {
+                type EmbeddedTime = $embedded_time<$int>;
+
+                fn to_embedded_time(self) -> Self::EmbeddedTime {
+                    $embedded_time(self.$from_fugit())
+                }
+            }
+        )*
+    };
+}
+
+use duration::{
+    Hours, Microseconds, Milliseconds, Minutes, Nanoseconds, Seconds,
+};
+use rate::{Hertz, Kilohertz, Megahertz};
+
+conversions! {
+    Nanoseconds<u32> <=> fugit::NanosDurationU32,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Microseconds<u32> <=> fugit::MicrosDurationU32,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Milliseconds<u32> <=> fugit::MillisDurationU32,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Seconds<u32> <=> fugit::SecsDurationU32,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Minutes<u32> <=> fugit::MinutesDurationU32,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Hours<u32> <=> fugit::HoursDurationU32,
+        from_fugit: ticks, to_fugit: from_ticks;
+
+    Nanoseconds<u64> <=> fugit::NanosDurationU64,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Microseconds<u64> <=> fugit::MicrosDurationU64,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Milliseconds<u64> <=> fugit::MillisDurationU64,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Seconds<u64> <=> fugit::SecsDurationU64,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Minutes<u64> <=> fugit::MinutesDurationU64,
+        from_fugit: ticks, to_fugit: from_ticks;
+    Hours<u64> <=> fugit::HoursDurationU64,
+        from_fugit: ticks, to_fugit: from_ticks;
+
+    Hertz<u32> <=> fugit::HertzU32,
+        from_fugit: raw, to_fugit: from_raw;
+    Kilohertz<u32> <=> fugit::KilohertzU32,
+        from_fugit: raw, to_fugit: from_raw;
+    Megahertz<u32> <=> fugit::MegahertzU32,
+        from_fugit: raw, to_fugit: from_raw;
+
+    Hertz<u64> <=> fugit::HertzU64,
+        from_fugit: raw, to_fugit: from_raw;
+    Kilohertz<u64> <=> fugit::KilohertzU64,
+        from_fugit: raw, to_fugit: from_raw;
+    Megahertz<u64> <=> fugit::MegahertzU64,
+        from_fugit: raw, to_fugit: from_raw;
+}
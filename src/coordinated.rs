@@ -0,0 +1,476 @@
+//! Coordinated motion of multiple axes
+//!
+//! See [`MultiStepper`] for more information. Curves can be approximated by
+//! following a path of straight lines (see [`MultiStepper::follow`]), for
+//! example one generated by [`Arc`].
+
+use core::fmt;
+
+use crate::motion_control::{DynError, DynMotionControl};
+
+/// Moves multiple axes along straight lines
+///
+/// Wraps `N` axes, which can be any drivers that implement [`MotionControl`]
+/// (see [`DynMotionControl`] for the requirements). [`MultiStepper::move_to`]
+/// moves all of them to an N-dimensional point, along a straight line.
+///
+/// Every axis plans its motion as if it traveled as far as the axis that
+/// travels the furthest, and only makes the steps it needs along the way (see
+/// [`MotionControl::move_to_position_synced`]). As long as all axes are
+/// configured with the same motion profile, they accelerate and decelerate in
+/// proportion, and arrive at the same time.
+///
+/// Axes that don't support this fall back to scaling their maximum velocity
+/// by their share of the distance. Their acceleration isn't scaled, which
+/// means that they leave the straight line while accelerating and
+/// decelerating, and the shorter their share, the earlier they arrive.
+///
+/// [`MotionControl`]: crate::traits::MotionControl
+/// [`MotionControl::move_to_position_synced`]: crate::traits::MotionControl::move_to_position_synced
+pub struct MultiStepper<'r, const N: usize> {
+    axes: [&'r mut dyn DynMotionControl; N],
+}
+
+impl<'r, const N: usize> MultiStepper<'r, N> {
+    /// Create a new instance of `MultiStepper`
+    pub fn new(axes: [&'r mut dyn DynMotionControl; N]) -> Self {
+        Self { axes }
+    }
+
+    /// Move all axes to the given point
+    ///
+    /// `max_velocity` applies to the axis that travels the furthest. The other
+    /// axes move proportionally slower, and start and end their motions at the
+    /// same time (see [`MultiStepper`] for the limitations).
+    ///
+    /// Like [`MotionControl::move_to_position`], this only starts the motion.
+    /// [`MultiStepper::update`] needs to be called until it has finished.
+    ///
+    /// [`MotionControl::move_to_position`]: crate::traits::MotionControl::move_to_position
+    pub fn move_to(
+        &mut self,
+        max_velocity: f32,
+        target: [i32; N],
+    ) -> Result<(), DynError> {
+        let mut distances = [0; N];
+        for (i, axis) in self.axes.iter_mut().enumerate() {
+            let position = axis.current_position()?;
+            distances[i] =
+                (i64::from(target[i]) - i64::from(position)).unsigned_abs();
+        }
+
+        let longest = distances.iter().copied().max().unwrap_or(0);
+        if longest == 0 {
+            return Ok(());
+        }
+
+        for (i, axis) in self.axes.iter_mut().enumerate() {
+            // Axes that don't need to move would end up with a velocity of
+            // zero, which might confuse the motion profile.
+            if distances[i] == 0 {
+                continue;
+            }
+
+            // The distance of any axis fits into a `u32`.
+            let num_steps = longest as u32;
+            if axis.move_to_position_synced(
+                max_velocity,
+                target[i],
+                num_steps,
+            )? {
+                continue;
+            }
+
+            let scale = distances[i] as f32 / longest as f32;
+            axis.move_to_position(max_velocity * scale, target[i])?;
+        }
+
+        Ok(())
+    }
+
+    /// Update the ongoing motion of all axes
+    ///
+    /// Returns `true`, if any of the axes is still moving.
+    pub fn update(&mut self) -> Result<bool, DynError> {
+        let mut moving = false;
+        for axis in self.axes.iter_mut() {
+            moving |= axis.update()?;
+        }
+
+        Ok(moving)
+    }
+
+    /// Returns the current position of all axes
+    pub fn current_position(&mut self) -> Result<[i32; N], DynError> {
+        let mut position = [0; N];
+        for (i, axis) in self.axes.iter_mut().enumerate() {
+            position[i] = axis.current_position()?;
+        }
+
+        Ok(position)
+    }
+
+    /// Decelerate all axes to a standstill
+    ///
+    /// Each axis decelerates on its own, so the motor leaves the straight
+    /// line while stopping.
+    pub fn stop(&mut self) -> Result<(), DynError> {
+        for axis in self.axes.iter_mut() {
+            axis.stop()?;
+        }
+
+        Ok(())
+    }
+
+    /// Stop all axes immediately
+    ///
+    /// See [`MotionControl::abort`].
+    ///
+    /// [`MotionControl::abort`]: crate::traits::MotionControl::abort
+    pub fn abort(&mut self) -> Result<(), DynError> {
+        // Try to abort all axes, even if one of them fails.
+        let mut result = Ok(());
+        for axis in self.axes.iter_mut() {
+            if let Err(err) = axis.abort() {
+                result = Err(err);
+            }
+        }
+
+        result
+    }
+
+    /// Move all axes along a path of points
+    ///
+    /// Moves to each point in turn, using [`MultiStepper::move_to`], once the
+    /// previous motion has finished. The returned [`FollowPath`] needs to be
+    /// updated until it has finished.
+    ///
+    /// Every segment of the path is a separate motion, so the axes come to a
+    /// stop at every point. Longer segments make for faster motion, shorter
+    /// segments for a more accurate approximation of a curve.
+    pub fn follow<Path>(
+        &mut self,
+        max_velocity: f32,
+        path: Path,
+    ) -> FollowPath<'_, 'r, Path, N>
+    where
+        Path: Iterator<Item = [i32; N]>,
+    {
+        FollowPath {
+            multi: self,
+            path,
+            max_velocity,
+        }
+    }
+
+    /// Access the axes
+    pub fn axes_mut(&mut self) -> &mut [&'r mut dyn DynMotionControl; N] {
+        &mut self.axes
+    }
+
+    /// Release the axes
+    pub fn release(self) -> [&'r mut dyn DynMotionControl; N] {
+        self.axes
+    }
+}
+
+/// A path that is being followed by a [`MultiStepper`]
+///
+/// Created by [`MultiStepper::follow`].
+pub struct FollowPath<'m, 'r, Path, const N: usize> {
+    multi: &'m mut MultiStepper<'r, N>,
+    path: Path,
+    max_velocity: f32,
+}
+
+impl<'m, 'r, Path, const N: usize> FollowPath<'m, 'r, Path, N>
+where
+    Path: Iterator<Item = [i32; N]>,
+{
+    /// Update the motion along the path
+    ///
+    /// Starts the motion to the next point, once the previous one has
+    /// finished. Returns `true`, if the path hasn't been completed yet.
+    pub fn update(&mut self) -> Result<bool, DynError> {
+        if self.multi.update()? {
+            return Ok(true);
+        }
+
+        match self.path.next() {
+            Some(point) => {
+                self.multi.move_to(self.max_velocity, point)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Wait until the path has been completed
+    ///
+    /// Calls [`FollowPath::update`] in a busy loop.
+    pub fn wait(&mut self) -> Result<(), DynError> {
+        while self.update()? {}
+        Ok(())
+    }
+}
+
+/// An arc, approximated by straight line segments
+///
+/// An iterator over the end points of the segments, which can be passed to
+/// [`MultiStepper::follow`]. The starting point itself is not included.
+pub struct Arc {
+    center: [f32; 2],
+    radius: [f32; 2],
+    cos: f32,
+    sin: f32,
+    segments_left: u32,
+}
+
+impl Arc {
+    /// Create an arc from a starting point, a center, and an angle
+    ///
+    /// The arc starts at `start` and goes around `center` by `sweep` radians.
+    /// Positive angles go counter-clockwise (from the positive first axis
+    /// towards the positive second axis), negative angles go clockwise. The
+    /// radius is the distance between `start` and `center`.
+    ///
+    /// The arc is split into `segments` straight lines of equal length. At
+    /// least one segment is always generated.
+    ///
+    /// Returns [`InvalidSweepError`], if `sweep` is infinite or NaN.
+    pub fn new(
+        start: [i32; 2],
+        center: [i32; 2],
+        sweep: f32,
+        segments: u32,
+    ) -> Result<Self, InvalidSweepError> {
+        if !sweep.is_finite() {
+            return Err(InvalidSweepError);
+        }
+
+        let segments = segments.max(1);
+        let (sin, cos) = sin_cos(sweep / segments as f32);
+
+        Ok(Self {
+            center: [center[0] as f32, center[1] as f32],
+            radius: [
+                (start[0] - center[0]) as f32,
+                (start[1] - center[1]) as f32,
+            ],
+            cos,
+            sin,
+            segments_left: segments,
+        })
+    }
+}
+
+impl Iterator for Arc {
+    type Item = [i32; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.segments_left == 0 {
+            return None;
+        }
+        self.segments_left -= 1;
+
+        let [x, y] = self.radius;
+        self.radius =
+            [x * self.cos - y * self.sin, x * self.sin + y * self.cos];
+
+        Some([
+            round(self.center[0] + self.radius[0]),
+            round(self.center[1] + self.radius[1]),
+        ])
+    }
+}
+
+/// The sweep angle of an [`Arc`] is infinite or NaN
+///
+/// Returned by [`Arc::new`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidSweepError;
+
+impl fmt::Display for InvalidSweepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "arc sweep angle is not finite")
+    }
+}
+
+/// Compute sine and cosine without the standard library
+fn sin_cos(angle: f32) -> (f32, f32) {
+    // The Taylor series are precise for small angles. Halve the angle until
+    // it's small enough, then use the double-angle formulas to get back.
+    let mut angle = angle;
+    let mut halvings = 0;
+    while angle.abs() > 0.1 {
+        angle /= 2.0;
+        halvings += 1;
+    }
+
+    let a2 = angle * angle;
+    let mut sin = angle * (1.0 - a2 / 6.0 * (1.0 - a2 / 20.0));
+    let mut cos = 1.0 - a2 / 2.0 * (1.0 - a2 / 12.0 * (1.0 - a2 / 30.0));
+
+    for _ in 0..halvings {
+        let (s, c) = (sin, cos);
+        sin = 2.0 * s * c;
+        cos = c * c - s * s;
+    }
+
+    (sin, cos)
+}
+
+fn round(value: f32) -> i32 {
+    if value < 0.0 {
+        (value - 0.5) as i32
+    } else {
+        (value + 0.5) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+    use fugit_timer::Timer as _;
+
+    use crate::{
+        mock::{MockDriver, MockMotionControl, MockTimer, Signal},
+        motion_control::{DelayToTicks, SoftwareMotionControl},
+        traits::{EnableMotionControl as _, MotionControl},
+    };
+
+    use super::{Arc, InvalidSweepError, MultiStepper};
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn move_to_should_scale_velocities() {
+        let mut x = MockMotionControl::<f32>::new();
+        let mut y = MockMotionControl::<f32>::new();
+        let mut z = MockMotionControl::<f32>::new();
+
+        let mut multi = MultiStepper::new([&mut x, &mut y, &mut z]);
+        multi.move_to(100.0, [200, -50, 0]).unwrap();
+        while multi.update().unwrap() {}
+        assert_eq!(multi.current_position().unwrap(), [200, -50, 0]);
+
+        assert_eq!(x.max_velocity(), Some(100.0));
+        assert_eq!(y.max_velocity(), Some(25.0));
+        assert_eq!(z.max_velocity(), None);
+    }
+
+    #[test]
+    fn move_to_should_keep_axes_in_proportion() {
+        let x_driver = MockDriver::new();
+        let y_driver = MockDriver::new();
+        let mut x = motion_control(&x_driver);
+        let mut y = motion_control(&y_driver);
+
+        let mut multi = MultiStepper::new([&mut x, &mut y]);
+        multi.move_to(1000.0, [1000, 100]).unwrap();
+        while multi.update().unwrap() {}
+        assert_eq!(multi.current_position().unwrap(), [1000, 100]);
+
+        // Both axes arrive at the same time.
+        let x_end = x_driver.timer::<1_000_000>().now();
+        let y_end = y_driver.timer::<1_000_000>().now();
+        assert_eq!(x_end, y_end);
+
+        // Every step of the shorter axis is made together with a step of the
+        // longer one, so the axes stay on the line along the way.
+        let x_steps = step_times(&x_driver);
+        let y_steps = step_times(&y_driver);
+        assert_eq!(x_steps.len(), 1000);
+        assert_eq!(y_steps.len(), 100);
+        for (i, y_step) in y_steps.into_iter().enumerate() {
+            assert_eq!(y_step, x_steps[i * 10 + 4]);
+        }
+    }
+
+    #[test]
+    fn arc_should_stay_on_circle_and_end_at_target() {
+        let start = [100, 50];
+        let center = [0, 50];
+
+        let points: Vec<_> =
+            Arc::new(start, center, -core::f32::consts::PI, 16)
+                .unwrap()
+                .collect();
+
+        assert_eq!(points.len(), 16);
+        assert_eq!(points[7], [0, -50]);
+        assert_eq!(points[15], [-100, 50]);
+        for [x, y] in points {
+            let radius = ((x * x + (y - 50) * (y - 50)) as f32).sqrt();
+            assert!((radius - 100.0).abs() < 1.0);
+        }
+
+        let mut x = MockMotionControl::<f32>::new();
+        let mut y = MockMotionControl::<f32>::new();
+        x.reset_position(start[0]).unwrap();
+        y.reset_position(start[1]).unwrap();
+
+        let mut multi = MultiStepper::new([&mut x, &mut y]);
+        multi
+            .follow(
+                100.0,
+                Arc::new(start, center, core::f32::consts::PI, 16).unwrap(),
+            )
+            .wait()
+            .unwrap();
+        assert_eq!(multi.current_position().unwrap(), [-100, 50]);
+    }
+
+    #[test]
+    fn arc_should_reject_non_finite_sweep() {
+        for sweep in [f32::INFINITY, f32::NEG_INFINITY, f32::NAN] {
+            assert_eq!(
+                Arc::new([100, 0], [0, 0], sweep, 16).err(),
+                Some(InvalidSweepError)
+            );
+        }
+    }
+
+    fn motion_control(
+        driver: &MockDriver,
+    ) -> SoftwareMotionControl<
+        MockDriver,
+        MockTimer<1_000_000>,
+        ramp_maker::Trapezoidal<Num>,
+        Convert,
+        1_000_000,
+    > {
+        driver.clone().enable_motion_control((
+            driver.timer(),
+            ramp_maker::Trapezoidal::new(Num::from_num(1000)),
+            Convert,
+        ))
+    }
+
+    /// Returns the time of each step pulse that was sent to the driver
+    fn step_times(driver: &MockDriver) -> Vec<u64> {
+        driver
+            .transitions()
+            .into_iter()
+            .filter(|transition| transition.signal == Signal::Step(true))
+            .map(|transition| transition.time_ns)
+            .collect()
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<Num, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: Num,
+        ) -> Result<TimerDuration<1_000_000>, Self::Error> {
+            let ticks = delay * Num::from_num(1_000_000);
+            Ok(TimerDuration::<1_000_000>::from_ticks(ticks.to_num()))
+        }
+    }
+}
@@ -0,0 +1,385 @@
+//! A3967 Driver
+//!
+//! Platform-agnostic driver API for the A3967 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The A3967 is used on the popular EasyDriver board. Please note that the
+//! EasyDriver pulls MS1 and MS2 high, so it defaults to 1/8 step mode, if step
+//! mode control is not enabled.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{OutputPin, PinState};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode8,
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableOutputControl, EnableSleepControl,
+        EnableStepControl, EnableStepModeControl, SetDirection,
+        SetOutputEnabled, SetPowerMode, SetStepMode, Step as StepTrait,
+    },
+    PowerMode,
+};
+
+/// The A3967 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`A3967::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+pub struct A3967<Enable, Sleep, MS1, MS2, Step, Dir> {
+    enable: Enable,
+    sleep: Sleep,
+    ms1: MS1,
+    ms2: MS2,
+    step: Step,
+    dir: Dir,
+}
+
+impl A3967<(), (), (), (), (), ()> {
+    /// Create a new instance of `A3967`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            sleep: (),
+            ms1: (),
+            ms2: (),
+            step: (),
+            dir: (),
+        }
+    }
+
+    /// Create a new instance of `A3967` with all pins connected
+    ///
+    /// This is an alternative to [`A3967::new`], followed by enabling each
+    /// capability separately. Unlike [`Stepper`](crate::Stepper)'s
+    /// `enable_*` methods, this doesn't apply an initial step mode or
+    /// direction. Set those before the first motion.
+    pub fn from_pins<Enable, Sleep, MS1, MS2, Step, Dir>(
+        pins: Pins<Enable, Sleep, MS1, MS2, Step, Dir>,
+    ) -> A3967<Enable, Sleep, MS1, MS2, Step, Dir> {
+        A3967 {
+            enable: pins.enable,
+            sleep: pins.sleep,
+            ms1: pins.ms1,
+            ms2: pins.ms2,
+            step: pins.step,
+            dir: pins.dir,
+        }
+    }
+}
+
+impl Default for A3967<(), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError>
+    EnableOutputControl<Enable> for A3967<(), Sleep, MS1, MS2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl = A3967<Enable, Sleep, MS1, MS2, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        A3967 {
+            enable,
+            sleep: self.sleep,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+/// The pins connected to the A3967
+///
+/// Passed to [`A3967::from_pins`], to connect all pins at once.
+pub struct Pins<Enable, Sleep, MS1, MS2, Step, Dir> {
+    /// The pin connected to ENABLE
+    pub enable: Enable,
+
+    /// The pin connected to SLEEP
+    pub sleep: Sleep,
+
+    /// The pin connected to MS1
+    pub ms1: MS1,
+
+    /// The pin connected to MS2
+    pub ms2: MS2,
+
+    /// The pin connected to STEP
+    pub step: Step,
+
+    /// The pin connected to DIR
+    pub dir: Dir,
+}
+
+/// A `A3967` with all pins connected
+///
+/// Uses one type for all pins. This works well with the type-erased pins that
+/// many HALs provide, and saves naming every pin type separately. See
+/// [`A3967::from_pins`].
+pub type AllPins<Output> =
+    A3967<Output, Output, Output, Output, Output, Output>;
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError> SetOutputEnabled
+    for A3967<Enable, Sleep, MS1, MS2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // ENABLE is active low.
+        self.enable.set_state(PinState::from(!enabled))
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError>
+    EnableSleepControl<Sleep> for A3967<Enable, (), MS1, MS2, Step, Dir>
+where
+    Sleep: OutputPin<Error = OutputPinError>,
+{
+    type WithSleepControl = A3967<Enable, Sleep, MS1, MS2, Step, Dir>;
+
+    fn enable_sleep_control(self, sleep: Sleep) -> Self::WithSleepControl {
+        A3967 {
+            enable: self.enable,
+            sleep,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError> SetPowerMode
+    for A3967<Enable, Sleep, MS1, MS2, Step, Dir>
+where
+    Sleep: OutputPin<Error = OutputPinError>,
+{
+    // The A3967 needs 1 ms to wake up, before it accepts STEP pulses.
+    const WAKE_UP_TIME: Nanoseconds = Nanoseconds::from_ticks(1_000_000);
+
+    type Error = OutputPinError;
+
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error> {
+        // SLEEP is active low.
+        self.sleep
+            .set_state(PinState::from(mode == PowerMode::Normal))
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError>
+    EnableStepModeControl<(MS1, MS2)>
+    for A3967<Enable, Sleep, (), (), Step, Dir>
+where
+    MS1: OutputPin<Error = OutputPinError>,
+    MS2: OutputPin<Error = OutputPinError>,
+{
+    type WithStepModeControl = A3967<Enable, Sleep, MS1, MS2, Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        (ms1, ms2): (MS1, MS2),
+    ) -> Self::WithStepModeControl {
+        A3967 {
+            enable: self.enable,
+            sleep: self.sleep,
+            ms1,
+            ms2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir> DisableStepModeControl
+    for A3967<Enable, Sleep, MS1, MS2, Step, Dir>
+{
+    type WithoutStepModeControl = A3967<Enable, Sleep, (), (), Step, Dir>;
+    type Resources = (MS1, MS2);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            A3967 {
+                enable: self.enable,
+                sleep: self.sleep,
+                ms1: (),
+                ms2: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            (self.ms1, self.ms2),
+        )
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError> SetStepMode
+    for A3967<Enable, Sleep, MS1, MS2, Step, Dir>
+where
+    MS1: OutputPin<Error = OutputPinError>,
+    MS2: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, A3967 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Error = OutputPinError;
+    type StepMode = StepMode8;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use PinState::*;
+        use StepMode8::*;
+        let (ms1, ms2) = match step_mode {
+            Full => (Low, Low),
+            M2 => (High, Low),
+            M4 => (Low, High),
+            M8 => (High, High),
+        };
+
+        // Set mode signals.
+        self.ms1.set_state(ms1)?;
+        self.ms2.set_state(ms2)?;
+
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        // The A3967 doesn't need to be reset to apply a new step mode. The
+        // new mode takes effect with the next step.
+        Ok(())
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError>
+    EnableDirectionControl<Dir> for A3967<Enable, Sleep, MS1, MS2, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl = A3967<Enable, Sleep, MS1, MS2, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        A3967 {
+            enable: self.enable,
+            sleep: self.sleep,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir> DisableDirectionControl
+    for A3967<Enable, Sleep, MS1, MS2, Step, Dir>
+{
+    type WithoutDirectionControl = A3967<Enable, Sleep, MS1, MS2, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            A3967 {
+                enable: self.enable,
+                sleep: self.sleep,
+                ms1: self.ms1,
+                ms2: self.ms2,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError> SetDirection
+    for A3967<Enable, Sleep, MS1, MS2, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, A3967 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError> EnableStepControl<Step>
+    for A3967<Enable, Sleep, MS1, MS2, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = A3967<Enable, Sleep, MS1, MS2, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        A3967 {
+            enable: self.enable,
+            sleep: self.sleep,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir> DisableStepControl
+    for A3967<Enable, Sleep, MS1, MS2, Step, Dir>
+{
+    type WithoutStepControl = A3967<Enable, Sleep, MS1, MS2, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            A3967 {
+                enable: self.enable,
+                sleep: self.sleep,
+                ms1: self.ms1,
+                ms2: self.ms2,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, Step, Dir, OutputPinError> StepTrait
+    for A3967<Enable, Sleep, MS1, MS2, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, A3967 datasheet
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(1000);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
@@ -17,9 +17,12 @@ use fugit::NanosDurationU32 as Nanoseconds;
 use crate::{
     step_mode::StepMode16,
     traits::{
-        EnableDirectionControl, EnableStepControl, EnableStepModeControl,
-        SetDirection, SetStepMode, Step as StepTrait,
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableOutputControl, EnableSleepControl,
+        EnableStepControl, EnableStepModeControl, SetDirection,
+        SetOutputEnabled, SetPowerMode, SetStepMode, Step as StepTrait,
     },
+    PowerMode,
 };
 
 /// The A4988 driver API
@@ -54,11 +57,207 @@ impl A4988<(), (), (), (), (), (), (), (), ()> {
             dir: (),
         }
     }
+
+    /// Create a new instance of `A4988` with all pins connected
+    ///
+    /// This is an alternative to [`A4988::new`], followed by enabling each
+    /// capability separately. Unlike [`Stepper`](crate::Stepper)'s
+    /// `enable_*` methods, this doesn't apply an initial step mode or
+    /// direction. Set those before the first motion.
+    pub fn from_pins<Enable, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>(
+        pins: Pins<Enable, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>,
+    ) -> A4988<Enable, (), Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir> {
+        A4988 {
+            enable: pins.enable,
+            fault: (),
+            sleep: pins.sleep,
+            reset: pins.reset,
+            mode0: pins.mode0,
+            mode1: pins.mode1,
+            mode2: pins.mode2,
+            step: pins.step,
+            dir: pins.dir,
+        }
+    }
+}
+
+impl Default for A4988<(), (), (), (), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableOutputControl<Enable>
+    for A4988<(), Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl =
+        A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        A4988 {
+            enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            reset: self.reset,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            mode2: self.mode2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+/// The pins connected to the A4988
+///
+/// Passed to [`A4988::from_pins`], to connect all pins at once.
+pub struct Pins<Enable, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir> {
+    /// The pin connected to ENABLE
+    pub enable: Enable,
+
+    /// The pin connected to SLEEP
+    pub sleep: Sleep,
+
+    /// The pin connected to RESET
+    pub reset: Reset,
+
+    /// The pin connected to MS1
+    pub mode0: Mode0,
+
+    /// The pin connected to MS2
+    pub mode1: Mode1,
+
+    /// The pin connected to MS3
+    pub mode2: Mode2,
+
+    /// The pin connected to STEP
+    pub step: Step,
+
+    /// The pin connected to DIR
+    pub dir: Dir,
+}
+
+/// A `A4988` with all pins connected
+///
+/// Uses one type for all pins. This works well with the type-erased pins that
+/// many HALs provide, and saves naming every pin type separately. See
+/// [`A4988::from_pins`].
+pub type AllPins<Output> =
+    A4988<Output, (), Output, Output, Output, Output, Output, Output, Output>;
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetOutputEnabled
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // ENABLE is active low.
+        self.enable.set_state(PinState::from(!enabled))
+    }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
-    EnableStepModeControl<(Reset, Mode0, Mode1, Mode2)>
-    for A4988<(), (), (), (), (), (), (), Step, Dir>
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableSleepControl<Sleep>
+    for A4988<Enable, Fault, (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Sleep: OutputPin<Error = OutputPinError>,
+{
+    type WithSleepControl =
+        A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
+
+    fn enable_sleep_control(self, sleep: Sleep) -> Self::WithSleepControl {
+        A4988 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep,
+            reset: self.reset,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            mode2: self.mode2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetPowerMode
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Sleep: OutputPin<Error = OutputPinError>,
+{
+    // Sleep Mode
+    // https://www.pololu.com/file/0J450/A4988.pdf
+    const WAKE_UP_TIME: Nanoseconds = Nanoseconds::from_ticks(1_000_000);
+
+    type Error = OutputPinError;
+
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error> {
+        // SLEEP is active low.
+        self.sleep
+            .set_state(PinState::from(mode == PowerMode::Normal))
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableStepModeControl<(Reset, Mode0, Mode1, Mode2)>
+    for A4988<Enable, Fault, Sleep, (), (), (), (), Step, Dir>
 where
     Reset: OutputPin<Error = OutputPinError>,
     Mode0: OutputPin<Error = OutputPinError>,
@@ -66,7 +265,7 @@ where
     Mode2: OutputPin<Error = OutputPinError>,
 {
     type WithStepModeControl =
-        A4988<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>;
+        A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
 
     fn enable_step_mode_control(
         self,
@@ -86,8 +285,47 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> SetStepMode
-    for A4988<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+impl<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+    DisableStepModeControl
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+{
+    type WithoutStepModeControl =
+        A4988<Enable, Fault, Sleep, (), (), (), (), Step, Dir>;
+    type Resources = (Reset, Mode0, Mode1, Mode2);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            A4988 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                reset: (),
+                mode0: (),
+                mode1: (),
+                mode2: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            (self.reset, self.mode0, self.mode1, self.mode2),
+        )
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetStepMode
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
 where
     Reset: OutputPin<Error = OutputPinError>,
     Mode0: OutputPin<Error = OutputPinError>,
@@ -132,14 +370,24 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
-    EnableDirectionControl<Dir>
-    for A4988<(), (), (), Reset, Mode0, Mode1, Mode2, Step, ()>
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableDirectionControl<Dir>
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, ()>
 where
     Dir: OutputPin<Error = OutputPinError>,
 {
     type WithDirectionControl =
-        A4988<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>;
+        A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
 
     fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
         A4988 {
@@ -156,8 +404,47 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> SetDirection
-    for A4988<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+impl<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+    DisableDirectionControl
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+{
+    type WithoutDirectionControl =
+        A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            A4988 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                reset: self.reset,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                mode2: self.mode2,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetDirection
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
 where
     Dir: OutputPin<Error = OutputPinError>,
 {
@@ -173,14 +460,24 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
-    EnableStepControl<Step>
-    for A4988<(), (), (), Reset, Mode0, Mode1, Mode2, (), Dir>
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableStepControl<Step>
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, (), Dir>
 where
     Step: OutputPin<Error = OutputPinError>,
 {
     type WithStepControl =
-        A4988<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>;
+        A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
 
     fn enable_step_control(self, step: Step) -> Self::WithStepControl {
         A4988 {
@@ -197,8 +494,47 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> StepTrait
-    for A4988<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+impl<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+    DisableStepControl
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+{
+    type WithoutStepControl =
+        A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            A4988 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                reset: self.reset,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                mode2: self.mode2,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > StepTrait
+    for A4988<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
 where
     Step: OutputPin<Error = OutputPinError>,
 {
@@ -0,0 +1,451 @@
+//! AMIS-30543 Driver
+//!
+//! Platform-agnostic driver API for the AMIS-30543 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The AMIS-30543 is controlled using its NXT (step) and DIR pins, while its
+//! configuration, including the microstepping mode and the motor current, is
+//! done over SPI. Step mode control is enabled by passing the SPI device.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::{digital::OutputPin, spi::SpiDevice};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode128,
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableStepControl, EnableStepModeControl,
+        ReadFault, SetCurrent, SetDirection, SetOutputEnabled, SetStepMode,
+        Step as StepTrait,
+    },
+};
+
+/// The AMIS-30543 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`AMIS30543::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// Please note that the AMIS-30543 powers up with its motor outputs disabled.
+/// Before the first motion, the motor current needs to be configured, and the
+/// outputs need to be enabled, using [`Stepper::set_current`] and
+/// [`Stepper::enable_output`]. Since the outputs are enabled over SPI, no
+/// additional resources are required for that.
+///
+/// [`Stepper::set_current`]: crate::Stepper::set_current
+/// [`Stepper::enable_output`]: crate::Stepper::enable_output
+pub struct AMIS30543<Spi, Step, Dir> {
+    spi: Spi,
+    step: Step,
+    dir: Dir,
+}
+
+impl AMIS30543<(), (), ()> {
+    /// Create a new instance of `AMIS30543`
+    pub fn new() -> Self {
+        Self {
+            spi: (),
+            step: (),
+            dir: (),
+        }
+    }
+}
+
+impl Default for AMIS30543<(), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Spi, Step, Dir> AMIS30543<Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    /// Write a value to a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn write_register(
+        &mut self,
+        address: u8,
+        value: u8,
+    ) -> Result<(), Spi::Error> {
+        self.spi.write(&[WRITE | address & ADDRESS_MASK, value])
+    }
+
+    /// Read a value from a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn read_register(&mut self, address: u8) -> Result<u8, Spi::Error> {
+        let mut buffer = [address & ADDRESS_MASK, 0];
+        self.spi.transfer_in_place(&mut buffer)?;
+        Ok(buffer[1])
+    }
+
+    /// Read the error flags from the status registers
+    ///
+    /// Most of the error flags are latched, and are cleared by reading them.
+    pub fn errors(&mut self) -> Result<Errors, Spi::Error> {
+        let sr0 = self.read_register(register::SR0)?;
+        let sr1 = self.read_register(register::SR1)?;
+        let sr2 = self.read_register(register::SR2)?;
+
+        Ok(Errors {
+            thermal_warning: sr0 & SR0_TW != 0,
+            thermal_shutdown: sr2 & SR2_TSD != 0,
+            charge_pump_failure: sr0 & SR0_CPFAIL != 0,
+            watchdog: sr0 & SR0_WD != 0,
+            open_coil_x: sr0 & SR0_OPENX != 0,
+            open_coil_y: sr0 & SR0_OPENY != 0,
+            overcurrent_x: sr1 & SR_OVERCURRENT_MASK != 0,
+            overcurrent_y: sr2 & SR_OVERCURRENT_MASK != 0,
+        })
+    }
+}
+
+impl<Spi, Step, Dir> SetCurrent for AMIS30543<Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    /// An index into the current table of the AMIS-30543 (`0` to `31`)
+    ///
+    /// Please refer to the datasheet for the current that corresponds to each
+    /// index.
+    type Current = u8;
+    type Error = Spi::Error;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        let cr0 = self.read_register(register::CR0)?;
+        let cr0 = cr0 & !CR0_CUR_MASK | current & CR0_CUR_MASK;
+        self.write_register(register::CR0, cr0)
+    }
+}
+
+impl<Spi, Step, Dir> EnableStepModeControl<Spi> for AMIS30543<(), Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    type WithStepModeControl = AMIS30543<Spi, Step, Dir>;
+
+    fn enable_step_mode_control(self, spi: Spi) -> Self::WithStepModeControl {
+        AMIS30543 {
+            spi,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Spi, Step, Dir> DisableStepModeControl for AMIS30543<Spi, Step, Dir> {
+    type WithoutStepModeControl = AMIS30543<(), Step, Dir>;
+    type Resources = Spi;
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            AMIS30543 {
+                spi: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            self.spi,
+        )
+    }
+}
+
+impl<Spi, Step, Dir> SetStepMode for AMIS30543<Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    // The new configuration takes effect as soon as it has been written.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Error = Spi::Error;
+    type StepMode = StepMode128;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        // The resolutions above 32 microsteps are only available through the
+        // extended step mode in CR3, which overrides the step mode in CR0.
+        use StepMode128::*;
+        let (sm, esm) = match step_mode {
+            Full => (SM_COMPENSATED_FULL, 0),
+            M2 => (SM_COMPENSATED_HALF, 0),
+            M4 => (3, 0),
+            M8 => (2, 0),
+            M16 => (1, 0),
+            M32 => (0, 0),
+            M64 => (0, ESM_64),
+            M128 => (0, ESM_128),
+        };
+
+        let cr0 = self.read_register(register::CR0)?;
+        let cr0 = cr0 & !CR0_SM_MASK | sm << CR0_SM_SHIFT;
+        self.write_register(register::CR0, cr0)?;
+        self.write_register(register::CR3, esm)
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<Spi, Step, Dir> SetOutputEnabled for AMIS30543<Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    type Error = Spi::Error;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        let cr2 = self.read_register(register::CR2)?;
+        let cr2 = if enabled {
+            cr2 | CR2_MOTEN
+        } else {
+            cr2 & !CR2_MOTEN
+        };
+        self.write_register(register::CR2, cr2)
+    }
+}
+
+impl<Spi, Step, Dir> ReadFault for AMIS30543<Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    type Fault = Errors;
+    type Error = Spi::Error;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        let errors = self.errors()?;
+
+        if errors.any() {
+            return Ok(Some(errors));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<Spi, Step, Dir, OutputPinError> EnableDirectionControl<Dir>
+    for AMIS30543<Spi, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl = AMIS30543<Spi, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        AMIS30543 {
+            spi: self.spi,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Spi, Step, Dir> DisableDirectionControl for AMIS30543<Spi, Step, Dir> {
+    type WithoutDirectionControl = AMIS30543<Spi, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            AMIS30543 {
+                spi: self.spi,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Spi, Step, Dir, OutputPinError> SetDirection for AMIS30543<Spi, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // Table: AC Parameters
+    // https://www.onsemi.com/pdf/datasheet/amis-30543-d.pdf
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(500);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Spi, Step, Dir, OutputPinError> EnableStepControl<Step>
+    for AMIS30543<Spi, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = AMIS30543<Spi, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        AMIS30543 {
+            spi: self.spi,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Spi, Step, Dir> DisableStepControl for AMIS30543<Spi, Step, Dir> {
+    type WithoutStepControl = AMIS30543<Spi, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            AMIS30543 {
+                spi: self.spi,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Spi, Step, Dir, OutputPinError> StepTrait for AMIS30543<Spi, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Table: AC Parameters
+    // https://www.onsemi.com/pdf/datasheet/amis-30543-d.pdf
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(2000);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+/// The error flags of the AMIS-30543
+///
+/// Returned by [`AMIS30543::errors`], and by [`ReadFault::read_fault`], if any
+/// error flag is set.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Errors {
+    /// The junction temperature is above the thermal warning level
+    pub thermal_warning: bool,
+
+    /// The junction temperature exceeded the thermal shutdown level
+    pub thermal_shutdown: bool,
+
+    /// The charge pump failed
+    pub charge_pump_failure: bool,
+
+    /// The watchdog timer expired
+    pub watchdog: bool,
+
+    /// An open coil was detected on coil X
+    pub open_coil_x: bool,
+
+    /// An open coil was detected on coil Y
+    pub open_coil_y: bool,
+
+    /// An overcurrent was detected in one of the transistors of coil X
+    pub overcurrent_x: bool,
+
+    /// An overcurrent was detected in one of the transistors of coil Y
+    pub overcurrent_y: bool,
+}
+
+impl Errors {
+    /// Indicates whether any error flag is set
+    ///
+    /// The thermal warning is not considered an error.
+    pub fn any(&self) -> bool {
+        self.thermal_shutdown
+            || self.charge_pump_failure
+            || self.watchdog
+            || self.open_coil_x
+            || self.open_coil_y
+            || self.overcurrent_x
+            || self.overcurrent_y
+    }
+}
+
+/// Addresses of the AMIS-30543's registers
+///
+/// Please refer to the AMIS-30543 datasheet for more information on the
+/// registers.
+pub mod register {
+    /// Watchdog register
+    pub const WR: u8 = 0x00;
+
+    /// Control register 0 (step mode and current)
+    pub const CR0: u8 = 0x01;
+
+    /// Control register 1 (direction, PWM, and slope configuration)
+    pub const CR1: u8 = 0x02;
+
+    /// Control register 2 (motor enable, sleep, and SLA configuration)
+    pub const CR2: u8 = 0x03;
+
+    /// Status register 0 (temperature, charge pump, and open coil flags)
+    pub const SR0: u8 = 0x04;
+
+    /// Status register 1 (coil X overcurrent flags)
+    pub const SR1: u8 = 0x05;
+
+    /// Status register 2 (coil Y overcurrent and thermal shutdown flags)
+    pub const SR2: u8 = 0x06;
+
+    /// Status register 3 (microstep position)
+    pub const SR3: u8 = 0x07;
+
+    /// Control register 3 (extended step mode)
+    pub const CR3: u8 = 0x09;
+
+    /// Status register 4 (microstep position, lower bits)
+    pub const SR4: u8 = 0x0A;
+}
+
+const WRITE: u8 = 0x80;
+const ADDRESS_MASK: u8 = 0x1f;
+
+const CR0_CUR_MASK: u8 = 0x1f;
+const CR0_SM_SHIFT: u8 = 5;
+const CR0_SM_MASK: u8 = 0x07 << CR0_SM_SHIFT;
+
+const SM_COMPENSATED_HALF: u8 = 4;
+const SM_COMPENSATED_FULL: u8 = 7;
+
+const ESM_128: u8 = 1;
+const ESM_64: u8 = 2;
+
+const CR2_MOTEN: u8 = 1 << 7;
+
+const SR0_TW: u8 = 1 << 6;
+const SR0_CPFAIL: u8 = 1 << 5;
+const SR0_WD: u8 = 1 << 4;
+const SR0_OPENX: u8 = 1 << 3;
+const SR0_OPENY: u8 = 1 << 2;
+
+// The four overcurrent flags of a coil, in SR1 and SR2
+const SR_OVERCURRENT_MASK: u8 = 0x0f << 3;
+
+const SR2_TSD: u8 = 1 << 2;
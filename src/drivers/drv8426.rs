@@ -0,0 +1,434 @@
+//! DRV8426 Driver
+//!
+//! Platform-agnostic driver API for the DRV8426 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! This driver also supports the DRV8424, which is identical to the DRV8426,
+//! except for its higher current rating.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{InputPin, OutputPin, PinState};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode256,
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableFaultDetection, EnableOutputControl,
+        EnableStepControl, EnableStepModeControl, ReadFault, SetDirection,
+        SetOutputEnabled, SetStepMode, Step as StepTrait,
+    },
+};
+
+use super::{QuadLevelPin, TriStatePin, UnspecifiedFault};
+
+/// The DRV8426 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`DRV8426::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// The M0 and M1 pins of the DRV8426 have more than two levels. Both can be
+/// left floating, and M0 can additionally be connected to ground through a
+/// 330 kΩ resistor. This is why step mode control requires M0 to implement
+/// [`QuadLevelPin`] and M1 to implement [`TriStatePin`].
+pub struct DRV8426<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> {
+    enable: Enable,
+    fault: Fault,
+    sleep: Sleep,
+    mode0: Mode0,
+    mode1: Mode1,
+    step: Step,
+    dir: Dir,
+}
+
+impl DRV8426<(), (), (), (), (), (), ()> {
+    /// Create a new instance of `DRV8426`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            fault: (),
+            sleep: (),
+            mode0: (),
+            mode1: (),
+            step: (),
+            dir: (),
+        }
+    }
+
+    /// Create a new instance of `DRV8426` with all pins connected
+    ///
+    /// This is an alternative to [`DRV8426::new`], followed by enabling each
+    /// capability separately. Unlike [`Stepper`](crate::Stepper)'s
+    /// `enable_*` methods, this doesn't apply an initial step mode or
+    /// direction. Set those before the first motion.
+    pub fn from_pins<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>(
+        pins: Pins<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>,
+    ) -> DRV8426<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> {
+        DRV8426 {
+            enable: pins.enable,
+            fault: pins.fault,
+            sleep: pins.sleep,
+            mode0: pins.mode0,
+            mode1: pins.mode1,
+            step: pins.step,
+            dir: pins.dir,
+        }
+    }
+}
+
+impl Default for DRV8426<(), (), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableOutputControl<Enable>
+    for DRV8426<(), Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl =
+        DRV8426<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        DRV8426 {
+            enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+/// The pins connected to the DRV8426
+///
+/// Passed to [`DRV8426::from_pins`], to connect all pins at once.
+pub struct Pins<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> {
+    /// The pin connected to ENABLE
+    pub enable: Enable,
+
+    /// The pin connected to FAULT
+    pub fault: Fault,
+
+    /// The pin connected to SLEEP
+    pub sleep: Sleep,
+
+    /// The pin connected to M0
+    pub mode0: Mode0,
+
+    /// The pin connected to M1
+    pub mode1: Mode1,
+
+    /// The pin connected to STEP
+    pub step: Step,
+
+    /// The pin connected to DIR
+    pub dir: Dir,
+}
+
+/// A `DRV8426` with all pins connected
+///
+/// Uses one type for all output pins, and one for the input pin. This works
+/// well with the type-erased pins that many HALs provide, and saves naming
+/// every pin type separately. See [`DRV8426::from_pins`].
+pub type AllPins<Output, Input> =
+    DRV8426<Output, Input, Output, Output, Output, Output, Output>;
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError>
+    SetOutputEnabled for DRV8426<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // EN is active high.
+        self.enable.set_state(PinState::from(enabled))
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, InputPinError>
+    EnableFaultDetection<Fault>
+    for DRV8426<Enable, (), Sleep, Mode0, Mode1, Step, Dir>
+where
+    Fault: InputPin<Error = InputPinError>,
+{
+    type WithFaultDetection =
+        DRV8426<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>;
+
+    fn enable_fault_detection(self, fault: Fault) -> Self::WithFaultDetection {
+        DRV8426 {
+            enable: self.enable,
+            fault,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, InputPinError> ReadFault
+    for DRV8426<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Fault: InputPin<Error = InputPinError>,
+{
+    type Fault = UnspecifiedFault;
+    type Error = InputPinError;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        // nFAULT is active low.
+        if self.fault.is_low()? {
+            return Ok(Some(UnspecifiedFault));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableStepModeControl<(Mode0, Mode1)>
+    for DRV8426<Enable, Fault, (), (), (), Step, Dir>
+where
+    Mode0: QuadLevelPin<Error = OutputPinError>,
+    Mode1: TriStatePin<Error = OutputPinError>,
+{
+    type WithStepModeControl =
+        DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        (mode0, mode1): (Mode0, Mode1),
+    ) -> Self::WithStepModeControl {
+        DRV8426 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            mode0,
+            mode1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir> DisableStepModeControl
+    for DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>
+{
+    type WithoutStepModeControl = DRV8426<Enable, Fault, (), (), (), Step, Dir>;
+    type Resources = (Mode0, Mode1);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            DRV8426 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                mode0: (),
+                mode1: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            (self.mode0, self.mode1),
+        )
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir, OutputPinError> SetStepMode
+    for DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>
+where
+    Mode0: QuadLevelPin<Error = OutputPinError>,
+    Mode1: TriStatePin<Error = OutputPinError>,
+{
+    // Timing Requirements, DRV8426 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Error = OutputPinError;
+    type StepMode = StepMode256;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use PinState::*;
+        use StepMode256::*;
+
+        // `None` means M1 is left floating.
+        let (mode0, mode1) = match step_mode {
+            Full => (Mode0Level::Driven(Low), Some(Low)),
+            M2 => (Mode0Level::Driven(Low), None),
+            M4 => (Mode0Level::Driven(High), Some(Low)),
+            M8 => (Mode0Level::Driven(High), Some(High)),
+            M16 => (Mode0Level::Driven(High), None),
+            M32 => (Mode0Level::Floating, Some(Low)),
+            M64 => (Mode0Level::PulledLow, None),
+            M128 => (Mode0Level::Floating, None),
+            M256 => (Mode0Level::Floating, Some(High)),
+        };
+
+        // Set mode signals.
+        match mode0 {
+            Mode0Level::Driven(state) => self.mode0.set_state(state)?,
+            Mode0Level::Floating => self.mode0.set_floating()?,
+            Mode0Level::PulledLow => self.mode0.set_pulled_low()?,
+        }
+        match mode1 {
+            Some(state) => self.mode1.set_state(state)?,
+            None => self.mode1.set_floating()?,
+        }
+
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        // The DRV8426 doesn't need to be reset to apply a new step mode.
+        Ok(())
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableDirectionControl<Dir>
+    for DRV8426<Enable, Fault, (), Mode0, Mode1, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl =
+        DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        DRV8426 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir> DisableDirectionControl
+    for DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>
+{
+    type WithoutDirectionControl =
+        DRV8426<Enable, Fault, (), Mode0, Mode1, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            DRV8426 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir, OutputPinError> SetDirection
+    for DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, DRV8426 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableStepControl<Step>
+    for DRV8426<Enable, Fault, (), Mode0, Mode1, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        DRV8426 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir> DisableStepControl
+    for DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>
+{
+    type WithoutStepControl = DRV8426<Enable, Fault, (), Mode0, Mode1, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            DRV8426 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, Fault, Mode0, Mode1, Step, Dir, OutputPinError> StepTrait
+    for DRV8426<Enable, Fault, (), Mode0, Mode1, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, DRV8426 datasheet
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(970);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+// The levels of the DRV8426's M0 pin
+enum Mode0Level {
+    Driven(PinState),
+    Floating,
+    PulledLow,
+}
@@ -11,15 +11,20 @@
 
 use core::convert::Infallible;
 
-use embedded_hal::digital::{OutputPin, PinState};
+use embedded_hal::digital::{InputPin, OutputPin, PinState};
 use fugit::NanosDurationU32 as Nanoseconds;
 
 use crate::{
+    drivers::UnspecifiedFault,
     step_mode::StepMode32,
     traits::{
-        EnableDirectionControl, EnableStepControl, EnableStepModeControl,
-        SetDirection, SetStepMode, Step as StepTrait,
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableFaultDetection, EnableOutputControl,
+        EnableSleepControl, EnableStepControl, EnableStepModeControl,
+        ReadFault, SetDirection, SetOutputEnabled, SetPowerMode, SetStepMode,
+        Step as StepTrait,
     },
+    PowerMode,
 };
 
 /// The DRV8825 driver API
@@ -55,11 +60,293 @@ impl DRV8825<(), (), (), (), (), (), (), (), ()> {
             dir: (),
         }
     }
+
+    /// Create a new instance of `DRV8825` with all pins connected
+    ///
+    /// This is an alternative to [`DRV8825::new`], followed by enabling each
+    /// capability separately. Unlike [`Stepper`](crate::Stepper)'s
+    /// `enable_*` methods, this doesn't apply an initial step mode or
+    /// direction. Set those before the first motion.
+    pub fn from_pins<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+    >(
+        pins: Pins<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>,
+    ) -> DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+    {
+        DRV8825 {
+            enable: pins.enable,
+            fault: pins.fault,
+            sleep: pins.sleep,
+            reset: pins.reset,
+            mode0: pins.mode0,
+            mode1: pins.mode1,
+            mode2: pins.mode2,
+            step: pins.step,
+            dir: pins.dir,
+        }
+    }
+}
+
+impl Default for DRV8825<(), (), (), (), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableOutputControl<Enable>
+    for DRV8825<(), Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl =
+        DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        DRV8825 {
+            enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            reset: self.reset,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            mode2: self.mode2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+/// The pins connected to the DRV8825
+///
+/// Passed to [`DRV8825::from_pins`], to connect all pins at once.
+pub struct Pins<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir> {
+    /// The pin connected to ENABLE
+    pub enable: Enable,
+
+    /// The pin connected to FAULT
+    pub fault: Fault,
+
+    /// The pin connected to SLEEP
+    pub sleep: Sleep,
+
+    /// The pin connected to RESET
+    pub reset: Reset,
+
+    /// The pin connected to MODE0
+    pub mode0: Mode0,
+
+    /// The pin connected to MODE1
+    pub mode1: Mode1,
+
+    /// The pin connected to MODE2
+    pub mode2: Mode2,
+
+    /// The pin connected to STEP
+    pub step: Step,
+
+    /// The pin connected to DIR
+    pub dir: Dir,
+}
+
+/// A `DRV8825` with all pins connected
+///
+/// Uses one type for all output pins, and one for the input pin. This works
+/// well with the type-erased pins that many HALs provide, and saves naming
+/// every pin type separately. See [`DRV8825::from_pins`].
+pub type AllPins<Output, Input> = DRV8825<
+    Output,
+    Input,
+    Output,
+    Output,
+    Output,
+    Output,
+    Output,
+    Output,
+    Output,
+>;
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetOutputEnabled
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // nENBL is active low.
+        self.enable.set_state(PinState::from(!enabled))
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableSleepControl<Sleep>
+    for DRV8825<Enable, Fault, (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Sleep: OutputPin<Error = OutputPinError>,
+{
+    type WithSleepControl =
+        DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
+
+    fn enable_sleep_control(self, sleep: Sleep) -> Self::WithSleepControl {
+        DRV8825 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep,
+            reset: self.reset,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            mode2: self.mode2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetPowerMode
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Sleep: OutputPin<Error = OutputPinError>,
+{
+    // Wakeup time (tWAKE)
+    // https://www.ti.com/lit/ds/symlink/drv8825.pdf
+    const WAKE_UP_TIME: Nanoseconds = Nanoseconds::from_ticks(1_700_000);
+
+    type Error = OutputPinError;
+
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error> {
+        // nSLEEP is active low.
+        self.sleep
+            .set_state(PinState::from(mode == PowerMode::Normal))
+    }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
-    EnableStepModeControl<(Reset, Mode0, Mode1, Mode2)>
-    for DRV8825<(), (), (), (), (), (), (), Step, Dir>
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        InputPinError,
+    > EnableFaultDetection<Fault>
+    for DRV8825<Enable, (), Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Fault: InputPin<Error = InputPinError>,
+{
+    type WithFaultDetection =
+        DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
+
+    fn enable_fault_detection(self, fault: Fault) -> Self::WithFaultDetection {
+        DRV8825 {
+            enable: self.enable,
+            fault,
+            sleep: self.sleep,
+            reset: self.reset,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            mode2: self.mode2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        InputPinError,
+    > ReadFault
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Fault: InputPin<Error = InputPinError>,
+{
+    type Fault = UnspecifiedFault;
+    type Error = InputPinError;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        // nFAULT is active low.
+        if self.fault.is_low()? {
+            return Ok(Some(UnspecifiedFault));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableStepModeControl<(Reset, Mode0, Mode1, Mode2)>
+    for DRV8825<Enable, Fault, Sleep, (), (), (), (), Step, Dir>
 where
     Reset: OutputPin<Error = OutputPinError>,
     Mode0: OutputPin<Error = OutputPinError>,
@@ -67,7 +354,7 @@ where
     Mode2: OutputPin<Error = OutputPinError>,
 {
     type WithStepModeControl =
-        DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>;
+        DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
 
     fn enable_step_mode_control(
         self,
@@ -87,8 +374,47 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> SetStepMode
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+impl<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+    DisableStepModeControl
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+{
+    type WithoutStepModeControl =
+        DRV8825<Enable, Fault, Sleep, (), (), (), (), Step, Dir>;
+    type Resources = (Reset, Mode0, Mode1, Mode2);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            DRV8825 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                reset: (),
+                mode0: (),
+                mode1: (),
+                mode2: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            (self.reset, self.mode0, self.mode1, self.mode2),
+        )
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetStepMode
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
 where
     Reset: OutputPin<Error = OutputPinError>,
     Mode0: OutputPin<Error = OutputPinError>,
@@ -134,14 +460,24 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
-    EnableDirectionControl<Dir>
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, ()>
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableDirectionControl<Dir>
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, ()>
 where
     Dir: OutputPin<Error = OutputPinError>,
 {
     type WithDirectionControl =
-        DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>;
+        DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
 
     fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
         DRV8825 {
@@ -158,8 +494,47 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> SetDirection
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+impl<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+    DisableDirectionControl
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+{
+    type WithoutDirectionControl =
+        DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            DRV8825 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                reset: self.reset,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                mode2: self.mode2,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetDirection
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
 where
     Dir: OutputPin<Error = OutputPinError>,
 {
@@ -175,14 +550,24 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
-    EnableStepControl<Step>
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, (), Dir>
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableStepControl<Step>
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, (), Dir>
 where
     Step: OutputPin<Error = OutputPinError>,
 {
     type WithStepControl =
-        DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>;
+        DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
 
     fn enable_step_control(self, step: Step) -> Self::WithStepControl {
         DRV8825 {
@@ -199,8 +584,47 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> StepTrait
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+impl<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+    DisableStepControl
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+{
+    type WithoutStepControl =
+        DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            DRV8825 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                reset: self.reset,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                mode2: self.mode2,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<
+        Enable,
+        Fault,
+        Sleep,
+        Reset,
+        Mode0,
+        Mode1,
+        Mode2,
+        Step,
+        Dir,
+        OutputPinError,
+    > StepTrait
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
 where
     Step: OutputPin<Error = OutputPinError>,
 {
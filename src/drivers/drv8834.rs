@@ -0,0 +1,464 @@
+//! DRV8834 Driver
+//!
+//! Platform-agnostic driver API for the DRV8834 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{InputPin, OutputPin, PinState};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode32,
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableFaultDetection, EnableOutputControl,
+        EnableSleepControl, EnableStepControl, EnableStepModeControl,
+        ReadFault, SetDirection, SetOutputEnabled, SetPowerMode, SetStepMode,
+        Step as StepTrait,
+    },
+    PowerMode,
+};
+
+use super::{TriStatePin, UnspecifiedFault};
+
+/// The DRV8834 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`DRV8834::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// The M0 pin of the DRV8834 has three levels. Leaving it floating selects the
+/// 1/4 and 1/32 step modes. This is why step mode control requires M0 to
+/// implement [`TriStatePin`].
+pub struct DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> {
+    enable: Enable,
+    fault: Fault,
+    sleep: Sleep,
+    mode0: Mode0,
+    mode1: Mode1,
+    step: Step,
+    dir: Dir,
+}
+
+impl DRV8834<(), (), (), (), (), (), ()> {
+    /// Create a new instance of `DRV8834`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            fault: (),
+            sleep: (),
+            mode0: (),
+            mode1: (),
+            step: (),
+            dir: (),
+        }
+    }
+
+    /// Create a new instance of `DRV8834` with all pins connected
+    ///
+    /// This is an alternative to [`DRV8834::new`], followed by enabling each
+    /// capability separately. Unlike [`Stepper`](crate::Stepper)'s
+    /// `enable_*` methods, this doesn't apply an initial step mode or
+    /// direction. Set those before the first motion.
+    pub fn from_pins<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>(
+        pins: Pins<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>,
+    ) -> DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> {
+        DRV8834 {
+            enable: pins.enable,
+            fault: pins.fault,
+            sleep: pins.sleep,
+            mode0: pins.mode0,
+            mode1: pins.mode1,
+            step: pins.step,
+            dir: pins.dir,
+        }
+    }
+}
+
+impl Default for DRV8834<(), (), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableOutputControl<Enable>
+    for DRV8834<(), Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl =
+        DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        DRV8834 {
+            enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+/// The pins connected to the DRV8834
+///
+/// Passed to [`DRV8834::from_pins`], to connect all pins at once.
+pub struct Pins<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> {
+    /// The pin connected to ENABLE
+    pub enable: Enable,
+
+    /// The pin connected to FAULT
+    pub fault: Fault,
+
+    /// The pin connected to SLEEP
+    pub sleep: Sleep,
+
+    /// The pin connected to M0
+    pub mode0: Mode0,
+
+    /// The pin connected to M1
+    pub mode1: Mode1,
+
+    /// The pin connected to STEP
+    pub step: Step,
+
+    /// The pin connected to DIR
+    pub dir: Dir,
+}
+
+/// A `DRV8834` with all pins connected
+///
+/// Uses one type for all output pins, and one for the input pin. This works
+/// well with the type-erased pins that many HALs provide, and saves naming
+/// every pin type separately. See [`DRV8834::from_pins`].
+pub type AllPins<Output, Input> =
+    DRV8834<Output, Input, Output, Output, Output, Output, Output>;
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError>
+    SetOutputEnabled for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // nENBL is active low.
+        self.enable.set_state(PinState::from(!enabled))
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableSleepControl<Sleep>
+    for DRV8834<Enable, Fault, (), Mode0, Mode1, Step, Dir>
+where
+    Sleep: OutputPin<Error = OutputPinError>,
+{
+    type WithSleepControl =
+        DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>;
+
+    fn enable_sleep_control(self, sleep: Sleep) -> Self::WithSleepControl {
+        DRV8834 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError> SetPowerMode
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Sleep: OutputPin<Error = OutputPinError>,
+{
+    // Wakeup time (tWAKE)
+    // https://www.ti.com/lit/ds/symlink/drv8834.pdf
+    const WAKE_UP_TIME: Nanoseconds = Nanoseconds::from_ticks(1_000_000);
+
+    type Error = OutputPinError;
+
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error> {
+        // nSLEEP is active low.
+        self.sleep
+            .set_state(PinState::from(mode == PowerMode::Normal))
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, InputPinError>
+    EnableFaultDetection<Fault>
+    for DRV8834<Enable, (), Sleep, Mode0, Mode1, Step, Dir>
+where
+    Fault: InputPin<Error = InputPinError>,
+{
+    type WithFaultDetection =
+        DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>;
+
+    fn enable_fault_detection(self, fault: Fault) -> Self::WithFaultDetection {
+        DRV8834 {
+            enable: self.enable,
+            fault,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, InputPinError> ReadFault
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Fault: InputPin<Error = InputPinError>,
+{
+    type Fault = UnspecifiedFault;
+    type Error = InputPinError;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        // nFAULT is active low.
+        if self.fault.is_low()? {
+            return Ok(Some(UnspecifiedFault));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableStepModeControl<(Mode0, Mode1)>
+    for DRV8834<Enable, Fault, Sleep, (), (), Step, Dir>
+where
+    Mode0: TriStatePin<Error = OutputPinError>,
+    Mode1: OutputPin<Error = OutputPinError>,
+{
+    type WithStepModeControl =
+        DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        (mode0, mode1): (Mode0, Mode1),
+    ) -> Self::WithStepModeControl {
+        DRV8834 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            mode0,
+            mode1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> DisableStepModeControl
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+{
+    type WithoutStepModeControl =
+        DRV8834<Enable, Fault, Sleep, (), (), Step, Dir>;
+    type Resources = (Mode0, Mode1);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            DRV8834 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                mode0: (),
+                mode1: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            (self.mode0, self.mode1),
+        )
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError> SetStepMode
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Mode0: TriStatePin<Error = OutputPinError>,
+    Mode1: OutputPin<Error = OutputPinError>,
+{
+    // 7.6 Timing Requirements (page 6)
+    // https://www.ti.com/lit/ds/symlink/drv8834.pdf
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Error = OutputPinError;
+    type StepMode = StepMode32;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use PinState::*;
+        use StepMode32::*;
+
+        // `None` means M0 is left floating.
+        let (mode0, mode1) = match step_mode {
+            Full => (Some(Low), Low),
+            M2 => (Some(High), Low),
+            M4 => (None, Low),
+            M8 => (Some(Low), High),
+            M16 => (Some(High), High),
+            M32 => (None, High),
+        };
+
+        // Set mode signals.
+        match mode0 {
+            Some(state) => self.mode0.set_state(state)?,
+            None => self.mode0.set_floating()?,
+        }
+        self.mode1.set_state(mode1)?;
+
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        // The DRV8834 doesn't need to be reset to apply a new step mode.
+        Ok(())
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableDirectionControl<Dir>
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl =
+        DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        DRV8834 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> DisableDirectionControl
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+{
+    type WithoutDirectionControl =
+        DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            DRV8834 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError> SetDirection
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // 7.6 Timing Requirements (page 6)
+    // https://www.ti.com/lit/ds/symlink/drv8834.pdf
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError>
+    EnableStepControl<Step>
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl =
+        DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        DRV8834 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir> DisableStepControl
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+{
+    type WithoutStepControl =
+        DRV8834<Enable, Fault, Sleep, Mode0, Mode1, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            DRV8834 {
+                enable: self.enable,
+                fault: self.fault,
+                sleep: self.sleep,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir, OutputPinError> StepTrait
+    for DRV8834<Enable, Fault, Sleep, Mode0, Mode1, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // 7.6 Timing Requirements (page 6)
+    // https://www.ti.com/lit/ds/symlink/drv8834.pdf
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(1900);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
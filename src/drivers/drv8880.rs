@@ -0,0 +1,470 @@
+//! DRV8880 Driver
+//!
+//! Platform-agnostic driver API for the DRV8880 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The TRQ0 and TRQ1 pins of the DRV8880 scale the motor current. If they are
+//! connected, they can be controlled as part of the current control
+//! capability. See [`Torque`].
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{OutputPin, PinState};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode16,
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableCurrentControl, EnableDirectionControl, EnableOutputControl,
+        EnableStepControl, EnableStepModeControl, SetCurrent, SetDirection,
+        SetOutputEnabled, SetStepMode, Step as StepTrait,
+    },
+};
+
+use super::TriStatePin;
+
+/// The DRV8880 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`DRV8880::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// The M0 pin of the DRV8880 has three levels. Leaving it floating selects the
+/// 1/8 step mode. This is why step mode control requires M0 to implement
+/// [`TriStatePin`].
+pub struct DRV8880<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir> {
+    enable: Enable,
+    sleep: Sleep,
+    mode0: Mode0,
+    mode1: Mode1,
+    trq0: Trq0,
+    trq1: Trq1,
+    step: Step,
+    dir: Dir,
+}
+
+impl DRV8880<(), (), (), (), (), (), (), ()> {
+    /// Create a new instance of `DRV8880`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            sleep: (),
+            mode0: (),
+            mode1: (),
+            trq0: (),
+            trq1: (),
+            step: (),
+            dir: (),
+        }
+    }
+
+    /// Create a new instance of `DRV8880` with all pins connected
+    ///
+    /// This is an alternative to [`DRV8880::new`], followed by enabling each
+    /// capability separately. Unlike [`Stepper`](crate::Stepper)'s
+    /// `enable_*` methods, this doesn't apply an initial step mode or
+    /// direction. Set those before the first motion.
+    pub fn from_pins<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir>(
+        pins: Pins<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir>,
+    ) -> DRV8880<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir> {
+        DRV8880 {
+            enable: pins.enable,
+            sleep: pins.sleep,
+            mode0: pins.mode0,
+            mode1: pins.mode1,
+            trq0: pins.trq0,
+            trq1: pins.trq1,
+            step: pins.step,
+            dir: pins.dir,
+        }
+    }
+}
+
+impl Default for DRV8880<(), (), (), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError>
+    EnableOutputControl<Enable>
+    for DRV8880<(), Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl =
+        DRV8880<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        DRV8880 {
+            enable,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            trq0: self.trq0,
+            trq1: self.trq1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+/// The pins connected to the DRV8880
+///
+/// Passed to [`DRV8880::from_pins`], to connect all pins at once.
+pub struct Pins<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir> {
+    /// The pin connected to ENABLE
+    pub enable: Enable,
+
+    /// The pin connected to SLEEP
+    pub sleep: Sleep,
+
+    /// The pin connected to M0
+    pub mode0: Mode0,
+
+    /// The pin connected to M1
+    pub mode1: Mode1,
+
+    /// The pin connected to TRQ0
+    pub trq0: Trq0,
+
+    /// The pin connected to TRQ1
+    pub trq1: Trq1,
+
+    /// The pin connected to STEP
+    pub step: Step,
+
+    /// The pin connected to DIR
+    pub dir: Dir,
+}
+
+/// A `DRV8880` with all pins connected
+///
+/// Uses one type for all pins. This works well with the type-erased pins that
+/// many HALs provide, and saves naming every pin type separately. See
+/// [`DRV8880::from_pins`].
+pub type AllPins<Output> =
+    DRV8880<Output, Output, Output, Output, Output, Output, Output, Output>;
+
+impl<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError>
+    SetOutputEnabled
+    for DRV8880<Enable, Sleep, Mode0, Mode1, Trq0, Trq1, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // ENABLE is active high.
+        self.enable.set_state(PinState::from(enabled))
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError>
+    EnableStepModeControl<(Mode0, Mode1)>
+    for DRV8880<Enable, (), (), (), Trq0, Trq1, Step, Dir>
+where
+    Mode0: TriStatePin<Error = OutputPinError>,
+    Mode1: OutputPin<Error = OutputPinError>,
+{
+    type WithStepModeControl =
+        DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        (mode0, mode1): (Mode0, Mode1),
+    ) -> Self::WithStepModeControl {
+        DRV8880 {
+            enable: self.enable,
+            sleep: self.sleep,
+            mode0,
+            mode1,
+            trq0: self.trq0,
+            trq1: self.trq1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir> DisableStepModeControl
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>
+{
+    type WithoutStepModeControl =
+        DRV8880<Enable, (), (), (), Trq0, Trq1, Step, Dir>;
+    type Resources = (Mode0, Mode1);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            DRV8880 {
+                enable: self.enable,
+                sleep: self.sleep,
+                mode0: (),
+                mode1: (),
+                trq0: self.trq0,
+                trq1: self.trq1,
+                step: self.step,
+                dir: self.dir,
+            },
+            (self.mode0, self.mode1),
+        )
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError> SetStepMode
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>
+where
+    Mode0: TriStatePin<Error = OutputPinError>,
+    Mode1: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, DRV8880 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Error = OutputPinError;
+    type StepMode = StepMode16;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use PinState::*;
+        use StepMode16::*;
+
+        // `None` means M0 is left floating.
+        let (mode0, mode1) = match step_mode {
+            Full => (Some(Low), Low),
+            M2 => (Some(Low), High),
+            M4 => (Some(High), High),
+            M8 => (None, Low),
+            M16 => (Some(High), Low),
+        };
+
+        // Set mode signals.
+        match mode0 {
+            Some(state) => self.mode0.set_state(state)?,
+            None => self.mode0.set_floating()?,
+        }
+        self.mode1.set_state(mode1)?;
+
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        // The DRV8880 doesn't need to be reset to apply a new step mode.
+        Ok(())
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError>
+    EnableCurrentControl<(Trq0, Trq1)>
+    for DRV8880<Enable, (), Mode0, Mode1, (), (), Step, Dir>
+where
+    Trq0: OutputPin<Error = OutputPinError>,
+    Trq1: OutputPin<Error = OutputPinError>,
+{
+    type WithCurrentControl =
+        DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>;
+
+    fn enable_current_control(
+        self,
+        (trq0, trq1): (Trq0, Trq1),
+    ) -> Self::WithCurrentControl {
+        DRV8880 {
+            enable: self.enable,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            trq0,
+            trq1,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError> SetCurrent
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>
+where
+    Trq0: OutputPin<Error = OutputPinError>,
+    Trq1: OutputPin<Error = OutputPinError>,
+{
+    type Current = Torque;
+    type Error = OutputPinError;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        use PinState::*;
+        let (trq0, trq1) = match current {
+            Torque::Full => (Low, Low),
+            Torque::ThreeQuarters => (High, Low),
+            Torque::Half => (Low, High),
+            Torque::Quarter => (High, High),
+        };
+
+        self.trq0.set_state(trq0)?;
+        self.trq1.set_state(trq1)?;
+
+        Ok(())
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError>
+    EnableDirectionControl<Dir>
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl =
+        DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        DRV8880 {
+            enable: self.enable,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            trq0: self.trq0,
+            trq1: self.trq1,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir> DisableDirectionControl
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>
+{
+    type WithoutDirectionControl =
+        DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            DRV8880 {
+                enable: self.enable,
+                sleep: self.sleep,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                trq0: self.trq0,
+                trq1: self.trq1,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError> SetDirection
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, DRV8880 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError>
+    EnableStepControl<Step>
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl =
+        DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        DRV8880 {
+            enable: self.enable,
+            sleep: self.sleep,
+            mode0: self.mode0,
+            mode1: self.mode1,
+            trq0: self.trq0,
+            trq1: self.trq1,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir> DisableStepControl
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>
+{
+    type WithoutStepControl =
+        DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            DRV8880 {
+                enable: self.enable,
+                sleep: self.sleep,
+                mode0: self.mode0,
+                mode1: self.mode1,
+                trq0: self.trq0,
+                trq1: self.trq1,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, Mode0, Mode1, Trq0, Trq1, Step, Dir, OutputPinError> StepTrait
+    for DRV8880<Enable, (), Mode0, Mode1, Trq0, Trq1, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, DRV8880 datasheet
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(970);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+/// The torque scaling of the DRV8880, as selected by the TRQ0 and TRQ1 pins
+///
+/// Given as a fraction of the full-scale current that is set using the VREF
+/// pin.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Torque {
+    /// The full-scale current (TRQ0 low, TRQ1 low)
+    Full,
+
+    /// Three quarters of the full-scale current (TRQ0 high, TRQ1 low)
+    ThreeQuarters,
+
+    /// Half of the full-scale current (TRQ0 low, TRQ1 high)
+    Half,
+
+    /// A quarter of the full-scale current (TRQ0 high, TRQ1 high)
+    Quarter,
+}
@@ -0,0 +1,251 @@
+//! Generic STEP/DIR Driver
+//!
+//! Platform-agnostic driver API for stepper motor drivers that are controlled
+//! using only STEP and DIR signals, and whose timing requirements are supplied
+//! by the user. Can be used on any platform for which implementations of the
+//! required [embedded-hal] traits are available.
+//!
+//! Many industrial stepper motor drivers (like the DM542 or DM860, and their
+//! various clones) are controlled in the same way and only differ in their
+//! timing requirements. [`GenericStepDir`] can be used with all of them.
+//!
+//! # Presets
+//!
+//! This module provides type aliases with the timing requirements of some
+//! popular driver models already filled in, like [`DQ542MA`] or [`DM556`]. If
+//! your model isn't covered, please supply its timing requirements to
+//! [`GenericStepDir`] directly.
+//!
+//! ``` rust
+//! use stepper::drivers::generic::DQ542MA;
+//!
+//! let driver = DQ542MA::new();
+//! ```
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::OutputPin;
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::traits::{
+    DisableDirectionControl, DisableStepControl, EnableDirectionControl,
+    EnableStepControl, SetDirection, Step as StepTrait,
+};
+
+/// The DQ542MA driver
+///
+/// See [`GenericStepDir`] for the driver API.
+// https://wiki.linuxcnc.org/cgi-bin/wiki.pl?Stepper_Drive_Timing
+pub type DQ542MA<Enable = (), Step = (), Dir = ()> =
+    GenericStepDir<500, 5050, Enable, Step, Dir>;
+
+/// The DM542 driver
+///
+/// See [`GenericStepDir`] for the driver API.
+// DM542 user manual: DIR ahead of PUL by at least 5 µs, pulse width at least
+// 2.5 µs.
+pub type DM542<Enable = (), Step = (), Dir = ()> =
+    GenericStepDir<5000, 2500, Enable, Step, Dir>;
+
+/// The DM556 driver
+///
+/// See [`GenericStepDir`] for the driver API.
+// DM556 user manual: DIR ahead of PUL by at least 5 µs, pulse width at least
+// 2.5 µs.
+pub type DM556<Enable = (), Step = (), Dir = ()> =
+    GenericStepDir<5000, 2500, Enable, Step, Dir>;
+
+/// The DM860 driver
+///
+/// See [`GenericStepDir`] for the driver API.
+// DM860 user manual: DIR ahead of PUL by at least 5 µs, pulse width at least
+// 2.5 µs.
+pub type DM860<Enable = (), Step = (), Dir = ()> =
+    GenericStepDir<5000, 2500, Enable, Step, Dir>;
+
+/// The generic STEP/DIR driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`GenericStepDir::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// The timing requirements are given in nanoseconds, as const generic
+/// parameters. `SETUP_TIME_NS` is the time the DIR signal must be stable before
+/// a step pulse, while `PULSE_LENGTH_NS` is the minimum length of a step pulse.
+/// Please refer to the documentation of your driver for the correct values.
+///
+/// ``` rust
+/// use stepper::drivers::generic::GenericStepDir;
+///
+/// // 5 µs DIR setup time, 2.5 µs step pulse length
+/// let driver = GenericStepDir::<5000, 2500>::new();
+/// ```
+pub struct GenericStepDir<
+    const SETUP_TIME_NS: u32,
+    const PULSE_LENGTH_NS: u32,
+    Enable = (),
+    Step = (),
+    Dir = (),
+> {
+    enable: Enable,
+    step: Step,
+    dir: Dir,
+}
+
+impl<const SETUP_TIME_NS: u32, const PULSE_LENGTH_NS: u32>
+    GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), (), ()>
+{
+    /// Create a new instance of `GenericStepDir`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            step: (),
+            dir: (),
+        }
+    }
+}
+
+impl<const SETUP_TIME_NS: u32, const PULSE_LENGTH_NS: u32> Default
+    for GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), (), ()>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableDirectionControl<Dir>
+    for GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl =
+        GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        GenericStepDir {
+            enable: self.enable,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<const SETUP_TIME_NS: u32, const PULSE_LENGTH_NS: u32, Step, Dir>
+    DisableDirectionControl
+    for GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), Step, Dir>
+{
+    type WithoutDirectionControl =
+        GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            GenericStepDir {
+                enable: self.enable,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        OutputPinError,
+    > SetDirection
+    for GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(SETUP_TIME_NS);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        OutputPinError,
+    > EnableStepControl<Step>
+    for GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl =
+        GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        GenericStepDir {
+            enable: self.enable,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<const SETUP_TIME_NS: u32, const PULSE_LENGTH_NS: u32, Step, Dir>
+    DisableStepControl
+    for GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), Step, Dir>
+{
+    type WithoutStepControl =
+        GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            GenericStepDir {
+                enable: self.enable,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        OutputPinError,
+    > StepTrait
+    for GenericStepDir<SETUP_TIME_NS, PULSE_LENGTH_NS, (), Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(PULSE_LENGTH_NS);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
@@ -0,0 +1,381 @@
+//! L298N Driver
+//!
+//! Platform-agnostic driver API for the L298N dual H-bridge, as commonly used to
+//! drive bipolar stepper motors. Can be used on any platform for which
+//! implementations of the required [embedded-hal] traits are available.
+//!
+//! Like the ULN2003, the L298N has no STEP and DIR inputs. Instead, this driver
+//! controls the two H-bridges directly, through the four inputs IN1 to IN4, and
+//! steps through a coil sequence in software. IN1 and IN2 control the first
+//! coil, IN3 and IN4 the second. See [`Sequence`] for the supported sequences.
+//!
+//! The enable inputs of the L298N (ENA and ENB) are not controlled by this
+//! driver. They need to be pulled high.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{self, ErrorType, OutputPin, PinState};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::traits::{
+    DisableStepControl, EnableStepControl, SetDirection, Step as StepTrait,
+};
+
+/// The L298N driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`L298N::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// The four input pins (IN1 to IN4) are passed to
+/// [`Stepper::enable_step_control`]. They are used for both step and direction
+/// control, so there's no need to call
+/// [`Stepper::enable_direction_control`].
+///
+/// [`Stepper::enable_step_control`]: crate::Stepper::enable_step_control
+/// [`Stepper::enable_direction_control`]: crate::Stepper::enable_direction_control
+pub struct L298N<Coils> {
+    coils: Coils,
+    sequence: Sequence,
+}
+
+impl L298N<()> {
+    /// Create a new instance of `L298N`
+    ///
+    /// Uses [`Sequence::Full`] by default. See [`L298N::set_sequence`].
+    pub fn new() -> Self {
+        Self {
+            coils: (),
+            sequence: Sequence::Full,
+        }
+    }
+}
+
+impl Default for L298N<()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Coils> L298N<Coils> {
+    /// Access the coil sequence that is used for stepping
+    pub fn sequence(&self) -> Sequence {
+        self.sequence
+    }
+
+    /// Set the coil sequence that is used for stepping
+    ///
+    /// Takes effect with the next step.
+    pub fn set_sequence(&mut self, sequence: Sequence) {
+        self.sequence = sequence;
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> L298N<Coils<IN1, IN2, IN3, IN4>>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+{
+    /// Switch off all coils
+    ///
+    /// Pulls all inputs low, which stops the current through both coils. The
+    /// motor doesn't hold its position while the coils are switched off, but it
+    /// also doesn't draw current or heat up. The coils are switched on again
+    /// with the next step.
+    pub fn release_coils(&mut self) -> Result<(), OutputPinError> {
+        self.coils.write(0b0000)
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> EnableStepControl<(IN1, IN2, IN3, IN4)>
+    for L298N<()>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    type WithStepControl = L298N<Coils<IN1, IN2, IN3, IN4>>;
+
+    fn enable_step_control(
+        self,
+        (in1, in2, in3, in4): (IN1, IN2, IN3, IN4),
+    ) -> Self::WithStepControl {
+        L298N {
+            coils: Coils {
+                in1,
+                in2,
+                in3,
+                in4,
+                signal: Signal::Step,
+                forward: true,
+                phase: 0,
+                sequence: self.sequence,
+            },
+            sequence: self.sequence,
+        }
+    }
+}
+
+impl<IN1, IN2, IN3, IN4> DisableStepControl
+    for L298N<Coils<IN1, IN2, IN3, IN4>>
+{
+    type WithoutStepControl = L298N<()>;
+    type Resources = (IN1, IN2, IN3, IN4);
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        let Coils {
+            in1, in2, in3, in4, ..
+        } = self.coils;
+
+        (
+            L298N {
+                coils: (),
+                sequence: self.sequence,
+            },
+            (in1, in2, in3, in4),
+        )
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> SetDirection
+    for L298N<Coils<IN1, IN2, IN3, IN4>>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    // The direction is only stored, not applied to any pins.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Dir = Coils<IN1, IN2, IN3, IN4>;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        self.coils.signal = Signal::Dir;
+        Ok(&mut self.coils)
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> StepTrait
+    for L298N<Coils<IN1, IN2, IN3, IN4>>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    // The coils are switched at the beginning of the pulse, so its length
+    // doesn't matter. The maximum step rate of the motor needs to be respected
+    // by the motion profile.
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Step = Coils<IN1, IN2, IN3, IN4>;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        self.coils.signal = Signal::Step;
+        self.coils.sequence = self.sequence;
+        Ok(&mut self.coils)
+    }
+}
+
+/// The coil sequence used for stepping
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Sequence {
+    /// Wave drive: one coil is energized at a time
+    ///
+    /// Uses the least power, but provides the least torque.
+    Wave,
+
+    /// Full-step drive: both coils are energized at all times
+    ///
+    /// Provides the most torque.
+    Full,
+
+    /// Half-step drive: alternates between one and both coils
+    ///
+    /// Doubles the resolution, at the cost of uneven torque.
+    Half,
+}
+
+impl Sequence {
+    // Each entry contains the states of IN1 to IN4, starting with IN1 at the
+    // least significant bit. IN1 high and IN2 low drives current through the
+    // first coil in one direction, IN1 low and IN2 high in the other. Same for
+    // IN3, IN4, and the second coil.
+    fn phases(&self) -> &'static [u8] {
+        match self {
+            Sequence::Wave => &[0b0001, 0b0100, 0b0010, 0b1000],
+            Sequence::Full => &[0b0101, 0b0110, 0b1010, 0b1001],
+            Sequence::Half => &[
+                0b0001, 0b0101, 0b0100, 0b0110, 0b0010, 0b1010, 0b1000, 0b1001,
+            ],
+        }
+    }
+}
+
+/// The input pins of the L298N
+///
+/// Serves as both the STEP and the DIR pin. Setting it high or low as the DIR
+/// pin selects the direction. Setting it high as the STEP pin switches the
+/// coils to the next phase of the sequence, in that direction. Setting it low
+/// as the STEP pin has no effect.
+///
+/// Created by [`L298N`]'s implementation of [`EnableStepControl`].
+pub struct Coils<IN1, IN2, IN3, IN4> {
+    in1: IN1,
+    in2: IN2,
+    in3: IN3,
+    in4: IN4,
+    signal: Signal,
+    forward: bool,
+    phase: usize,
+    sequence: Sequence,
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> Coils<IN1, IN2, IN3, IN4>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+{
+    fn advance(&mut self) -> Result<(), OutputPinError> {
+        let phases = self.sequence.phases();
+
+        // The sequence might have changed since the last step, so make sure
+        // we're within bounds.
+        self.phase = if self.forward {
+            (self.phase + 1) % phases.len()
+        } else {
+            (self.phase + phases.len() - 1) % phases.len()
+        };
+
+        self.write(phases[self.phase])
+    }
+
+    fn write(&mut self, coils: u8) -> Result<(), OutputPinError> {
+        self.in1.set_state(PinState::from(coils & 0b0001 != 0))?;
+        self.in2.set_state(PinState::from(coils & 0b0010 != 0))?;
+        self.in3.set_state(PinState::from(coils & 0b0100 != 0))?;
+        self.in4.set_state(PinState::from(coils & 0b1000 != 0))?;
+
+        Ok(())
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> ErrorType for Coils<IN1, IN2, IN3, IN4>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    type Error = OutputPinError;
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> OutputPin for Coils<IN1, IN2, IN3, IN4>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        match self.signal {
+            Signal::Dir => {
+                self.forward = true;
+                Ok(())
+            }
+            Signal::Step => self.advance(),
+        }
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        if let Signal::Dir = self.signal {
+            self.forward = false;
+        }
+
+        Ok(())
+    }
+}
+
+// Which signal `Coils` is currently acting as
+enum Signal {
+    Dir,
+    Step,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_hal::digital::{ErrorType, OutputPin};
+
+    use crate::traits::{EnableStepControl as _, SetDirection, Step};
+
+    use super::{Coils, Sequence, L298N};
+
+    #[test]
+    fn should_step_through_sequence_in_both_directions() {
+        let mut driver = L298N::new().enable_step_control((
+            MockPin(false),
+            MockPin(false),
+            MockPin(false),
+            MockPin(false),
+        ));
+        driver.set_sequence(Sequence::Half);
+
+        let step =
+            |driver: &mut L298N<Coils<MockPin, MockPin, MockPin, MockPin>>| {
+                let coils = driver.step().unwrap();
+                coils.set_high().unwrap();
+                coils.set_low().unwrap();
+
+                [coils.in1.0, coils.in2.0, coils.in3.0, coils.in4.0]
+            };
+
+        assert_eq!(step(&mut driver), [true, false, true, false]);
+        assert_eq!(step(&mut driver), [false, false, true, false]);
+
+        driver.dir().unwrap().set_low().unwrap();
+
+        assert_eq!(step(&mut driver), [true, false, true, false]);
+        assert_eq!(step(&mut driver), [true, false, false, false]);
+        assert_eq!(step(&mut driver), [true, false, false, true]);
+    }
+
+    struct MockPin(bool);
+
+    impl ErrorType for MockPin {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for MockPin {
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.0 = true;
+            Ok(())
+        }
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.0 = false;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,483 @@
+//! L6470 Driver
+//!
+//! Platform-agnostic driver API for the L6470 (dSPIN) motion controller. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The L6470 generates its own acceleration and deceleration ramps, which this
+//! driver uses to provide motion control capability natively, without
+//! requiring the software fallback from the
+//! [`motion_control`](crate::motion_control) module.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use embedded_hal::spi::SpiDevice;
+
+use crate::{
+    error::{ErasedError, ErrorKind},
+    traits::{EnableMotionControl, MotionControl, ReadVelocity},
+    Direction,
+};
+
+/// The L6470 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`L6470::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// Motion control is enabled by passing an SPI device to
+/// [`Stepper::enable_motion_control`]. The L6470 expects the chip select line
+/// to be released after every byte, so every byte is sent as a separate SPI
+/// transaction. Daisy-chained devices are not supported.
+///
+/// The L6470 latches its fault flags, including the undervoltage flag, which
+/// is set after power-up. Call [`L6470::status`] once after power-up, to clear
+/// those flags. Otherwise the first call to [`MotionControl::update`] will
+/// report an error.
+///
+/// [`Stepper::enable_motion_control`]: crate::Stepper::enable_motion_control
+pub struct L6470<Spi> {
+    spi: Spi,
+}
+
+impl L6470<()> {
+    /// Create a new instance of `L6470`
+    pub fn new() -> Self {
+        Self { spi: () }
+    }
+}
+
+impl Default for L6470<()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Spi> L6470<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// Write a value to a parameter register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers. Depending on the register, only the lower one, two, or three
+    /// bytes of the value are sent.
+    pub fn set_param(
+        &mut self,
+        address: u8,
+        value: u32,
+    ) -> Result<(), Spi::Error> {
+        self.send(command::SET_PARAM | address)?;
+        self.send_value(value, register::len(address))?;
+        Ok(())
+    }
+
+    /// Read a value from a parameter register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn get_param(&mut self, address: u8) -> Result<u32, Spi::Error> {
+        self.send(command::GET_PARAM | address)?;
+        self.send_value(0, register::len(address))
+    }
+
+    /// Read the status register and clear its fault flags
+    ///
+    /// See the [`status`] module for the meaning of the individual bits.
+    pub fn status(&mut self) -> Result<u16, Spi::Error> {
+        self.send(command::GET_STATUS)?;
+        let status = self.send_value(0, 2)?;
+        Ok(status as u16)
+    }
+
+    /// Run the motor at a constant speed
+    ///
+    /// The speed is given in the L6470's `SPEED` unit. The motor keeps
+    /// running, until it is stopped.
+    pub fn run(
+        &mut self,
+        direction: Direction,
+        speed: u32,
+    ) -> Result<(), Spi::Error> {
+        self.send(command::RUN | direction_bit(direction))?;
+        self.send_value(speed & SPEED_MASK, 3)?;
+        Ok(())
+    }
+
+    /// Decelerate the motor and stop it
+    pub fn soft_stop(&mut self) -> Result<(), Spi::Error> {
+        self.send(command::SOFT_STOP)?;
+        Ok(())
+    }
+
+    /// Stop the motor immediately
+    pub fn hard_stop(&mut self) -> Result<(), Spi::Error> {
+        self.send(command::HARD_STOP)?;
+        Ok(())
+    }
+
+    fn send_value(
+        &mut self,
+        value: u32,
+        len: usize,
+    ) -> Result<u32, Spi::Error> {
+        let mut reply = 0;
+
+        for &byte in &value.to_be_bytes()[4 - len..] {
+            reply = reply << 8 | self.send(byte)? as u32;
+        }
+
+        Ok(reply)
+    }
+
+    fn send(&mut self, byte: u8) -> Result<u8, Spi::Error> {
+        let mut buffer = [byte];
+        self.spi.transfer_in_place(&mut buffer)?;
+        Ok(buffer[0])
+    }
+}
+
+impl<Spi, const TIMER_HZ: u32> EnableMotionControl<Spi, TIMER_HZ> for L6470<()>
+where
+    Spi: SpiDevice,
+{
+    type WithMotionControl = L6470<Spi>;
+
+    fn enable_motion_control(self, spi: Spi) -> Self::WithMotionControl {
+        L6470 { spi }
+    }
+}
+
+impl<Spi> MotionControl for L6470<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// The maximum velocity, in the L6470's `MAX_SPEED` unit
+    type Velocity = u16;
+    type Error = Error<Spi::Error>;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.set_param(register::MAX_SPEED, max_velocity.into())
+            .map_err(Error::Spi)?;
+
+        self.send(command::GO_TO).map_err(Error::Spi)?;
+        self.send_value(target_step as u32 & POSITION_MASK, 3)
+            .map_err(Error::Spi)?;
+
+        Ok(())
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        if step == 0 {
+            self.send(command::RESET_POS).map_err(Error::Spi)?;
+        } else {
+            self.set_param(register::ABS_POS, step as u32 & POSITION_MASK)
+                .map_err(Error::Spi)?;
+        }
+
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        let status = self.status().map_err(Error::Spi)?;
+        check_status(status)?;
+
+        // The BUSY flag is active low.
+        Ok(status & status::BUSY == 0)
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        // `ABS_POS` is a 22-bit two's complement value. Sign-extend it.
+        let position = self.get_param(register::ABS_POS).map_err(Error::Spi)?;
+        Ok(((position << 10) as i32) >> 10)
+    }
+
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: Self::Velocity,
+    ) -> Result<(), Self::Error> {
+        // Positions wrap around after 22 bits, and `GoTo` takes the shortest
+        // path, so we can't just move to a distant position. Use `Run`
+        // instead. `SPEED` has 10 more fractional bits than `MAX_SPEED`.
+        self.run(direction, u32::from(velocity) << 10)
+            .map_err(Error::Spi)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        self.soft_stop().map_err(Error::Spi)
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        self.hard_stop().map_err(Error::Spi)
+    }
+}
+
+impl<Spi> ReadVelocity for L6470<Spi>
+where
+    Spi: SpiDevice,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        // `SPEED` has 10 more fractional bits than `MAX_SPEED`.
+        let speed = self.get_param(register::SPEED).map_err(Error::Spi)?;
+        Ok((speed >> 10) as u16)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Spi> crate::traits::RegisterWaker for L6470<Spi> {
+    fn register_waker(&mut self, waker: &core::task::Waker) {
+        // The L6470 signals the end of a motion through its BUSY output, but we
+        // don't have access to that. All we can do is keep polling.
+        waker.wake_by_ref();
+    }
+}
+
+fn direction_bit(direction: Direction) -> u8 {
+    match direction {
+        Direction::Forward => 1,
+        Direction::Backward => 0,
+    }
+}
+
+/// An error that can occur while using the L6470
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<SpiError> {
+    /// Error while communicating over SPI
+    Spi(SpiError),
+
+    /// The L6470 detected an overcurrent
+    Overcurrent,
+
+    /// The L6470 shut down due to overtemperature
+    ThermalShutdown,
+
+    /// The supply voltage dropped below the undervoltage lockout threshold
+    Undervoltage,
+
+    /// The L6470 detected a stall
+    StepLoss,
+
+    /// The L6470 rejected a command
+    ///
+    /// This happens, if the command doesn't exist, or can't be performed in
+    /// the current state, for example when writing `ABS_POS` while the motor
+    /// is moving.
+    CommandRejected,
+}
+
+impl<SpiError> From<Error<SpiError>> for ErasedError {
+    fn from(err: Error<SpiError>) -> Self {
+        match err {
+            Error::Spi(_) => ErasedError::new(ErrorKind::Communication),
+            Error::Overcurrent => {
+                ErasedError::new(ErrorKind::Driver).with_context("overcurrent")
+            }
+            Error::ThermalShutdown => ErasedError::new(ErrorKind::Driver)
+                .with_context("thermal shutdown"),
+            Error::Undervoltage => {
+                ErasedError::new(ErrorKind::Driver).with_context("undervoltage")
+            }
+            Error::StepLoss => ErasedError::new(ErrorKind::StepLoss),
+            Error::CommandRejected => ErasedError::new(ErrorKind::Driver)
+                .with_context("command rejected"),
+        }
+    }
+}
+
+fn check_status<SpiError>(status: u16) -> Result<(), Error<SpiError>> {
+    // Most of the fault flags are active low.
+    if status & status::OCD == 0 {
+        return Err(Error::Overcurrent);
+    }
+    if status & status::TH_SD == 0 {
+        return Err(Error::ThermalShutdown);
+    }
+    if status & status::UVLO == 0 {
+        return Err(Error::Undervoltage);
+    }
+    if status & (status::STEP_LOSS_A | status::STEP_LOSS_B)
+        != status::STEP_LOSS_A | status::STEP_LOSS_B
+    {
+        return Err(Error::StepLoss);
+    }
+    if status & (status::NOTPERF_CMD | status::WRONG_CMD) != 0 {
+        return Err(Error::CommandRejected);
+    }
+
+    Ok(())
+}
+
+/// Addresses of the L6470's parameter registers
+///
+/// Please refer to the L6470 datasheet for more information on the registers.
+pub mod register {
+    /// Current position
+    pub const ABS_POS: u8 = 0x01;
+
+    /// Electrical position
+    pub const EL_POS: u8 = 0x02;
+
+    /// Mark position
+    pub const MARK: u8 = 0x03;
+
+    /// Current speed
+    pub const SPEED: u8 = 0x04;
+
+    /// Acceleration
+    pub const ACC: u8 = 0x05;
+
+    /// Deceleration
+    pub const DEC: u8 = 0x06;
+
+    /// Maximum speed
+    pub const MAX_SPEED: u8 = 0x07;
+
+    /// Minimum speed
+    pub const MIN_SPEED: u8 = 0x08;
+
+    /// Holding voltage amplitude
+    pub const KVAL_HOLD: u8 = 0x09;
+
+    /// Constant speed voltage amplitude
+    pub const KVAL_RUN: u8 = 0x0A;
+
+    /// Acceleration starting voltage amplitude
+    pub const KVAL_ACC: u8 = 0x0B;
+
+    /// Deceleration starting voltage amplitude
+    pub const KVAL_DEC: u8 = 0x0C;
+
+    /// Overcurrent threshold
+    pub const OCD_TH: u8 = 0x13;
+
+    /// Stall detection threshold
+    pub const STALL_TH: u8 = 0x14;
+
+    /// Full-step speed
+    pub const FS_SPD: u8 = 0x15;
+
+    /// Step mode
+    pub const STEP_MODE: u8 = 0x16;
+
+    /// Alarm enable
+    pub const ALARM_EN: u8 = 0x17;
+
+    /// IC configuration
+    pub const CONFIG: u8 = 0x18;
+
+    /// Status
+    pub const STATUS: u8 = 0x19;
+
+    /// The length of a register in bytes
+    pub(super) fn len(address: u8) -> usize {
+        match address {
+            ABS_POS | MARK | SPEED => 3,
+            EL_POS | ACC | DEC | MAX_SPEED | MIN_SPEED | FS_SPD | CONFIG
+            | STATUS => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Bits of the L6470's status register
+///
+/// Please refer to the L6470 datasheet for more information.
+pub mod status {
+    /// Bridges are in high impedance state
+    pub const HIZ: u16 = 1 << 0;
+
+    /// Motor is moving (active low)
+    pub const BUSY: u16 = 1 << 1;
+
+    /// Switch input is closed
+    pub const SW_F: u16 = 1 << 2;
+
+    /// Switch input falling edge detected
+    pub const SW_EVN: u16 = 1 << 3;
+
+    /// Motor direction is forward
+    pub const DIR: u16 = 1 << 4;
+
+    /// Command could not be performed
+    pub const NOTPERF_CMD: u16 = 1 << 7;
+
+    /// Command does not exist
+    pub const WRONG_CMD: u16 = 1 << 8;
+
+    /// Undervoltage lockout (active low)
+    pub const UVLO: u16 = 1 << 9;
+
+    /// Thermal warning (active low)
+    pub const TH_WRN: u16 = 1 << 10;
+
+    /// Thermal shutdown (active low)
+    pub const TH_SD: u16 = 1 << 11;
+
+    /// Overcurrent detected (active low)
+    pub const OCD: u16 = 1 << 12;
+
+    /// Stall detected on bridge A (active low)
+    pub const STEP_LOSS_A: u16 = 1 << 13;
+
+    /// Stall detected on bridge B (active low)
+    pub const STEP_LOSS_B: u16 = 1 << 14;
+}
+
+mod command {
+    pub const SET_PARAM: u8 = 0x00;
+    pub const GET_PARAM: u8 = 0x20;
+    pub const RUN: u8 = 0x50;
+    pub const GO_TO: u8 = 0x60;
+    pub const RESET_POS: u8 = 0xD8;
+    pub const SOFT_STOP: u8 = 0xB0;
+    pub const HARD_STOP: u8 = 0xB8;
+    pub const GET_STATUS: u8 = 0xD0;
+}
+
+// `ABS_POS` and the `GoTo` target are 22-bit two's complement values.
+const POSITION_MASK: u32 = 0x3F_FFFF;
+
+// The speed argument of the `Run` command has 20 bits.
+const SPEED_MASK: u32 = 0x0F_FFFF;
+
+#[cfg(test)]
+mod tests {
+    use super::{check_status, status, Error};
+
+    #[test]
+    fn check_status_should_map_fault_flags() {
+        let ok = status::UVLO
+            | status::TH_WRN
+            | status::TH_SD
+            | status::OCD
+            | status::STEP_LOSS_A
+            | status::STEP_LOSS_B;
+
+        assert_eq!(check_status::<()>(ok), Ok(()));
+        assert_eq!(
+            check_status::<()>(ok & !status::OCD),
+            Err(Error::Overcurrent)
+        );
+        assert_eq!(
+            check_status::<()>(ok & !status::UVLO),
+            Err(Error::Undervoltage)
+        );
+        assert_eq!(
+            check_status::<()>(ok & !status::STEP_LOSS_B),
+            Err(Error::StepLoss)
+        );
+        assert_eq!(
+            check_status::<()>(ok | status::WRONG_CMD),
+            Err(Error::CommandRejected)
+        );
+    }
+}
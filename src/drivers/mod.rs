@@ -4,6 +4,48 @@
 //! by Stepper. Each sub-module is behind a feature gate, to allow users to only
 //! enable the drivers they actually need. By default, all drivers are enabled.
 
+use embedded_hal::digital::OutputPin;
+
+/// An output pin that can also be left floating
+///
+/// Some drivers have configuration inputs with three levels: low, high, and
+/// floating (high impedance). Those drivers require a pin that implements this
+/// trait.
+///
+/// embedded-hal doesn't provide an abstraction for this, so users need to
+/// implement this trait themselves, typically by switching the pin to input
+/// mode when it is supposed to float.
+pub trait TriStatePin: OutputPin {
+    /// Stop driving the pin, leaving it floating
+    fn set_floating(&mut self) -> Result<(), Self::Error>;
+}
+
+/// A tri-state pin that can also be pulled low weakly
+///
+/// Some drivers have configuration inputs with four levels: low, high,
+/// floating, and connected to ground through a resistor. Those drivers require
+/// a pin that implements this trait.
+///
+/// This typically requires an external resistor between the pin and ground, of
+/// the value that the driver's datasheet specifies. The pin is then left
+/// floating to select that level, while floating the input of the driver
+/// requires disconnecting the resistor in some way.
+pub trait QuadLevelPin: TriStatePin {
+    /// Connect the pin to ground through a resistor
+    fn set_pulled_low(&mut self) -> Result<(), Self::Error>;
+}
+
+/// A fault that is signaled without further details
+///
+/// Returned by [`ReadFault::read_fault`] for drivers that have a fault output,
+/// but no way of reporting which fault occurred. Please refer to the driver's
+/// datasheet for the conditions that activate the fault output.
+///
+/// [`ReadFault::read_fault`]: crate::traits::ReadFault::read_fault
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UnspecifiedFault;
+
 #[cfg(feature = "a4988")]
 pub mod a4988;
 
@@ -13,5 +55,59 @@ pub mod drv8825;
 #[cfg(feature = "stspin220")]
 pub mod stspin220;
 
-#[cfg(feature = "dq542ma")]
-pub mod dq542ma;
+#[cfg(feature = "tmc5160")]
+pub mod tmc5160;
+
+#[cfg(feature = "tmc2209")]
+pub mod tmc2209;
+
+#[cfg(feature = "l6470")]
+pub mod l6470;
+
+#[cfg(feature = "tmc2130")]
+pub mod tmc2130;
+
+#[cfg(feature = "generic")]
+pub mod generic;
+
+#[cfg(feature = "uln2003")]
+pub mod uln2003;
+
+#[cfg(feature = "amis30543")]
+pub mod amis30543;
+
+#[cfg(feature = "powerstep01")]
+pub mod powerstep01;
+
+#[cfg(feature = "drv8834")]
+pub mod drv8834;
+
+#[cfg(feature = "tb6600")]
+pub mod tb6600;
+
+#[cfg(feature = "mp6500")]
+pub mod mp6500;
+
+#[cfg(feature = "tmc5072")]
+pub mod tmc5072;
+
+#[cfg(feature = "tmc4361a")]
+pub mod tmc4361a;
+
+#[cfg(feature = "drv8880")]
+pub mod drv8880;
+
+#[cfg(feature = "a3967")]
+pub mod a3967;
+
+#[cfg(feature = "tb67s128ftg")]
+pub mod tb67s128ftg;
+
+#[cfg(feature = "l298n")]
+pub mod l298n;
+
+#[cfg(feature = "drv8426")]
+pub mod drv8426;
+
+#[cfg(feature = "tmc2660")]
+pub mod tmc2660;
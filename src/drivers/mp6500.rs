@@ -0,0 +1,449 @@
+//! MP6500 Driver
+//!
+//! Platform-agnostic driver API for the MP6500 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! This driver supports boards that provide digital current control through
+//! two pins, I1 and I2, like Pololu's MP6500 carrier with digital current
+//! control. See [`Current`].
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{OutputPin, PinState};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode8,
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableCurrentControl, EnableDirectionControl, EnableOutputControl,
+        EnableStepControl, EnableStepModeControl, SetCurrent, SetDirection,
+        SetOutputEnabled, SetStepMode, Step as StepTrait,
+    },
+};
+
+/// The MP6500 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`MP6500::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+pub struct MP6500<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir> {
+    enable: Enable,
+    sleep: Sleep,
+    ms1: MS1,
+    ms2: MS2,
+    i1: I1,
+    i2: I2,
+    step: Step,
+    dir: Dir,
+}
+
+impl MP6500<(), (), (), (), (), (), (), ()> {
+    /// Create a new instance of `MP6500`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            sleep: (),
+            ms1: (),
+            ms2: (),
+            i1: (),
+            i2: (),
+            step: (),
+            dir: (),
+        }
+    }
+
+    /// Create a new instance of `MP6500` with all pins connected
+    ///
+    /// This is an alternative to [`MP6500::new`], followed by enabling each
+    /// capability separately. Unlike [`Stepper`](crate::Stepper)'s
+    /// `enable_*` methods, this doesn't apply an initial step mode or
+    /// direction. Set those before the first motion.
+    pub fn from_pins<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir>(
+        pins: Pins<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir>,
+    ) -> MP6500<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir> {
+        MP6500 {
+            enable: pins.enable,
+            sleep: pins.sleep,
+            ms1: pins.ms1,
+            ms2: pins.ms2,
+            i1: pins.i1,
+            i2: pins.i2,
+            step: pins.step,
+            dir: pins.dir,
+        }
+    }
+}
+
+impl Default for MP6500<(), (), (), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir, OutputPinError>
+    EnableOutputControl<Enable>
+    for MP6500<(), Sleep, MS1, MS2, I1, I2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl = MP6500<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        MP6500 {
+            enable,
+            sleep: self.sleep,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            i1: self.i1,
+            i2: self.i2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+/// The pins connected to the MP6500
+///
+/// Passed to [`MP6500::from_pins`], to connect all pins at once.
+pub struct Pins<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir> {
+    /// The pin connected to ENABLE
+    pub enable: Enable,
+
+    /// The pin connected to SLEEP
+    pub sleep: Sleep,
+
+    /// The pin connected to MS1
+    pub ms1: MS1,
+
+    /// The pin connected to MS2
+    pub ms2: MS2,
+
+    /// The pin connected to I1
+    pub i1: I1,
+
+    /// The pin connected to I2
+    pub i2: I2,
+
+    /// The pin connected to STEP
+    pub step: Step,
+
+    /// The pin connected to DIR
+    pub dir: Dir,
+}
+
+/// A `MP6500` with all pins connected
+///
+/// Uses one type for all pins. This works well with the type-erased pins that
+/// many HALs provide, and saves naming every pin type separately. See
+/// [`MP6500::from_pins`].
+pub type AllPins<Output> =
+    MP6500<Output, Output, Output, Output, Output, Output, Output, Output>;
+
+impl<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir, OutputPinError>
+    SetOutputEnabled for MP6500<Enable, Sleep, MS1, MS2, I1, I2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // EN is active high.
+        self.enable.set_state(PinState::from(enabled))
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir, OutputPinError>
+    EnableStepModeControl<(MS1, MS2)>
+    for MP6500<Enable, (), (), (), I1, I2, Step, Dir>
+where
+    MS1: OutputPin<Error = OutputPinError>,
+    MS2: OutputPin<Error = OutputPinError>,
+{
+    type WithStepModeControl = MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        (ms1, ms2): (MS1, MS2),
+    ) -> Self::WithStepModeControl {
+        MP6500 {
+            enable: self.enable,
+            sleep: self.sleep,
+            ms1,
+            ms2,
+            i1: self.i1,
+            i2: self.i2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir> DisableStepModeControl
+    for MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>
+{
+    type WithoutStepModeControl = MP6500<Enable, (), (), (), I1, I2, Step, Dir>;
+    type Resources = (MS1, MS2);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            MP6500 {
+                enable: self.enable,
+                sleep: self.sleep,
+                ms1: (),
+                ms2: (),
+                i1: self.i1,
+                i2: self.i2,
+                step: self.step,
+                dir: self.dir,
+            },
+            (self.ms1, self.ms2),
+        )
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir, OutputPinError> SetStepMode
+    for MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>
+where
+    MS1: OutputPin<Error = OutputPinError>,
+    MS2: OutputPin<Error = OutputPinError>,
+{
+    // Timing Characteristics, MP6500 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Error = OutputPinError;
+    type StepMode = StepMode8;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use PinState::*;
+        use StepMode8::*;
+        let (ms1, ms2) = match step_mode {
+            Full => (Low, Low),
+            M2 => (High, Low),
+            M4 => (Low, High),
+            M8 => (High, High),
+        };
+
+        // Set mode signals.
+        self.ms1.set_state(ms1)?;
+        self.ms2.set_state(ms2)?;
+
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        // The MP6500 doesn't need to be reset to apply a new step mode.
+        Ok(())
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir, OutputPinError>
+    EnableCurrentControl<(I1, I2)>
+    for MP6500<Enable, (), MS1, MS2, (), (), Step, Dir>
+where
+    I1: OutputPin<Error = OutputPinError>,
+    I2: OutputPin<Error = OutputPinError>,
+{
+    type WithCurrentControl = MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>;
+
+    fn enable_current_control(
+        self,
+        (i1, i2): (I1, I2),
+    ) -> Self::WithCurrentControl {
+        MP6500 {
+            enable: self.enable,
+            sleep: self.sleep,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            i1,
+            i2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir, OutputPinError> SetCurrent
+    for MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>
+where
+    I1: OutputPin<Error = OutputPinError>,
+    I2: OutputPin<Error = OutputPinError>,
+{
+    type Current = Current;
+    type Error = OutputPinError;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        use PinState::*;
+        let (i1, i2) = match current {
+            Current::Quarter => (Low, Low),
+            Current::Half => (High, Low),
+            Current::ThreeQuarters => (Low, High),
+            Current::Full => (High, High),
+        };
+
+        self.i1.set_state(i1)?;
+        self.i2.set_state(i2)?;
+
+        Ok(())
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir, OutputPinError>
+    EnableDirectionControl<Dir>
+    for MP6500<Enable, (), MS1, MS2, I1, I2, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl = MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        MP6500 {
+            enable: self.enable,
+            sleep: self.sleep,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            i1: self.i1,
+            i2: self.i2,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir> DisableDirectionControl
+    for MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>
+{
+    type WithoutDirectionControl =
+        MP6500<Enable, (), MS1, MS2, I1, I2, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            MP6500 {
+                enable: self.enable,
+                sleep: self.sleep,
+                ms1: self.ms1,
+                ms2: self.ms2,
+                i1: self.i1,
+                i2: self.i2,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir, OutputPinError> SetDirection
+    for MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // Timing Characteristics, MP6500 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(200);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir, OutputPinError>
+    EnableStepControl<Step> for MP6500<Enable, (), MS1, MS2, I1, I2, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        MP6500 {
+            enable: self.enable,
+            sleep: self.sleep,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            i1: self.i1,
+            i2: self.i2,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir> DisableStepControl
+    for MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>
+{
+    type WithoutStepControl = MP6500<Enable, (), MS1, MS2, I1, I2, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            MP6500 {
+                enable: self.enable,
+                sleep: self.sleep,
+                ms1: self.ms1,
+                ms2: self.ms2,
+                i1: self.i1,
+                i2: self.i2,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, MS1, MS2, I1, I2, Step, Dir, OutputPinError> StepTrait
+    for MP6500<Enable, (), MS1, MS2, I1, I2, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Timing Characteristics, MP6500 datasheet
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(1000);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+/// The current limit of the MP6500, as selected by the I1 and I2 pins
+///
+/// Given as a fraction of the board's maximum current limit. Please refer to
+/// the documentation of your board for the actual currents.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Current {
+    /// A quarter of the maximum current (I1 low, I2 low)
+    Quarter,
+
+    /// Half of the maximum current (I1 high, I2 low)
+    Half,
+
+    /// Three quarters of the maximum current (I1 low, I2 high)
+    ThreeQuarters,
+
+    /// The maximum current (I1 high, I2 high)
+    Full,
+}
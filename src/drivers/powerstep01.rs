@@ -0,0 +1,654 @@
+//! PowerSTEP01 Driver
+//!
+//! Platform-agnostic driver API for the PowerSTEP01 motion controller. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The PowerSTEP01 has an integrated motion engine, which this driver uses to
+//! provide motion control capability natively, without requiring the software
+//! fallback from the [`motion_control`](crate::motion_control) module.
+//!
+//! Alternatively, the PowerSTEP01 can be used in step-clock mode, where it is
+//! controlled through its STCK pin, like a regular STEP/DIR driver. This makes
+//! it possible to use the software fallback instead. See [`PowerSTEP01`] for
+//! details.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::{
+    digital::{ErrorType, OutputPin},
+    spi::SpiDevice,
+};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    error::{ErasedError, ErrorKind},
+    traits::{
+        DisableStepControl, EnableMotionControl, EnableStepControl,
+        MotionControl, ReadVelocity, SetDirection, Step as StepTrait,
+    },
+    Direction,
+};
+
+/// The PowerSTEP01 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`PowerSTEP01::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// Motion control is enabled by passing an SPI device to
+/// [`Stepper::enable_motion_control`]. The PowerSTEP01 expects the chip select
+/// line to be released after every byte, so every byte is sent as a separate
+/// SPI transaction. Daisy-chained devices are not supported.
+///
+/// To use step-clock mode instead of the integrated motion engine, pass the
+/// STCK pin to [`Stepper::enable_step_control`] after enabling motion control.
+/// The PowerSTEP01 has no DIR pin. The direction is selected by sending the
+/// StepClock command over SPI, which also enters step-clock mode.
+///
+/// The PowerSTEP01 latches its fault flags, including the undervoltage flag,
+/// which is set after power-up. Call [`PowerSTEP01::status`] once after
+/// power-up, to clear those flags. Otherwise the first call to
+/// [`MotionControl::update`] will report an error.
+///
+/// [`Stepper::enable_motion_control`]: crate::Stepper::enable_motion_control
+/// [`Stepper::enable_step_control`]: crate::Stepper::enable_step_control
+pub struct PowerSTEP01<Spi, Step> {
+    interface: Interface<Spi>,
+    step: Step,
+}
+
+impl PowerSTEP01<(), ()> {
+    /// Create a new instance of `PowerSTEP01`
+    pub fn new() -> Self {
+        Self {
+            interface: Interface { spi: () },
+            step: (),
+        }
+    }
+}
+
+impl Default for PowerSTEP01<(), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Spi, Step> PowerSTEP01<Spi, Step>
+where
+    Spi: SpiDevice,
+{
+    /// Write a value to a parameter register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers. Depending on the register, only the lower one, two, or three
+    /// bytes of the value are sent.
+    pub fn set_param(
+        &mut self,
+        address: u8,
+        value: u32,
+    ) -> Result<(), Spi::Error> {
+        self.interface.send(command::SET_PARAM | address)?;
+        self.interface.send_value(value, register::len(address))?;
+        Ok(())
+    }
+
+    /// Read a value from a parameter register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn get_param(&mut self, address: u8) -> Result<u32, Spi::Error> {
+        self.interface.send(command::GET_PARAM | address)?;
+        self.interface.send_value(0, register::len(address))
+    }
+
+    /// Read the status register and clear its fault flags
+    ///
+    /// See the [`status`] module for the meaning of the individual bits.
+    pub fn status(&mut self) -> Result<u16, Spi::Error> {
+        self.interface.send(command::GET_STATUS)?;
+        let status = self.interface.send_value(0, 2)?;
+        Ok(status as u16)
+    }
+
+    /// Run the motor at a constant speed
+    ///
+    /// The speed is given in the PowerSTEP01's `SPEED` unit. The motor keeps
+    /// running, until it is stopped.
+    pub fn run(
+        &mut self,
+        direction: Direction,
+        speed: u32,
+    ) -> Result<(), Spi::Error> {
+        self.interface
+            .send(command::RUN | direction_bit(direction))?;
+        self.interface.send_value(speed & SPEED_MASK, 3)?;
+        Ok(())
+    }
+
+    /// Decelerate the motor and stop it
+    pub fn soft_stop(&mut self) -> Result<(), Spi::Error> {
+        self.interface.send(command::SOFT_STOP)?;
+        Ok(())
+    }
+
+    /// Stop the motor immediately
+    pub fn hard_stop(&mut self) -> Result<(), Spi::Error> {
+        self.interface.send(command::HARD_STOP)?;
+        Ok(())
+    }
+}
+
+impl<Spi, const TIMER_HZ: u32> EnableMotionControl<Spi, TIMER_HZ>
+    for PowerSTEP01<(), ()>
+where
+    Spi: SpiDevice,
+{
+    type WithMotionControl = PowerSTEP01<Spi, ()>;
+
+    fn enable_motion_control(self, spi: Spi) -> Self::WithMotionControl {
+        PowerSTEP01 {
+            interface: Interface { spi },
+            step: self.step,
+        }
+    }
+}
+
+impl<Spi, Step> MotionControl for PowerSTEP01<Spi, Step>
+where
+    Spi: SpiDevice,
+{
+    /// The maximum velocity, in the PowerSTEP01's `MAX_SPEED` unit
+    type Velocity = u16;
+    type Error = Error<Spi::Error>;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.set_param(register::MAX_SPEED, max_velocity.into())
+            .map_err(Error::Spi)?;
+
+        self.interface.send(command::GO_TO).map_err(Error::Spi)?;
+        self.interface
+            .send_value(target_step as u32 & POSITION_MASK, 3)
+            .map_err(Error::Spi)?;
+
+        Ok(())
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        if step == 0 {
+            self.interface
+                .send(command::RESET_POS)
+                .map_err(Error::Spi)?;
+        } else {
+            self.set_param(register::ABS_POS, step as u32 & POSITION_MASK)
+                .map_err(Error::Spi)?;
+        }
+
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        let status = self.status().map_err(Error::Spi)?;
+        check_status(status)?;
+
+        // The BUSY flag is active low.
+        Ok(status & status::BUSY == 0)
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        // `ABS_POS` is a 22-bit two's complement value. Sign-extend it.
+        let position = self.get_param(register::ABS_POS).map_err(Error::Spi)?;
+        Ok(((position << 10) as i32) >> 10)
+    }
+
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: Self::Velocity,
+    ) -> Result<(), Self::Error> {
+        // Positions wrap around after 22 bits, and `GoTo` takes the shortest
+        // path, so we can't just move to a distant position. Use `Run`
+        // instead. `SPEED` has 10 more fractional bits than `MAX_SPEED`.
+        self.run(direction, u32::from(velocity) << 10)
+            .map_err(Error::Spi)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        self.soft_stop().map_err(Error::Spi)
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        self.hard_stop().map_err(Error::Spi)
+    }
+}
+
+impl<Spi, Step> ReadVelocity for PowerSTEP01<Spi, Step>
+where
+    Spi: SpiDevice,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        // `SPEED` has 10 more fractional bits than `MAX_SPEED`.
+        let speed = self.get_param(register::SPEED).map_err(Error::Spi)?;
+        Ok((speed >> 10) as u16)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Spi, Step> crate::traits::RegisterWaker for PowerSTEP01<Spi, Step> {
+    fn register_waker(&mut self, waker: &core::task::Waker) {
+        // The PowerSTEP01 signals the end of a motion through its BUSY output,
+        // but we don't have access to that. All we can do is keep polling.
+        waker.wake_by_ref();
+    }
+}
+
+impl<Spi, Step, OutputPinError> EnableStepControl<Step> for PowerSTEP01<Spi, ()>
+where
+    Spi: SpiDevice,
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = PowerSTEP01<Spi, StepClock<Step>>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        PowerSTEP01 {
+            interface: self.interface,
+            step: StepClock(step),
+        }
+    }
+}
+
+impl<Spi, Step> DisableStepControl for PowerSTEP01<Spi, StepClock<Step>> {
+    type WithoutStepControl = PowerSTEP01<Spi, ()>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            PowerSTEP01 {
+                interface: self.interface,
+                step: (),
+            },
+            self.step.0,
+        )
+    }
+}
+
+impl<Spi, Step, OutputPinError> SetDirection
+    for PowerSTEP01<Spi, StepClock<Step>>
+where
+    Spi: SpiDevice,
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // The StepClock command takes effect as soon as it has been sent.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Dir = Interface<Spi>;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.interface)
+    }
+}
+
+impl<Spi, Step, OutputPinError> StepTrait for PowerSTEP01<Spi, StepClock<Step>>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Chosen conservatively. The PowerSTEP01 samples STCK with its internal
+    // oscillator, so the pulse needs to be longer than a few clock cycles.
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(1000);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step.0)
+    }
+}
+
+/// The STCK pin of the PowerSTEP01, as used in step-clock mode
+///
+/// Created by [`PowerSTEP01`]'s implementation of [`EnableStepControl`].
+pub struct StepClock<Pin>(Pin);
+
+/// The SPI interface of the PowerSTEP01
+///
+/// Also serves as the DIR pin in step-clock mode. Setting it high sends the
+/// StepClock command with the forward direction, setting it low sends it with
+/// the backward direction.
+pub struct Interface<Spi> {
+    spi: Spi,
+}
+
+impl<Spi> Interface<Spi>
+where
+    Spi: SpiDevice,
+{
+    fn send_value(
+        &mut self,
+        value: u32,
+        len: usize,
+    ) -> Result<u32, Spi::Error> {
+        let mut reply = 0;
+
+        for &byte in &value.to_be_bytes()[4 - len..] {
+            reply = reply << 8 | self.send(byte)? as u32;
+        }
+
+        Ok(reply)
+    }
+
+    fn send(&mut self, byte: u8) -> Result<u8, Spi::Error> {
+        let mut buffer = [byte];
+        self.spi.transfer_in_place(&mut buffer)?;
+        Ok(buffer[0])
+    }
+}
+
+impl<Spi> ErrorType for Interface<Spi>
+where
+    Spi: SpiDevice,
+{
+    type Error = DirError<Spi::Error>;
+}
+
+impl<Spi> OutputPin for Interface<Spi>
+where
+    Spi: SpiDevice,
+{
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.send(command::STEP_CLOCK | direction_bit(Direction::Forward))
+            .map_err(DirError)?;
+        Ok(())
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.send(command::STEP_CLOCK | direction_bit(Direction::Backward))
+            .map_err(DirError)?;
+        Ok(())
+    }
+}
+
+/// An error that can occur while using the PowerSTEP01
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<SpiError> {
+    /// Error while communicating over SPI
+    Spi(SpiError),
+
+    /// The PowerSTEP01 detected an overcurrent
+    Overcurrent,
+
+    /// The PowerSTEP01 shut down due to overtemperature
+    ThermalShutdown,
+
+    /// The supply voltage dropped below the undervoltage lockout threshold
+    Undervoltage,
+
+    /// The PowerSTEP01 detected a stall
+    StepLoss,
+
+    /// The PowerSTEP01 rejected a command
+    ///
+    /// This happens, if the command doesn't exist, or can't be performed in
+    /// the current state, for example when writing `ABS_POS` while the motor
+    /// is moving.
+    CommandRejected,
+}
+
+impl<SpiError> From<Error<SpiError>> for ErasedError {
+    fn from(err: Error<SpiError>) -> Self {
+        match err {
+            Error::Spi(_) => ErasedError::new(ErrorKind::Communication),
+            Error::Overcurrent => {
+                ErasedError::new(ErrorKind::Driver).with_context("overcurrent")
+            }
+            Error::ThermalShutdown => ErasedError::new(ErrorKind::Driver)
+                .with_context("thermal shutdown"),
+            Error::Undervoltage => {
+                ErasedError::new(ErrorKind::Driver).with_context("undervoltage")
+            }
+            Error::StepLoss => ErasedError::new(ErrorKind::StepLoss),
+            Error::CommandRejected => ErasedError::new(ErrorKind::Driver)
+                .with_context("command rejected"),
+        }
+    }
+}
+
+/// An SPI error that occurred while selecting the direction
+///
+/// Returned by [`Interface`], when it is used as the DIR pin.
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DirError<SpiError>(pub SpiError);
+
+impl<SpiError> embedded_hal::digital::Error for DirError<SpiError>
+where
+    SpiError: core::fmt::Debug,
+{
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+impl<SpiError> From<DirError<SpiError>> for ErasedError {
+    fn from(_: DirError<SpiError>) -> Self {
+        ErasedError::new(ErrorKind::Communication)
+            .with_context("selecting direction")
+    }
+}
+
+fn check_status<SpiError>(status: u16) -> Result<(), Error<SpiError>> {
+    // Most of the fault flags are active low.
+    if status & status::OCD == 0 {
+        return Err(Error::Overcurrent);
+    }
+    if status & status::TH_STATUS_MASK >= status::TH_STATUS_BRIDGE_SHUTDOWN {
+        return Err(Error::ThermalShutdown);
+    }
+    if status & status::UVLO == 0 {
+        return Err(Error::Undervoltage);
+    }
+    if status & (status::STALL_A | status::STALL_B)
+        != status::STALL_A | status::STALL_B
+    {
+        return Err(Error::StepLoss);
+    }
+    if status & status::CMD_ERROR != 0 {
+        return Err(Error::CommandRejected);
+    }
+
+    Ok(())
+}
+
+fn direction_bit(direction: Direction) -> u8 {
+    match direction {
+        Direction::Forward => 1,
+        Direction::Backward => 0,
+    }
+}
+
+/// Addresses of the PowerSTEP01's parameter registers
+///
+/// Please refer to the PowerSTEP01 datasheet for more information on the
+/// registers.
+pub mod register {
+    /// Current position
+    pub const ABS_POS: u8 = 0x01;
+
+    /// Electrical position
+    pub const EL_POS: u8 = 0x02;
+
+    /// Mark position
+    pub const MARK: u8 = 0x03;
+
+    /// Current speed
+    pub const SPEED: u8 = 0x04;
+
+    /// Acceleration
+    pub const ACC: u8 = 0x05;
+
+    /// Deceleration
+    pub const DEC: u8 = 0x06;
+
+    /// Maximum speed
+    pub const MAX_SPEED: u8 = 0x07;
+
+    /// Minimum speed
+    pub const MIN_SPEED: u8 = 0x08;
+
+    /// Holding voltage amplitude (voltage mode) or reference (current mode)
+    pub const KVAL_HOLD: u8 = 0x09;
+
+    /// Constant speed voltage amplitude (voltage mode) or reference (current
+    /// mode)
+    pub const KVAL_RUN: u8 = 0x0A;
+
+    /// Acceleration voltage amplitude (voltage mode) or reference (current
+    /// mode)
+    pub const KVAL_ACC: u8 = 0x0B;
+
+    /// Deceleration voltage amplitude (voltage mode) or reference (current
+    /// mode)
+    pub const KVAL_DEC: u8 = 0x0C;
+
+    /// Overcurrent threshold
+    pub const OCD_TH: u8 = 0x13;
+
+    /// Stall detection threshold
+    pub const STALL_TH: u8 = 0x14;
+
+    /// Full-step speed
+    pub const FS_SPD: u8 = 0x15;
+
+    /// Step mode
+    pub const STEP_MODE: u8 = 0x16;
+
+    /// Alarm enable
+    pub const ALARM_EN: u8 = 0x17;
+
+    /// Gate driver configuration
+    pub const GATECFG1: u8 = 0x18;
+
+    /// Gate driver configuration
+    pub const GATECFG2: u8 = 0x19;
+
+    /// IC configuration
+    pub const CONFIG: u8 = 0x1A;
+
+    /// Status
+    pub const STATUS: u8 = 0x1B;
+
+    /// The length of a register in bytes
+    pub(super) fn len(address: u8) -> usize {
+        match address {
+            ABS_POS | MARK | SPEED => 3,
+            EL_POS | ACC | DEC | MAX_SPEED | MIN_SPEED | FS_SPD | GATECFG1
+            | CONFIG | STATUS => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Bits of the PowerSTEP01's status register
+///
+/// Please refer to the PowerSTEP01 datasheet for more information.
+pub mod status {
+    /// Bridges are in high impedance state
+    pub const HIZ: u16 = 1 << 0;
+
+    /// Motor is moving (active low)
+    pub const BUSY: u16 = 1 << 1;
+
+    /// Switch input is closed
+    pub const SW_F: u16 = 1 << 2;
+
+    /// Switch input falling edge detected
+    pub const SW_EVN: u16 = 1 << 3;
+
+    /// Motor direction is forward
+    pub const DIR: u16 = 1 << 4;
+
+    /// Command could not be performed or does not exist
+    pub const CMD_ERROR: u16 = 1 << 7;
+
+    /// Device is in step-clock mode
+    pub const STCK_MOD: u16 = 1 << 8;
+
+    /// Undervoltage lockout (active low)
+    pub const UVLO: u16 = 1 << 9;
+
+    /// ADC undervoltage (active low)
+    pub const UVLO_ADC: u16 = 1 << 10;
+
+    /// Thermal status (normal, warning, bridge shutdown, device shutdown)
+    pub const TH_STATUS_MASK: u16 = 0b11 << 11;
+
+    /// Overcurrent detected (active low)
+    pub const OCD: u16 = 1 << 13;
+
+    /// Stall detected on bridge A (active low)
+    pub const STALL_A: u16 = 1 << 14;
+
+    /// Stall detected on bridge B (active low)
+    pub const STALL_B: u16 = 1 << 15;
+
+    pub(super) const TH_STATUS_BRIDGE_SHUTDOWN: u16 = 0b10 << 11;
+}
+
+mod command {
+    pub const SET_PARAM: u8 = 0x00;
+    pub const GET_PARAM: u8 = 0x20;
+    pub const RUN: u8 = 0x50;
+    pub const STEP_CLOCK: u8 = 0x58;
+    pub const GO_TO: u8 = 0x60;
+    pub const RESET_POS: u8 = 0xD8;
+    pub const SOFT_STOP: u8 = 0xB0;
+    pub const HARD_STOP: u8 = 0xB8;
+    pub const GET_STATUS: u8 = 0xD0;
+}
+
+// `ABS_POS` and the `GoTo` target are 22-bit two's complement values.
+const POSITION_MASK: u32 = 0x3F_FFFF;
+
+// The speed argument of the `Run` command has 20 bits.
+const SPEED_MASK: u32 = 0x0F_FFFF;
+
+#[cfg(test)]
+mod tests {
+    use super::{check_status, status, Error};
+
+    #[test]
+    fn check_status_should_map_fault_flags() {
+        let ok = status::UVLO
+            | status::UVLO_ADC
+            | status::OCD
+            | status::STALL_A
+            | status::STALL_B;
+
+        assert_eq!(check_status::<()>(ok), Ok(()));
+        assert_eq!(check_status::<()>(ok | 0b01 << 11), Ok(()));
+        assert_eq!(
+            check_status::<()>(ok | 0b10 << 11),
+            Err(Error::ThermalShutdown)
+        );
+        assert_eq!(
+            check_status::<()>(ok & !status::STALL_A),
+            Err(Error::StepLoss)
+        );
+        assert_eq!(
+            check_status::<()>(ok | status::CMD_ERROR),
+            Err(Error::CommandRejected)
+        );
+    }
+}
@@ -11,15 +11,19 @@
 
 use core::convert::Infallible;
 
-use embedded_hal::digital::{OutputPin, PinState};
+use embedded_hal::digital::{InputPin, OutputPin, PinState};
 use fugit::NanosDurationU32 as Nanoseconds;
 
 use crate::{
+    drivers::UnspecifiedFault,
     step_mode::StepMode256,
     traits::{
-        EnableDirectionControl, EnableStepControl, EnableStepModeControl,
-        SetDirection, SetStepMode, Step,
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableOutputControl, EnableStepControl,
+        EnableStepModeControl, ReadFault, SetDirection, SetOutputEnabled,
+        SetPowerMode, SetStepMode, Step,
     },
+    PowerMode,
 };
 
 /// The STSPIN220 driver API
@@ -27,6 +31,10 @@ use crate::{
 /// Users are not expected to use this API directly, except to create an
 /// instance using [`STSPIN220::new`]. Please check out
 /// [`Stepper`](crate::Stepper) instead.
+///
+/// The STBY/RESET pin is used for both step mode control and sleep control.
+/// Sleep control becomes available once step mode control has been enabled,
+/// without a separate call to `enable_sleep_control`.
 pub struct STSPIN220<
     EnableFault,
     StandbyReset,
@@ -41,6 +49,7 @@ pub struct STSPIN220<
     mode2: Mode2,
     step_mode3: StepMode3,
     dir_mode4: DirMode4,
+    step_mode: StepMode256,
 }
 
 impl STSPIN220<(), (), (), (), (), ()> {
@@ -53,10 +62,99 @@ impl STSPIN220<(), (), (), (), (), ()> {
             mode2: (),
             step_mode3: (),
             dir_mode4: (),
+            step_mode: StepMode256::Full,
         }
     }
 }
 
+impl Default for STSPIN220<(), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        EnableFault,
+        StandbyReset,
+        Mode1,
+        Mode2,
+        StepMode3,
+        DirMode4,
+        OutputPinError,
+    > EnableOutputControl<EnableFault>
+    for STSPIN220<(), StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+where
+    EnableFault: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl =
+        STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>;
+
+    fn enable_output_control(
+        self,
+        enable_fault: EnableFault,
+    ) -> Self::WithOutputControl {
+        STSPIN220 {
+            enable_fault,
+            standby_reset: self.standby_reset,
+            mode1: self.mode1,
+            mode2: self.mode2,
+            step_mode3: self.step_mode3,
+            dir_mode4: self.dir_mode4,
+            step_mode: self.step_mode,
+        }
+    }
+}
+
+impl<
+        EnableFault,
+        StandbyReset,
+        Mode1,
+        Mode2,
+        StepMode3,
+        DirMode4,
+        OutputPinError,
+    > SetOutputEnabled
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+where
+    EnableFault: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // EN/FAULT is active high.
+        self.enable_fault.set_state(PinState::from(enabled))
+    }
+}
+
+impl<
+        EnableFault,
+        StandbyReset,
+        Mode1,
+        Mode2,
+        StepMode3,
+        DirMode4,
+        PinError,
+    > ReadFault
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+where
+    EnableFault: OutputPin<Error = PinError> + InputPin<Error = PinError>,
+{
+    type Fault = UnspecifiedFault;
+    type Error = PinError;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        // The STSPIN220 signals a fault by pulling EN/FAULT low. This can only
+        // be detected while the outputs are enabled, and requires the pin to
+        // be configured as open-drain, so it's not driving against the
+        // driver.
+        if self.enable_fault.is_low()? {
+            return Ok(Some(UnspecifiedFault));
+        }
+
+        Ok(None)
+    }
+}
+
 impl<
         EnableFault,
         StandbyReset,
@@ -88,10 +186,37 @@ where
             mode2,
             step_mode3: self.step_mode3,
             dir_mode4: self.dir_mode4,
+            step_mode: self.step_mode,
         }
     }
 }
 
+impl<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+    DisableStepModeControl
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+{
+    type WithoutStepModeControl =
+        STSPIN220<EnableFault, (), (), (), StepMode3, DirMode4>;
+    type Resources = (StandbyReset, Mode1, Mode2);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            STSPIN220 {
+                enable_fault: self.enable_fault,
+                standby_reset: (),
+                mode1: (),
+                mode2: (),
+                step_mode3: self.step_mode3,
+                dir_mode4: self.dir_mode4,
+                step_mode: self.step_mode,
+            },
+            (self.standby_reset, self.mode1, self.mode2),
+        )
+    }
+}
+
 impl<
         EnableFault,
         StandbyReset,
@@ -100,31 +225,17 @@ impl<
         StepMode3,
         DirMode4,
         OutputPinError,
-    > SetStepMode
-    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+    > STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
 where
-    StandbyReset: OutputPin<Error = OutputPinError>,
     Mode1: OutputPin<Error = OutputPinError>,
     Mode2: OutputPin<Error = OutputPinError>,
     StepMode3: OutputPin<Error = OutputPinError>,
     DirMode4: OutputPin<Error = OutputPinError>,
 {
-    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(1_000);
-    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(100_000);
-
-    type Error = OutputPinError;
-    type StepMode = StepMode256;
-
-    fn apply_mode_config(
-        &mut self,
-        step_mode: Self::StepMode,
-    ) -> Result<(), Self::Error> {
-        // Force driver into standby mode.
-        self.standby_reset.set_low()?;
-
+    fn set_mode_signals(&mut self) -> Result<(), OutputPinError> {
         use PinState::*;
         use StepMode256::*;
-        let (mode1, mode2, mode3, mode4) = match step_mode {
+        let (mode1, mode2, mode3, mode4) = match self.step_mode {
             Full => (Low, Low, Low, Low),
             M2 => (High, Low, High, Low),
             M4 => (Low, High, Low, High),
@@ -144,6 +255,80 @@ where
 
         Ok(())
     }
+}
+
+impl<
+        EnableFault,
+        StandbyReset,
+        Mode1,
+        Mode2,
+        StepMode3,
+        DirMode4,
+        OutputPinError,
+    > SetPowerMode
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+where
+    StandbyReset: OutputPin<Error = OutputPinError>,
+    Mode1: OutputPin<Error = OutputPinError>,
+    Mode2: OutputPin<Error = OutputPinError>,
+    StepMode3: OutputPin<Error = OutputPinError>,
+    DirMode4: OutputPin<Error = OutputPinError>,
+{
+    // Leaving standby mode is the same as re-enabling the driver after setting
+    // the step mode, so the same hold time applies.
+    const WAKE_UP_TIME: Nanoseconds = <Self as SetStepMode>::HOLD_TIME;
+
+    type Error = OutputPinError;
+
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error> {
+        match mode {
+            PowerMode::Sleep => {
+                self.standby_reset.set_low()?;
+
+                // The step mode is latched again when leaving standby mode.
+                // MODE3 and MODE4 are shared with STEP and DIR, so we need to
+                // restore the step mode signals now, to give them enough
+                // setup time.
+                self.set_mode_signals()
+            }
+            PowerMode::Normal => self.standby_reset.set_high(),
+        }
+    }
+}
+
+impl<
+        EnableFault,
+        StandbyReset,
+        Mode1,
+        Mode2,
+        StepMode3,
+        DirMode4,
+        OutputPinError,
+    > SetStepMode
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+where
+    StandbyReset: OutputPin<Error = OutputPinError>,
+    Mode1: OutputPin<Error = OutputPinError>,
+    Mode2: OutputPin<Error = OutputPinError>,
+    StepMode3: OutputPin<Error = OutputPinError>,
+    DirMode4: OutputPin<Error = OutputPinError>,
+{
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(1_000);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(100_000);
+
+    type Error = OutputPinError;
+    type StepMode = StepMode256;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        // Force driver into standby mode.
+        self.standby_reset.set_low()?;
+
+        self.step_mode = step_mode;
+        self.set_mode_signals()
+    }
 
     fn enable_driver(&mut self) -> Result<(), Self::Error> {
         // Leave standby mode.
@@ -178,10 +363,37 @@ where
             mode2: self.mode2,
             step_mode3: self.step_mode3,
             dir_mode4,
+            step_mode: self.step_mode,
         }
     }
 }
 
+impl<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+    DisableDirectionControl
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+{
+    type WithoutDirectionControl =
+        STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, ()>;
+    type Resources = DirMode4;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            STSPIN220 {
+                enable_fault: self.enable_fault,
+                standby_reset: self.standby_reset,
+                mode1: self.mode1,
+                mode2: self.mode2,
+                step_mode3: self.step_mode3,
+                dir_mode4: (),
+                step_mode: self.step_mode,
+            },
+            self.dir_mode4,
+        )
+    }
+}
+
 impl<
         EnableFault,
         StandbyReset,
@@ -232,10 +444,37 @@ where
             mode2: self.mode2,
             step_mode3,
             dir_mode4: self.dir_mode4,
+            step_mode: self.step_mode,
         }
     }
 }
 
+impl<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+    DisableStepControl
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+{
+    type WithoutStepControl =
+        STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, (), DirMode4>;
+    type Resources = StepMode3;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            STSPIN220 {
+                enable_fault: self.enable_fault,
+                standby_reset: self.standby_reset,
+                mode1: self.mode1,
+                mode2: self.mode2,
+                step_mode3: (),
+                dir_mode4: self.dir_mode4,
+                step_mode: self.step_mode,
+            },
+            self.step_mode3,
+        )
+    }
+}
+
 impl<
         EnableFault,
         StandbyReset,
@@ -0,0 +1,190 @@
+//! TB6600 Driver
+//!
+//! Platform-agnostic driver API for the TB6600 stepper motor driver module. Can
+//! be used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The microstepping mode of the TB6600 is selected using DIP switches, so it
+//! can't be controlled by software.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{OutputPin, PinState};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::traits::{
+    DisableDirectionControl, DisableStepControl, EnableDirectionControl,
+    EnableOutputControl, EnableStepControl, SetDirection, SetOutputEnabled,
+    Step as StepTrait,
+};
+
+/// The TB6600 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`TB6600::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// This driver assumes the TB6600's inputs are wired as common anode, meaning
+/// `PUL+`, `DIR+`, and `ENA+` are connected to the supply voltage, and the
+/// microcontroller drives `PUL-`, `DIR-`, and `ENA-`.
+pub struct TB6600<Enable, Step, Dir> {
+    enable: Enable,
+    step: Step,
+    dir: Dir,
+}
+
+impl TB6600<(), (), ()> {
+    /// Create a new instance of `TB6600`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            step: (),
+            dir: (),
+        }
+    }
+}
+
+impl Default for TB6600<(), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Step, Dir, Enable, OutputPinError> EnableOutputControl<Enable>
+    for TB6600<(), Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl = TB6600<Enable, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        TB6600 {
+            enable,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Step, Dir, OutputPinError> SetOutputEnabled
+    for TB6600<Enable, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // The ENA input of the TB6600 disables the motor outputs, when current
+        // flows through it. With common anode wiring, this is the case when
+        // `ENA-` is low, so we drive `ENA-` high to enable the outputs, and low
+        // to disable them. The outputs are also enabled, if `ENA-` is not
+        // connected.
+        self.enable.set_state(PinState::from(enabled))
+    }
+}
+
+impl<Enable, Step, Dir, OutputPinError> EnableDirectionControl<Dir>
+    for TB6600<Enable, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl = TB6600<Enable, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        TB6600 {
+            enable: self.enable,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Enable, Step, Dir> DisableDirectionControl for TB6600<Enable, Step, Dir> {
+    type WithoutDirectionControl = TB6600<Enable, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            TB6600 {
+                enable: self.enable,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, Step, Dir, OutputPinError> SetDirection
+    for TB6600<Enable, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // The TB6600 modules on the market use optocouplers on their inputs, which
+    // are much slower than the driver IC itself. These are the timings given in
+    // the manuals of those modules.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(5000);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, Step, Dir, OutputPinError> EnableStepControl<Step>
+    for TB6600<Enable, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = TB6600<Enable, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        TB6600 {
+            enable: self.enable,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Step, Dir> DisableStepControl for TB6600<Enable, Step, Dir> {
+    type WithoutStepControl = TB6600<Enable, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            TB6600 {
+                enable: self.enable,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, Step, Dir, OutputPinError> StepTrait for TB6600<Enable, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // See comment on `SetDirection` implementation.
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(2500);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
@@ -0,0 +1,392 @@
+//! TB67S128FTG Driver
+//!
+//! Platform-agnostic driver API for the TB67S128FTG stepper motor driver. Can
+//! be used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The TB67S128FTG can be configured in two ways, selected by the level of its
+//! MODE pin:
+//!
+//! - In standalone mode, the microstepping mode is selected using the DMODE0,
+//!   DMODE1, and DMODE2 pins, up to 1/32 steps. Step mode control is enabled
+//!   by passing those pins.
+//! - In serial mode, the microstepping mode, up to 1/128 steps, and the motor
+//!   current are configured through the serial interface, which is driven by
+//!   an SPI device. Step mode control is enabled by passing a [`Serial`]
+//!   instance. This also provides current control capability.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::{
+    digital::{OutputPin, PinState},
+    spi::SpiDevice,
+};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::{StepMode128, StepMode32},
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableStepControl, EnableStepModeControl,
+        SetCurrent, SetDirection, SetStepMode, Step as StepTrait,
+    },
+};
+
+/// The TB67S128FTG driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`TB67S128FTG::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+pub struct TB67S128FTG<Mode, Step, Dir> {
+    mode: Mode,
+    step: Step,
+    dir: Dir,
+}
+
+impl TB67S128FTG<(), (), ()> {
+    /// Create a new instance of `TB67S128FTG`
+    pub fn new() -> Self {
+        Self {
+            mode: (),
+            step: (),
+            dir: (),
+        }
+    }
+}
+
+impl Default for TB67S128FTG<(), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<DMode0, DMode1, DMode2, Step, Dir, OutputPinError>
+    EnableStepModeControl<(DMode0, DMode1, DMode2)>
+    for TB67S128FTG<(), Step, Dir>
+where
+    DMode0: OutputPin<Error = OutputPinError>,
+    DMode1: OutputPin<Error = OutputPinError>,
+    DMode2: OutputPin<Error = OutputPinError>,
+{
+    type WithStepModeControl = TB67S128FTG<(DMode0, DMode1, DMode2), Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        mode: (DMode0, DMode1, DMode2),
+    ) -> Self::WithStepModeControl {
+        TB67S128FTG {
+            mode,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<DMode0, DMode1, DMode2, Step, Dir> DisableStepModeControl
+    for TB67S128FTG<(DMode0, DMode1, DMode2), Step, Dir>
+{
+    type WithoutStepModeControl = TB67S128FTG<(), Step, Dir>;
+    type Resources = (DMode0, DMode1, DMode2);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            TB67S128FTG {
+                mode: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            self.mode,
+        )
+    }
+}
+
+impl<DMode0, DMode1, DMode2, Step, Dir, OutputPinError> SetStepMode
+    for TB67S128FTG<(DMode0, DMode1, DMode2), Step, Dir>
+where
+    DMode0: OutputPin<Error = OutputPinError>,
+    DMode1: OutputPin<Error = OutputPinError>,
+    DMode2: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, TB67S128FTG datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(1000);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(1000);
+
+    type Error = OutputPinError;
+    type StepMode = StepMode32;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use PinState::*;
+        use StepMode32::*;
+
+        // All DMODE pins low would put the driver into standby mode, so that
+        // combination is never used here.
+        let (dmode0, dmode1, dmode2) = match step_mode {
+            Full => (High, Low, Low),
+            M2 => (Low, High, Low),
+            M4 => (High, High, Low),
+            M8 => (High, Low, High),
+            M16 => (Low, High, High),
+            M32 => (High, High, High),
+        };
+
+        // Set mode signals.
+        self.mode.0.set_state(dmode0)?;
+        self.mode.1.set_state(dmode1)?;
+        self.mode.2.set_state(dmode2)?;
+
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        // The TB67S128FTG doesn't need to be reset to apply a new step mode.
+        Ok(())
+    }
+}
+
+impl<Spi, Step, Dir> EnableStepModeControl<Serial<Spi>>
+    for TB67S128FTG<(), Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    type WithStepModeControl = TB67S128FTG<Serial<Spi>, Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        serial: Serial<Spi>,
+    ) -> Self::WithStepModeControl {
+        TB67S128FTG {
+            mode: serial,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Spi, Step, Dir> DisableStepModeControl
+    for TB67S128FTG<Serial<Spi>, Step, Dir>
+{
+    type WithoutStepModeControl = TB67S128FTG<(), Step, Dir>;
+    type Resources = Serial<Spi>;
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            TB67S128FTG {
+                mode: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            self.mode,
+        )
+    }
+}
+
+impl<Spi, Step, Dir> SetStepMode for TB67S128FTG<Serial<Spi>, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    // Timing Requirements, TB67S128FTG datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(1000);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(1000);
+
+    type Error = Spi::Error;
+    type StepMode = StepMode128;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use StepMode128::*;
+        let dmode = match step_mode {
+            Full => 0b0001,
+            M2 => 0b0010,
+            M4 => 0b0011,
+            M8 => 0b0101,
+            M16 => 0b0110,
+            M32 => 0b0111,
+            M64 => 0b1000,
+            M128 => 0b1001,
+        };
+
+        self.mode.dmode = dmode;
+        self.mode.send()
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        // The new step mode is applied as soon as the serial data is latched.
+        Ok(())
+    }
+}
+
+impl<Spi, Step, Dir> SetCurrent for TB67S128FTG<Serial<Spi>, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    /// The motor current, in sixteenths of the full-scale current
+    ///
+    /// The full-scale current is set using the VREF pin. Values above 15 are
+    /// capped.
+    type Current = u8;
+    type Error = Spi::Error;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        self.mode.torque = current.min(TORQUE_MAX);
+        self.mode.send()
+    }
+}
+
+impl<Mode, Step, Dir, OutputPinError> EnableDirectionControl<Dir>
+    for TB67S128FTG<Mode, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl = TB67S128FTG<Mode, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        TB67S128FTG {
+            mode: self.mode,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Mode, Step, Dir> DisableDirectionControl for TB67S128FTG<Mode, Step, Dir> {
+    type WithoutDirectionControl = TB67S128FTG<Mode, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            TB67S128FTG {
+                mode: self.mode,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Mode, Step, Dir, OutputPinError> SetDirection
+    for TB67S128FTG<Mode, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, TB67S128FTG datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(1000);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Mode, Step, Dir, OutputPinError> EnableStepControl<Step>
+    for TB67S128FTG<Mode, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = TB67S128FTG<Mode, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        TB67S128FTG {
+            mode: self.mode,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Mode, Step, Dir> DisableStepControl for TB67S128FTG<Mode, Step, Dir> {
+    type WithoutStepControl = TB67S128FTG<Mode, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            TB67S128FTG {
+                mode: self.mode,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Mode, Step, Dir, OutputPinError> StepTrait for TB67S128FTG<Mode, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Timing Requirements, TB67S128FTG datasheet
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(300);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+/// The serial interface of the TB67S128FTG
+///
+/// Wraps the SPI device that drives the serial interface. The SPI device's chip
+/// select needs to be connected to the TB67S128FTG's latch input, so the
+/// configuration is applied at the end of each transfer.
+///
+/// The TB67S128FTG's serial interface is write-only. This is why the current
+/// configuration is kept here, so it can be sent as a whole, whenever any part
+/// of it changes.
+pub struct Serial<Spi> {
+    spi: Spi,
+    dmode: u8,
+    torque: u8,
+}
+
+impl<Spi> Serial<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// Create a new instance of `Serial`
+    ///
+    /// Nothing is sent to the TB67S128FTG until the step mode or the current is
+    /// set.
+    pub fn new(spi: Spi) -> Self {
+        Self {
+            spi,
+            // Full step mode
+            dmode: 0b0001,
+            torque: TORQUE_MAX,
+        }
+    }
+
+    fn send(&mut self) -> Result<(), Spi::Error> {
+        let frame = (self.torque as u16) << TORQUE_SHIFT
+            | (self.dmode as u16) << DMODE_SHIFT;
+        self.spi.write(&frame.to_be_bytes())
+    }
+}
+
+const DMODE_SHIFT: u16 = 0;
+const TORQUE_SHIFT: u16 = 4;
+const TORQUE_MAX: u8 = 15;
@@ -0,0 +1,456 @@
+//! TMC2130 Driver
+//!
+//! Platform-agnostic driver API for the TMC2130 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! This driver assumes the TMC2130 is used in SPI mode, which is the only mode
+//! that provides access to the full range of microstepping resolutions. Step
+//! mode control is enabled by passing the SPI device, which also provides
+//! access to the chopper and current configuration.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::{
+    digital::{OutputPin, PinState},
+    spi::SpiDevice,
+};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode256,
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableOutputControl, EnableStepControl,
+        EnableStepModeControl, SetCurrent, SetDirection, SetOutputEnabled,
+        SetStepMode, Step as StepTrait,
+    },
+};
+
+/// The TMC2130 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`TMC2130::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// Please note that the TMC2130 powers up with its motor outputs disabled.
+/// Before the first motion, the chopper and the motor current need to be
+/// configured, using [`TMC2130::set_chopper`] and [`Stepper::set_current`].
+///
+/// [`Stepper::set_current`]: crate::Stepper::set_current
+pub struct TMC2130<Enable, Spi, Step, Dir> {
+    enable: Enable,
+    spi: Spi,
+    step: Step,
+    dir: Dir,
+}
+
+impl TMC2130<(), (), (), ()> {
+    /// Create a new instance of `TMC2130`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            spi: (),
+            step: (),
+            dir: (),
+        }
+    }
+}
+
+impl Default for TMC2130<(), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> EnableOutputControl<Enable>
+    for TMC2130<(), Spi, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl = TMC2130<Enable, Spi, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        TMC2130 {
+            enable,
+            spi: self.spi,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> SetOutputEnabled
+    for TMC2130<Enable, Spi, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // DRV_ENN is active low.
+        self.enable.set_state(PinState::from(!enabled))
+    }
+}
+
+impl<Enable, Spi, Step, Dir> TMC2130<Enable, Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    /// Write a value to a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn write_register(
+        &mut self,
+        address: u8,
+        value: u32,
+    ) -> Result<(), Spi::Error> {
+        self.transfer(address | WRITE, value)?;
+        Ok(())
+    }
+
+    /// Read a value from a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn read_register(&mut self, address: u8) -> Result<u32, Spi::Error> {
+        // The TMC2130 returns the data requested by a read access with the
+        // next datagram. We need to send the read access twice.
+        self.transfer(address, 0)?;
+        self.transfer(address, 0)
+    }
+
+    /// Configure the chopper
+    ///
+    /// `toff` sets the off time and enables the driver, if it is not zero
+    /// (`0` to `15`). `hstrt` and `hend` configure the hysteresis (`0` to `7`
+    /// and `0` to `15`, respectively), while `tbl` selects the comparator blank
+    /// time (`0` to `3`). Please refer to the datasheet for recommended values.
+    ///
+    /// All other fields of `CHOPCONF`, including the microstepping resolution,
+    /// are not affected.
+    pub fn set_chopper(
+        &mut self,
+        toff: u8,
+        hstrt: u8,
+        hend: u8,
+        tbl: u8,
+    ) -> Result<(), Spi::Error> {
+        let chopper = u32::from(toff & 0x0f)
+            | u32::from(hstrt & 0x07) << 4
+            | u32::from(hend & 0x0f) << 7
+            | u32::from(tbl & 0x03) << 15;
+
+        let chopconf = self.read_register(register::CHOPCONF)?;
+        let chopconf = chopconf & !CHOPCONF_CHOPPER_MASK | chopper;
+        self.write_register(register::CHOPCONF, chopconf)
+    }
+
+    fn transfer(&mut self, address: u8, data: u32) -> Result<u32, Spi::Error> {
+        let data = data.to_be_bytes();
+        let mut datagram = [address, data[0], data[1], data[2], data[3]];
+
+        self.spi.transfer_in_place(&mut datagram)?;
+
+        // The first byte of the reply contains the SPI status. We're only
+        // interested in the data.
+        let [_, data @ ..] = datagram;
+        Ok(u32::from_be_bytes(data))
+    }
+}
+
+impl<Enable, Spi, Step, Dir> SetCurrent for TMC2130<Enable, Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    type Current = Current;
+    type Error = Spi::Error;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        let Current {
+            run,
+            hold,
+            hold_delay,
+        } = current;
+
+        let value = u32::from(hold & 0x1f)
+            | u32::from(run & 0x1f) << 8
+            | u32::from(hold_delay & 0x0f) << 16;
+        self.write_register(register::IHOLD_IRUN, value)
+    }
+}
+
+impl<Enable, Spi, Step, Dir> EnableStepModeControl<Spi>
+    for TMC2130<Enable, (), Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    type WithStepModeControl = TMC2130<Enable, Spi, Step, Dir>;
+
+    fn enable_step_mode_control(self, spi: Spi) -> Self::WithStepModeControl {
+        TMC2130 {
+            enable: self.enable,
+            spi,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Spi, Step, Dir> DisableStepModeControl
+    for TMC2130<Enable, Spi, Step, Dir>
+{
+    type WithoutStepModeControl = TMC2130<Enable, (), Step, Dir>;
+    type Resources = Spi;
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            TMC2130 {
+                enable: self.enable,
+                spi: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            self.spi,
+        )
+    }
+}
+
+impl<Enable, Spi, Step, Dir> SetStepMode for TMC2130<Enable, Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    // The new configuration takes effect as soon as it has been written.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Error = Spi::Error;
+    type StepMode = StepMode256;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use StepMode256::*;
+        let mres = match step_mode {
+            M256 => 0,
+            M128 => 1,
+            M64 => 2,
+            M32 => 3,
+            M16 => 4,
+            M8 => 5,
+            M4 => 6,
+            M2 => 7,
+            Full => 8,
+        };
+
+        let chopconf = self.read_register(register::CHOPCONF)?;
+        let chopconf =
+            chopconf & !CHOPCONF_MRES_MASK | mres << CHOPCONF_MRES_SHIFT;
+        self.write_register(register::CHOPCONF, chopconf)
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> EnableDirectionControl<Dir>
+    for TMC2130<Enable, Spi, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl = TMC2130<Enable, Spi, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        TMC2130 {
+            enable: self.enable,
+            spi: self.spi,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Enable, Spi, Step, Dir> DisableDirectionControl
+    for TMC2130<Enable, Spi, Step, Dir>
+{
+    type WithoutDirectionControl = TMC2130<Enable, Spi, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            TMC2130 {
+                enable: self.enable,
+                spi: self.spi,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> SetDirection
+    for TMC2130<Enable, Spi, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // STEP/DIR Timing, TMC2130 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(20);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> EnableStepControl<Step>
+    for TMC2130<Enable, Spi, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = TMC2130<Enable, Spi, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        TMC2130 {
+            enable: self.enable,
+            spi: self.spi,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Spi, Step, Dir> DisableStepControl
+    for TMC2130<Enable, Spi, Step, Dir>
+{
+    type WithoutStepControl = TMC2130<Enable, Spi, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            TMC2130 {
+                enable: self.enable,
+                spi: self.spi,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> StepTrait
+    for TMC2130<Enable, Spi, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // STEP/DIR Timing, TMC2130 datasheet
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(100);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+/// The motor current of the TMC2130
+///
+/// Used with [`Stepper::set_current`].
+///
+/// [`Stepper::set_current`]: crate::Stepper::set_current
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Current {
+    /// The current while the motor is running
+    ///
+    /// Given in 32nds of the full-scale current (`0` to `31`).
+    pub run: u8,
+
+    /// The current while the motor is at standstill
+    ///
+    /// Given in 32nds of the full-scale current (`0` to `31`).
+    pub hold: u8,
+
+    /// The delay before the current is reduced to `hold`
+    ///
+    /// Defines the number of clock cycles (in units of 2^18) over which the
+    /// current is reduced to `hold`, after the motor has stopped (`0` to
+    /// `15`).
+    pub hold_delay: u8,
+}
+
+/// Addresses of the TMC2130's registers
+///
+/// Please refer to the TMC2130 datasheet for more information on the
+/// registers.
+pub mod register {
+    /// Global configuration flags
+    pub const GCONF: u8 = 0x00;
+
+    /// Global status flags
+    pub const GSTAT: u8 = 0x01;
+
+    /// Reads the state of all input pins available
+    pub const IOIN: u8 = 0x04;
+
+    /// Driver current control
+    pub const IHOLD_IRUN: u8 = 0x10;
+
+    /// Delay before power down in standstill
+    pub const TPOWERDOWN: u8 = 0x11;
+
+    /// Actual measured time between two microsteps
+    pub const TSTEP: u8 = 0x12;
+
+    /// Upper velocity for StealthChop voltage PWM mode
+    pub const TPWMTHRS: u8 = 0x13;
+
+    /// Lower threshold velocity for switching on CoolStep and StallGuard
+    pub const TCOOLTHRS: u8 = 0x14;
+
+    /// Velocity threshold for switching into a different chopper mode
+    pub const THIGH: u8 = 0x15;
+
+    /// Microstep counter
+    pub const MSCNT: u8 = 0x6A;
+
+    /// Chopper and driver configuration
+    pub const CHOPCONF: u8 = 0x6C;
+
+    /// CoolStep and StallGuard configuration
+    pub const COOLCONF: u8 = 0x6D;
+
+    /// Driver status flags and StallGuard value
+    pub const DRV_STATUS: u8 = 0x6F;
+
+    /// StealthChop voltage PWM mode configuration
+    pub const PWMCONF: u8 = 0x70;
+}
+
+const WRITE: u8 = 0x80;
+
+// TOFF, HSTRT, HEND, and TBL
+const CHOPCONF_CHOPPER_MASK: u32 = 0x0f | 0x07 << 4 | 0x0f << 7 | 0x03 << 15;
+
+const CHOPCONF_MRES_SHIFT: u32 = 24;
+const CHOPCONF_MRES_MASK: u32 = 0x0f << CHOPCONF_MRES_SHIFT;
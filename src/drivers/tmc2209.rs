@@ -0,0 +1,846 @@
+//! TMC2209 Driver
+//!
+//! Platform-agnostic driver API for the TMC2209 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The TMC2209 can be used in standalone mode, where the microstepping mode is
+//! configured using the MS1/MS2 pins, or it can be controlled through UART.
+//! UART control provides access to the full range of the chip's configuration,
+//! including motor current, microstepping resolution of up to 256 microsteps,
+//! StealthChop/SpreadCycle selection, and StallGuard. See
+//! [`TMC2209::enable_uart_control`].
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{InputPin, OutputPin, PinState};
+use embedded_hal_stable::serial::{Read, Write};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    drivers::UnspecifiedFault,
+    error::{ErasedError, ErrorKind},
+    step_mode::{StepMode256, StepMode64},
+    traits::{
+        DetectStall, DisableDirectionControl, DisableStepControl,
+        DisableStepModeControl, EnableDirectionControl, EnableFaultDetection,
+        EnableOutputControl, EnableStepControl, EnableStepModeControl,
+        ReadFault, SetCurrent, SetDirection, SetOutputEnabled, SetStepMode,
+        Step as StepTrait,
+    },
+};
+
+/// The TMC2209 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`TMC2209::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+pub struct TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir> {
+    enable: Enable,
+    diag: Diag,
+    uart: Uart,
+    address: u8,
+    ms1: MS1,
+    ms2: MS2,
+    step: Step,
+    dir: Dir,
+}
+
+impl TMC2209<(), (), (), (), (), (), ()> {
+    /// Create a new instance of `TMC2209`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            diag: (),
+            uart: (),
+            address: 0,
+            ms1: (),
+            ms2: (),
+            step: (),
+            dir: (),
+        }
+    }
+}
+
+impl Default for TMC2209<(), (), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, OutputPinError>
+    EnableOutputControl<Enable> for TMC2209<(), Diag, Uart, MS1, MS2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl = TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        TMC2209 {
+            enable,
+            diag: self.diag,
+            uart: self.uart,
+            address: self.address,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, OutputPinError> SetOutputEnabled
+    for TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // ENN is active low.
+        self.enable.set_state(PinState::from(!enabled))
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, InputPinError>
+    EnableFaultDetection<Diag>
+    for TMC2209<Enable, (), Uart, MS1, MS2, Step, Dir>
+where
+    Diag: InputPin<Error = InputPinError>,
+{
+    type WithFaultDetection = TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>;
+
+    fn enable_fault_detection(self, diag: Diag) -> Self::WithFaultDetection {
+        TMC2209 {
+            enable: self.enable,
+            diag,
+            uart: self.uart,
+            address: self.address,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, InputPinError> ReadFault
+    for TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>
+where
+    Diag: InputPin<Error = InputPinError>,
+{
+    type Fault = UnspecifiedFault;
+    type Error = InputPinError;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        // DIAG is active high. Besides driver errors like overtemperature or a
+        // short, it also signals a stall, if StallGuard is configured.
+        if self.diag.is_high()? {
+            return Ok(Some(UnspecifiedFault));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, InputPinError> DetectStall
+    for TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>
+where
+    Diag: InputPin<Error = InputPinError>,
+{
+    type Error = InputPinError;
+
+    fn is_stalled(&mut self) -> Result<bool, Self::Error> {
+        // DIAG doesn't distinguish between a stall and a driver error. Either
+        // way, the motor isn't moving.
+        self.diag.is_high()
+    }
+}
+
+impl<Enable, Diag, Step, Dir> TMC2209<Enable, Diag, (), (), (), Step, Dir> {
+    /// Enable control of the driver through UART
+    ///
+    /// Takes the serial interface connected to the TMC2209's PDN_UART pin, and
+    /// the slave address of the TMC2209 (`0` to `3`), as configured by its
+    /// MS1/MS2 pins. Since those pins select the address when using UART,
+    /// they can't be used to control the step mode. Once UART control is
+    /// enabled, the step mode is set through UART instead, which supports
+    /// resolutions up to 256 microsteps.
+    ///
+    /// The TMC2209 uses a single-wire interface. If the RX and TX signals of
+    /// the serial interface are both connected to PDN_UART, every byte sent
+    /// will also be received. Use [`TMC2209::set_discard_echo`] in that case.
+    pub fn enable_uart_control<Serial>(
+        self,
+        serial: Serial,
+        address: u8,
+    ) -> TMC2209<Enable, Diag, Uart<Serial>, (), (), Step, Dir>
+    where
+        Serial: Read<u8> + Write<u8>,
+    {
+        TMC2209 {
+            enable: self.enable,
+            diag: self.diag,
+            uart: Uart {
+                serial,
+                discard_echo: false,
+            },
+            address: address & 0x03,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Diag, Step, Dir, Serial>
+    TMC2209<Enable, Diag, Uart<Serial>, (), (), Step, Dir>
+where
+    Serial: Read<u8> + Write<u8>,
+{
+    /// Configure whether to discard the echo of sent bytes
+    ///
+    /// Enable this, if the RX and TX signals of the serial interface are both
+    /// connected to the TMC2209's PDN_UART pin, so the serial interface
+    /// receives everything it sends.
+    pub fn set_discard_echo(&mut self, discard_echo: bool) {
+        self.uart.discard_echo = discard_echo;
+    }
+
+    /// Write a value to a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    #[allow(clippy::type_complexity)]
+    pub fn write_register(
+        &mut self,
+        address: u8,
+        value: u32,
+    ) -> Result<
+        (),
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>,
+    > {
+        let value = value.to_be_bytes();
+        let mut datagram = [
+            SYNC,
+            self.address,
+            address | WRITE,
+            value[0],
+            value[1],
+            value[2],
+            value[3],
+            0,
+        ];
+        datagram[7] = crc(&datagram[..7]);
+
+        self.uart.send(&datagram)
+    }
+
+    /// Read a value from a register
+    ///
+    /// Please note that this method blocks until the TMC2209 has replied. If
+    /// the TMC2209 doesn't reply, it will block forever.
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    #[allow(clippy::type_complexity)]
+    pub fn read_register(
+        &mut self,
+        address: u8,
+    ) -> Result<
+        u32,
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>,
+    > {
+        let mut request = [SYNC, self.address, address, 0];
+        request[3] = crc(&request[..3]);
+
+        self.uart.send(&request)?;
+
+        let mut reply = [0; 8];
+        for byte in &mut reply {
+            *byte =
+                nb::block!(self.uart.serial.read()).map_err(UartError::Read)?;
+        }
+
+        if reply[7] != crc(&reply[..7]) {
+            return Err(UartError::Crc);
+        }
+        if reply[2] != address {
+            return Err(UartError::UnexpectedReply);
+        }
+
+        Ok(u32::from_be_bytes([reply[3], reply[4], reply[5], reply[6]]))
+    }
+
+    /// Select SpreadCycle or StealthChop mode
+    ///
+    /// Enables SpreadCycle, if `spread_cycle` is `true`, StealthChop
+    /// otherwise.
+    #[allow(clippy::type_complexity)]
+    pub fn set_spread_cycle(
+        &mut self,
+        spread_cycle: bool,
+    ) -> Result<
+        (),
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>,
+    > {
+        self.modify_register(register::GCONF, |gconf| {
+            if spread_cycle {
+                gconf | GCONF_EN_SPREADCYCLE
+            } else {
+                gconf & !GCONF_EN_SPREADCYCLE
+            }
+        })
+    }
+
+    /// Set the StallGuard threshold
+    ///
+    /// The DIAG output signals a stall, if the StallGuard result falls below
+    /// twice this value.
+    #[allow(clippy::type_complexity)]
+    pub fn set_stallguard_threshold(
+        &mut self,
+        threshold: u8,
+    ) -> Result<
+        (),
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>,
+    > {
+        self.write_register(register::SGTHRS, u32::from(threshold))
+    }
+
+    /// Read the StallGuard result
+    ///
+    /// Higher values mean lower motor load.
+    #[allow(clippy::type_complexity)]
+    pub fn stallguard_result(
+        &mut self,
+    ) -> Result<
+        u16,
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>,
+    > {
+        let sg_result = self.read_register(register::SG_RESULT)?;
+        Ok((sg_result & 0x3ff) as u16)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn modify_register(
+        &mut self,
+        address: u8,
+        f: impl FnOnce(u32) -> u32,
+    ) -> Result<
+        (),
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>,
+    > {
+        let value = self.read_register(address)?;
+        self.write_register(address, f(value))
+    }
+}
+
+impl<Enable, Diag, Step, Dir, Serial> SetCurrent
+    for TMC2209<Enable, Diag, Uart<Serial>, (), (), Step, Dir>
+where
+    Serial: Read<u8> + Write<u8>,
+{
+    type Current = Current;
+    type Error =
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        let Current {
+            run,
+            hold,
+            hold_delay,
+        } = current;
+
+        let value = u32::from(hold & 0x1f)
+            | u32::from(run & 0x1f) << 8
+            | u32::from(hold_delay & 0x0f) << 16;
+        self.write_register(register::IHOLD_IRUN, value)
+    }
+}
+
+impl<Enable, Diag, MS1, MS2, Step, Dir, OutputPinError>
+    EnableStepModeControl<(MS1, MS2)>
+    for TMC2209<Enable, Diag, (), (), (), Step, Dir>
+where
+    MS1: OutputPin<Error = OutputPinError>,
+    MS2: OutputPin<Error = OutputPinError>,
+{
+    type WithStepModeControl = TMC2209<Enable, Diag, (), MS1, MS2, Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        (ms1, ms2): (MS1, MS2),
+    ) -> Self::WithStepModeControl {
+        TMC2209 {
+            enable: self.enable,
+            diag: self.diag,
+            uart: self.uart,
+            address: self.address,
+            ms1,
+            ms2,
+            step: self.step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Diag, MS1, MS2, Step, Dir> DisableStepModeControl
+    for TMC2209<Enable, Diag, (), MS1, MS2, Step, Dir>
+{
+    type WithoutStepModeControl = TMC2209<Enable, Diag, (), (), (), Step, Dir>;
+    type Resources = (MS1, MS2);
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            TMC2209 {
+                enable: self.enable,
+                diag: self.diag,
+                uart: self.uart,
+                address: self.address,
+                ms1: (),
+                ms2: (),
+                step: self.step,
+                dir: self.dir,
+            },
+            (self.ms1, self.ms2),
+        )
+    }
+}
+
+impl<Enable, Diag, MS1, MS2, Step, Dir, OutputPinError> SetStepMode
+    for TMC2209<Enable, Diag, (), MS1, MS2, Step, Dir>
+where
+    MS1: OutputPin<Error = OutputPinError>,
+    MS2: OutputPin<Error = OutputPinError>,
+{
+    // The mode pins are not latched, so there's no need to wait.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Error = PinStepModeError<OutputPinError>;
+    type StepMode = StepMode64;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        // Table 4: MS1, MS2 Configuration of Microstep Resolution (page 10)
+        // https://www.trinamic.com/fileadmin/assets/Products/ICs_Documents/TMC2209_Datasheet_V103.pdf
+        use PinState::*;
+        use StepMode64::*;
+        let (ms1, ms2) = match step_mode {
+            M8 => (Low, Low),
+            M16 => (High, High),
+            M32 => (High, Low),
+            M64 => (Low, High),
+            Full | M2 | M4 => {
+                return Err(PinStepModeError::Unsupported(step_mode))
+            }
+        };
+
+        self.ms1.set_state(ms1).map_err(PinStepModeError::Pin)?;
+        self.ms2.set_state(ms2).map_err(PinStepModeError::Pin)?;
+
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<Enable, Diag, Step, Dir, Serial> SetStepMode
+    for TMC2209<Enable, Diag, Uart<Serial>, (), (), Step, Dir>
+where
+    Serial: Read<u8> + Write<u8>,
+{
+    // The new configuration takes effect as soon as it has been written.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Error =
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>;
+    type StepMode = StepMode256;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use StepMode256::*;
+        let mres = match step_mode {
+            M256 => 0,
+            M128 => 1,
+            M64 => 2,
+            M32 => 3,
+            M16 => 4,
+            M8 => 5,
+            M4 => 6,
+            M2 => 7,
+            Full => 8,
+        };
+
+        // Make sure the step mode is taken from the register, not from the
+        // MS1/MS2 pins, which select the UART address.
+        self.modify_register(register::GCONF, |gconf| {
+            gconf | GCONF_MSTEP_REG_SELECT
+        })?;
+        self.modify_register(register::CHOPCONF, |chopconf| {
+            chopconf & !CHOPCONF_MRES_MASK | mres << CHOPCONF_MRES_SHIFT
+        })
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, OutputPinError>
+    EnableDirectionControl<Dir>
+    for TMC2209<Enable, Diag, Uart, MS1, MS2, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl =
+        TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        TMC2209 {
+            enable: self.enable,
+            diag: self.diag,
+            uart: self.uart,
+            address: self.address,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step: self.step,
+            dir,
+        }
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir> DisableDirectionControl
+    for TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>
+{
+    type WithoutDirectionControl =
+        TMC2209<Enable, Diag, Uart, MS1, MS2, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            TMC2209 {
+                enable: self.enable,
+                diag: self.diag,
+                uart: self.uart,
+                address: self.address,
+                ms1: self.ms1,
+                ms2: self.ms2,
+                step: self.step,
+                dir: (),
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, OutputPinError> SetDirection
+    for TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // Table: STEP/DIR Timing (page 76)
+    // https://www.trinamic.com/fileadmin/assets/Products/ICs_Documents/TMC2209_Datasheet_V103.pdf
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(20);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, OutputPinError>
+    EnableStepControl<Step> for TMC2209<Enable, Diag, Uart, MS1, MS2, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        TMC2209 {
+            enable: self.enable,
+            diag: self.diag,
+            uart: self.uart,
+            address: self.address,
+            ms1: self.ms1,
+            ms2: self.ms2,
+            step,
+            dir: self.dir,
+        }
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir> DisableStepControl
+    for TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>
+{
+    type WithoutStepControl = TMC2209<Enable, Diag, Uart, MS1, MS2, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            TMC2209 {
+                enable: self.enable,
+                diag: self.diag,
+                uart: self.uart,
+                address: self.address,
+                ms1: self.ms1,
+                ms2: self.ms2,
+                step: (),
+                dir: self.dir,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, Diag, Uart, MS1, MS2, Step, Dir, OutputPinError> StepTrait
+    for TMC2209<Enable, Diag, Uart, MS1, MS2, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Table: STEP/DIR Timing (page 76)
+    // https://www.trinamic.com/fileadmin/assets/Products/ICs_Documents/TMC2209_Datasheet_V103.pdf
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(100);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+/// The motor current of the TMC2209
+///
+/// Used with [`Stepper::set_current`].
+///
+/// [`Stepper::set_current`]: crate::Stepper::set_current
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Current {
+    /// The current while the motor is running
+    ///
+    /// Given in 32nds of the full-scale current (`0` to `31`).
+    pub run: u8,
+
+    /// The current while the motor is at standstill
+    ///
+    /// Given in 32nds of the full-scale current (`0` to `31`).
+    pub hold: u8,
+
+    /// The delay before the current is reduced to `hold`
+    ///
+    /// Defines the number of clock cycles (in units of 2^18) over which the
+    /// current is reduced to `hold`, after the motor has stopped (`0` to
+    /// `15`).
+    pub hold_delay: u8,
+}
+
+/// The serial interface used to control the TMC2209
+///
+/// Created by [`TMC2209::enable_uart_control`].
+pub struct Uart<Serial> {
+    serial: Serial,
+    discard_echo: bool,
+}
+
+impl<Serial> Uart<Serial>
+where
+    Serial: Read<u8> + Write<u8>,
+{
+    #[allow(clippy::type_complexity)]
+    fn send(
+        &mut self,
+        datagram: &[u8],
+    ) -> Result<
+        (),
+        UartError<<Serial as Read<u8>>::Error, <Serial as Write<u8>>::Error>,
+    > {
+        for &byte in datagram {
+            nb::block!(self.serial.write(byte)).map_err(UartError::Write)?;
+        }
+        nb::block!(self.serial.flush()).map_err(UartError::Write)?;
+
+        if self.discard_echo {
+            for _ in datagram {
+                nb::block!(self.serial.read()).map_err(UartError::Read)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An error that can occur while setting the step mode using the MS1/MS2 pins
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PinStepModeError<PinError> {
+    /// An error originated from using the [`OutputPin`] trait
+    Pin(PinError),
+
+    /// The step mode can't be configured using the MS1/MS2 pins
+    ///
+    /// Only 8, 16, 32, and 64 microsteps are available through the MS1/MS2
+    /// pins. All other step modes require UART control.
+    Unsupported(StepMode64),
+}
+
+/// An error that can occur while communicating with the TMC2209 through UART
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UartError<ReadError, WriteError> {
+    /// Error while reading from the serial interface
+    Read(ReadError),
+
+    /// Error while writing to the serial interface
+    Write(WriteError),
+
+    /// The CRC of a reply did not match its contents
+    Crc,
+
+    /// A reply did not refer to the register that was read
+    UnexpectedReply,
+}
+
+impl<PinError> From<PinStepModeError<PinError>> for ErasedError {
+    fn from(err: PinStepModeError<PinError>) -> Self {
+        match err {
+            PinStepModeError::Pin(_) => ErasedError::new(ErrorKind::Pin),
+            PinStepModeError::Unsupported(_) => {
+                ErasedError::new(ErrorKind::StepMode)
+                    .with_context("not supported by MS1/MS2 pins")
+            }
+        }
+    }
+}
+
+impl<ReadError, WriteError> From<UartError<ReadError, WriteError>>
+    for ErasedError
+{
+    fn from(err: UartError<ReadError, WriteError>) -> Self {
+        let context = match err {
+            UartError::Read(_) => "reading from UART",
+            UartError::Write(_) => "writing to UART",
+            UartError::Crc => "CRC mismatch",
+            UartError::UnexpectedReply => "unexpected reply",
+        };
+
+        ErasedError::new(ErrorKind::Communication).with_context(context)
+    }
+}
+
+/// Addresses of the TMC2209's registers
+///
+/// Please refer to the TMC2209 datasheet for more information on the
+/// registers.
+pub mod register {
+    /// Global configuration flags
+    pub const GCONF: u8 = 0x00;
+
+    /// Global status flags
+    pub const GSTAT: u8 = 0x01;
+
+    /// Interface transmission counter
+    pub const IFCNT: u8 = 0x02;
+
+    /// Driver current control
+    pub const IHOLD_IRUN: u8 = 0x10;
+
+    /// Delay before power down at standstill
+    pub const TPOWERDOWN: u8 = 0x11;
+
+    /// Measured time between two microsteps
+    pub const TSTEP: u8 = 0x12;
+
+    /// Lower velocity threshold for CoolStep and StallGuard output
+    pub const TCOOLTHRS: u8 = 0x14;
+
+    /// Velocity for motor movement through UART
+    pub const VACTUAL: u8 = 0x22;
+
+    /// StallGuard threshold
+    pub const SGTHRS: u8 = 0x40;
+
+    /// StallGuard result
+    pub const SG_RESULT: u8 = 0x41;
+
+    /// CoolStep configuration
+    pub const COOLCONF: u8 = 0x42;
+
+    /// Microstep counter
+    pub const MSCNT: u8 = 0x6A;
+
+    /// Chopper and driver configuration
+    pub const CHOPCONF: u8 = 0x6C;
+
+    /// Driver status flags
+    pub const DRV_STATUS: u8 = 0x6F;
+
+    /// StealthChop PWM configuration
+    pub const PWMCONF: u8 = 0x70;
+}
+
+const SYNC: u8 = 0x05;
+const WRITE: u8 = 0x80;
+
+const GCONF_EN_SPREADCYCLE: u32 = 1 << 2;
+const GCONF_MSTEP_REG_SELECT: u32 = 1 << 7;
+
+const CHOPCONF_MRES_SHIFT: u32 = 24;
+const CHOPCONF_MRES_MASK: u32 = 0x0f << CHOPCONF_MRES_SHIFT;
+
+// CRC8 with the polynomial x^8 + x^2 + x + 1, as specified in section 4.2 of
+// the datasheet.
+fn crc(datagram: &[u8]) -> u8 {
+    let mut crc = 0u8;
+
+    for &byte in datagram {
+        let mut byte = byte;
+        for _ in 0..8 {
+            if (crc >> 7) ^ (byte & 0x01) != 0 {
+                crc = (crc << 1) ^ 0x07;
+            } else {
+                crc <<= 1;
+            }
+            byte >>= 1;
+        }
+    }
+
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::crc;
+
+    #[test]
+    fn crc_should_match_datasheet_examples() {
+        // Read access requests for GCONF and IOIN, respectively.
+        assert_eq!(crc(&[0x05, 0x00, 0x00]), 0x48);
+        assert_eq!(crc(&[0x05, 0x00, 0x06]), 0x6f);
+    }
+}
@@ -0,0 +1,595 @@
+//! TMC2660 Driver
+//!
+//! Platform-agnostic driver API for the TMC2660 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The TMC2660 is controlled using its STEP and DIR pins, while its
+//! configuration, including the microstepping resolution, is done over SPI.
+//! Step mode control is enabled by passing the SPI device.
+//!
+//! The TMC2660's registers are write-only. Each write returns a status
+//! response instead, which contains status flags and, depending on the
+//! configuration, the microstep position or the StallGuard value. See
+//! [`TMC2660::status`].
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead. The methods specific to this
+//! driver are available through [`Stepper::driver_mut`].
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+//! [`Stepper::driver_mut`]: crate::Stepper::driver_mut
+
+use core::convert::Infallible;
+
+use embedded_hal::{
+    digital::{OutputPin, PinState},
+    spi::SpiDevice,
+};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode256,
+    traits::{
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableDirectionControl, EnableOutputControl, EnableStepControl,
+        EnableStepModeControl, ReadFault, SetCurrent, SetDirection,
+        SetOutputEnabled, SetStepMode, Step as StepTrait,
+    },
+};
+
+/// The TMC2660 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`TMC2660::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// Please note that the TMC2660 powers up with its motor outputs disabled.
+/// Before the first motion, the chopper and the motor current need to be
+/// configured, using [`TMC2660::set_chopper`] and [`Stepper::set_current`].
+///
+/// Since the TMC2660's registers can't be read back, this driver keeps a copy
+/// of each register. Any change is applied to that copy, which is then written
+/// as a whole.
+///
+/// [`Stepper::set_current`]: crate::Stepper::set_current
+pub struct TMC2660<Enable, Spi, Step, Dir> {
+    enable: Enable,
+    spi: Spi,
+    step: Step,
+    dir: Dir,
+    registers: Registers,
+}
+
+impl TMC2660<(), (), (), ()> {
+    /// Create a new instance of `TMC2660`
+    pub fn new() -> Self {
+        Self {
+            enable: (),
+            spi: (),
+            step: (),
+            dir: (),
+            registers: Registers {
+                drvctrl: register::DRVCTRL,
+                chopconf: register::CHOPCONF,
+                sgcsconf: register::SGCSCONF,
+                drvconf: register::DRVCONF,
+                status: Status::default(),
+            },
+        }
+    }
+}
+
+impl Default for TMC2660<(), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> EnableOutputControl<Enable>
+    for TMC2660<(), Spi, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type WithOutputControl = TMC2660<Enable, Spi, Step, Dir>;
+
+    fn enable_output_control(self, enable: Enable) -> Self::WithOutputControl {
+        TMC2660 {
+            enable,
+            spi: self.spi,
+            step: self.step,
+            dir: self.dir,
+            registers: self.registers,
+        }
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> SetOutputEnabled
+    for TMC2660<Enable, Spi, Step, Dir>
+where
+    Enable: OutputPin<Error = OutputPinError>,
+{
+    type Error = OutputPinError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        // ENN is active low.
+        self.enable.set_state(PinState::from(!enabled))
+    }
+}
+
+impl<Enable, Spi, Step, Dir> ReadFault for TMC2660<Enable, Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    type Fault = Status;
+    type Error = Spi::Error;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        let status = self.status()?;
+
+        if status.is_fault() {
+            return Ok(Some(status));
+        }
+
+        Ok(None)
+    }
+}
+
+impl<Enable, Spi, Step, Dir> TMC2660<Enable, Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    /// Configure the chopper
+    ///
+    /// `toff` sets the off time and enables the driver, if it is not zero
+    /// (`0` to `15`). `hstrt` and `hend` configure the hysteresis (`0` to `7`
+    /// and `0` to `15`, respectively), while `tbl` selects the comparator blank
+    /// time (`0` to `3`). Please refer to the datasheet for recommended values.
+    ///
+    /// All other fields of `CHOPCONF` are not affected.
+    pub fn set_chopper(
+        &mut self,
+        toff: u8,
+        hstrt: u8,
+        hend: u8,
+        tbl: u8,
+    ) -> Result<(), Spi::Error> {
+        let chopper = u32::from(toff & 0x0f)
+            | u32::from(hstrt & 0x07) << 4
+            | u32::from(hend & 0x0f) << 7
+            | u32::from(tbl & 0x03) << 15;
+
+        self.registers.chopconf =
+            self.registers.chopconf & !CHOPCONF_CHOPPER_MASK | chopper;
+        self.write_register(self.registers.chopconf)
+    }
+
+    /// Configure StallGuard
+    ///
+    /// `threshold` is the StallGuard threshold (`-64` to `63`). A higher value
+    /// makes StallGuard less sensitive. If `filter` is `true`, the StallGuard
+    /// value is only updated once every four full steps, which makes it more
+    /// precise.
+    pub fn set_stallguard(
+        &mut self,
+        threshold: i8,
+        filter: bool,
+    ) -> Result<(), Spi::Error> {
+        let stallguard = (threshold as u32 & 0x7f) << SGCSCONF_SGT_SHIFT
+            | u32::from(filter) << SGCSCONF_SFILT_SHIFT;
+
+        self.registers.sgcsconf =
+            self.registers.sgcsconf & !SGCSCONF_STALLGUARD_MASK | stallguard;
+        self.write_register(self.registers.sgcsconf)
+    }
+
+    /// Select which value is returned in the status response
+    ///
+    /// See [`Status::value`].
+    pub fn set_readout(&mut self, readout: Readout) -> Result<(), Spi::Error> {
+        let rdsel = match readout {
+            Readout::MicrostepPosition => 0b00,
+            Readout::StallGuard => 0b01,
+            Readout::StallGuardAndCoolStep => 0b10,
+        };
+
+        self.registers.drvconf = self.registers.drvconf & !DRVCONF_RDSEL_MASK
+            | rdsel << DRVCONF_RDSEL_SHIFT;
+        self.write_register(self.registers.drvconf)
+    }
+
+    /// Read the current status
+    ///
+    /// Rewrites `DRVCTRL`, which doesn't change its value, to get a fresh
+    /// status response.
+    pub fn status(&mut self) -> Result<Status, Spi::Error> {
+        self.write_register(self.registers.drvctrl)?;
+        Ok(self.registers.status)
+    }
+
+    /// Access the status response of the last write
+    ///
+    /// Doesn't communicate with the TMC2660. Use [`TMC2660::status`] to get a
+    /// fresh status response.
+    pub fn last_status(&self) -> Status {
+        self.registers.status
+    }
+
+    /// Write a value to a register
+    ///
+    /// The value must include the address bits of the register. See the
+    /// [`register`] module. Please note that the value written here is
+    /// overwritten, if any other method of this driver that changes the same
+    /// register is called later.
+    pub fn write_register(&mut self, value: u32) -> Result<(), Spi::Error> {
+        let data = (value & DATAGRAM_MASK).to_be_bytes();
+        let mut datagram = [data[1], data[2], data[3]];
+
+        self.spi.transfer_in_place(&mut datagram)?;
+
+        // The TMC2660 uses 20-bit datagrams. The response is shifted out
+        // first, so it ends up in the most significant bits of the 24 bits
+        // we've received.
+        let response =
+            u32::from_be_bytes([0, datagram[0], datagram[1], datagram[2]]) >> 4;
+        self.registers.status = Status::from_response(response);
+
+        Ok(())
+    }
+}
+
+impl<Enable, Spi, Step, Dir> SetCurrent for TMC2660<Enable, Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    /// The motor current, in 32nds of the full-scale current (`0` to `31`)
+    type Current = u8;
+    type Error = Spi::Error;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        self.registers.sgcsconf = self.registers.sgcsconf & !SGCSCONF_CS_MASK
+            | u32::from(current & 0x1f);
+        self.write_register(self.registers.sgcsconf)
+    }
+}
+
+impl<Enable, Spi, Step, Dir> EnableStepModeControl<Spi>
+    for TMC2660<Enable, (), Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    type WithStepModeControl = TMC2660<Enable, Spi, Step, Dir>;
+
+    fn enable_step_mode_control(self, spi: Spi) -> Self::WithStepModeControl {
+        TMC2660 {
+            enable: self.enable,
+            spi,
+            step: self.step,
+            dir: self.dir,
+            registers: self.registers,
+        }
+    }
+}
+
+impl<Enable, Spi, Step, Dir> DisableStepModeControl
+    for TMC2660<Enable, Spi, Step, Dir>
+{
+    type WithoutStepModeControl = TMC2660<Enable, (), Step, Dir>;
+    type Resources = Spi;
+
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources) {
+        (
+            TMC2660 {
+                enable: self.enable,
+                spi: (),
+                step: self.step,
+                dir: self.dir,
+                registers: self.registers,
+            },
+            self.spi,
+        )
+    }
+}
+
+impl<Enable, Spi, Step, Dir> SetStepMode for TMC2660<Enable, Spi, Step, Dir>
+where
+    Spi: SpiDevice,
+{
+    // The new configuration takes effect as soon as it has been written.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Error = Spi::Error;
+    type StepMode = StepMode256;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use StepMode256::*;
+        let mres = match step_mode {
+            M256 => 0,
+            M128 => 1,
+            M64 => 2,
+            M32 => 3,
+            M16 => 4,
+            M8 => 5,
+            M4 => 6,
+            M2 => 7,
+            Full => 8,
+        };
+
+        self.registers.drvctrl =
+            self.registers.drvctrl & !DRVCTRL_MRES_MASK | mres;
+        self.write_register(self.registers.drvctrl)
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> EnableDirectionControl<Dir>
+    for TMC2660<Enable, Spi, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl = TMC2660<Enable, Spi, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        TMC2660 {
+            enable: self.enable,
+            spi: self.spi,
+            step: self.step,
+            dir,
+            registers: self.registers,
+        }
+    }
+}
+
+impl<Enable, Spi, Step, Dir> DisableDirectionControl
+    for TMC2660<Enable, Spi, Step, Dir>
+{
+    type WithoutDirectionControl = TMC2660<Enable, Spi, Step, ()>;
+    type Resources = Dir;
+
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources) {
+        (
+            TMC2660 {
+                enable: self.enable,
+                spi: self.spi,
+                step: self.step,
+                dir: (),
+                registers: self.registers,
+            },
+            self.dir,
+        )
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> SetDirection
+    for TMC2660<Enable, Spi, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // STEP/DIR Timing, TMC2660 datasheet
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(20);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> EnableStepControl<Step>
+    for TMC2660<Enable, Spi, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = TMC2660<Enable, Spi, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        TMC2660 {
+            enable: self.enable,
+            spi: self.spi,
+            step,
+            dir: self.dir,
+            registers: self.registers,
+        }
+    }
+}
+
+impl<Enable, Spi, Step, Dir> DisableStepControl
+    for TMC2660<Enable, Spi, Step, Dir>
+{
+    type WithoutStepControl = TMC2660<Enable, Spi, (), Dir>;
+    type Resources = Step;
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        (
+            TMC2660 {
+                enable: self.enable,
+                spi: self.spi,
+                step: (),
+                dir: self.dir,
+                registers: self.registers,
+            },
+            self.step,
+        )
+    }
+}
+
+impl<Enable, Spi, Step, Dir, OutputPinError> StepTrait
+    for TMC2660<Enable, Spi, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // STEP/DIR Timing, TMC2660 datasheet
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(100);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+/// Selects the value that is returned in the status response
+///
+/// See [`TMC2660::set_readout`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Readout {
+    /// The position within the microstep table (`0` to `1023`)
+    MicrostepPosition,
+
+    /// The StallGuard value (`0` to `1023`)
+    StallGuard,
+
+    /// The upper 5 bits of the StallGuard value, and the current scaling
+    /// applied by CoolStep
+    StallGuardAndCoolStep,
+}
+
+/// The status response of the TMC2660
+///
+/// Returned by [`TMC2660::status`] and [`TMC2660::last_status`], and by
+/// [`ReadFault::read_fault`], if a fault flag is set.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Status {
+    /// The value selected by [`TMC2660::set_readout`]
+    ///
+    /// This is the microstep position by default.
+    pub value: u16,
+
+    /// The motor is at standstill
+    pub standstill: bool,
+
+    /// Open load detected on coil B
+    pub open_load_b: bool,
+
+    /// Open load detected on coil A
+    pub open_load_a: bool,
+
+    /// Short to ground detected on coil B
+    pub short_to_ground_b: bool,
+
+    /// Short to ground detected on coil A
+    pub short_to_ground_a: bool,
+
+    /// The overtemperature warning threshold has been exceeded
+    pub overtemperature_warning: bool,
+
+    /// The driver has been shut down due to overtemperature
+    pub overtemperature_shutdown: bool,
+
+    /// StallGuard has detected a stall
+    pub stall: bool,
+}
+
+impl Status {
+    /// Indicates whether any fault flag is set
+    ///
+    /// The overtemperature warning and the StallGuard flag are not considered
+    /// faults. The latter depends on the StallGuard configuration.
+    pub fn is_fault(&self) -> bool {
+        self.open_load_a
+            || self.open_load_b
+            || self.short_to_ground_a
+            || self.short_to_ground_b
+            || self.overtemperature_shutdown
+    }
+
+    fn from_response(response: u32) -> Self {
+        let flag = |bit: u32| response & 1 << bit != 0;
+
+        Self {
+            value: (response >> 10 & 0x3ff) as u16,
+            standstill: flag(7),
+            open_load_b: flag(6),
+            open_load_a: flag(5),
+            short_to_ground_b: flag(4),
+            short_to_ground_a: flag(3),
+            overtemperature_warning: flag(2),
+            overtemperature_shutdown: flag(1),
+            stall: flag(0),
+        }
+    }
+}
+
+/// Address bits of the TMC2660's registers
+///
+/// Each value written to the TMC2660 starts with the address bits of the
+/// register it is written to. The constants in this module contain those bits
+/// in their correct position, and can be combined with the register contents
+/// using a bitwise OR.
+///
+/// Please refer to the TMC2660 datasheet for more information on the
+/// registers.
+pub mod register {
+    /// Driver control (STEP/DIR mode)
+    pub const DRVCTRL: u32 = 0b000 << 17;
+
+    /// Chopper configuration
+    pub const CHOPCONF: u32 = 0b100 << 17;
+
+    /// CoolStep configuration
+    pub const SMARTEN: u32 = 0b101 << 17;
+
+    /// StallGuard and current configuration
+    pub const SGCSCONF: u32 = 0b110 << 17;
+
+    /// Driver configuration
+    pub const DRVCONF: u32 = 0b111 << 17;
+}
+
+// Shadow copies of the write-only registers, and the last status response
+struct Registers {
+    drvctrl: u32,
+    chopconf: u32,
+    sgcsconf: u32,
+    drvconf: u32,
+    status: Status,
+}
+
+const DATAGRAM_MASK: u32 = 0xf_ffff;
+
+const DRVCTRL_MRES_MASK: u32 = 0x0f;
+
+// TOFF, HSTRT, HEND, and TBL
+const CHOPCONF_CHOPPER_MASK: u32 = 0x0f | 0x07 << 4 | 0x0f << 7 | 0x03 << 15;
+
+const SGCSCONF_CS_MASK: u32 = 0x1f;
+const SGCSCONF_SGT_SHIFT: u32 = 8;
+const SGCSCONF_SFILT_SHIFT: u32 = 16;
+const SGCSCONF_STALLGUARD_MASK: u32 =
+    0x7f << SGCSCONF_SGT_SHIFT | 1 << SGCSCONF_SFILT_SHIFT;
+
+const DRVCONF_RDSEL_SHIFT: u32 = 4;
+const DRVCONF_RDSEL_MASK: u32 = 0b11 << DRVCONF_RDSEL_SHIFT;
+
+#[cfg(test)]
+mod tests {
+    use super::Status;
+
+    #[test]
+    fn should_decode_status_response() {
+        let status = Status::from_response(0x2a9 << 10 | 0b1000_0001);
+
+        assert_eq!(status.value, 0x2a9);
+        assert!(status.standstill);
+        assert!(status.stall);
+        assert!(!status.overtemperature_shutdown);
+    }
+}
@@ -0,0 +1,442 @@
+//! TMC4361A Driver
+//!
+//! Platform-agnostic driver API for the TMC4361A motion controller. Can be used
+//! on any platform for which implementations of the required [embedded-hal]
+//! traits are available.
+//!
+//! The TMC4361A is a ramp generator that sits between the microcontroller and a
+//! stepper motor driver, which it controls via STEP/DIR signals. It provides
+//! S-shaped ramps, which this driver uses to provide motion control capability
+//! natively, without requiring the software fallback from the
+//! [`motion_control`](crate::motion_control) module. See [`Ramp`] for how to
+//! configure it.
+//!
+//! If an encoder is connected to the TMC4361A, it can also close the loop, by
+//! correcting the motor position according to the encoder feedback. See
+//! [`TMC4361A::enable_closed_loop`].
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::traits::{EnableMotionControl, MotionControl, ReadVelocity};
+
+/// The TMC4361A driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`TMC4361A::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// Motion control is enabled by passing an SPI device to
+/// [`Stepper::enable_motion_control`]. The TMC4361A needs to be configured for
+/// the stepper motor driver it is connected to, before the first motion. Use
+/// [`TMC4361A::write_register`] with [`register::GENERAL_CONF`] and
+/// [`register::STEP_CONF`] to do that.
+///
+/// [`Stepper::enable_motion_control`]: crate::Stepper::enable_motion_control
+pub struct TMC4361A<Spi> {
+    spi: Spi,
+    ramp: Ramp,
+    stopping: bool,
+}
+
+impl TMC4361A<()> {
+    /// Create a new instance of `TMC4361A`
+    pub fn new() -> Self {
+        Self {
+            spi: (),
+            ramp: Ramp::default(),
+            stopping: false,
+        }
+    }
+}
+
+impl Default for TMC4361A<()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Spi> TMC4361A<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// Access the ramp configuration that is used for motions
+    pub fn ramp(&self) -> &Ramp {
+        &self.ramp
+    }
+
+    /// Set the ramp configuration that is used for motions
+    ///
+    /// Takes effect when the next motion is started. See [`Ramp`] for details.
+    pub fn set_ramp(&mut self, ramp: Ramp) {
+        self.ramp = ramp;
+    }
+
+    /// Enable closed-loop operation
+    ///
+    /// Requires an encoder to be connected to the TMC4361A's encoder inputs.
+    /// `encoder_resolution` is the number of encoder counts per motor
+    /// revolution. Any further configuration of the encoder interface needs to
+    /// be done using [`TMC4361A::write_register`], with
+    /// [`register::ENC_IN_CONF`], before calling this method.
+    ///
+    /// Once closed-loop operation is enabled, the TMC4361A compensates any
+    /// deviation between the motor position and the encoder position.
+    pub fn enable_closed_loop(
+        &mut self,
+        encoder_resolution: u32,
+    ) -> Result<(), Spi::Error> {
+        self.write_register(register::ENC_IN_RES, encoder_resolution)?;
+
+        let enc_in_conf = self.read_register(register::ENC_IN_CONF)?;
+        let enc_in_conf = enc_in_conf & !ENC_IN_CONF_REGULATION_MODUS_MASK
+            | ENC_IN_CONF_CLOSED_LOOP;
+        self.write_register(register::ENC_IN_CONF, enc_in_conf)?;
+
+        Ok(())
+    }
+
+    /// Disable closed-loop operation
+    ///
+    /// See [`TMC4361A::enable_closed_loop`].
+    pub fn disable_closed_loop(&mut self) -> Result<(), Spi::Error> {
+        let enc_in_conf = self.read_register(register::ENC_IN_CONF)?;
+        let enc_in_conf = enc_in_conf & !ENC_IN_CONF_REGULATION_MODUS_MASK;
+        self.write_register(register::ENC_IN_CONF, enc_in_conf)
+    }
+
+    /// Read the position, as measured by the encoder
+    ///
+    /// The position is given in steps.
+    pub fn encoder_position(&mut self) -> Result<i32, Spi::Error> {
+        let position = self.read_register(register::ENC_POS)?;
+        Ok(position as i32)
+    }
+
+    /// Read the deviation between the motor position and the encoder position
+    ///
+    /// The deviation is given in steps.
+    pub fn position_deviation(&mut self) -> Result<i32, Spi::Error> {
+        let deviation = self.read_register(register::ENC_POS_DEV)?;
+        Ok(deviation as i32)
+    }
+
+    /// Write a value to a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn write_register(
+        &mut self,
+        address: u8,
+        value: u32,
+    ) -> Result<(), Spi::Error> {
+        self.transfer(address | WRITE, value)?;
+        Ok(())
+    }
+
+    /// Read a value from a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn read_register(&mut self, address: u8) -> Result<u32, Spi::Error> {
+        // The TMC4361A returns the data requested by a read access with the
+        // next datagram. We need to send the read access twice.
+        self.transfer(address, 0)?;
+        self.transfer(address, 0)
+    }
+
+    fn transfer(&mut self, address: u8, data: u32) -> Result<u32, Spi::Error> {
+        let data = data.to_be_bytes();
+        let mut datagram = [address, data[0], data[1], data[2], data[3]];
+
+        self.spi.transfer_in_place(&mut datagram)?;
+
+        // The first byte of the reply contains status flags. We're only
+        // interested in the data.
+        let [_, data @ ..] = datagram;
+        Ok(u32::from_be_bytes(data))
+    }
+}
+
+impl<Spi, const TIMER_HZ: u32> EnableMotionControl<Spi, TIMER_HZ>
+    for TMC4361A<()>
+where
+    Spi: SpiDevice,
+{
+    type WithMotionControl = TMC4361A<Spi>;
+
+    fn enable_motion_control(self, spi: Spi) -> Self::WithMotionControl {
+        TMC4361A {
+            spi,
+            ramp: self.ramp,
+            stopping: false,
+        }
+    }
+}
+
+impl<Spi> MotionControl for TMC4361A<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// The maximum velocity, in the TMC4361A's internal velocity unit
+    type Velocity = u32;
+    type Error = <Spi as ErrorType>::Error;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        let ramp = self.ramp;
+
+        self.write_register(register::VSTART, ramp.v_start)?;
+        self.write_register(register::VSTOP, ramp.v_stop)?;
+        self.write_register(register::AMAX, ramp.a_max)?;
+        self.write_register(register::DMAX, ramp.d_max)?;
+        self.write_register(register::BOW1, ramp.bow1)?;
+        self.write_register(register::BOW2, ramp.bow2)?;
+        self.write_register(register::BOW3, ramp.bow3)?;
+        self.write_register(register::BOW4, ramp.bow4)?;
+        self.write_register(register::VMAX, max_velocity)?;
+
+        // The motion starts as soon as we enter positioning mode, so the target
+        // needs to be in place before we do that.
+        self.write_register(register::X_TARGET, target_step as u32)?;
+        self.write_register(
+            register::RAMPMODE,
+            rampmode::S_SHAPED_POSITIONING,
+        )?;
+        self.stopping = false;
+
+        Ok(())
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        // Writing `XACTUAL` in positioning mode would start a motion. Switch to
+        // velocity mode, with zero velocity, first. Setting the target to the
+        // same value makes sure nothing moves, once we're back in positioning
+        // mode.
+        self.write_register(register::VMAX, 0)?;
+        self.write_register(register::RAMPMODE, rampmode::S_SHAPED_VELOCITY)?;
+        self.write_register(register::XACTUAL, step as u32)?;
+        self.write_register(register::X_TARGET, step as u32)?;
+        self.write_register(
+            register::RAMPMODE,
+            rampmode::S_SHAPED_POSITIONING,
+        )?;
+
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        if self.stopping {
+            // We're stopping in velocity mode, so the target won't be
+            // reached. Wait until the motor is standing still instead.
+            let velocity = self.read_register(register::VACTUAL)?;
+            return Ok(velocity != 0);
+        }
+
+        let status = self.read_register(register::STATUS)?;
+        let target_reached = status & STATUS_TARGET_REACHED != 0;
+
+        Ok(!target_reached)
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        let position = self.read_register(register::XACTUAL)?;
+        Ok(position as i32)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        // In velocity mode, the motor decelerates to `VMAX`, using the ramp
+        // configuration that was written when the motion started.
+        self.write_register(register::VMAX, 0)?;
+        self.write_register(register::RAMPMODE, rampmode::S_SHAPED_VELOCITY)?;
+        self.stopping = true;
+
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        // Without a ramp, the velocity follows `VMAX` immediately. The ramp
+        // configuration is written again, once the next motion starts.
+        self.write_register(register::VMAX, 0)?;
+        self.write_register(register::RAMPMODE, rampmode::NO_RAMP_VELOCITY)?;
+        self.stopping = true;
+
+        Ok(())
+    }
+}
+
+impl<Spi> ReadVelocity for TMC4361A<Spi>
+where
+    Spi: SpiDevice,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        // `VACTUAL` is a signed integer, while `VMAX` has 8 fractional bits.
+        let vactual = self.read_register(register::VACTUAL)? as i32;
+        let velocity = vactual.unsigned_abs().saturating_mul(1 << 8);
+
+        Ok(velocity)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Spi> crate::traits::RegisterWaker for TMC4361A<Spi> {
+    fn register_waker(&mut self, waker: &core::task::Waker) {
+        // The TMC4361A can signal the end of a motion through its interrupt
+        // output, but we don't have access to that. All we can do is keep
+        // polling.
+        waker.wake_by_ref();
+    }
+}
+
+/// Configuration of the TMC4361A's S-shaped ramp generator
+///
+/// A motion starts at `v_start` and accelerates up to the maximum velocity that
+/// was passed to [`MotionControl::move_to_position`], then decelerates down to
+/// `v_stop`. The acceleration and deceleration are limited by `a_max` and
+/// `d_max`. The bow values limit how quickly the acceleration changes, at the
+/// start and end of the acceleration (`bow1`, `bow2`) and deceleration
+/// (`bow3`, `bow4`) phases.
+///
+/// All values are given in the TMC4361A's internal units. Please note that none
+/// of the bow values must be zero.
+///
+/// Please refer to the TMC4361A datasheet for more information.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Ramp {
+    /// Start velocity (`VSTART`)
+    pub v_start: u32,
+
+    /// Stop velocity (`VSTOP`)
+    pub v_stop: u32,
+
+    /// Maximum acceleration (`AMAX`)
+    pub a_max: u32,
+
+    /// Maximum deceleration (`DMAX`)
+    pub d_max: u32,
+
+    /// Bow at the start of the acceleration phase (`BOW1`)
+    pub bow1: u32,
+
+    /// Bow at the end of the acceleration phase (`BOW2`)
+    pub bow2: u32,
+
+    /// Bow at the start of the deceleration phase (`BOW3`)
+    pub bow3: u32,
+
+    /// Bow at the end of the deceleration phase (`BOW4`)
+    pub bow4: u32,
+}
+
+impl Default for Ramp {
+    /// An S-shaped ramp with a conservative acceleration
+    fn default() -> Self {
+        Self {
+            v_start: 0,
+            v_stop: 0,
+            a_max: DEFAULT_ACCELERATION,
+            d_max: DEFAULT_ACCELERATION,
+            bow1: DEFAULT_BOW,
+            bow2: DEFAULT_BOW,
+            bow3: DEFAULT_BOW,
+            bow4: DEFAULT_BOW,
+        }
+    }
+}
+
+/// Addresses of the TMC4361A's registers
+///
+/// Please refer to the TMC4361A datasheet for more information on the
+/// registers.
+pub mod register {
+    /// General configuration flags
+    pub const GENERAL_CONF: u8 = 0x00;
+
+    /// Reference switch configuration
+    pub const REFERENCE_CONF: u8 = 0x01;
+
+    /// Encoder interface configuration, including the closed-loop mode
+    pub const ENC_IN_CONF: u8 = 0x07;
+
+    /// Step/direction output configuration
+    pub const STEP_CONF: u8 = 0x0A;
+
+    /// Events that have occurred since the last read
+    pub const EVENTS: u8 = 0x0E;
+
+    /// Status flags
+    pub const STATUS: u8 = 0x0F;
+
+    /// Ramp mode (positioning or velocity mode, ramp shape)
+    pub const RAMPMODE: u8 = 0x20;
+
+    /// Actual motor position
+    pub const XACTUAL: u8 = 0x21;
+
+    /// Actual motor velocity from the ramp generator
+    pub const VACTUAL: u8 = 0x22;
+
+    /// Maximum velocity
+    pub const VMAX: u8 = 0x24;
+
+    /// Start velocity
+    pub const VSTART: u8 = 0x25;
+
+    /// Stop velocity
+    pub const VSTOP: u8 = 0x26;
+
+    /// Maximum acceleration
+    pub const AMAX: u8 = 0x28;
+
+    /// Maximum deceleration
+    pub const DMAX: u8 = 0x29;
+
+    /// Bow at the start of the acceleration phase
+    pub const BOW1: u8 = 0x2D;
+
+    /// Bow at the end of the acceleration phase
+    pub const BOW2: u8 = 0x2E;
+
+    /// Bow at the start of the deceleration phase
+    pub const BOW3: u8 = 0x2F;
+
+    /// Bow at the end of the deceleration phase
+    pub const BOW4: u8 = 0x30;
+
+    /// Target position for positioning mode
+    pub const X_TARGET: u8 = 0x37;
+
+    /// Actual encoder position
+    pub const ENC_POS: u8 = 0x50;
+
+    /// Deviation between the motor position and the encoder position
+    pub const ENC_POS_DEV: u8 = 0x52;
+
+    /// Encoder resolution, in counts per revolution
+    pub const ENC_IN_RES: u8 = 0x54;
+}
+
+mod rampmode {
+    pub const NO_RAMP_VELOCITY: u32 = 0b000;
+    pub const S_SHAPED_VELOCITY: u32 = 0b010;
+    pub const S_SHAPED_POSITIONING: u32 = 0b110;
+}
+
+const WRITE: u8 = 0x80;
+
+const STATUS_TARGET_REACHED: u32 = 1 << 0;
+
+const ENC_IN_CONF_REGULATION_MODUS_MASK: u32 = 0b11 << 22;
+const ENC_IN_CONF_CLOSED_LOOP: u32 = 0b01 << 22;
+
+// Arbitrarily chosen to be on the safe side. Users are expected to set values
+// that suit their application.
+const DEFAULT_ACCELERATION: u32 = 1000;
+const DEFAULT_BOW: u32 = 1000;
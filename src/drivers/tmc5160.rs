@@ -0,0 +1,397 @@
+//! TMC5160 Driver
+//!
+//! Platform-agnostic driver API for the TMC5160 motion controller. Can be used
+//! on any platform for which implementations of the required [embedded-hal]
+//! traits are available.
+//!
+//! The TMC5160 has an integrated six-point ramp generator, which this driver
+//! uses to provide motion control capability natively, without requiring the
+//! software fallback from the [`motion_control`](crate::motion_control) module.
+//! See [`Ramp`] for how to configure it.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use embedded_hal::spi::{ErrorType, SpiDevice};
+
+use crate::traits::{EnableMotionControl, MotionControl, ReadVelocity};
+
+/// The TMC5160 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`TMC5160::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// Motion control is enabled by passing an SPI device to
+/// [`Stepper::enable_motion_control`]. Please note that the TMC5160 powers up
+/// with its motor outputs disabled. Before the first motion, the chopper and
+/// the motor current need to be configured, using
+/// [`TMC5160::write_register`] with [`register::CHOPCONF`] and
+/// [`register::IHOLD_IRUN`].
+///
+/// [`Stepper::enable_motion_control`]: crate::Stepper::enable_motion_control
+pub struct TMC5160<Spi> {
+    spi: Spi,
+    ramp: Ramp,
+    stopping: bool,
+}
+
+impl TMC5160<()> {
+    /// Create a new instance of `TMC5160`
+    pub fn new() -> Self {
+        Self {
+            spi: (),
+            ramp: Ramp::default(),
+            stopping: false,
+        }
+    }
+}
+
+impl Default for TMC5160<()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Spi> TMC5160<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// Access the acceleration that is used for motions
+    ///
+    /// See [`TMC5160::set_acceleration`].
+    pub fn acceleration(&self) -> u32 {
+        self.ramp.a_max
+    }
+
+    /// Set the acceleration that is used for motions
+    ///
+    /// The value is given in the TMC5160's internal acceleration unit and is
+    /// used for all acceleration and deceleration phases of the ramp. It takes
+    /// effect when the next motion is started.
+    ///
+    /// Use [`TMC5160::set_ramp`], if you need more control.
+    pub fn set_acceleration(&mut self, acceleration: u32) {
+        self.ramp.a1 = acceleration;
+        self.ramp.a_max = acceleration;
+        self.ramp.d_max = acceleration;
+        self.ramp.d1 = acceleration;
+    }
+
+    /// Access the ramp configuration that is used for motions
+    pub fn ramp(&self) -> &Ramp {
+        &self.ramp
+    }
+
+    /// Set the ramp configuration that is used for motions
+    ///
+    /// Takes effect when the next motion is started. See [`Ramp`] for details.
+    pub fn set_ramp(&mut self, ramp: Ramp) {
+        self.ramp = ramp;
+    }
+
+    /// Write a value to a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn write_register(
+        &mut self,
+        address: u8,
+        value: u32,
+    ) -> Result<(), Spi::Error> {
+        self.transfer(address | WRITE, value)?;
+        Ok(())
+    }
+
+    /// Read a value from a register
+    ///
+    /// See the [`register`] module for the addresses of the available
+    /// registers.
+    pub fn read_register(&mut self, address: u8) -> Result<u32, Spi::Error> {
+        // The TMC5160 returns the data requested by a read access with the
+        // next datagram. We need to send the read access twice.
+        self.transfer(address, 0)?;
+        self.transfer(address, 0)
+    }
+
+    fn transfer(&mut self, address: u8, data: u32) -> Result<u32, Spi::Error> {
+        let data = data.to_be_bytes();
+        let mut datagram = [address, data[0], data[1], data[2], data[3]];
+
+        self.spi.transfer_in_place(&mut datagram)?;
+
+        // The first byte of the reply contains the SPI status. We're only
+        // interested in the data.
+        let [_, data @ ..] = datagram;
+        Ok(u32::from_be_bytes(data))
+    }
+}
+
+impl<Spi, const TIMER_HZ: u32> EnableMotionControl<Spi, TIMER_HZ> for TMC5160<()>
+where
+    Spi: SpiDevice,
+{
+    type WithMotionControl = TMC5160<Spi>;
+
+    fn enable_motion_control(self, spi: Spi) -> Self::WithMotionControl {
+        TMC5160 {
+            spi,
+            ramp: self.ramp,
+            stopping: false,
+        }
+    }
+}
+
+impl<Spi> MotionControl for TMC5160<Spi>
+where
+    Spi: SpiDevice,
+{
+    /// The maximum velocity, in the TMC5160's internal velocity unit
+    type Velocity = u32;
+    type Error = <Spi as ErrorType>::Error;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        let ramp = self.ramp;
+
+        self.write_register(register::VSTART, ramp.v_start)?;
+        self.write_register(register::A1, ramp.a1)?;
+        self.write_register(register::V1, ramp.v1)?;
+        self.write_register(register::AMAX, ramp.a_max)?;
+        self.write_register(register::DMAX, ramp.d_max)?;
+        self.write_register(register::D1, ramp.d1)?;
+        self.write_register(register::VSTOP, ramp.v_stop)?;
+        self.write_register(register::VMAX, max_velocity)?;
+
+        // The motion starts as soon as we enter positioning mode, so the target
+        // needs to be in place before we do that.
+        self.write_register(register::XTARGET, target_step as u32)?;
+        self.write_register(register::RAMPMODE, rampmode::POSITIONING)?;
+        self.stopping = false;
+
+        Ok(())
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        // Writing `XACTUAL` in positioning mode would start a motion. Switch to
+        // hold mode first. Setting the target to the same value makes sure
+        // nothing moves, once we're back in positioning mode.
+        self.write_register(register::RAMPMODE, rampmode::HOLD)?;
+        self.write_register(register::XACTUAL, step as u32)?;
+        self.write_register(register::XTARGET, step as u32)?;
+        self.write_register(register::RAMPMODE, rampmode::POSITIONING)?;
+
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        if self.stopping {
+            // We're stopping in velocity mode, so the position won't be
+            // reached. Wait until the motor is standing still instead.
+            let ramp_stat = self.read_register(register::RAMP_STAT)?;
+            let vzero = ramp_stat & RAMP_STAT_VZERO != 0;
+
+            return Ok(!vzero);
+        }
+
+        let ramp_stat = self.read_register(register::RAMP_STAT)?;
+        let position_reached = ramp_stat & RAMP_STAT_POSITION_REACHED != 0;
+
+        Ok(!position_reached)
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        let position = self.read_register(register::XACTUAL)?;
+        Ok(position as i32)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        // In velocity mode, `AMAX` is used for deceleration too. Use the
+        // deceleration from the ramp configuration instead, to stop like at
+        // the end of a motion.
+        self.write_register(register::VMAX, 0)?;
+        self.write_register(register::AMAX, self.ramp.d_max)?;
+        self.write_register(register::RAMPMODE, rampmode::VELOCITY_POSITIVE)?;
+        self.stopping = true;
+
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        // There's no way to stop without a ramp. Switching to velocity mode
+        // with a target velocity of zero and the highest possible acceleration
+        // comes closest. The ramp configuration is written again, once the
+        // next motion starts.
+        self.write_register(register::VMAX, 0)?;
+        self.write_register(register::AMAX, AMAX_MAX)?;
+        self.write_register(register::RAMPMODE, rampmode::VELOCITY_POSITIVE)?;
+        self.stopping = true;
+
+        Ok(())
+    }
+}
+
+impl<Spi> ReadVelocity for TMC5160<Spi>
+where
+    Spi: SpiDevice,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        // `VACTUAL` is a signed 24-bit value. Sign-extend it, to get the
+        // magnitude.
+        let vactual = self.read_register(register::VACTUAL)?;
+        let vactual = ((vactual << 8) as i32) >> 8;
+
+        Ok(vactual.unsigned_abs())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Spi> crate::traits::RegisterWaker for TMC5160<Spi> {
+    fn register_waker(&mut self, waker: &core::task::Waker) {
+        // The TMC5160 can signal the end of a motion through an interrupt
+        // output, but we don't have access to that. All we can do is keep
+        // polling.
+        waker.wake_by_ref();
+    }
+}
+
+/// Configuration of the TMC5160's six-point ramp generator
+///
+/// A motion starts at `v_start` and accelerates with `a1` up to `v1`, then with
+/// `a_max` up to the maximum velocity that was passed to
+/// [`MotionControl::move_to_position`]. Deceleration mirrors this, using
+/// `d_max` down to `v1`, then `d1` down to `v_stop`.
+///
+/// All values are given in the TMC5160's internal units. Setting `v1` to zero
+/// disables the first acceleration and last deceleration phase, resulting in a
+/// trapezoidal ramp. Please note that `d1` must not be zero, even if `v1` is,
+/// and that `v_stop` must not be lower than `v_start`.
+///
+/// Please refer to the TMC5160 datasheet for more information.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Ramp {
+    /// Start velocity (`VSTART`)
+    pub v_start: u32,
+
+    /// First acceleration, between `v_start` and `v1` (`A1`)
+    pub a1: u32,
+
+    /// Threshold velocity between the two acceleration phases (`V1`)
+    pub v1: u32,
+
+    /// Second acceleration, between `v1` and the maximum velocity (`AMAX`)
+    pub a_max: u32,
+
+    /// First deceleration, between the maximum velocity and `v1` (`DMAX`)
+    pub d_max: u32,
+
+    /// Second deceleration, between `v1` and `v_stop` (`D1`)
+    pub d1: u32,
+
+    /// Stop velocity (`VSTOP`)
+    pub v_stop: u32,
+}
+
+impl Default for Ramp {
+    /// A trapezoidal ramp with a conservative acceleration
+    fn default() -> Self {
+        Self {
+            v_start: 0,
+            a1: DEFAULT_ACCELERATION,
+            v1: 0,
+            a_max: DEFAULT_ACCELERATION,
+            d_max: DEFAULT_ACCELERATION,
+            d1: DEFAULT_ACCELERATION,
+            v_stop: DEFAULT_VSTOP,
+        }
+    }
+}
+
+/// Addresses of the TMC5160's registers
+///
+/// Please refer to the TMC5160 datasheet for more information on the
+/// registers.
+pub mod register {
+    /// Global configuration flags
+    pub const GCONF: u8 = 0x00;
+
+    /// Global status flags
+    pub const GSTAT: u8 = 0x01;
+
+    /// Driver current control
+    pub const IHOLD_IRUN: u8 = 0x10;
+
+    /// Ramp mode (positioning, velocity, or hold)
+    pub const RAMPMODE: u8 = 0x20;
+
+    /// Actual motor position
+    pub const XACTUAL: u8 = 0x21;
+
+    /// Actual motor velocity from the ramp generator
+    pub const VACTUAL: u8 = 0x22;
+
+    /// Motor start velocity
+    pub const VSTART: u8 = 0x23;
+
+    /// First acceleration between `VSTART` and `V1`
+    pub const A1: u8 = 0x24;
+
+    /// First acceleration/deceleration phase threshold velocity
+    pub const V1: u8 = 0x25;
+
+    /// Second acceleration between `V1` and `VMAX`
+    pub const AMAX: u8 = 0x26;
+
+    /// Motion ramp target velocity
+    pub const VMAX: u8 = 0x27;
+
+    /// Deceleration between `VMAX` and `V1`
+    pub const DMAX: u8 = 0x28;
+
+    /// Deceleration between `V1` and `VSTOP`
+    pub const D1: u8 = 0x2A;
+
+    /// Motor stop velocity
+    pub const VSTOP: u8 = 0x2B;
+
+    /// Waiting time after ramping down to zero velocity
+    pub const TZEROWAIT: u8 = 0x2C;
+
+    /// Target position for ramp mode
+    pub const XTARGET: u8 = 0x2D;
+
+    /// Ramp status and switch event status
+    pub const RAMP_STAT: u8 = 0x35;
+
+    /// Chopper and driver configuration
+    pub const CHOPCONF: u8 = 0x6C;
+
+    /// Driver status flags and StallGuard value
+    pub const DRV_STATUS: u8 = 0x6F;
+}
+
+mod rampmode {
+    pub const POSITIONING: u32 = 0;
+    pub const VELOCITY_POSITIVE: u32 = 1;
+    pub const HOLD: u32 = 3;
+}
+
+const WRITE: u8 = 0x80;
+
+const RAMP_STAT_POSITION_REACHED: u32 = 1 << 9;
+const RAMP_STAT_VZERO: u32 = 1 << 10;
+
+// `AMAX` is a 16-bit value.
+const AMAX_MAX: u32 = 0xFFFF;
+
+// Arbitrarily chosen to be on the safe side. Users are expected to set an
+// acceleration that suits their application.
+const DEFAULT_ACCELERATION: u32 = 1000;
+
+// Recommended by the datasheet, to make sure the motor actually stops.
+const DEFAULT_VSTOP: u32 = 10;
@@ -0,0 +1,399 @@
+//! ULN2003 Driver
+//!
+//! Platform-agnostic driver API for the ULN2003 Darlington transistor array,
+//! as commonly used to drive the 28BYJ-48 unipolar stepper motor. Can be used
+//! on any platform for which implementations of the required [embedded-hal]
+//! traits are available.
+//!
+//! Unlike the other drivers in this crate, the ULN2003 has no STEP and DIR
+//! inputs. Instead, this driver controls the four coils of the motor directly,
+//! and steps through a coil sequence in software. See [`Sequence`] for the
+//! supported sequences.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::{self, ErrorType, OutputPin, PinState};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::traits::{
+    DisableStepControl, EnableStepControl, SetDirection, Step as StepTrait,
+};
+
+/// The ULN2003 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`ULN2003::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// The four coil pins (IN1 to IN4) are passed to
+/// [`Stepper::enable_step_control`]. They are used for both step and direction
+/// control, so there's no need to call
+/// [`Stepper::enable_direction_control`].
+///
+/// [`Stepper::enable_step_control`]: crate::Stepper::enable_step_control
+/// [`Stepper::enable_direction_control`]: crate::Stepper::enable_direction_control
+pub struct ULN2003<Coils> {
+    coils: Coils,
+    sequence: Sequence,
+}
+
+impl ULN2003<()> {
+    /// Create a new instance of `ULN2003`
+    ///
+    /// Uses [`Sequence::Full`] by default. See [`ULN2003::set_sequence`].
+    pub fn new() -> Self {
+        Self {
+            coils: (),
+            sequence: Sequence::Full,
+        }
+    }
+}
+
+impl Default for ULN2003<()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Coils> ULN2003<Coils> {
+    /// Access the coil sequence that is used for stepping
+    pub fn sequence(&self) -> Sequence {
+        self.sequence
+    }
+
+    /// Set the coil sequence that is used for stepping
+    ///
+    /// Takes effect with the next step.
+    pub fn set_sequence(&mut self, sequence: Sequence) {
+        self.sequence = sequence;
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> ULN2003<Coils<IN1, IN2, IN3, IN4>>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+{
+    /// Switch off all coils
+    ///
+    /// The motor doesn't hold its position while the coils are switched off,
+    /// but it also doesn't draw current or heat up. The coils are switched on
+    /// again with the next step.
+    pub fn release_coils(&mut self) -> Result<(), OutputPinError> {
+        self.coils.write(0b0000)
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> EnableStepControl<(IN1, IN2, IN3, IN4)>
+    for ULN2003<()>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    type WithStepControl = ULN2003<Coils<IN1, IN2, IN3, IN4>>;
+
+    fn enable_step_control(
+        self,
+        (in1, in2, in3, in4): (IN1, IN2, IN3, IN4),
+    ) -> Self::WithStepControl {
+        ULN2003 {
+            coils: Coils {
+                in1,
+                in2,
+                in3,
+                in4,
+                signal: Signal::Step,
+                forward: true,
+                phase: 0,
+                sequence: self.sequence,
+            },
+            sequence: self.sequence,
+        }
+    }
+}
+
+impl<IN1, IN2, IN3, IN4> DisableStepControl
+    for ULN2003<Coils<IN1, IN2, IN3, IN4>>
+{
+    type WithoutStepControl = ULN2003<()>;
+    type Resources = (IN1, IN2, IN3, IN4);
+
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources) {
+        let Coils {
+            in1, in2, in3, in4, ..
+        } = self.coils;
+
+        (
+            ULN2003 {
+                coils: (),
+                sequence: self.sequence,
+            },
+            (in1, in2, in3, in4),
+        )
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> SetDirection
+    for ULN2003<Coils<IN1, IN2, IN3, IN4>>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    // The direction is only stored, not applied to any pins.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Dir = Coils<IN1, IN2, IN3, IN4>;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        self.coils.signal = Signal::Dir;
+        Ok(&mut self.coils)
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> StepTrait
+    for ULN2003<Coils<IN1, IN2, IN3, IN4>>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    // The coils are switched at the beginning of the pulse, so its length
+    // doesn't matter. The maximum step rate of the motor needs to be respected
+    // by the motion profile.
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Step = Coils<IN1, IN2, IN3, IN4>;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        self.coils.signal = Signal::Step;
+        self.coils.sequence = self.sequence;
+        Ok(&mut self.coils)
+    }
+}
+
+/// The coil sequence used for stepping
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Sequence {
+    /// Wave drive: one coil is switched on at a time
+    ///
+    /// Uses the least power, but provides the least torque.
+    Wave,
+
+    /// Full-step drive: two coils are switched on at a time
+    ///
+    /// Provides the most torque.
+    Full,
+
+    /// Half-step drive: alternates between one and two coils
+    ///
+    /// Doubles the resolution, at the cost of uneven torque.
+    Half,
+}
+
+impl Sequence {
+    // Each entry contains the states of IN1 to IN4, starting with IN1 at the
+    // least significant bit.
+    fn phases(&self) -> &'static [u8] {
+        match self {
+            Sequence::Wave => &[0b0001, 0b0010, 0b0100, 0b1000],
+            Sequence::Full => &[0b0011, 0b0110, 0b1100, 0b1001],
+            Sequence::Half => &[
+                0b0001, 0b0011, 0b0010, 0b0110, 0b0100, 0b1100, 0b1000, 0b1001,
+            ],
+        }
+    }
+}
+
+/// The coil pins of the ULN2003
+///
+/// Serves as both the STEP and the DIR pin. Setting it high or low as the DIR
+/// pin selects the direction. Setting it high as the STEP pin switches the
+/// coils to the next phase of the sequence, in that direction. Setting it low
+/// as the STEP pin has no effect.
+///
+/// Created by [`ULN2003`]'s implementation of [`EnableStepControl`].
+pub struct Coils<IN1, IN2, IN3, IN4> {
+    in1: IN1,
+    in2: IN2,
+    in3: IN3,
+    in4: IN4,
+    signal: Signal,
+    forward: bool,
+    phase: usize,
+    sequence: Sequence,
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> Coils<IN1, IN2, IN3, IN4>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+{
+    fn advance(&mut self) -> Result<(), OutputPinError> {
+        let phases = self.sequence.phases();
+
+        // The sequence might have changed since the last step, so make sure
+        // we're within bounds.
+        self.phase = if self.forward {
+            (self.phase + 1) % phases.len()
+        } else {
+            (self.phase + phases.len() - 1) % phases.len()
+        };
+
+        self.write(phases[self.phase])
+    }
+
+    fn write(&mut self, coils: u8) -> Result<(), OutputPinError> {
+        self.in1.set_state(PinState::from(coils & 0b0001 != 0))?;
+        self.in2.set_state(PinState::from(coils & 0b0010 != 0))?;
+        self.in3.set_state(PinState::from(coils & 0b0100 != 0))?;
+        self.in4.set_state(PinState::from(coils & 0b1000 != 0))?;
+
+        Ok(())
+    }
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> ErrorType for Coils<IN1, IN2, IN3, IN4>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    type Error = OutputPinError;
+}
+
+impl<IN1, IN2, IN3, IN4, OutputPinError> OutputPin for Coils<IN1, IN2, IN3, IN4>
+where
+    IN1: OutputPin<Error = OutputPinError>,
+    IN2: OutputPin<Error = OutputPinError>,
+    IN3: OutputPin<Error = OutputPinError>,
+    IN4: OutputPin<Error = OutputPinError>,
+    OutputPinError: digital::Error,
+{
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        match self.signal {
+            Signal::Dir => {
+                self.forward = true;
+                Ok(())
+            }
+            Signal::Step => self.advance(),
+        }
+    }
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        if let Signal::Dir = self.signal {
+            self.forward = false;
+        }
+
+        Ok(())
+    }
+}
+
+// Which signal `Coils` is currently acting as
+enum Signal {
+    Dir,
+    Step,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_hal::digital::{ErrorType, OutputPin};
+
+    use crate::traits::{
+        DisableStepControl as _, EnableStepControl as _, SetDirection, Step,
+    };
+
+    use super::{Coils, Sequence, ULN2003};
+
+    #[test]
+    fn should_step_through_sequence_in_both_directions() {
+        let mut driver = ULN2003::new().enable_step_control((
+            MockPin(false),
+            MockPin(false),
+            MockPin(false),
+            MockPin(false),
+        ));
+        driver.set_sequence(Sequence::Half);
+
+        let step = |driver: &mut ULN2003<
+            Coils<MockPin, MockPin, MockPin, MockPin>,
+        >| {
+            let coils = driver.step().unwrap();
+            coils.set_high().unwrap();
+            coils.set_low().unwrap();
+
+            [coils.in1.0, coils.in2.0, coils.in3.0, coils.in4.0]
+        };
+
+        assert_eq!(step(&mut driver), [true, true, false, false]);
+        assert_eq!(step(&mut driver), [false, true, false, false]);
+
+        driver.dir().unwrap().set_low().unwrap();
+
+        assert_eq!(step(&mut driver), [true, true, false, false]);
+        assert_eq!(step(&mut driver), [true, false, false, false]);
+        assert_eq!(step(&mut driver), [true, false, false, true]);
+    }
+
+    #[test]
+    fn should_return_pins_when_disabling_step_control() {
+        let mut driver = ULN2003::new().enable_step_control((
+            MockPin(false),
+            MockPin(false),
+            MockPin(false),
+            MockPin(false),
+        ));
+        driver.set_sequence(Sequence::Wave);
+
+        let coils = driver.step().unwrap();
+        coils.set_high().unwrap();
+        coils.set_low().unwrap();
+
+        let (driver, (in1, in2, in3, in4)) = driver.disable_step_control();
+
+        assert_eq!([in1.0, in2.0, in3.0, in4.0], [false, true, false, false]);
+        assert_eq!(driver.sequence(), Sequence::Wave);
+    }
+
+    struct MockPin(bool);
+
+    impl ErrorType for MockPin {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for MockPin {
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.0 = true;
+            Ok(())
+        }
+
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.0 = false;
+            Ok(())
+        }
+    }
+}
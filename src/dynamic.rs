@@ -0,0 +1,273 @@
+//! Capabilities that are decided at runtime
+//!
+//! See [`DynamicDriver`] for more information.
+
+use core::{convert::Infallible, fmt};
+
+use embedded_hal::digital::OutputPin;
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    traits::{SetDirection, SetOutputEnabled, Step as StepTrait},
+    SignalError, Stepper,
+};
+
+/// A [`Stepper`] whose capabilities are decided at runtime
+///
+/// See [`DynamicDriver`].
+pub type DynamicStepper<
+    const SETUP_TIME_NS: u32,
+    const PULSE_LENGTH_NS: u32,
+    Step,
+    Dir,
+    Enable = (),
+> = Stepper<DynamicDriver<SETUP_TIME_NS, PULSE_LENGTH_NS, Step, Dir, Enable>>;
+
+/// A STEP/DIR driver whose pins are attached and detached at runtime
+///
+/// The typestate-based drivers check at compile time, that a capability has
+/// been enabled before it is used. This makes it impossible to decide at
+/// runtime which capabilities to enable, for example based on the hardware
+/// that was detected. `DynamicDriver` stores its pins as `Option`s instead, and
+/// always implements [`SetDirection`], [`Step`], and [`SetOutputEnabled`].
+/// Using a capability whose pin hasn't been attached results in a
+/// [`MissingCapability`] error.
+///
+/// Like [`GenericStepDir`], the timing requirements are given in nanoseconds,
+/// as const generic parameters. Wrap the driver in a [`Stepper`] (see
+/// [`DynamicStepper`]), and attach pins using [`Stepper::driver_mut`].
+///
+/// [`Step`]: crate::traits::Step
+/// [`GenericStepDir`]: crate::drivers::generic::GenericStepDir
+pub struct DynamicDriver<
+    const SETUP_TIME_NS: u32,
+    const PULSE_LENGTH_NS: u32,
+    Step,
+    Dir,
+    Enable = (),
+> {
+    step: Option<Step>,
+    dir: Option<Dir>,
+    enable: Option<Enable>,
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        Enable,
+    > DynamicDriver<SETUP_TIME_NS, PULSE_LENGTH_NS, Step, Dir, Enable>
+{
+    /// Create a new instance of `DynamicDriver`, without any pins attached
+    pub fn new() -> Self {
+        Self {
+            step: None,
+            dir: None,
+            enable: None,
+        }
+    }
+
+    /// Attach the STEP pin, enabling step control
+    ///
+    /// Returns the previously attached pin, if any.
+    pub fn attach_step(&mut self, step: Step) -> Option<Step> {
+        self.step.replace(step)
+    }
+
+    /// Detach the STEP pin, disabling step control
+    pub fn detach_step(&mut self) -> Option<Step> {
+        self.step.take()
+    }
+
+    /// Indicates whether the STEP pin is attached
+    pub fn has_step(&self) -> bool {
+        self.step.is_some()
+    }
+
+    /// Attach the DIR pin, enabling direction control
+    ///
+    /// Returns the previously attached pin, if any.
+    pub fn attach_dir(&mut self, dir: Dir) -> Option<Dir> {
+        self.dir.replace(dir)
+    }
+
+    /// Detach the DIR pin, disabling direction control
+    pub fn detach_dir(&mut self) -> Option<Dir> {
+        self.dir.take()
+    }
+
+    /// Indicates whether the DIR pin is attached
+    pub fn has_dir(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    /// Attach the enable pin, enabling output control
+    ///
+    /// The enable input is assumed to be active low, as it is on most
+    /// drivers. Returns the previously attached pin, if any.
+    pub fn attach_enable(&mut self, enable: Enable) -> Option<Enable> {
+        self.enable.replace(enable)
+    }
+
+    /// Detach the enable pin, disabling output control
+    pub fn detach_enable(&mut self) -> Option<Enable> {
+        self.enable.take()
+    }
+
+    /// Indicates whether the enable pin is attached
+    pub fn has_enable(&self) -> bool {
+        self.enable.is_some()
+    }
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        Enable,
+    > Default
+    for DynamicDriver<SETUP_TIME_NS, PULSE_LENGTH_NS, Step, Dir, Enable>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        Enable,
+    > SetDirection
+    for DynamicDriver<SETUP_TIME_NS, PULSE_LENGTH_NS, Step, Dir, Enable>
+where
+    Dir: OutputPin,
+{
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(SETUP_TIME_NS);
+
+    type Dir = Dir;
+    type Error = MissingCapability;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        self.dir.as_mut().ok_or(MissingCapability::Direction)
+    }
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        Enable,
+    > StepTrait
+    for DynamicDriver<SETUP_TIME_NS, PULSE_LENGTH_NS, Step, Dir, Enable>
+where
+    Step: OutputPin,
+{
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(PULSE_LENGTH_NS);
+
+    type Step = Step;
+    type Error = MissingCapability;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        self.step.as_mut().ok_or(MissingCapability::Step)
+    }
+}
+
+impl<
+        const SETUP_TIME_NS: u32,
+        const PULSE_LENGTH_NS: u32,
+        Step,
+        Dir,
+        Enable,
+    > SetOutputEnabled
+    for DynamicDriver<SETUP_TIME_NS, PULSE_LENGTH_NS, Step, Dir, Enable>
+where
+    Enable: OutputPin,
+{
+    type Error = SignalError<MissingCapability, Enable::Error, Infallible>;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        let enable = self
+            .enable
+            .as_mut()
+            .ok_or(SignalError::PinUnavailable(MissingCapability::Output))?;
+
+        if enabled {
+            enable.set_low().map_err(SignalError::Pin)
+        } else {
+            enable.set_high().map_err(SignalError::Pin)
+        }
+    }
+}
+
+/// A capability of a [`DynamicDriver`] was used, but its pin isn't attached
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MissingCapability {
+    /// Direction control was used, but no DIR pin is attached
+    Direction,
+
+    /// Step control was used, but no STEP pin is attached
+    Step,
+
+    /// Output control was used, but no enable pin is attached
+    Output,
+}
+
+impl fmt::Display for MissingCapability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Direction => write!(f, "no DIR pin attached"),
+            Self::Step => write!(f, "no STEP pin attached"),
+            Self::Output => write!(f, "no enable pin attached"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mock::{MockDriver, MockPin, Signal},
+        traits::Step as _,
+        SignalError,
+    };
+
+    use super::{DynamicDriver, DynamicStepper, MissingCapability};
+
+    #[test]
+    fn missing_capabilities_should_result_in_errors() {
+        let mut mock = MockDriver::new();
+        let mut timer = mock.timer::<1_000_000>();
+
+        let mut stepper: DynamicStepper<650, 1900, MockPin, MockPin> =
+            DynamicStepper::from_driver(DynamicDriver::new());
+
+        assert_eq!(
+            stepper.step(&mut timer).wait(),
+            Err(SignalError::PinUnavailable(MissingCapability::Step))
+        );
+
+        stepper
+            .driver_mut()
+            .attach_step(mock.step().unwrap().clone());
+        stepper.step(&mut timer).wait().unwrap();
+        assert_eq!(
+            mock.transitions()
+                .iter()
+                .map(|transition| transition.signal)
+                .collect::<Vec<_>>(),
+            [Signal::Step(true), Signal::Step(false)]
+        );
+
+        assert_eq!(
+            stepper
+                .set_direction(crate::Direction::Forward, &mut timer)
+                .wait(),
+            Err(SignalError::PinUnavailable(MissingCapability::Direction))
+        );
+    }
+}
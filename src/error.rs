@@ -0,0 +1,557 @@
+//! Erased, non-generic error type
+//!
+//! The errors returned by Stepper are generic over the errors of the pins,
+//! timers, and drivers they wrap, which makes them precise, but also verbose.
+//! Application code that just wants to log an error and stop can convert all of
+//! them into [`ErasedError`] instead, usually by using the `?` operator in a
+//! function that returns `Result<_, ErasedError>`.
+//!
+//! The erased error keeps the [`ErrorKind`] and, where available, a short
+//! description of the context the error occurred in. The errors of pins,
+//! timers, and other HAL types are dropped in the conversion.
+
+use core::{convert::Infallible, fmt};
+
+use crate::{
+    coordinated::InvalidSweepError,
+    dynamic::MissingCapability,
+    gantry::GantryError,
+    limit_switch,
+    motion_control::{
+        self, BusyError, ClosedLoopError, DynError, LimitMonitorError,
+        PulseTrainError, QueueError, TimeConversionError, TimedMoveError,
+        TravelLimitError,
+    },
+    step_mode::InvalidStepModeError,
+    traits::{SetDirection, Step},
+    BuildError, CommandPendingError, HomingError, NoDirectionError, ProbeError,
+    SignalError,
+};
+
+/// An error, without the errors of the pins, timers, or drivers it came from
+///
+/// All error types in this crate convert into `ErasedError`. See the
+/// [module documentation](self) for more information.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ErasedError {
+    kind: ErrorKind,
+    context: Option<&'static str>,
+}
+
+impl ErasedError {
+    /// Create an `ErasedError` of the given kind, without context
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            context: None,
+        }
+    }
+
+    /// Attach a description of the context the error occurred in
+    ///
+    /// Replaces any context that was attached before.
+    pub fn with_context(self, context: &'static str) -> Self {
+        Self {
+            kind: self.kind,
+            context: Some(context),
+        }
+    }
+
+    /// Return the kind of the error
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Return the context the error occurred in, if known
+    pub fn context(&self) -> Option<&'static str> {
+        self.context
+    }
+}
+
+impl fmt::Display for ErasedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(context) = self.context {
+            write!(f, " ({})", context)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<ErrorKind> for ErasedError {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+/// The kind of an [`ErasedError`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorKind {
+    /// A pin was not accessible
+    PinUnavailable,
+
+    /// Error while setting an output pin
+    Pin,
+
+    /// Error while working with a timer
+    Timer,
+
+    /// A value couldn't be converted, for example a delay into timer ticks
+    Conversion,
+
+    /// Error while communicating with the driver, for example over SPI
+    Communication,
+
+    /// The driver reported a fault, or rejected a command
+    Driver,
+
+    /// Error while reading a sensor, like a limit switch or a probe
+    Sensor,
+
+    /// A motion was rejected or stopped, because it would have left the
+    /// allowed range of motion
+    Limit,
+
+    /// The motor stalled, or lost steps
+    StepLoss,
+
+    /// Homing or probing didn't find the switch or probe it was looking for
+    Homing,
+
+    /// Error while changing the step mode
+    StepMode,
+
+    /// An operation couldn't be started, because another one is ongoing
+    Busy,
+
+    /// A value was outside of the supported range
+    InvalidValue,
+
+    /// The driver and timer were lost, due to a panic in a previous call
+    InvalidState,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Self::PinUnavailable => "pin unavailable",
+            Self::Pin => "pin error",
+            Self::Timer => "timer error",
+            Self::Conversion => "conversion error",
+            Self::Communication => "communication error",
+            Self::Driver => "driver error",
+            Self::Sensor => "sensor error",
+            Self::Limit => "limit reached",
+            Self::StepLoss => "step loss",
+            Self::Homing => "homing failed",
+            Self::StepMode => "step mode error",
+            Self::Busy => "busy",
+            Self::InvalidValue => "invalid value",
+            Self::InvalidState => "invalid state",
+        };
+
+        write!(f, "{}", description)
+    }
+}
+
+impl From<Infallible> for ErasedError {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+impl<PinUnavailableError, PinError, DelayToTicksError, TimerError>
+    From<
+        crate::Error<
+            PinUnavailableError,
+            PinError,
+            DelayToTicksError,
+            TimerError,
+        >,
+    > for ErasedError
+{
+    fn from(
+        err: crate::Error<
+            PinUnavailableError,
+            PinError,
+            DelayToTicksError,
+            TimerError,
+        >,
+    ) -> Self {
+        match err {
+            crate::Error::Signal(err) => err.into(),
+            crate::Error::MotionControl(err) => err.into(),
+        }
+    }
+}
+
+impl<PinUnavailableError, PinError, TimerError>
+    From<SignalError<PinUnavailableError, PinError, TimerError>>
+    for ErasedError
+{
+    fn from(
+        err: SignalError<PinUnavailableError, PinError, TimerError>,
+    ) -> Self {
+        let kind = match err {
+            SignalError::PinUnavailable(_) => ErrorKind::PinUnavailable,
+            SignalError::Pin(_) => ErrorKind::Pin,
+            SignalError::Timer(_) => ErrorKind::Timer,
+        };
+
+        Self::new(kind)
+    }
+}
+
+impl<
+        SetDirectionPinUnavailable,
+        SetDirectionError,
+        StepPinUnavailable,
+        StepError,
+        TimerError,
+        DelayToTicksError,
+    >
+    From<
+        motion_control::Error<
+            SetDirectionPinUnavailable,
+            SetDirectionError,
+            StepPinUnavailable,
+            StepError,
+            TimerError,
+            DelayToTicksError,
+        >,
+    > for ErasedError
+{
+    fn from(
+        err: motion_control::Error<
+            SetDirectionPinUnavailable,
+            SetDirectionError,
+            StepPinUnavailable,
+            StepError,
+            TimerError,
+            DelayToTicksError,
+        >,
+    ) -> Self {
+        use motion_control::Error;
+
+        match err {
+            Error::SetDirection(err) => {
+                Self::from(err).with_context("setting direction")
+            }
+            Error::Step(err) => Self::from(err).with_context("stepping"),
+            Error::TimeConversion(err) => err.into(),
+            Error::StepDelay(_) => {
+                Self::new(ErrorKind::Timer).with_context("step delay")
+            }
+            Error::TravelLimit(err) => err.into(),
+            Error::Stalled => {
+                Self::new(ErrorKind::StepLoss).with_context("motor stalled")
+            }
+            Error::StepMode => Self::new(ErrorKind::StepMode)
+                .with_context("applying queued step mode"),
+            Error::DelayTooShort => Self::new(ErrorKind::InvalidValue)
+                .with_context("step delay too short"),
+            Error::InvalidState => Self::new(ErrorKind::InvalidState),
+        }
+    }
+}
+
+impl<
+        SetDirectionPinUnavailable,
+        SetDirectionError,
+        GeneratePulseTrainError,
+        DelayToTicksError,
+    >
+    From<
+        PulseTrainError<
+            SetDirectionPinUnavailable,
+            SetDirectionError,
+            GeneratePulseTrainError,
+            DelayToTicksError,
+        >,
+    > for ErasedError
+{
+    fn from(
+        err: PulseTrainError<
+            SetDirectionPinUnavailable,
+            SetDirectionError,
+            GeneratePulseTrainError,
+            DelayToTicksError,
+        >,
+    ) -> Self {
+        match err {
+            PulseTrainError::SetDirection(err) => {
+                Self::from(err).with_context("setting direction")
+            }
+            PulseTrainError::PulseTrain(_) => {
+                Self::new(ErrorKind::Driver).with_context("generating pulses")
+            }
+            PulseTrainError::TimeConversion(err) => err.into(),
+        }
+    }
+}
+
+impl<DelayToTicksError> From<TimeConversionError<DelayToTicksError>>
+    for ErasedError
+{
+    fn from(err: TimeConversionError<DelayToTicksError>) -> Self {
+        match err {
+            TimeConversionError::DelayToTicks(_) => {
+                Self::new(ErrorKind::Conversion)
+                    .with_context("converting delay to timer ticks")
+            }
+        }
+    }
+}
+
+impl From<TravelLimitError> for ErasedError {
+    fn from(err: TravelLimitError) -> Self {
+        let context = match err {
+            TravelLimitError::TargetOutOfRange(_) => {
+                "target outside of travel limits"
+            }
+            TravelLimitError::LimitReached(_) => "travel limit reached",
+        };
+
+        Self::new(ErrorKind::Limit).with_context(context)
+    }
+}
+
+impl<T> From<BusyError<T>> for ErasedError
+where
+    T: Into<ErasedError>,
+{
+    fn from(err: BusyError<T>) -> Self {
+        match err {
+            BusyError::Busy => Self::new(ErrorKind::Busy),
+            BusyError::Other(err) => err.into(),
+        }
+    }
+}
+
+impl From<DynError> for ErasedError {
+    fn from(err: DynError) -> Self {
+        match err {
+            DynError::Driver => Self::new(ErrorKind::Driver),
+            DynError::VelocityConversion => Self::new(ErrorKind::Conversion)
+                .with_context("converting velocity"),
+        }
+    }
+}
+
+impl From<QueueError> for ErasedError {
+    fn from(err: QueueError) -> Self {
+        match err {
+            QueueError::Full => {
+                Self::new(ErrorKind::Busy).with_context("motion queue full")
+            }
+            QueueError::TravelLimit(err) => err.into(),
+        }
+    }
+}
+
+impl From<TimedMoveError> for ErasedError {
+    fn from(err: TimedMoveError) -> Self {
+        match err {
+            TimedMoveError::Busy => Self::new(ErrorKind::Busy),
+            TimedMoveError::TooShort => Self::new(ErrorKind::InvalidValue)
+                .with_context("duration too short for motion"),
+            TimedMoveError::TravelLimit(err) => err.into(),
+        }
+    }
+}
+
+impl<MotionError, FeedbackError>
+    From<ClosedLoopError<MotionError, FeedbackError>> for ErasedError
+where
+    MotionError: Into<ErasedError>,
+{
+    fn from(err: ClosedLoopError<MotionError, FeedbackError>) -> Self {
+        match err {
+            ClosedLoopError::Motion(err) => err.into(),
+            ClosedLoopError::Feedback(_) => Self::new(ErrorKind::Sensor)
+                .with_context("reading position feedback"),
+            ClosedLoopError::CorrectionFailed(_) => {
+                Self::new(ErrorKind::StepLoss)
+                    .with_context("failed to correct deviation")
+            }
+            ClosedLoopError::StepLoss(_) => Self::new(ErrorKind::StepLoss),
+        }
+    }
+}
+
+impl<MotionControlError, MinError, MaxError>
+    From<LimitMonitorError<MotionControlError, MinError, MaxError>>
+    for ErasedError
+where
+    MotionControlError: Into<ErasedError>,
+{
+    fn from(
+        err: LimitMonitorError<MotionControlError, MinError, MaxError>,
+    ) -> Self {
+        match err {
+            LimitMonitorError::MotionControl(err) => err.into(),
+            LimitMonitorError::LimitSwitch(err) => err.into(),
+            LimitMonitorError::LimitReached(_) => Self::new(ErrorKind::Limit)
+                .with_context("limit switch triggered"),
+        }
+    }
+}
+
+impl<MinError, MaxError> From<limit_switch::Error<MinError, MaxError>>
+    for ErasedError
+{
+    fn from(err: limit_switch::Error<MinError, MaxError>) -> Self {
+        let context = match err {
+            limit_switch::Error::Min(_) => "reading minimum limit switch",
+            limit_switch::Error::Max(_) => "reading maximum limit switch",
+        };
+
+        Self::new(ErrorKind::Sensor).with_context(context)
+    }
+}
+
+impl<MotionControlError, MinError, MaxError>
+    From<HomingError<MotionControlError, MinError, MaxError>> for ErasedError
+where
+    MotionControlError: Into<ErasedError>,
+{
+    fn from(err: HomingError<MotionControlError, MinError, MaxError>) -> Self {
+        match err {
+            HomingError::MotionControl(err) => err.into(),
+            HomingError::LimitSwitch(err) => err.into(),
+            HomingError::SwitchNotFound => Self::new(ErrorKind::Homing)
+                .with_context("limit switch not found"),
+            HomingError::SwitchStillTriggered => Self::new(ErrorKind::Homing)
+                .with_context("limit switch still triggered"),
+        }
+    }
+}
+
+impl<MotionControlError, InputError>
+    From<ProbeError<MotionControlError, InputError>> for ErasedError
+where
+    MotionControlError: Into<ErasedError>,
+{
+    fn from(err: ProbeError<MotionControlError, InputError>) -> Self {
+        match err {
+            ProbeError::MotionControl(err) => err.into(),
+            ProbeError::Probe(_) => {
+                Self::new(ErrorKind::Sensor).with_context("reading probe")
+            }
+            ProbeError::NotTriggered => {
+                Self::new(ErrorKind::Homing).with_context("probe not triggered")
+            }
+        }
+    }
+}
+
+impl<StepModeError, DirectionError>
+    From<BuildError<StepModeError, DirectionError>> for ErasedError
+where
+    StepModeError: Into<ErasedError>,
+    DirectionError: Into<ErasedError>,
+{
+    fn from(err: BuildError<StepModeError, DirectionError>) -> Self {
+        match err {
+            BuildError::StepMode(err) => {
+                err.into().with_context("setting initial step mode")
+            }
+            BuildError::Direction(err) => {
+                err.into().with_context("setting initial direction")
+            }
+        }
+    }
+}
+
+impl<First, Second> From<GantryError<First, Second>> for ErasedError
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+{
+    fn from(err: GantryError<First, Second>) -> Self {
+        match err {
+            GantryError::FirstDir(err) => {
+                Self::from(err).with_context("DIR signal of first driver")
+            }
+            GantryError::FirstStep(err) => {
+                Self::from(err).with_context("STEP signal of first driver")
+            }
+            GantryError::SecondDir(err) => {
+                Self::from(err).with_context("DIR signal of second driver")
+            }
+            GantryError::SecondStep(err) => {
+                Self::from(err).with_context("STEP signal of second driver")
+            }
+        }
+    }
+}
+
+impl From<MissingCapability> for ErasedError {
+    fn from(err: MissingCapability) -> Self {
+        let context = match err {
+            MissingCapability::Direction => "no DIR pin attached",
+            MissingCapability::Step => "no STEP pin attached",
+            MissingCapability::Output => "no enable pin attached",
+        };
+
+        Self::new(ErrorKind::PinUnavailable).with_context(context)
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl From<crate::mock::MockError> for ErasedError {
+    fn from(_: crate::mock::MockError) -> Self {
+        Self::new(ErrorKind::Driver).with_context("injected error")
+    }
+}
+
+impl From<InvalidStepModeError> for ErasedError {
+    fn from(_: InvalidStepModeError) -> Self {
+        Self::new(ErrorKind::InvalidValue).with_context("invalid step mode")
+    }
+}
+
+impl From<NoDirectionError> for ErasedError {
+    fn from(_: NoDirectionError) -> Self {
+        Self::new(ErrorKind::InvalidValue)
+            .with_context("step delta of zero has no direction")
+    }
+}
+
+impl From<InvalidSweepError> for ErasedError {
+    fn from(_: InvalidSweepError) -> Self {
+        Self::new(ErrorKind::InvalidValue)
+            .with_context("arc sweep angle is not finite")
+    }
+}
+
+impl From<CommandPendingError> for ErasedError {
+    fn from(_: CommandPendingError) -> Self {
+        Self::new(ErrorKind::Busy).with_context("previous command pending")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use crate::{motion_control, SignalError};
+
+    use super::{ErasedError, ErrorKind};
+
+    #[test]
+    fn motion_control_errors_should_keep_kind_and_context() {
+        fn update() -> Result<(), ErasedError> {
+            let err: motion_control::Error<(), (), (), (), (), Infallible> =
+                motion_control::Error::Step(SignalError::Pin(()));
+            Err(err)?
+        }
+
+        let err = update().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Pin);
+        assert_eq!(err.context(), Some("stepping"));
+    }
+}
@@ -0,0 +1,329 @@
+//! Two motors driving one axis
+//!
+//! See [`Gantry`] for more information.
+
+use core::{convert::Infallible, fmt};
+
+use embedded_hal::digital::{self, ErrorType, OutputPin};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    traits::{SetDirection, Step},
+    SignalError,
+};
+
+/// Drives two drivers as one logical axis
+///
+/// Many machines move one axis with two motors, one on each side of a gantry.
+/// `Gantry` wraps the two drivers and acts like a single driver that implements
+/// [`SetDirection`] and [`Step`]. Every change to the DIR and STEP signals is
+/// applied to both drivers, so it can be used with [`Stepper`] and
+/// [`SoftwareMotionControl`], like any other driver.
+///
+/// If the motors face each other, they need to turn in opposite directions.
+/// Use [`Gantry::mirrored`] for that, which inverts the DIR signal of the
+/// second driver.
+///
+/// [`Stepper`]: crate::Stepper
+/// [`SoftwareMotionControl`]: crate::motion_control::SoftwareMotionControl
+pub struct Gantry<First, Second> {
+    pins: GantryPin<First, Second>,
+}
+
+impl<First, Second> Gantry<First, Second> {
+    /// Create a gantry whose motors turn in the same direction
+    pub fn new(first: First, second: Second) -> Self {
+        Self::with_mirroring(first, second, false)
+    }
+
+    /// Create a gantry whose motors turn in opposite directions
+    pub fn mirrored(first: First, second: Second) -> Self {
+        Self::with_mirroring(first, second, true)
+    }
+
+    fn with_mirroring(first: First, second: Second, mirrored: bool) -> Self {
+        Self {
+            pins: GantryPin {
+                first,
+                second,
+                mirrored,
+                signal: Signal::Dir,
+            },
+        }
+    }
+
+    /// Access a mutable reference to the first driver
+    pub fn first_mut(&mut self) -> &mut First {
+        &mut self.pins.first
+    }
+
+    /// Access a mutable reference to the second driver
+    pub fn second_mut(&mut self) -> &mut Second {
+        &mut self.pins.second
+    }
+
+    /// Release the wrapped drivers
+    pub fn release(self) -> (First, Second) {
+        (self.pins.first, self.pins.second)
+    }
+}
+
+impl<First, Second> SetDirection for Gantry<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+    GantryError<First, Second>: digital::Error,
+{
+    const SETUP_TIME: Nanoseconds = max(First::SETUP_TIME, Second::SETUP_TIME);
+
+    type Dir = GantryPin<First, Second>;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        self.pins.signal = Signal::Dir;
+        Ok(&mut self.pins)
+    }
+}
+
+impl<First, Second> Step for Gantry<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+    GantryError<First, Second>: digital::Error,
+{
+    const PULSE_LENGTH: Nanoseconds =
+        max(First::PULSE_LENGTH, Second::PULSE_LENGTH);
+
+    type Step = GantryPin<First, Second>;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        self.pins.signal = Signal::Step;
+        Ok(&mut self.pins)
+    }
+}
+
+/// The DIR or STEP pin of a [`Gantry`]
+///
+/// Sets the respective signal of both drivers.
+pub struct GantryPin<First, Second> {
+    first: First,
+    second: Second,
+    mirrored: bool,
+    signal: Signal,
+}
+
+impl<First, Second> GantryPin<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+{
+    fn set(&mut self, high: bool) -> Result<(), GantryError<First, Second>> {
+        match self.signal {
+            Signal::Dir => {
+                set(self.first.dir(), high).map_err(GantryError::FirstDir)?;
+                set(self.second.dir(), high != self.mirrored)
+                    .map_err(GantryError::SecondDir)?;
+            }
+            Signal::Step => {
+                set(self.first.step(), high).map_err(GantryError::FirstStep)?;
+                set(self.second.step(), high)
+                    .map_err(GantryError::SecondStep)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<First, Second> ErrorType for GantryPin<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+    GantryError<First, Second>: digital::Error,
+{
+    type Error = GantryError<First, Second>;
+}
+
+impl<First, Second> OutputPin for GantryPin<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+    GantryError<First, Second>: digital::Error,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set(false)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set(true)
+    }
+}
+
+/// An error that occurred while setting a signal of a [`Gantry`]
+///
+/// Identifies which driver and which signal the error originated from.
+pub enum GantryError<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+{
+    /// Error while setting the DIR signal of the first driver
+    FirstDir(
+        SignalError<
+            <First as SetDirection>::Error,
+            <<First as SetDirection>::Dir as ErrorType>::Error,
+            Infallible,
+        >,
+    ),
+
+    /// Error while setting the STEP signal of the first driver
+    FirstStep(
+        SignalError<
+            <First as Step>::Error,
+            <<First as Step>::Step as ErrorType>::Error,
+            Infallible,
+        >,
+    ),
+
+    /// Error while setting the DIR signal of the second driver
+    SecondDir(
+        SignalError<
+            <Second as SetDirection>::Error,
+            <<Second as SetDirection>::Dir as ErrorType>::Error,
+            Infallible,
+        >,
+    ),
+
+    /// Error while setting the STEP signal of the second driver
+    SecondStep(
+        SignalError<
+            <Second as Step>::Error,
+            <<Second as Step>::Step as ErrorType>::Error,
+            Infallible,
+        >,
+    ),
+}
+
+impl<First, Second> fmt::Debug for GantryError<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+    <First as SetDirection>::Error: fmt::Debug,
+    <First as Step>::Error: fmt::Debug,
+    <Second as SetDirection>::Error: fmt::Debug,
+    <Second as Step>::Error: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FirstDir(err) => {
+                f.debug_tuple("FirstDir").field(err).finish()
+            }
+            Self::FirstStep(err) => {
+                f.debug_tuple("FirstStep").field(err).finish()
+            }
+            Self::SecondDir(err) => {
+                f.debug_tuple("SecondDir").field(err).finish()
+            }
+            Self::SecondStep(err) => {
+                f.debug_tuple("SecondStep").field(err).finish()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<First, Second> defmt::Format for GantryError<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+    <First as SetDirection>::Error: defmt::Format,
+    <<First as SetDirection>::Dir as ErrorType>::Error: defmt::Format,
+    <First as Step>::Error: defmt::Format,
+    <<First as Step>::Step as ErrorType>::Error: defmt::Format,
+    <Second as SetDirection>::Error: defmt::Format,
+    <<Second as SetDirection>::Dir as ErrorType>::Error: defmt::Format,
+    <Second as Step>::Error: defmt::Format,
+    <<Second as Step>::Step as ErrorType>::Error: defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Self::FirstDir(err) => defmt::write!(f, "FirstDir({})", err),
+            Self::FirstStep(err) => defmt::write!(f, "FirstStep({})", err),
+            Self::SecondDir(err) => defmt::write!(f, "SecondDir({})", err),
+            Self::SecondStep(err) => defmt::write!(f, "SecondStep({})", err),
+        }
+    }
+}
+
+impl<First, Second> digital::Error for GantryError<First, Second>
+where
+    First: SetDirection + Step,
+    Second: SetDirection + Step,
+    Self: fmt::Debug,
+{
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+enum Signal {
+    Dir,
+    Step,
+}
+
+fn set<Pin, PinUnavailableError>(
+    pin: Result<&mut Pin, PinUnavailableError>,
+    high: bool,
+) -> Result<(), SignalError<PinUnavailableError, Pin::Error, Infallible>>
+where
+    Pin: OutputPin,
+{
+    let pin = pin.map_err(SignalError::PinUnavailable)?;
+    let result = if high { pin.set_high() } else { pin.set_low() };
+    result.map_err(SignalError::Pin)
+}
+
+const fn max(a: Nanoseconds, b: Nanoseconds) -> Nanoseconds {
+    if a.ticks() > b.ticks() {
+        a
+    } else {
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::digital::OutputPin;
+
+    use crate::{
+        mock::{MockDriver, Signal, MOCK_DELAY},
+        traits::{SetDirection, Step},
+    };
+
+    use super::Gantry;
+
+    #[test]
+    fn gantry_should_set_signals_of_both_drivers() {
+        let (first, second) = (MockDriver::new(), MockDriver::new());
+
+        let mut gantry = Gantry::mirrored(first.clone(), second.clone());
+        assert_eq!(
+            <Gantry<MockDriver, MockDriver> as Step>::PULSE_LENGTH,
+            MOCK_DELAY
+        );
+
+        gantry.dir().unwrap().set_high().unwrap();
+        gantry.step().unwrap().set_high().unwrap();
+
+        let signals = |driver: &MockDriver| -> Vec<_> {
+            driver
+                .transitions()
+                .into_iter()
+                .map(|transition| transition.signal)
+                .collect()
+        };
+        assert_eq!(signals(&first), [Signal::Dir(true), Signal::Step(true)]);
+        assert_eq!(signals(&second), [Signal::Dir(false), Signal::Step(true)]);
+    }
+}
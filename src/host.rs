@@ -0,0 +1,124 @@
+//! Support for running on hosts with the standard library
+//!
+//! Only available, if the `std` feature is enabled. Provides implementations
+//! of the timing traits that Stepper requires, based on [`std::time`]. This
+//! allows running the full `Stepper` API on Linux single-board computers, like
+//! the Raspberry Pi, and in host-side integration tests.
+//!
+//! No GPIO adapters are required for [linux-embedded-hal]: Its pins implement
+//! the `embedded-hal` 1.0 traits since version 0.4, and the pins of earlier
+//! versions can be wrapped using [`compat::Pin`] and [`compat::Input`].
+//!
+//! [linux-embedded-hal]: https://crates.io/crates/linux-embedded-hal
+//! [`compat::Pin`]: crate::compat::Pin
+//! [`compat::Input`]: crate::compat::Input
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use embedded_hal::delay::DelayNs;
+use fugit::{
+    TimerDurationU32 as TimerDuration, TimerInstantU32 as TimerInstant,
+};
+
+use crate::compat::NotRunningError;
+
+/// A timer that is based on [`Instant`]
+///
+/// Implements [`fugit_timer::Timer`] with the given frequency. The instant
+/// returned by [`fugit_timer::Timer::now`] counts the ticks since the timer
+/// was created, and wraps around when it overflows.
+pub struct Timer<const TIMER_HZ: u32> {
+    created: Instant,
+    deadline: Option<Instant>,
+}
+
+impl<const TIMER_HZ: u32> Timer<TIMER_HZ> {
+    /// Create a new instance of `Timer`
+    pub fn new() -> Self {
+        Self {
+            created: Instant::now(),
+            deadline: None,
+        }
+    }
+}
+
+impl<const TIMER_HZ: u32> Default for Timer<TIMER_HZ> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const TIMER_HZ: u32> fugit_timer::Timer<TIMER_HZ> for Timer<TIMER_HZ> {
+    type Error = NotRunningError;
+
+    fn now(&mut self) -> TimerInstant<TIMER_HZ> {
+        let nanos = self.created.elapsed().as_nanos();
+        let ticks = nanos * u128::from(TIMER_HZ) / 1_000_000_000;
+
+        TimerInstant::from_ticks(ticks as u32)
+    }
+
+    fn start(
+        &mut self,
+        duration: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        let nanos =
+            u64::from(duration.ticks()) * 1_000_000_000 / u64::from(TIMER_HZ);
+
+        self.deadline = Some(Instant::now() + Duration::from_nanos(nanos));
+        Ok(())
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.deadline.take().map(|_| ()).ok_or(NotRunningError)
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        let deadline = self.deadline.ok_or(NotRunningError)?;
+
+        if Instant::now() < deadline {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.deadline = None;
+        Ok(())
+    }
+}
+
+/// A delay that puts the current thread to sleep
+///
+/// Implements [`DelayNs`], for use with the blocking methods of [`Stepper`].
+/// The operating system might let the thread sleep for longer than requested,
+/// which is fine for the minimum delays that Stepper uses it for.
+///
+/// [`Stepper`]: crate::Stepper
+pub struct Delay;
+
+impl DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        thread::sleep(Duration::from_nanos(u64::from(ns)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fugit::TimerDurationU32 as TimerDuration;
+    use fugit_timer::Timer as _;
+
+    use super::Timer;
+
+    #[test]
+    fn timer_should_expire_after_duration() {
+        let mut timer = Timer::<1_000_000>::new();
+
+        let start = timer.now();
+        timer.start(TimerDuration::from_ticks(1000)).unwrap();
+        nb::block!(timer.wait()).unwrap();
+
+        assert!((timer.now() - start).ticks() >= 1000);
+        assert!(timer.cancel().is_err());
+    }
+}
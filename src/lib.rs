@@ -10,7 +10,31 @@
 //! - [A4988](crate::drivers::a4988::A4988)
 //! - [DRV8825](crate::drivers::drv8825::DRV8825)
 //! - [STSPIN220](crate::drivers::stspin220::STSPIN220)
-//! - [DQ542MA](crate::drivers::dq542ma::DQ542MA)
+//! - [TMC5160](crate::drivers::tmc5160::TMC5160)
+//! - [TMC2209](crate::drivers::tmc2209::TMC2209)
+//! - [L6470](crate::drivers::l6470::L6470)
+//! - [TMC2130](crate::drivers::tmc2130::TMC2130)
+//! - [ULN2003](crate::drivers::uln2003::ULN2003)
+//! - [AMIS-30543](crate::drivers::amis30543::AMIS30543)
+//! - [PowerSTEP01](crate::drivers::powerstep01::PowerSTEP01)
+//! - [DRV8834](crate::drivers::drv8834::DRV8834)
+//! - [TB6600](crate::drivers::tb6600::TB6600)
+//! - [MP6500](crate::drivers::mp6500::MP6500)
+//! - [TMC5072](crate::drivers::tmc5072::TMC5072)
+//! - [TMC4361A](crate::drivers::tmc4361a::TMC4361A)
+//! - [DRV8880](crate::drivers::drv8880::DRV8880)
+//! - [A3967](crate::drivers::a3967::A3967) (EasyDriver)
+//! - [TB67S128FTG](crate::drivers::tb67s128ftg::TB67S128FTG)
+//! - [L298N](crate::drivers::l298n::L298N)
+//! - [DRV8426/DRV8424](crate::drivers::drv8426::DRV8426)
+//! - [TMC2660](crate::drivers::tmc2660::TMC2660)
+//! - External driver boxes like the
+//!   [DQ542MA](crate::drivers::generic::DQ542MA),
+//!   [DM542](crate::drivers::generic::DM542),
+//!   [DM556](crate::drivers::generic::DM556), and
+//!   [DM860](crate::drivers::generic::DM860)
+//! - Any driver controlled by STEP/DIR signals with user-supplied timing, via
+//!   [GenericStepDir](crate::drivers::generic::GenericStepDir)
 //!
 //! Please check out the documentation of [`Stepper`], which is the main entry
 //! point to this API.
@@ -155,7 +179,7 @@
 //!
 //! [RampMaker]: https://crates.io/crates/ramp-maker
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![deny(missing_docs, rustdoc::broken_intra_doc_links)]
 
 pub extern crate embedded_hal;
@@ -163,18 +187,33 @@ pub extern crate fugit;
 pub extern crate ramp_maker;
 
 pub mod compat;
+pub mod coordinated;
 pub mod drivers;
+pub mod dynamic;
+pub mod error;
+pub mod gantry;
+#[cfg(feature = "std")]
+pub mod host;
+pub mod limit_switch;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
 pub mod motion_control;
 pub mod step_mode;
 pub mod traits;
+pub mod units;
 pub mod util;
 
+mod about;
 mod stepper;
 
-pub use self::stepper::*;
+pub use self::{
+    about::{about, About},
+    stepper::*,
+};
 
 /// Defines the direction in which to rotate the motor
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Direction {
     /// Rotate the motor forward
     ///
@@ -188,3 +227,109 @@ pub enum Direction {
     /// driver's DIR signal set is LOW.
     Backward = -1,
 }
+
+impl Direction {
+    /// Returns the opposite direction
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::Forward => Self::Backward,
+            Self::Backward => Self::Forward,
+        }
+    }
+}
+
+impl From<Direction> for i8 {
+    /// Returns `1` for [`Direction::Forward`], `-1` for [`Direction::Backward`]
+    fn from(direction: Direction) -> Self {
+        direction as i8
+    }
+}
+
+impl From<Direction> for i32 {
+    /// Returns `1` for [`Direction::Forward`], `-1` for [`Direction::Backward`]
+    fn from(direction: Direction) -> Self {
+        direction as i32
+    }
+}
+
+impl core::convert::TryFrom<i32> for Direction {
+    type Error = NoDirectionError;
+
+    /// Returns the direction of a step delta
+    ///
+    /// Positive deltas move forward, negative ones backward. A delta of zero
+    /// doesn't have a direction.
+    fn try_from(delta: i32) -> Result<Self, Self::Error> {
+        match delta.signum() {
+            1 => Ok(Self::Forward),
+            -1 => Ok(Self::Backward),
+            _ => Err(NoDirectionError),
+        }
+    }
+}
+
+impl core::ops::Mul<i32> for Direction {
+    type Output = i32;
+
+    /// Turns a number of steps into a step delta in this direction
+    fn mul(self, steps: i32) -> Self::Output {
+        steps * self as i32
+    }
+}
+
+impl core::ops::Mul<Direction> for i32 {
+    type Output = i32;
+
+    /// Turns a number of steps into a step delta in the given direction
+    fn mul(self, direction: Direction) -> Self::Output {
+        direction * self
+    }
+}
+
+impl core::ops::Mul<u32> for Direction {
+    type Output = i64;
+
+    /// Turns a number of steps into a step delta in this direction
+    ///
+    /// Returns an `i64`, as every `u32` fits into that in both directions.
+    fn mul(self, steps: u32) -> Self::Output {
+        i64::from(steps) * self as i64
+    }
+}
+
+impl core::ops::Mul<Direction> for u32 {
+    type Output = i64;
+
+    /// Turns a number of steps into a step delta in the given direction
+    fn mul(self, direction: Direction) -> Self::Output {
+        direction * self
+    }
+}
+
+/// A step delta of zero doesn't have a direction
+///
+/// Returned by the `TryFrom<i32>` implementation of [`Direction`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NoDirectionError;
+
+impl core::fmt::Display for NoDirectionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "step delta of zero has no direction")
+    }
+}
+
+/// Defines the power mode of a driver
+///
+/// Used with [`Stepper::set_power_mode`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PowerMode {
+    /// The driver is operating normally
+    Normal,
+
+    /// The driver is in its low-power sleep or standby mode
+    ///
+    /// Most of the driver's internal circuitry is disabled, and the motor is
+    /// not held in place.
+    Sleep,
+}
@@ -0,0 +1,270 @@
+//! Limit switch support
+//!
+//! Limit switches (also called endstops) are mounted at the ends of an axis,
+//! to detect when the motor has reached the end of its travel range. This
+//! module provides the [`LimitSwitch`] trait, which abstracts over them, and
+//! [`Endstop`], an implementation based on an [`InputPin`].
+//!
+//! Limit switches can be attached to a [`Stepper`] using
+//! [`Stepper::attach_limit_switches`].
+//!
+//! [`Stepper`]: crate::Stepper
+//! [`Stepper::attach_limit_switches`]: crate::Stepper::attach_limit_switches
+
+use core::{convert::Infallible, fmt};
+
+use embedded_hal::digital::InputPin;
+
+use crate::Direction;
+
+/// Implemented by limit switches
+///
+/// This crate provides [`Endstop`], which implements this trait for any
+/// [`InputPin`]. Users can implement this trait themselves, if they require
+/// anything more involved, like a stall detection signal or a sensor that is
+/// read over a bus.
+pub trait LimitSwitch {
+    /// The error that can occur while reading the limit switch
+    type Error;
+
+    /// Indicates whether the limit switch is currently triggered
+    ///
+    /// Implementations that debounce their input expect this method to be
+    /// called regularly.
+    fn is_triggered(&mut self) -> Result<bool, Self::Error>;
+}
+
+/// A limit switch that is connected to an input pin
+///
+/// Uses [`NoDebounce`] by default. Use [`Endstop::with_debounce`] to
+/// configure debouncing.
+pub struct Endstop<Pin, Debounce = NoDebounce> {
+    pin: Pin,
+    polarity: Polarity,
+    debounce: Debounce,
+}
+
+impl<Pin> Endstop<Pin, NoDebounce>
+where
+    Pin: InputPin,
+{
+    /// Create a new instance of `Endstop`
+    ///
+    /// `polarity` defines which level of the pin means that the switch is
+    /// triggered.
+    pub fn new(pin: Pin, polarity: Polarity) -> Self {
+        Self {
+            pin,
+            polarity,
+            debounce: NoDebounce,
+        }
+    }
+}
+
+impl<Pin, Debounce> Endstop<Pin, Debounce> {
+    /// Debounce the input using the provided debouncer
+    pub fn with_debounce<D>(self, debounce: D) -> Endstop<Pin, D>
+    where
+        D: self::Debounce,
+    {
+        Endstop {
+            pin: self.pin,
+            polarity: self.polarity,
+            debounce,
+        }
+    }
+
+    /// Release the input pin
+    pub fn release(self) -> Pin {
+        self.pin
+    }
+}
+
+impl<Pin, Debounce> LimitSwitch for Endstop<Pin, Debounce>
+where
+    Pin: InputPin,
+    Debounce: self::Debounce,
+{
+    type Error = Pin::Error;
+
+    fn is_triggered(&mut self) -> Result<bool, Self::Error> {
+        let triggered = match self.polarity {
+            Polarity::ActiveHigh => self.pin.is_high()?,
+            Polarity::ActiveLow => self.pin.is_low()?,
+        };
+
+        Ok(self.debounce.update(triggered))
+    }
+}
+
+/// Defines which level of an input pin means that a limit switch is triggered
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Polarity {
+    /// The switch is triggered, if the pin is high
+    ActiveHigh,
+
+    /// The switch is triggered, if the pin is low
+    ///
+    /// This is typical for normally open switches that connect the pin to
+    /// ground, with a pull-up resistor.
+    ActiveLow,
+}
+
+/// Implemented by types that debounce the input of a limit switch
+pub trait Debounce {
+    /// Update the debouncer with a new sample
+    ///
+    /// Returns the debounced state.
+    fn update(&mut self, triggered: bool) -> bool;
+}
+
+/// Doesn't debounce the input
+///
+/// Suitable for switches that are debounced in hardware, or for optical
+/// switches, which don't bounce.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct NoDebounce;
+
+impl Debounce for NoDebounce {
+    fn update(&mut self, triggered: bool) -> bool {
+        triggered
+    }
+}
+
+/// Debounces the input by requiring a number of consecutive, equal samples
+///
+/// The debounced state only changes after the raw input has had the new state
+/// for the configured number of samples. How much time this amounts to depends
+/// on how often the limit switch is read.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Consecutive {
+    required: u8,
+    count: u8,
+    state: bool,
+}
+
+impl Consecutive {
+    /// Create a new instance of `Consecutive`
+    ///
+    /// `required` is the number of consecutive samples that are required for
+    /// a change of state.
+    pub fn new(required: u8) -> Self {
+        Self {
+            required,
+            count: 0,
+            state: false,
+        }
+    }
+}
+
+impl Debounce for Consecutive {
+    fn update(&mut self, triggered: bool) -> bool {
+        if triggered == self.state {
+            self.count = 0;
+            return self.state;
+        }
+
+        self.count += 1;
+        if self.count >= self.required {
+            self.state = triggered;
+            self.count = 0;
+        }
+
+        self.state
+    }
+}
+
+/// A placeholder for a limit switch that doesn't exist
+///
+/// Can be used with [`LimitSwitches`], if an axis only has a limit switch on
+/// one end. It is never triggered.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct NoLimitSwitch;
+
+impl LimitSwitch for NoLimitSwitch {
+    type Error = Infallible;
+
+    fn is_triggered(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+}
+
+/// The limit switches at both ends of an axis
+///
+/// Created by [`Stepper::attach_limit_switches`].
+///
+/// [`Stepper::attach_limit_switches`]: crate::Stepper::attach_limit_switches
+pub struct LimitSwitches<Min, Max> {
+    /// The limit switch at the minimum position
+    ///
+    /// This is the switch that the motor reaches when moving backward.
+    pub min: Min,
+
+    /// The limit switch at the maximum position
+    ///
+    /// This is the switch that the motor reaches when moving forward.
+    pub max: Max,
+}
+
+impl<Min, Max> LimitSwitches<Min, Max>
+where
+    Min: LimitSwitch,
+    Max: LimitSwitch,
+{
+    /// Indicates whether the limit in the given direction has been reached
+    pub fn is_triggered(
+        &mut self,
+        direction: Direction,
+    ) -> Result<bool, Error<Min::Error, Max::Error>> {
+        match direction {
+            Direction::Forward => self.max.is_triggered().map_err(Error::Max),
+            Direction::Backward => self.min.is_triggered().map_err(Error::Min),
+        }
+    }
+}
+
+/// An error that can occur while reading [`LimitSwitches`]
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<MinError, MaxError> {
+    /// Error while reading the limit switch at the minimum position
+    Min(MinError),
+
+    /// Error while reading the limit switch at the maximum position
+    Max(MaxError),
+}
+
+impl<MinError, MaxError> fmt::Display for Error<MinError, MaxError>
+where
+    MinError: fmt::Debug,
+    MaxError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Min(err) => {
+                write!(f, "error reading minimum limit switch: {:?}", err)
+            }
+            Self::Max(err) => {
+                write!(f, "error reading maximum limit switch: {:?}", err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Consecutive, Debounce as _};
+
+    #[test]
+    fn consecutive_should_require_stable_input() {
+        let mut debounce = Consecutive::new(3);
+
+        assert!(!debounce.update(true));
+        assert!(!debounce.update(false));
+        assert!(!debounce.update(true));
+        assert!(!debounce.update(true));
+        assert!(debounce.update(true));
+        assert!(debounce.update(false));
+        assert!(debounce.update(true));
+    }
+}
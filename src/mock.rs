@@ -0,0 +1,709 @@
+//! Simulated driver for testing
+//!
+//! Only available, if the `test-util` feature is enabled. [`MockDriver`]
+//! implements all driver traits without any hardware, and records every
+//! signal it receives, so applications can unit-test their motion logic on
+//! the host. [`MockMotionControl`] simulates a motion controller, for testing
+//! code that only depends on [`MotionControl`].
+
+use std::{
+    cell::RefCell, collections::VecDeque, convert::Infallible, fmt, rc::Rc,
+    vec::Vec,
+};
+
+use embedded_hal::digital::{self, ErrorType, OutputPin};
+use fugit::{
+    NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+    TimerInstantU32 as TimerInstant,
+};
+
+use crate::{
+    compat::NotRunningError,
+    drivers::UnspecifiedFault,
+    step_mode::StepMode256,
+    traits::{
+        DetectStall, EnableDirectionControl, EnableFaultDetection,
+        EnableOutputControl, EnableSleepControl, EnableStepControl,
+        EnableStepModeControl, GeneratePulseTrain, GetStepMode, MotionControl,
+        ReadFault, ReadVelocity, SetCurrent, SetDirection, SetOutputEnabled,
+        SetPowerMode, SetStepMode, Step,
+    },
+    PowerMode,
+};
+
+/// The length of all delays that [`MockDriver`] requires
+///
+/// Used for the DIR setup time, the STEP pulse length, and the timing of step
+/// mode changes and waking up from sleep mode.
+pub const MOCK_DELAY: Nanoseconds = Nanoseconds::from_ticks(1000);
+
+/// The number of pulses that the pulse train queue of [`MockDriver`] can hold
+///
+/// Small enough that [`GeneratePulseTrain::queue_pulses`] doesn't accept all
+/// delays at once, to exercise the buffering of the caller.
+const MOCK_PULSE_QUEUE: usize = 5;
+
+/// A simulated driver
+///
+/// Implements all driver traits, including the ones for enabling
+/// capabilities, which take `()` as their resources. Every signal that is
+/// sent to the driver is recorded as a [`Transition`], together with the time
+/// of the [`MockTimer`]s created from the same driver. Errors and faults can be
+/// injected, to test error handling.
+///
+/// As a [`GeneratePulseTrain`] implementation, the driver generates one
+/// queued pulse every time [`GeneratePulseTrain::pending_pulses`] is called.
+/// The time advances by the pulse's delay, and the pulse is recorded as
+/// `Signal::Step(true)`.
+///
+/// The driver is cheap to clone. All clones share the same recording.
+///
+/// ``` rust
+/// use stepper::{mock::{MockDriver, Signal}, Direction, Stepper};
+///
+/// let driver = MockDriver::new();
+/// let mut timer = driver.timer::<1_000_000>();
+/// let mut stepper = Stepper::from_driver(driver);
+///
+/// stepper.set_direction(Direction::Backward, &mut timer).wait().unwrap();
+/// stepper.step(&mut timer).wait().unwrap();
+///
+/// let signals: Vec<_> = stepper
+///     .driver()
+///     .transitions()
+///     .into_iter()
+///     .map(|transition| transition.signal)
+///     .collect();
+/// assert_eq!(
+///     signals,
+///     [Signal::Dir(false), Signal::Step(true), Signal::Step(false)],
+/// );
+/// ```
+#[derive(Clone)]
+pub struct MockDriver {
+    dir: MockPin,
+    step: MockPin,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl MockDriver {
+    /// Create a new instance of `MockDriver`
+    pub fn new() -> Self {
+        let shared = Rc::new(RefCell::new(Shared {
+            now_ns: 0,
+            transitions: Vec::new(),
+            fail_after: None,
+            step_mode: None,
+            fault: false,
+            stalled: false,
+            pulses: VecDeque::new(),
+        }));
+
+        Self {
+            dir: MockPin {
+                signal: Signal::Dir,
+                shared: shared.clone(),
+            },
+            step: MockPin {
+                signal: Signal::Step,
+                shared: shared.clone(),
+            },
+            shared,
+        }
+    }
+
+    /// Create a timer that shares its time with this driver
+    ///
+    /// The timer doesn't actually wait. Once its `wait` method is called, it
+    /// advances the time to the end of the running delay, and returns right
+    /// away.
+    pub fn timer<const TIMER_HZ: u32>(&self) -> MockTimer<TIMER_HZ> {
+        MockTimer {
+            deadline_ns: None,
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Return all transitions that have been recorded so far
+    pub fn transitions(&self) -> Vec<Transition> {
+        self.shared.borrow().transitions.clone()
+    }
+
+    /// Remove all recorded transitions
+    pub fn clear_transitions(&self) {
+        self.shared.borrow_mut().transitions.clear();
+    }
+
+    /// Fail after the given number of signals has been received successfully
+    ///
+    /// The signal after that results in a [`MockError`], and isn't recorded.
+    /// Pass `0` to fail the next signal.
+    pub fn fail_after(&self, signals: usize) {
+        self.shared.borrow_mut().fail_after = Some(signals);
+    }
+
+    /// Set whether the driver signals a fault
+    ///
+    /// See [`ReadFault`].
+    pub fn set_fault(&self, fault: bool) {
+        self.shared.borrow_mut().fault = fault;
+    }
+
+    /// Set whether the driver signals a stalled motor
+    ///
+    /// See [`DetectStall`].
+    pub fn set_stalled(&self, stalled: bool) {
+        self.shared.borrow_mut().stalled = stalled;
+    }
+
+    fn record(&mut self, signal: Signal) -> Result<(), MockError> {
+        self.shared.borrow_mut().record(signal)
+    }
+}
+
+impl Default for MockDriver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EnableDirectionControl<()> for MockDriver {
+    type WithDirectionControl = Self;
+
+    fn enable_direction_control(self, _: ()) -> Self::WithDirectionControl {
+        self
+    }
+}
+
+impl SetDirection for MockDriver {
+    const SETUP_TIME: Nanoseconds = MOCK_DELAY;
+
+    type Dir = MockPin;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl EnableStepControl<()> for MockDriver {
+    type WithStepControl = Self;
+
+    fn enable_step_control(self, _: ()) -> Self::WithStepControl {
+        self
+    }
+}
+
+impl Step for MockDriver {
+    const PULSE_LENGTH: Nanoseconds = MOCK_DELAY;
+
+    type Step = MockPin;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+impl EnableStepModeControl<()> for MockDriver {
+    type WithStepModeControl = Self;
+
+    fn enable_step_mode_control(self, _: ()) -> Self::WithStepModeControl {
+        self
+    }
+}
+
+impl SetStepMode for MockDriver {
+    const SETUP_TIME: Nanoseconds = MOCK_DELAY;
+    const HOLD_TIME: Nanoseconds = MOCK_DELAY;
+
+    type Error = MockError;
+    type StepMode = StepMode256;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        self.record(Signal::StepMode(step_mode.into()))?;
+        self.shared.borrow_mut().step_mode = Some(step_mode);
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl GetStepMode for MockDriver {
+    type StepMode = StepMode256;
+
+    fn step_mode(&self) -> Option<Self::StepMode> {
+        self.shared.borrow().step_mode
+    }
+}
+
+impl EnableOutputControl<()> for MockDriver {
+    type WithOutputControl = Self;
+
+    fn enable_output_control(self, _: ()) -> Self::WithOutputControl {
+        self
+    }
+}
+
+impl SetOutputEnabled for MockDriver {
+    type Error = MockError;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.record(Signal::OutputEnabled(enabled))
+    }
+}
+
+impl EnableSleepControl<()> for MockDriver {
+    type WithSleepControl = Self;
+
+    fn enable_sleep_control(self, _: ()) -> Self::WithSleepControl {
+        self
+    }
+}
+
+impl SetPowerMode for MockDriver {
+    const WAKE_UP_TIME: Nanoseconds = MOCK_DELAY;
+
+    type Error = MockError;
+
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error> {
+        self.record(Signal::PowerMode(mode))
+    }
+}
+
+impl EnableFaultDetection<()> for MockDriver {
+    type WithFaultDetection = Self;
+
+    fn enable_fault_detection(self, _: ()) -> Self::WithFaultDetection {
+        self
+    }
+}
+
+impl ReadFault for MockDriver {
+    type Fault = UnspecifiedFault;
+    type Error = Infallible;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        let fault = self.shared.borrow().fault;
+        Ok(fault.then_some(UnspecifiedFault))
+    }
+}
+
+impl DetectStall for MockDriver {
+    type Error = Infallible;
+
+    fn is_stalled(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.shared.borrow().stalled)
+    }
+}
+
+impl SetCurrent for MockDriver {
+    type Current = u32;
+    type Error = MockError;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        self.record(Signal::Current(current))
+    }
+}
+
+impl<const TIMER_HZ: u32> GeneratePulseTrain<TIMER_HZ> for MockDriver {
+    type Error = MockError;
+
+    fn queue_pulses(
+        &mut self,
+        delays: &[TimerDuration<TIMER_HZ>],
+    ) -> Result<usize, Self::Error> {
+        let mut shared = self.shared.borrow_mut();
+
+        let accepted = delays.len().min(MOCK_PULSE_QUEUE - shared.pulses.len());
+        shared.pulses.extend(delays[..accepted].iter().map(|delay| {
+            u64::from(delay.ticks()) * 1_000_000_000 / u64::from(TIMER_HZ)
+        }));
+
+        Ok(accepted)
+    }
+
+    fn pending_pulses(&mut self) -> Result<u32, Self::Error> {
+        let mut shared = self.shared.borrow_mut();
+
+        if let Some(delay_ns) = shared.pulses.pop_front() {
+            shared.now_ns += delay_ns;
+            shared.record(Signal::Step(true))?;
+        }
+
+        Ok(shared.pulses.len() as u32)
+    }
+
+    fn clear_pulses(&mut self) -> Result<u32, Self::Error> {
+        let mut shared = self.shared.borrow_mut();
+
+        let removed = shared.pulses.len() as u32;
+        shared.pulses.clear();
+
+        Ok(removed)
+    }
+}
+
+/// A simulated output pin of [`MockDriver`]
+///
+/// Records every change of its level as a [`Transition`].
+#[derive(Clone)]
+pub struct MockPin {
+    signal: fn(bool) -> Signal,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl ErrorType for MockPin {
+    type Error = MockError;
+}
+
+impl OutputPin for MockPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.shared.borrow_mut().record((self.signal)(false))
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.shared.borrow_mut().record((self.signal)(true))
+    }
+}
+
+/// A simulated timer that shares its time with a [`MockDriver`]
+///
+/// Created by [`MockDriver::timer`].
+pub struct MockTimer<const TIMER_HZ: u32> {
+    deadline_ns: Option<u64>,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl<const TIMER_HZ: u32> fugit_timer::Timer<TIMER_HZ> for MockTimer<TIMER_HZ> {
+    type Error = NotRunningError;
+
+    fn now(&mut self) -> TimerInstant<TIMER_HZ> {
+        let now_ns = u128::from(self.shared.borrow().now_ns);
+        let ticks = now_ns * u128::from(TIMER_HZ) / 1_000_000_000;
+
+        TimerInstant::from_ticks(ticks as u32)
+    }
+
+    fn start(
+        &mut self,
+        duration: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        let duration_ns =
+            u64::from(duration.ticks()) * 1_000_000_000 / u64::from(TIMER_HZ);
+
+        self.deadline_ns = Some(self.shared.borrow().now_ns + duration_ns);
+        Ok(())
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.deadline_ns.take().map(|_| ()).ok_or(NotRunningError)
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        let deadline_ns = self.deadline_ns.take().ok_or(NotRunningError)?;
+
+        let mut shared = self.shared.borrow_mut();
+        shared.now_ns = shared.now_ns.max(deadline_ns);
+
+        Ok(())
+    }
+}
+
+/// A simulated motion controller
+///
+/// Implements [`MotionControl`], without any timing: Every call to
+/// [`MotionControl::update`] makes one step towards the target. This makes it
+/// useful for testing code that builds on top of `MotionControl`, like homing
+/// or coordinated motion, where the details of the motion profile don't
+/// matter.
+///
+/// Besides the position reported through `MotionControl`, which can be reset,
+/// the mock keeps track of the physical position of the motor, which only
+/// changes when a step is made. Steps can be lost on purpose, to simulate a
+/// stalled motor (see [`MockMotionControl::lose_steps`]).
+///
+/// Like [`MockDriver`], the mock is cheap to clone, and all clones share the
+/// same state. This allows simulated sensors to observe the motor.
+#[derive(Clone)]
+pub struct MockMotionControl<Velocity = ()> {
+    shared: Rc<RefCell<Axis<Velocity>>>,
+}
+
+impl<Velocity> MockMotionControl<Velocity> {
+    /// Create a new instance of `MockMotionControl`
+    ///
+    /// Both the position and the physical position start at `0`.
+    pub fn new() -> Self {
+        Self {
+            shared: Rc::new(RefCell::new(Axis {
+                position: 0,
+                physical_position: 0,
+                target: 0,
+                max_velocity: None,
+                steps_to_lose: 0,
+                stop_distance: 0,
+            })),
+        }
+    }
+
+    /// Returns the physical position of the motor
+    ///
+    /// This only changes, if a step is made that isn't lost. It is not
+    /// affected by [`MotionControl::reset_position`].
+    pub fn physical_position(&self) -> i32 {
+        self.shared.borrow().physical_position
+    }
+
+    /// Returns the maximum velocity that was passed to the last motion
+    ///
+    /// Returns `None`, if no motion has been started yet.
+    pub fn max_velocity(&self) -> Option<Velocity>
+    where
+        Velocity: Copy,
+    {
+        self.shared.borrow().max_velocity
+    }
+
+    /// Don't move the motor for the given number of steps
+    ///
+    /// The steps are still counted in the position reported through
+    /// [`MotionControl`], but not in [`MockMotionControl::physical_position`].
+    pub fn lose_steps(&self, steps: u32) {
+        self.shared.borrow_mut().steps_to_lose = steps;
+    }
+
+    /// Set the number of steps that [`MotionControl::stop`] takes to stop
+    ///
+    /// Simulates a motor that decelerates, instead of stopping right away.
+    /// Defaults to `0`.
+    pub fn set_stop_distance(&self, steps: u32) {
+        self.shared.borrow_mut().stop_distance = steps;
+    }
+}
+
+impl<Velocity> Default for MockMotionControl<Velocity> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Velocity> MotionControl for MockMotionControl<Velocity>
+where
+    Velocity: Copy,
+{
+    type Velocity = Velocity;
+    type Error = Infallible;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        let mut axis = self.shared.borrow_mut();
+        axis.max_velocity = Some(max_velocity);
+        axis.target = target_step;
+        Ok(())
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        let mut axis = self.shared.borrow_mut();
+        axis.position = step;
+        axis.target = step;
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        let mut axis = self.shared.borrow_mut();
+
+        let step = (axis.target - axis.position).signum();
+        axis.position += step;
+        if axis.steps_to_lose > 0 {
+            axis.steps_to_lose -= 1;
+        } else {
+            axis.physical_position += step;
+        }
+
+        Ok(axis.position != axis.target)
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        Ok(self.shared.borrow().position)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        let mut axis = self.shared.borrow_mut();
+
+        let direction = (axis.target - axis.position).signum();
+        let stop_distance = axis.stop_distance as i32;
+        axis.target = axis.position + direction * stop_distance;
+
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        let mut axis = self.shared.borrow_mut();
+        axis.target = axis.position;
+        Ok(())
+    }
+}
+
+impl<Velocity> ReadVelocity for MockMotionControl<Velocity>
+where
+    Velocity: Copy + Default,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        let axis = self.shared.borrow();
+
+        let velocity = match axis.max_velocity {
+            Some(max_velocity) if axis.position != axis.target => max_velocity,
+            _ => Velocity::default(),
+        };
+
+        Ok(velocity)
+    }
+}
+
+/// A signal that was sent to a [`MockDriver`]
+///
+/// Returned by [`MockDriver::transitions`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Transition {
+    /// The time of the transition, in nanoseconds since the driver was created
+    pub time_ns: u64,
+
+    /// The signal and its new value
+    pub signal: Signal,
+}
+
+/// A signal of a [`MockDriver`], and its new value
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Signal {
+    /// The DIR pin was set to the given level
+    Dir(bool),
+
+    /// The STEP pin was set to the given level
+    Step(bool),
+
+    /// The given step mode was applied
+    StepMode(u16),
+
+    /// The motor outputs were enabled or disabled
+    OutputEnabled(bool),
+
+    /// The given power mode was applied
+    PowerMode(PowerMode),
+
+    /// The given current was set
+    Current(u32),
+}
+
+/// An error that was injected into a [`MockDriver`]
+///
+/// See [`MockDriver::fail_after`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MockError;
+
+impl digital::Error for MockError {
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+impl fmt::Display for MockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "injected error")
+    }
+}
+
+struct Axis<Velocity> {
+    position: i32,
+    physical_position: i32,
+    target: i32,
+    max_velocity: Option<Velocity>,
+    steps_to_lose: u32,
+    stop_distance: u32,
+}
+
+struct Shared {
+    now_ns: u64,
+    transitions: Vec<Transition>,
+    fail_after: Option<usize>,
+    step_mode: Option<StepMode256>,
+    fault: bool,
+    stalled: bool,
+    pulses: VecDeque<u64>,
+}
+
+impl Shared {
+    fn record(&mut self, signal: Signal) -> Result<(), MockError> {
+        match self.fail_after {
+            Some(0) => {
+                self.fail_after = None;
+                return Err(MockError);
+            }
+            Some(ref mut signals) => *signals -= 1,
+            None => {}
+        }
+
+        self.transitions.push(Transition {
+            time_ns: self.now_ns,
+            signal,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Direction, SignalError, Stepper};
+
+    use super::{MockDriver, MockError, Signal, Transition};
+
+    #[test]
+    fn mock_driver_should_record_transitions_and_inject_errors() {
+        let driver = MockDriver::new();
+        let mut timer = driver.timer::<1_000_000>();
+        let mut stepper = Stepper::from_driver(driver);
+
+        stepper
+            .set_direction(Direction::Forward, &mut timer)
+            .wait()
+            .unwrap();
+        stepper.step(&mut timer).wait().unwrap();
+
+        assert_eq!(
+            stepper.driver().transitions(),
+            [
+                Transition {
+                    time_ns: 0,
+                    signal: Signal::Dir(true),
+                },
+                Transition {
+                    time_ns: 1000,
+                    signal: Signal::Step(true),
+                },
+                Transition {
+                    time_ns: 2000,
+                    signal: Signal::Step(false),
+                },
+            ]
+        );
+
+        stepper.driver().fail_after(1);
+        assert_eq!(
+            stepper.step(&mut timer).wait(),
+            Err(SignalError::Pin(MockError))
+        );
+        assert_eq!(stepper.driver().transitions().len(), 4);
+    }
+}
@@ -0,0 +1,49 @@
+use core::ops;
+
+use num_traits::{clamp_max, One};
+use ramp_maker::{util::traits::Sqrt, MotionProfile, Trapezoidal};
+
+/// Implemented by motion profiles whose acceleration can be changed
+///
+/// Allows [`SoftwareMotionControl::set_acceleration`] to configure the
+/// acceleration and deceleration independently, as many mechanisms can speed
+/// up harder than they can safely slow down.
+///
+/// Profiles that only support one value for both use the lower of the two, so
+/// the deceleration limit is never exceeded.
+///
+/// [`SoftwareMotionControl::set_acceleration`]: super::SoftwareMotionControl::set_acceleration
+pub trait SetAcceleration: MotionProfile {
+    /// The type used to represent acceleration
+    type Acceleration;
+
+    /// Set the maximum acceleration and deceleration
+    ///
+    /// Both values are given in steps per (unit of time)^2, and must be
+    /// positive. This must only be called while the profile is idle.
+    fn set_acceleration(
+        &mut self,
+        accel: Self::Acceleration,
+        decel: Self::Acceleration,
+    );
+}
+
+impl<Num> SetAcceleration for Trapezoidal<Num>
+where
+    Self: MotionProfile,
+    Num: Copy
+        + PartialOrd
+        + One
+        + ops::Add<Output = Num>
+        + ops::Div<Output = Num>
+        + Sqrt,
+{
+    type Acceleration = Num;
+
+    fn set_acceleration(&mut self, accel: Num, decel: Num) {
+        // `Trapezoidal` uses the same value for both. The profile has no state
+        // left over from the last motion, once it's idle, so replacing it is
+        // safe.
+        *self = Trapezoidal::new(clamp_max(accel, decel));
+    }
+}
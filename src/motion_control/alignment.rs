@@ -0,0 +1,50 @@
+/// Round the target step to the nearest multiple of `alignment`
+///
+/// Ties are rounded away from zero. The result saturates, if the nearest
+/// multiple is not representable as an `i32`.
+pub fn align(target_step: i32, alignment: u16) -> i32 {
+    let target_step = i64::from(target_step);
+    let alignment = i64::from(alignment);
+
+    let remainder = target_step.rem_euclid(alignment);
+    let rounded_down = target_step - remainder;
+
+    let aligned = if remainder * 2 > alignment
+        || remainder * 2 == alignment && target_step > 0
+    {
+        rounded_down + alignment
+    } else {
+        rounded_down
+    };
+
+    aligned.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::align;
+
+    #[test]
+    fn align_should_round_to_nearest_multiple() {
+        assert_eq!(align(0, 16), 0);
+        assert_eq!(align(7, 16), 0);
+        assert_eq!(align(9, 16), 16);
+        assert_eq!(align(-7, 16), 0);
+        assert_eq!(align(-9, 16), -16);
+        assert_eq!(align(33, 4), 32);
+        assert_eq!(align(35, 4), 36);
+    }
+
+    #[test]
+    fn align_should_round_ties_away_from_zero() {
+        assert_eq!(align(8, 16), 16);
+        assert_eq!(align(-8, 16), -16);
+    }
+
+    #[test]
+    fn align_should_leave_aligned_targets_alone() {
+        assert_eq!(align(32, 16), 32);
+        assert_eq!(align(-32, 16), -32);
+        assert_eq!(align(5, 1), 5);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::Direction;
+
+/// Keeps track of the steps required to take up backlash
+///
+/// Whenever the direction of motion reverses, the mechanism first needs to
+/// take up the play before the axis actually moves. This inserts the
+/// configured number of extra steps at the start of such a motion. Those steps
+/// don't count towards the position.
+pub struct Backlash {
+    steps: u16,
+    left: u32,
+    last_direction: Option<Direction>,
+}
+
+impl Backlash {
+    /// Create a new instance of `Backlash`
+    ///
+    /// A `steps` value of `0` disables the compensation.
+    pub fn new(steps: u16) -> Self {
+        Self {
+            steps,
+            left: 0,
+            last_direction: None,
+        }
+    }
+
+    /// Access the number of compensation steps
+    pub fn steps(&self) -> u16 {
+        self.steps
+    }
+
+    /// Change the number of compensation steps
+    ///
+    /// Only affects direction reversals that happen after this call.
+    pub fn set_steps(&mut self, steps: u16) {
+        self.steps = steps;
+    }
+
+    /// Indicates whether compensation steps are still outstanding
+    pub fn pending(&self) -> bool {
+        self.left > 0
+    }
+
+    /// Returns the number of compensation steps that are still outstanding
+    pub fn left(&self) -> u32 {
+        self.left
+    }
+
+    /// Prepare for a motion in the given direction
+    ///
+    /// The compensation steps of a motion that doesn't complete them remain
+    /// outstanding, and are accounted for by the next motion.
+    ///
+    /// Returns the number of compensation steps that the motion needs to make
+    /// in addition to the steps that change the position.
+    pub fn start_motion(&mut self, direction: Direction) -> u32 {
+        // If we don't know where we came from, we don't know where the play is,
+        // so there's nothing we can do.
+        if let Some(last_direction) = self.last_direction {
+            if last_direction != direction {
+                // Whatever has been taken up of the play in the other
+                // direction, needs to be given back first. If the previous
+                // motion ended before it made any compensation steps (because
+                // it was stopped or aborted), that's nothing.
+                self.left = u32::from(self.steps).saturating_sub(self.left);
+                self.last_direction = Some(direction);
+            }
+        }
+
+        self.left
+    }
+
+    /// Notify about a step that has been made in the given direction
+    ///
+    /// Returns `true`, if the step changes the position, `false`, if it was a
+    /// compensation step.
+    pub fn step_made(&mut self, direction: Direction) -> bool {
+        self.last_direction = Some(direction);
+
+        if self.left > 0 {
+            self.left -= 1;
+            return false;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Direction;
+
+    use super::Backlash;
+
+    #[test]
+    fn backlash_should_insert_steps_on_reversal() {
+        let mut backlash = Backlash::new(2);
+
+        assert_eq!(backlash.start_motion(Direction::Forward), 0);
+        assert!(backlash.step_made(Direction::Forward));
+
+        assert_eq!(backlash.start_motion(Direction::Forward), 0);
+        assert!(backlash.step_made(Direction::Forward));
+
+        assert_eq!(backlash.start_motion(Direction::Backward), 2);
+        assert!(!backlash.step_made(Direction::Backward));
+        assert!(!backlash.step_made(Direction::Backward));
+        assert!(backlash.step_made(Direction::Backward));
+    }
+
+    #[test]
+    fn backlash_should_account_for_interrupted_compensation() {
+        let mut backlash = Backlash::new(5);
+
+        backlash.start_motion(Direction::Forward);
+        assert!(backlash.step_made(Direction::Forward));
+
+        // Reversal is interrupted before any compensation step is made.
+        assert_eq!(backlash.start_motion(Direction::Backward), 5);
+        assert_eq!(backlash.start_motion(Direction::Forward), 0);
+        assert!(backlash.step_made(Direction::Forward));
+
+        // Reversal is interrupted after some compensation steps.
+        assert_eq!(backlash.start_motion(Direction::Backward), 5);
+        assert!(!backlash.step_made(Direction::Backward));
+        assert!(!backlash.step_made(Direction::Backward));
+        assert_eq!(backlash.start_motion(Direction::Forward), 2);
+        assert!(!backlash.step_made(Direction::Forward));
+        assert!(!backlash.step_made(Direction::Forward));
+        assert!(backlash.step_made(Direction::Forward));
+    }
+}
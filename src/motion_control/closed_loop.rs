@@ -0,0 +1,410 @@
+use crate::{
+    traits::{MotionControl, PositionFeedback, ReadVelocity},
+    Direction,
+};
+
+use super::ClosedLoopError;
+
+/// The number of consecutive corrections, before giving up
+const MAX_CORRECTIONS: u8 = 3;
+
+/// Closed-loop position correction, using an encoder
+///
+/// Wraps any driver that implements [`MotionControl`] (like
+/// [`SoftwareMotionControl`]), and a sensor that implements
+/// [`PositionFeedback`]. Every call to [`MotionControl::update`] compares the
+/// commanded position with the measured one. Once a motion has finished, any
+/// deviation beyond the tolerance is corrected by moving the motor again,
+/// which turns the wrapped driver into a basic closed-loop system.
+///
+/// Corrections are only made while the motor is at rest, so steps lost during
+/// a motion are made up at its end. If the motor doesn't reach the target
+/// after 3 corrections in a row, [`ClosedLoopError::CorrectionFailed`] is
+/// returned. No corrections are made after [`MotionControl::abort`], until
+/// the next motion is started.
+///
+/// [`MotionControl::current_position`] returns the commanded position, which
+/// the measured position is brought back to. Like the wrapped driver,
+/// `ClosedLoop` can be used through the [`Stepper`] API.
+///
+/// # Step-Loss Detection
+///
+/// If correcting isn't wanted, [`ClosedLoop::detect_step_loss`] creates an
+/// instance that only detects when the measured position diverges from the
+/// commanded one. This is checked during motions too. Once it happens, the
+/// motion is aborted, and [`ClosedLoopError::StepLoss`] is returned, so the
+/// machine can halt and re-home.
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`Stepper`]: crate::Stepper
+pub struct ClosedLoop<Inner: MotionControl, Feedback> {
+    inner: Inner,
+    feedback: Feedback,
+    mode: Mode<Inner::Velocity>,
+    tolerance: u32,
+    offset: i32,
+    deviation: i32,
+    corrections: u8,
+    correct: bool,
+    step_loss: bool,
+}
+
+impl<Inner, Feedback> ClosedLoop<Inner, Feedback>
+where
+    Inner: MotionControl,
+    Feedback: PositionFeedback,
+{
+    /// Create a new instance of `ClosedLoop`
+    ///
+    /// Corrections are made with a maximum velocity of `correction_velocity`.
+    /// Deviations of up to `tolerance` steps are ignored, which should at
+    /// least cover the resolution of the encoder.
+    ///
+    /// The measured position is assumed to match the current position of
+    /// `inner` initially.
+    pub fn new(
+        inner: Inner,
+        feedback: Feedback,
+        correction_velocity: Inner::Velocity,
+        tolerance: u32,
+    ) -> Result<Self, ClosedLoopError<Inner::Error, Feedback::Error>> {
+        Self::with_mode(
+            inner,
+            feedback,
+            Mode::Correct(correction_velocity),
+            tolerance,
+        )
+    }
+
+    /// Create an instance of `ClosedLoop` that only detects step loss
+    ///
+    /// Deviations of more than `tolerance` steps are reported as
+    /// [`ClosedLoopError::StepLoss`], instead of being corrected. See the
+    /// struct documentation for more information.
+    ///
+    /// The measured position is assumed to match the current position of
+    /// `inner` initially.
+    pub fn detect_step_loss(
+        inner: Inner,
+        feedback: Feedback,
+        tolerance: u32,
+    ) -> Result<Self, ClosedLoopError<Inner::Error, Feedback::Error>> {
+        Self::with_mode(inner, feedback, Mode::Detect, tolerance)
+    }
+
+    fn with_mode(
+        mut inner: Inner,
+        mut feedback: Feedback,
+        mode: Mode<Inner::Velocity>,
+        tolerance: u32,
+    ) -> Result<Self, ClosedLoopError<Inner::Error, Feedback::Error>> {
+        let offset = offset(&mut inner, &mut feedback)?;
+
+        Ok(Self {
+            inner,
+            feedback,
+            mode,
+            tolerance,
+            offset,
+            deviation: 0,
+            corrections: 0,
+            correct: true,
+            step_loss: false,
+        })
+    }
+
+    /// Access a reference to the wrapped driver
+    pub fn inner(&self) -> &Inner {
+        &self.inner
+    }
+
+    /// Access a mutable reference to the wrapped driver
+    pub fn inner_mut(&mut self) -> &mut Inner {
+        &mut self.inner
+    }
+
+    /// Access a mutable reference to the position sensor
+    pub fn feedback_mut(&mut self) -> &mut Feedback {
+        &mut self.feedback
+    }
+
+    /// Release the wrapped driver and the position sensor
+    pub fn release(self) -> (Inner, Feedback) {
+        (self.inner, self.feedback)
+    }
+
+    /// Returns the deviation found by the last call to `update`
+    ///
+    /// This is the measured minus the commanded position, in steps.
+    pub fn deviation(&self) -> i32 {
+        self.deviation
+    }
+
+    /// Indicates whether step loss has been detected
+    ///
+    /// Only set by instances created with [`ClosedLoop::detect_step_loss`].
+    /// Once set, no further step loss is reported, until the position is
+    /// reset (for example after homing) using
+    /// [`MotionControl::reset_position`].
+    pub fn step_loss_detected(&self) -> bool {
+        self.step_loss
+    }
+
+    /// Read the measured position, in steps
+    pub fn measured_position(
+        &mut self,
+    ) -> Result<i32, ClosedLoopError<Inner::Error, Feedback::Error>> {
+        let measured = self
+            .feedback
+            .read_position()
+            .map_err(ClosedLoopError::Feedback)?;
+        Ok(measured.wrapping_add(self.offset))
+    }
+}
+
+impl<Inner, Feedback> MotionControl for ClosedLoop<Inner, Feedback>
+where
+    Inner: MotionControl,
+    Feedback: PositionFeedback,
+{
+    type Velocity = Inner::Velocity;
+    type Error = ClosedLoopError<Inner::Error, Feedback::Error>;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.corrections = 0;
+        self.correct = true;
+        self.inner
+            .move_to_position(max_velocity, target_step)
+            .map_err(ClosedLoopError::Motion)
+    }
+
+    fn move_to_position_synced(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+        num_steps: u32,
+    ) -> Result<bool, Self::Error> {
+        self.corrections = 0;
+        self.correct = true;
+        self.inner
+            .move_to_position_synced(max_velocity, target_step, num_steps)
+            .map_err(ClosedLoopError::Motion)
+    }
+
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.corrections = 0;
+        self.correct = true;
+        self.inner
+            .retarget(max_velocity, target_step)
+            .map_err(ClosedLoopError::Motion)
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        self.inner
+            .reset_position(step)
+            .map_err(ClosedLoopError::Motion)?;
+        self.offset = offset(&mut self.inner, &mut self.feedback)?;
+        self.deviation = 0;
+        self.step_loss = false;
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        let moving = self.inner.update().map_err(ClosedLoopError::Motion)?;
+
+        let commanded = self
+            .inner
+            .current_position()
+            .map_err(ClosedLoopError::Motion)?;
+        let measured = self.measured_position()?;
+        self.deviation = measured.wrapping_sub(commanded);
+
+        let correction_velocity = match self.mode {
+            Mode::Correct(velocity) => velocity,
+            Mode::Detect => {
+                if !self.step_loss
+                    && self.deviation.unsigned_abs() > self.tolerance
+                {
+                    self.step_loss = true;
+                    self.inner.abort().map_err(ClosedLoopError::Motion)?;
+                    return Err(ClosedLoopError::StepLoss(self.deviation));
+                }
+
+                return Ok(moving);
+            }
+        };
+
+        if moving
+            || !self.correct
+            || self.deviation.unsigned_abs() <= self.tolerance
+        {
+            return Ok(moving);
+        }
+
+        if self.corrections == MAX_CORRECTIONS {
+            return Err(ClosedLoopError::CorrectionFailed(self.deviation));
+        }
+        self.corrections += 1;
+
+        // Tell the driver where the motor actually is, then move it back to
+        // where it's supposed to be.
+        self.inner
+            .reset_position(measured)
+            .map_err(ClosedLoopError::Motion)?;
+        self.inner
+            .move_to_position(correction_velocity, commanded)
+            .map_err(ClosedLoopError::Motion)?;
+
+        Ok(true)
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        self.inner
+            .current_position()
+            .map_err(ClosedLoopError::Motion)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        self.inner.stop().map_err(ClosedLoopError::Motion)
+    }
+
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: Self::Velocity,
+    ) -> Result<(), Self::Error> {
+        self.corrections = 0;
+        self.correct = true;
+        self.inner
+            .start_jog(direction, velocity)
+            .map_err(ClosedLoopError::Motion)
+    }
+
+    fn stop_jog(&mut self) -> Result<(), Self::Error> {
+        self.inner.stop_jog().map_err(ClosedLoopError::Motion)
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        // Moving the motor after an emergency stop is the last thing anyone
+        // wants.
+        self.correct = false;
+        self.inner.abort().map_err(ClosedLoopError::Motion)
+    }
+}
+
+impl<Inner, Feedback> ReadVelocity for ClosedLoop<Inner, Feedback>
+where
+    Inner: ReadVelocity,
+    Feedback: PositionFeedback,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        self.inner
+            .current_velocity()
+            .map_err(ClosedLoopError::Motion)
+    }
+}
+
+enum Mode<Velocity> {
+    Correct(Velocity),
+    Detect,
+}
+
+/// The offset to add to the measured position, to get the commanded one
+fn offset<Inner, Feedback>(
+    inner: &mut Inner,
+    feedback: &mut Feedback,
+) -> Result<i32, ClosedLoopError<Inner::Error, Feedback::Error>>
+where
+    Inner: MotionControl,
+    Feedback: PositionFeedback,
+{
+    let commanded =
+        inner.current_position().map_err(ClosedLoopError::Motion)?;
+    let measured = feedback
+        .read_position()
+        .map_err(ClosedLoopError::Feedback)?;
+
+    Ok(commanded.wrapping_sub(measured))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use crate::{
+        mock::MockMotionControl,
+        traits::{MotionControl, PositionFeedback},
+    };
+
+    use super::{ClosedLoop, ClosedLoopError};
+
+    #[test]
+    fn closed_loop_should_correct_lost_steps() {
+        let axis = MockMotionControl::<f32>::new();
+        axis.lose_steps(7);
+
+        let mut closed_loop =
+            ClosedLoop::new(axis.clone(), Encoder(axis.clone()), 1.0, 1)
+                .unwrap();
+        closed_loop.move_to_position(10.0, 100).unwrap();
+        while closed_loop.update().unwrap() {}
+
+        assert_eq!(axis.physical_position(), 100);
+        assert_eq!(closed_loop.current_position().unwrap(), 100);
+        assert_eq!(closed_loop.deviation(), 0);
+
+        // A blocked motor can't be corrected.
+        axis.lose_steps(u32::MAX);
+        closed_loop.move_to_position(10.0, 200).unwrap();
+        let result = loop {
+            match closed_loop.update() {
+                Ok(true) => continue,
+                result => break result,
+            }
+        };
+        assert_eq!(result, Err(ClosedLoopError::CorrectionFailed(-100)));
+    }
+
+    #[test]
+    fn step_loss_should_be_detected_during_motion() {
+        let axis = MockMotionControl::<f32>::new();
+        axis.lose_steps(7);
+
+        let mut closed_loop =
+            ClosedLoop::detect_step_loss(axis.clone(), Encoder(axis), 5)
+                .unwrap();
+        closed_loop.move_to_position(10.0, 100).unwrap();
+        let result = loop {
+            match closed_loop.update() {
+                Ok(true) => continue,
+                result => break result,
+            }
+        };
+
+        assert_eq!(result, Err(ClosedLoopError::StepLoss(-6)));
+        assert!(closed_loop.step_loss_detected());
+        assert_eq!(closed_loop.current_position().unwrap(), 6);
+        assert!(!closed_loop.update().unwrap());
+
+        closed_loop.reset_position(0).unwrap();
+        assert!(!closed_loop.step_loss_detected());
+    }
+
+    struct Encoder(MockMotionControl<f32>);
+
+    impl PositionFeedback for Encoder {
+        type Error = Infallible;
+
+        fn read_position(&mut self) -> Result<i32, Self::Error> {
+            Ok(self.0.physical_position())
+        }
+    }
+}
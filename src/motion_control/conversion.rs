@@ -1,3 +1,5 @@
+use core::{convert::Infallible, fmt};
+
 use fugit::TimerDurationU32 as TimerDuration;
 
 /// Converts delay values from RampMaker into timer ticks
@@ -8,6 +10,10 @@ use fugit::TimerDurationU32 as TimerDuration;
 /// environment.
 ///
 /// The `Delay` parameter specifies the type of delay value used by RampMaker.
+///
+/// If the motion profile uses timer ticks as its unit of time, the conversion
+/// is trivial, and [`DelayInTicks`] can be used instead of a custom
+/// implementation.
 pub trait DelayToTicks<Delay, const TIMER_HZ: u32> {
     /// The error that can happen during conversion
     type Error;
@@ -17,4 +23,119 @@ pub trait DelayToTicks<Delay, const TIMER_HZ: u32> {
         &self,
         delay: Delay,
     ) -> Result<TimerDuration<TIMER_HZ>, Self::Error>;
+
+    /// Convert delay value into timer duration, keeping the fractional ticks
+    ///
+    /// Returns the timer duration, rounded down to whole ticks, and the part
+    /// of a tick that was lost to rounding, in units of 2^-32 ticks. Stepper
+    /// accumulates the fractional ticks and spreads them over the step delays,
+    /// which makes the average step rate match the motion profile exactly.
+    ///
+    /// The default implementation calls [`DelayToTicks::delay_to_ticks`] and
+    /// reports no fractional ticks. Override it to enable the dithering.
+    fn delay_to_fractional_ticks(
+        &self,
+        delay: Delay,
+    ) -> Result<(TimerDuration<TIMER_HZ>, u32), Self::Error> {
+        Ok((self.delay_to_ticks(delay)?, 0))
+    }
+}
+
+/// Converts delays that are already given in timer ticks
+///
+/// Use this, if the motion profile uses timer ticks as its unit of time (that
+/// means velocity is given in steps per tick, and acceleration in steps per
+/// tick^2). The conversion is then just a change of type.
+///
+/// Implemented for `u32`, and, if the `fixed` feature is enabled, for
+/// `fixed::FixedI64`. The fixed-point conversion keeps the fractional ticks
+/// (see [`DelayToTicks::delay_to_fractional_ticks`]).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DelayInTicks;
+
+impl<const TIMER_HZ: u32> DelayToTicks<u32, TIMER_HZ> for DelayInTicks {
+    type Error = Infallible;
+
+    fn delay_to_ticks(
+        &self,
+        delay: u32,
+    ) -> Result<TimerDuration<TIMER_HZ>, Self::Error> {
+        Ok(TimerDuration::<TIMER_HZ>::from_ticks(delay))
+    }
+}
+
+#[cfg(feature = "fixed")]
+impl<Frac, const TIMER_HZ: u32> DelayToTicks<fixed::FixedI64<Frac>, TIMER_HZ>
+    for DelayInTicks
+where
+    Frac: fixed::types::extra::LeEqU64,
+{
+    type Error = DelayOutOfRange;
+
+    fn delay_to_ticks(
+        &self,
+        delay: fixed::FixedI64<Frac>,
+    ) -> Result<TimerDuration<TIMER_HZ>, Self::Error> {
+        let ticks = delay.checked_to_num().ok_or(DelayOutOfRange)?;
+        Ok(TimerDuration::<TIMER_HZ>::from_ticks(ticks))
+    }
+
+    fn delay_to_fractional_ticks(
+        &self,
+        delay: fixed::FixedI64<Frac>,
+    ) -> Result<(TimerDuration<TIMER_HZ>, u32), Self::Error> {
+        let ticks = self.delay_to_ticks(delay)?;
+
+        // The delay is known to be positive at this point. Scale the bits of
+        // the fractional part to 32 bits.
+        let bits = delay.frac().to_bits() as u64;
+        let frac_bits = fixed::FixedI64::<Frac>::FRAC_NBITS;
+        let fraction = if frac_bits <= 32 {
+            bits << (32 - frac_bits)
+        } else {
+            bits >> (frac_bits - 32)
+        };
+
+        Ok((ticks, fraction as u32))
+    }
+}
+
+/// A delay couldn't be represented as a number of timer ticks
+///
+/// Returned by [`DelayInTicks`], if a delay is negative, or too large.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DelayOutOfRange;
+
+impl fmt::Display for DelayOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "delay out of range of timer ticks")
+    }
+}
+
+#[cfg(all(test, feature = "fixed"))]
+mod tests {
+    use super::{DelayInTicks, DelayOutOfRange, DelayToTicks};
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn delay_in_ticks_should_keep_fractional_ticks() {
+        let (ticks, fraction) =
+            DelayToTicks::<Num, 1_000_000>::delay_to_fractional_ticks(
+                &DelayInTicks,
+                Num::from_num(10.25),
+            )
+            .unwrap();
+        assert_eq!(ticks.ticks(), 10);
+        assert_eq!(fraction, 1 << 30);
+
+        assert_eq!(
+            DelayToTicks::<Num, 1_000_000>::delay_to_ticks(
+                &DelayInTicks,
+                Num::from_num(-1),
+            ),
+            Err(DelayOutOfRange)
+        );
+    }
 }
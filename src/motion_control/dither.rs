@@ -0,0 +1,45 @@
+/// Dithers the fractional part of step delays
+///
+/// Step delays are rounded down to whole timer ticks, which quantizes the
+/// velocity, and at low timer frequencies, that can be audible. `Dither`
+/// accumulates the fractional ticks that are lost to rounding, and adds a tick
+/// to a delay whenever they add up to a whole one. Each delay is off by less
+/// than one tick, but the average step rate matches the profile exactly.
+pub struct Dither {
+    error: u32,
+}
+
+impl Dither {
+    /// Create a new instance of `Dither`
+    pub fn new() -> Self {
+        Self { error: 0 }
+    }
+
+    /// Apply the accumulated rounding error to the given number of timer ticks
+    ///
+    /// `fraction` is the part of the delay that was lost to rounding, in units
+    /// of 2^-32 ticks.
+    pub fn apply(&mut self, ticks: u32, fraction: u32) -> u32 {
+        let (error, carry) = self.error.overflowing_add(fraction);
+        self.error = error;
+
+        ticks.saturating_add(u32::from(carry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dither;
+
+    #[test]
+    fn dither_should_match_average_delay() {
+        let mut dither = Dither::new();
+
+        // 10.25 ticks per delay
+        let fraction = 1 << 30;
+        let ticks: Vec<_> =
+            (0..8).map(|_| dither.apply(10, fraction)).collect();
+
+        assert_eq!(ticks, [10, 10, 10, 11, 10, 10, 10, 11]);
+    }
+}
@@ -0,0 +1,214 @@
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::{
+    traits::{MotionControl, ReadVelocity},
+    Direction,
+};
+
+use super::DynError;
+
+/// Object-safe version of [`MotionControl`]
+///
+/// [`MotionControl`] can't be used as a trait object, unless the velocity and
+/// error types are specified, which means drivers of different types can't be
+/// stored in the same array. This trait fixes the velocity type to `f32` and
+/// erases the error type, so motors can be accessed through
+/// `&mut dyn DynMotionControl`, for example to iterate over all axes of a
+/// machine.
+///
+/// Implemented for all types that implement [`MotionControl`] and
+/// [`ReadVelocity`], as long as their velocity type can be converted to and
+/// from `f32`.
+pub trait DynMotionControl {
+    /// Move to the given position
+    ///
+    /// See [`MotionControl::move_to_position`].
+    fn move_to_position(
+        &mut self,
+        max_velocity: f32,
+        target_step: i32,
+    ) -> Result<(), DynError>;
+
+    /// Move to the given position, in lockstep with a longer motion
+    ///
+    /// See [`MotionControl::move_to_position_synced`].
+    fn move_to_position_synced(
+        &mut self,
+        max_velocity: f32,
+        target_step: i32,
+        num_steps: u32,
+    ) -> Result<bool, DynError>;
+
+    /// Change the target of an ongoing motion
+    ///
+    /// See [`MotionControl::retarget`].
+    fn retarget(
+        &mut self,
+        max_velocity: f32,
+        target_step: i32,
+    ) -> Result<(), DynError>;
+
+    /// Reset internal position to the given value
+    ///
+    /// See [`MotionControl::reset_position`].
+    fn reset_position(&mut self, step: i32) -> Result<(), DynError>;
+
+    /// Update an ongoing motion
+    ///
+    /// See [`MotionControl::update`].
+    fn update(&mut self) -> Result<bool, DynError>;
+
+    /// Returns the current position of the motor
+    ///
+    /// See [`MotionControl::current_position`].
+    fn current_position(&mut self) -> Result<i32, DynError>;
+
+    /// Returns the current velocity of the motor
+    ///
+    /// See [`ReadVelocity::current_velocity`].
+    fn current_velocity(&mut self) -> Result<f32, DynError>;
+
+    /// Decelerate the motor to a standstill
+    ///
+    /// See [`MotionControl::stop`].
+    fn stop(&mut self) -> Result<(), DynError>;
+
+    /// Start moving continuously in the given direction
+    ///
+    /// See [`MotionControl::start_jog`].
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: f32,
+    ) -> Result<(), DynError>;
+
+    /// Decelerate the motor to a standstill, after it has been jogging
+    ///
+    /// See [`MotionControl::stop_jog`].
+    fn stop_jog(&mut self) -> Result<(), DynError>;
+
+    /// Stop the motor immediately
+    ///
+    /// See [`MotionControl::abort`].
+    fn abort(&mut self) -> Result<(), DynError>;
+}
+
+impl<T> DynMotionControl for T
+where
+    T: ReadVelocity,
+    T::Velocity: FromPrimitive + ToPrimitive,
+{
+    fn move_to_position(
+        &mut self,
+        max_velocity: f32,
+        target_step: i32,
+    ) -> Result<(), DynError> {
+        let max_velocity = from_f32(max_velocity)?;
+        MotionControl::move_to_position(self, max_velocity, target_step)
+            .map_err(|_| DynError::Driver)
+    }
+
+    fn move_to_position_synced(
+        &mut self,
+        max_velocity: f32,
+        target_step: i32,
+        num_steps: u32,
+    ) -> Result<bool, DynError> {
+        let max_velocity = from_f32(max_velocity)?;
+        MotionControl::move_to_position_synced(
+            self,
+            max_velocity,
+            target_step,
+            num_steps,
+        )
+        .map_err(|_| DynError::Driver)
+    }
+
+    fn retarget(
+        &mut self,
+        max_velocity: f32,
+        target_step: i32,
+    ) -> Result<(), DynError> {
+        let max_velocity = from_f32(max_velocity)?;
+        MotionControl::retarget(self, max_velocity, target_step)
+            .map_err(|_| DynError::Driver)
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), DynError> {
+        MotionControl::reset_position(self, step).map_err(|_| DynError::Driver)
+    }
+
+    fn update(&mut self) -> Result<bool, DynError> {
+        MotionControl::update(self).map_err(|_| DynError::Driver)
+    }
+
+    fn current_position(&mut self) -> Result<i32, DynError> {
+        MotionControl::current_position(self).map_err(|_| DynError::Driver)
+    }
+
+    fn current_velocity(&mut self) -> Result<f32, DynError> {
+        ReadVelocity::current_velocity(self)
+            .map_err(|_| DynError::Driver)?
+            .to_f32()
+            .ok_or(DynError::VelocityConversion)
+    }
+
+    fn stop(&mut self) -> Result<(), DynError> {
+        MotionControl::stop(self).map_err(|_| DynError::Driver)
+    }
+
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: f32,
+    ) -> Result<(), DynError> {
+        let velocity = from_f32(velocity)?;
+        MotionControl::start_jog(self, direction, velocity)
+            .map_err(|_| DynError::Driver)
+    }
+
+    fn stop_jog(&mut self) -> Result<(), DynError> {
+        MotionControl::stop_jog(self).map_err(|_| DynError::Driver)
+    }
+
+    fn abort(&mut self) -> Result<(), DynError> {
+        MotionControl::abort(self).map_err(|_| DynError::Driver)
+    }
+}
+
+fn from_f32<Velocity: FromPrimitive>(
+    velocity: f32,
+) -> Result<Velocity, DynError> {
+    Velocity::from_f32(velocity).ok_or(DynError::VelocityConversion)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockMotionControl;
+
+    use super::{DynError, DynMotionControl};
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn different_drivers_should_be_usable_as_trait_objects() {
+        let mut x = MockMotionControl::<f32>::new();
+        let mut y = MockMotionControl::<Num>::new();
+
+        let mut axes: [&mut dyn DynMotionControl; 2] = [&mut x, &mut y];
+
+        for (i, axis) in axes.iter_mut().enumerate() {
+            axis.move_to_position(1.5, i as i32 + 2).unwrap();
+            assert_eq!(axis.current_velocity(), Ok(1.5));
+
+            while axis.update().unwrap() {}
+            assert_eq!(axis.current_velocity(), Ok(0.0));
+            assert_eq!(axis.current_position(), Ok(i as i32 + 2));
+        }
+
+        assert_eq!(
+            axes[1].move_to_position(f32::NAN, 0),
+            Err(DynError::VelocityConversion)
+        );
+    }
+}
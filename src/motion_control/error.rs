@@ -1,7 +1,16 @@
+use core::{convert::Infallible, fmt};
+
+use crate::{limit_switch, Direction};
+
+// The `Display` implementations in this module format the errors of drivers,
+// pins, timers, and other motion controllers using `Debug`, as that's all that
+// most HALs provide.
+
 /// An error that can occur while using [`SoftwareMotionControl`]
 ///
 /// [`SoftwareMotionControl`]: super::SoftwareMotionControl
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error<
     SetDirectionPinUnavailable,
     SetDirectionError,
@@ -27,17 +36,204 @@ pub enum Error<
 
     /// Error while waiting for a step to finish
     StepDelay(TimerError),
+
+    /// The travel limits prevented a motion
+    ///
+    /// See [`SoftwareMotionControl::set_travel_limits`].
+    ///
+    /// [`SoftwareMotionControl::set_travel_limits`]: super::SoftwareMotionControl::set_travel_limits
+    TravelLimit(TravelLimitError),
+
+    /// The motor stalled, and the motion was aborted
+    ///
+    /// See [`SoftwareMotionControl::enable_stall_detection`].
+    ///
+    /// [`SoftwareMotionControl::enable_stall_detection`]: super::SoftwareMotionControl::enable_stall_detection
+    Stalled,
+
+    /// Applying a queued step mode failed
+    ///
+    /// See [`SoftwareMotionControl::queue_step_mode`] for the state that the
+    /// step mode is left in.
+    ///
+    /// [`SoftwareMotionControl::queue_step_mode`]: super::SoftwareMotionControl::queue_step_mode
+    StepMode,
+
+    /// A step delay was too short for the driver, and the motion was stopped
+    ///
+    /// See [`SoftwareMotionControl::set_short_delay_mode`].
+    ///
+    /// [`SoftwareMotionControl::set_short_delay_mode`]: super::SoftwareMotionControl::set_short_delay_mode
+    DelayTooShort,
+
+    /// The driver and timer were lost, due to a panic in a previous call
+    ///
+    /// See [`SoftwareMotionControl::recover`].
+    ///
+    /// [`SoftwareMotionControl::recover`]: super::SoftwareMotionControl::recover
+    InvalidState,
+}
+
+impl<
+        SetDirectionPinUnavailable,
+        SetDirectionError,
+        StepPinUnavailable,
+        StepError,
+        TimerError,
+        DelayToTicksError,
+    > fmt::Display
+    for Error<
+        SetDirectionPinUnavailable,
+        SetDirectionError,
+        StepPinUnavailable,
+        StepError,
+        TimerError,
+        DelayToTicksError,
+    >
+where
+    SetDirectionPinUnavailable: fmt::Debug,
+    SetDirectionError: fmt::Debug,
+    StepPinUnavailable: fmt::Debug,
+    StepError: fmt::Debug,
+    TimerError: fmt::Debug,
+    DelayToTicksError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SetDirection(err) => {
+                write!(f, "error while setting direction: {}", err)
+            }
+            Self::Step(err) => write!(f, "error while stepping: {}", err),
+            Self::TimeConversion(err) => write!(f, "{}", err),
+            Self::StepDelay(err) => {
+                write!(f, "timer error during step delay: {:?}", err)
+            }
+            Self::TravelLimit(err) => write!(f, "{}", err),
+            Self::Stalled => write!(f, "motor stalled, motion aborted"),
+            Self::StepMode => write!(f, "failed to apply queued step mode"),
+            Self::DelayTooShort => {
+                write!(f, "step delay too short for driver, motion stopped")
+            }
+            Self::InvalidState => {
+                write!(f, "driver and timer lost due to a previous panic")
+            }
+        }
+    }
+}
+
+/// An error that can occur while using [`PulseTrainMotionControl`]
+///
+/// [`PulseTrainMotionControl`]: super::PulseTrainMotionControl
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PulseTrainError<
+    SetDirectionPinUnavailable,
+    SetDirectionError,
+    GeneratePulseTrainError,
+    DelayToTicksError,
+> {
+    /// Error while setting direction
+    SetDirection(
+        crate::SignalError<
+            SetDirectionPinUnavailable,
+            SetDirectionError,
+            Infallible,
+        >,
+    ),
+
+    /// Error while generating pulses
+    PulseTrain(GeneratePulseTrainError),
+
+    /// Error while converting between time formats
+    TimeConversion(TimeConversionError<DelayToTicksError>),
+}
+
+impl<
+        SetDirectionPinUnavailable,
+        SetDirectionError,
+        GeneratePulseTrainError,
+        DelayToTicksError,
+    > fmt::Display
+    for PulseTrainError<
+        SetDirectionPinUnavailable,
+        SetDirectionError,
+        GeneratePulseTrainError,
+        DelayToTicksError,
+    >
+where
+    SetDirectionPinUnavailable: fmt::Debug,
+    SetDirectionError: fmt::Debug,
+    GeneratePulseTrainError: fmt::Debug,
+    DelayToTicksError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SetDirection(err) => {
+                write!(f, "error while setting direction: {}", err)
+            }
+            Self::PulseTrain(err) => {
+                write!(f, "error while generating pulses: {:?}", err)
+            }
+            Self::TimeConversion(err) => write!(f, "{}", err),
+        }
+    }
 }
 
 /// An error occurred while converting between time formats
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TimeConversionError<DelayToTicksError> {
     /// Error converting from RampMaker delay value to timer ticks
     DelayToTicks(DelayToTicksError),
 }
 
+impl<DelayToTicksError> fmt::Display for TimeConversionError<DelayToTicksError>
+where
+    DelayToTicksError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DelayToTicks(err) => {
+                write!(f, "error converting delay to timer ticks: {:?}", err)
+            }
+        }
+    }
+}
+
+/// A motion would have left the travel limits
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TravelLimitError {
+    /// The target step is outside of the travel limits
+    ///
+    /// Only returned, if the limits are configured to reject such targets.
+    TargetOutOfRange(i32),
+
+    /// The motion was stopped, because the next step would have left the
+    /// travel limits
+    ///
+    /// This can happen, if the limits were changed during a motion, or if the
+    /// position was reset to a value outside of the limits. The value is the
+    /// step at which the motion was stopped.
+    LimitReached(i32),
+}
+
+impl fmt::Display for TravelLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TargetOutOfRange(step) => {
+                write!(f, "target step {} outside of travel limits", step)
+            }
+            Self::LimitReached(step) => {
+                write!(f, "travel limit reached at step {}", step)
+            }
+        }
+    }
+}
+
 /// The software motion control was busy, or another generic error occurred
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum BusyError<T> {
     /// The software motion control was busy
     ///
@@ -48,3 +244,188 @@ pub enum BusyError<T> {
     /// Another error has occurred
     Other(T),
 }
+
+impl<T> fmt::Display for BusyError<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Busy => write!(f, "busy, a motion is ongoing"),
+            Self::Other(err) => write!(f, "{:?}", err),
+        }
+    }
+}
+
+/// An error that can occur while using [`DynMotionControl`]
+///
+/// The error of the underlying driver is erased, so drivers with different
+/// error types can be used through the same trait object.
+///
+/// [`DynMotionControl`]: super::DynMotionControl
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DynError {
+    /// The driver returned an error
+    Driver,
+
+    /// A velocity couldn't be converted to or from the driver's velocity type
+    VelocityConversion,
+}
+
+impl fmt::Display for DynError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Driver => write!(f, "driver error"),
+            Self::VelocityConversion => {
+                write!(f, "failed to convert velocity")
+            }
+        }
+    }
+}
+
+/// A motion couldn't be queued
+///
+/// Returned by [`SoftwareMotionControl::queue_motion`].
+///
+/// [`SoftwareMotionControl::queue_motion`]: super::SoftwareMotionControl::queue_motion
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum QueueError {
+    /// The queue is full
+    Full,
+
+    /// The travel limits rejected the target
+    TravelLimit(TravelLimitError),
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Full => write!(f, "motion queue is full"),
+            Self::TravelLimit(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// A time-parameterized motion couldn't be started
+///
+/// See [`SoftwareMotionControl::move_to_position_in`].
+///
+/// [`SoftwareMotionControl::move_to_position_in`]: super::SoftwareMotionControl::move_to_position_in
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimedMoveError {
+    /// A motion is ongoing
+    Busy,
+
+    /// The motion can't be completed in the given time
+    ///
+    /// The motion profile's acceleration limits don't allow reaching the
+    /// target any faster.
+    TooShort,
+
+    /// The travel limits prevented the motion
+    TravelLimit(TravelLimitError),
+}
+
+impl fmt::Display for TimedMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Busy => write!(f, "busy, a motion is ongoing"),
+            Self::TooShort => {
+                write!(f, "motion can't be completed in the given time")
+            }
+            Self::TravelLimit(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// An error that can occur while using [`ClosedLoop`]
+///
+/// [`ClosedLoop`]: super::ClosedLoop
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ClosedLoopError<MotionError, FeedbackError> {
+    /// Error from the wrapped motion controller
+    Motion(MotionError),
+
+    /// Error while reading the measured position
+    Feedback(FeedbackError),
+
+    /// The motor didn't reach the target, despite repeated corrections
+    ///
+    /// The value is the remaining deviation of the measured from the commanded
+    /// position, in steps. This usually means the motor is blocked.
+    CorrectionFailed(i32),
+
+    /// The measured position diverged from the commanded one
+    ///
+    /// The value is the deviation of the measured from the commanded position,
+    /// in steps. Only returned by [`ClosedLoop::detect_step_loss`].
+    ///
+    /// [`ClosedLoop::detect_step_loss`]: super::ClosedLoop::detect_step_loss
+    StepLoss(i32),
+}
+
+impl<MotionError, FeedbackError> fmt::Display
+    for ClosedLoopError<MotionError, FeedbackError>
+where
+    MotionError: fmt::Debug,
+    FeedbackError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Motion(err) => write!(f, "motion control error: {:?}", err),
+            Self::Feedback(err) => {
+                write!(f, "error reading position feedback: {:?}", err)
+            }
+            Self::CorrectionFailed(deviation) => {
+                write!(f, "failed to correct deviation of {} steps", deviation)
+            }
+            Self::StepLoss(deviation) => {
+                write!(f, "step loss, deviation of {} steps", deviation)
+            }
+        }
+    }
+}
+
+/// An error that can occur while using [`LimitMonitor`]
+///
+/// [`LimitMonitor`]: super::LimitMonitor
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LimitMonitorError<MotionControlError, MinError, MaxError> {
+    /// Error from the wrapped motion controller
+    MotionControl(MotionControlError),
+
+    /// Error while reading a limit switch
+    LimitSwitch(limit_switch::Error<MinError, MaxError>),
+
+    /// A limit switch triggered, and the motion was aborted
+    ///
+    /// The value is the direction the motor was moving in.
+    LimitReached(Direction),
+}
+
+impl<MotionControlError, MinError, MaxError> fmt::Display
+    for LimitMonitorError<MotionControlError, MinError, MaxError>
+where
+    MotionControlError: fmt::Debug,
+    MinError: fmt::Debug,
+    MaxError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MotionControl(err) => {
+                write!(f, "motion control error: {:?}", err)
+            }
+            Self::LimitSwitch(err) => write!(f, "{}", err),
+            Self::LimitReached(direction) => write!(
+                f,
+                "limit switch triggered while moving {:?}, motion aborted",
+                direction
+            ),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+/// Scales step delays by a feedrate override
+///
+/// The override is a percentage of the velocity planned by the motion profile.
+/// Values above `100` shorten the delays, values below lengthen them.
+#[derive(Clone, Copy)]
+pub struct Feedrate {
+    percent: u16,
+}
+
+impl Feedrate {
+    /// Create a new instance of `Feedrate`
+    ///
+    /// A `percent` value of `0` is treated like `1`, as the motor would
+    /// otherwise never make another step.
+    pub fn new(percent: u16) -> Self {
+        Self {
+            percent: percent.max(1),
+        }
+    }
+
+    /// Access the override in percent
+    pub fn percent(&self) -> u16 {
+        self.percent
+    }
+
+    /// Scale the given number of timer ticks
+    ///
+    /// `fraction` is the fractional part of the ticks, in units of 2^-32
+    /// ticks. Returns the scaled ticks and their fractional part, so the
+    /// fraction can be dithered after scaling, instead of getting lost.
+    ///
+    /// Saturates, if the result doesn't fit into a `u32`.
+    pub fn apply(&self, ticks: u32, fraction: u32) -> (u32, u32) {
+        if self.percent == 100 {
+            return (ticks, fraction);
+        }
+
+        let ticks = (u64::from(ticks) << 32 | u64::from(fraction)) as u128;
+        let ticks = ticks * 100 / u128::from(self.percent);
+
+        if ticks >> 32 > u128::from(u32::MAX) {
+            return (u32::MAX, 0);
+        }
+
+        ((ticks >> 32) as u32, ticks as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Feedrate;
+
+    #[test]
+    fn feedrate_should_scale_delays() {
+        assert_eq!(Feedrate::new(100).apply(1000, 0), (1000, 0));
+        assert_eq!(Feedrate::new(200).apply(1000, 0), (500, 0));
+        assert_eq!(Feedrate::new(10).apply(1000, 0), (10_000, 0));
+        assert_eq!(Feedrate::new(0).apply(1000, 0), (100_000, 0));
+        assert_eq!(Feedrate::new(1).apply(u32::MAX, 0), (u32::MAX, 0));
+    }
+
+    #[test]
+    fn feedrate_should_scale_fraction() {
+        // 10.25 ticks at 200% are 5.125 ticks.
+        assert_eq!(Feedrate::new(200).apply(10, 1 << 30), (5, 1 << 29));
+
+        // 1001 ticks at 200% are 500.5 ticks.
+        assert_eq!(Feedrate::new(200).apply(1001, 0), (500, 1 << 31));
+    }
+}
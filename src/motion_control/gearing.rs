@@ -0,0 +1,64 @@
+/// Spreads the steps of a motion evenly over the steps of a longer one
+///
+/// The motion profile plans the longer motion, and for each of its steps,
+/// `Gearing` decides whether the shorter motion makes a step too, like
+/// Bresenham's line algorithm does for pixels. This keeps motors that are
+/// planned the same way in lockstep, even though they travel different
+/// distances.
+#[derive(Clone, Copy)]
+pub struct Gearing {
+    steps: u64,
+    num_steps: u64,
+    error: u64,
+}
+
+impl Gearing {
+    /// Create a new instance of `Gearing`
+    ///
+    /// `steps` is the number of steps of the shorter motion, `num_steps` that
+    /// of the longer one.
+    pub fn new(steps: u32, num_steps: u32) -> Self {
+        let num_steps = u64::from(num_steps.max(1));
+
+        Self {
+            steps: u64::from(steps).min(num_steps),
+            num_steps,
+            // Starting halfway rounds to the nearest step, instead of down.
+            error: num_steps / 2,
+        }
+    }
+
+    /// Advance by one step of the longer motion
+    ///
+    /// Returns `true`, if the shorter motion makes a step too.
+    pub fn advance(&mut self) -> bool {
+        self.error += self.steps;
+
+        if self.error >= self.num_steps {
+            self.error -= self.num_steps;
+            return true;
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Gearing;
+
+    #[test]
+    fn gearing_should_spread_steps_evenly() {
+        let mut gearing = Gearing::new(3, 10);
+
+        let steps: Vec<_> = (0..10).map(|_| gearing.advance()).collect();
+
+        assert_eq!(
+            steps,
+            [
+                false, true, false, false, true, false, false, false, true,
+                false,
+            ]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+/// Dithers step delays within strict bounds
+///
+/// Spreading out the timing of step pulses spreads the spectral content of the
+/// pulse train, which can help with EMC compliance.
+///
+/// The offset applied to each delay is the difference between two consecutive
+/// pseudo-random values from `0..=max`. This keeps every offset within
+/// `-max..=max`, while the offsets of all delays add up to no more than `max`
+/// in total. The aggregate step rate is left unaffected.
+pub struct Jitter {
+    max: u32,
+    random: u32,
+    previous: u32,
+}
+
+impl Jitter {
+    /// Create a new instance of `Jitter`
+    ///
+    /// A `max` value of `0` disables the jitter.
+    pub fn new(max: u32) -> Self {
+        Self {
+            max,
+            // Any non-zero value will do as a seed for xorshift.
+            random: 0x2545_f491,
+            previous: 0,
+        }
+    }
+
+    /// Access the maximum offset
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    /// Apply a pseudo-random offset to the given number of timer ticks
+    ///
+    /// If the offset is larger than the delay, the delay saturates at zero.
+    pub fn apply(&mut self, ticks: u32) -> u32 {
+        if self.max == 0 {
+            return ticks;
+        }
+
+        let next =
+            (u64::from(self.next_random()) % (u64::from(self.max) + 1)) as u32;
+        let ticks =
+            i64::from(ticks) + i64::from(next) - i64::from(self.previous);
+        self.previous = next;
+
+        ticks.clamp(0, i64::from(u32::MAX)) as u32
+    }
+
+    // xorshift32, which is simple, cheap, and more than good enough for this
+    // purpose.
+    fn next_random(&mut self) -> u32 {
+        let mut x = self.random;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.random = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Jitter;
+
+    #[test]
+    fn jitter_should_stay_within_bounds() {
+        let mut jitter = Jitter::new(10);
+
+        let mut total = 0;
+        for _ in 0..1000 {
+            let ticks = jitter.apply(100);
+            assert!((90..=110).contains(&ticks));
+
+            total += i64::from(ticks) - 100;
+            assert!((0..=10).contains(&total));
+        }
+    }
+
+    #[test]
+    fn jitter_should_do_nothing_if_disabled() {
+        let mut jitter = Jitter::new(0);
+
+        for _ in 0..100 {
+            assert_eq!(jitter.apply(100), 100);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{
+    limit_switch::{LimitSwitch, LimitSwitches},
+    traits::{MotionControl, ReadVelocity},
+    Direction,
+};
+
+use super::LimitMonitorError;
+
+/// Aborts motions, once a limit switch triggers
+///
+/// Wraps any driver that implements [`MotionControl`] (like
+/// [`SoftwareMotionControl`]), together with its [`LimitSwitches`]. Every call
+/// to [`MotionControl::update`] reads the limit switch in the direction the
+/// motor is moving in. If it has triggered, the motion is aborted, and
+/// [`LimitMonitorError::LimitReached`] is returned. This way, the application
+/// doesn't need to poll the switches in parallel to every motion.
+///
+/// Motions away from a triggered switch are still possible, so the motor can
+/// be moved back into the travel range. Homing moves towards a switch on
+/// purpose, so monitoring needs to be disabled for that, using
+/// [`LimitMonitor::set_enabled`].
+///
+/// Like the wrapped driver, `LimitMonitor` can be used through the
+/// [`Stepper`] API.
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`Stepper`]: crate::Stepper
+pub struct LimitMonitor<Inner, Min, Max> {
+    inner: Inner,
+    limits: LimitSwitches<Min, Max>,
+    enabled: bool,
+    direction: Option<Direction>,
+    last_position: i32,
+}
+
+impl<Inner, Min, Max> LimitMonitor<Inner, Min, Max>
+where
+    Inner: MotionControl,
+    Min: LimitSwitch,
+    Max: LimitSwitch,
+{
+    /// Create a new instance of `LimitMonitor`
+    ///
+    /// Monitoring is enabled initially.
+    pub fn new(inner: Inner, limits: LimitSwitches<Min, Max>) -> Self {
+        Self {
+            inner,
+            limits,
+            enabled: true,
+            direction: None,
+            last_position: 0,
+        }
+    }
+
+    /// Enable or disable monitoring of the limit switches
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Access a mutable reference to the wrapped driver
+    pub fn inner_mut(&mut self) -> &mut Inner {
+        &mut self.inner
+    }
+
+    /// Access a mutable reference to the limit switches
+    pub fn limits_mut(&mut self) -> &mut LimitSwitches<Min, Max> {
+        &mut self.limits
+    }
+
+    /// Release the wrapped driver and the limit switches
+    pub fn release(self) -> (Inner, LimitSwitches<Min, Max>) {
+        (self.inner, self.limits)
+    }
+
+    /// Remember in which direction a new motion is going to move
+    #[allow(clippy::type_complexity)]
+    fn start_motion(
+        &mut self,
+        target_step: i32,
+    ) -> Result<(), LimitMonitorError<Inner::Error, Min::Error, Max::Error>>
+    {
+        let position = self
+            .inner
+            .current_position()
+            .map_err(LimitMonitorError::MotionControl)?;
+
+        self.last_position = position;
+        if target_step != position {
+            self.direction = Some(direction(target_step > position));
+        }
+
+        Ok(())
+    }
+}
+
+impl<Inner, Min, Max> MotionControl for LimitMonitor<Inner, Min, Max>
+where
+    Inner: MotionControl,
+    Min: LimitSwitch,
+    Max: LimitSwitch,
+{
+    type Velocity = Inner::Velocity;
+    type Error = LimitMonitorError<Inner::Error, Min::Error, Max::Error>;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.start_motion(target_step)?;
+        self.inner
+            .move_to_position(max_velocity, target_step)
+            .map_err(LimitMonitorError::MotionControl)
+    }
+
+    fn move_to_position_synced(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+        num_steps: u32,
+    ) -> Result<bool, Self::Error> {
+        self.start_motion(target_step)?;
+        self.inner
+            .move_to_position_synced(max_velocity, target_step, num_steps)
+            .map_err(LimitMonitorError::MotionControl)
+    }
+
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        // The motor keeps moving in its current direction for a while, which
+        // `update` figures out by itself.
+        self.inner
+            .retarget(max_velocity, target_step)
+            .map_err(LimitMonitorError::MotionControl)
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        self.last_position = step;
+        self.inner
+            .reset_position(step)
+            .map_err(LimitMonitorError::MotionControl)
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        let moving = self
+            .inner
+            .update()
+            .map_err(LimitMonitorError::MotionControl)?;
+
+        // The direction in which the motor has actually moved is more reliable
+        // than the direction of the target, as the motor might still be
+        // slowing down after a change of targets.
+        let position = self
+            .inner
+            .current_position()
+            .map_err(LimitMonitorError::MotionControl)?;
+        if position != self.last_position {
+            self.direction = Some(direction(position > self.last_position));
+            self.last_position = position;
+        }
+
+        if !moving || !self.enabled {
+            return Ok(moving);
+        }
+
+        if let Some(direction) = self.direction {
+            let triggered = self
+                .limits
+                .is_triggered(direction)
+                .map_err(LimitMonitorError::LimitSwitch)?;
+            if triggered {
+                self.inner
+                    .abort()
+                    .map_err(LimitMonitorError::MotionControl)?;
+                return Err(LimitMonitorError::LimitReached(direction));
+            }
+        }
+
+        Ok(moving)
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        self.inner
+            .current_position()
+            .map_err(LimitMonitorError::MotionControl)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        self.inner.stop().map_err(LimitMonitorError::MotionControl)
+    }
+
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: Self::Velocity,
+    ) -> Result<(), Self::Error> {
+        self.direction = Some(direction);
+        self.inner
+            .start_jog(direction, velocity)
+            .map_err(LimitMonitorError::MotionControl)
+    }
+
+    fn stop_jog(&mut self) -> Result<(), Self::Error> {
+        self.inner
+            .stop_jog()
+            .map_err(LimitMonitorError::MotionControl)
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        self.inner.abort().map_err(LimitMonitorError::MotionControl)
+    }
+}
+
+impl<Inner, Min, Max> ReadVelocity for LimitMonitor<Inner, Min, Max>
+where
+    Inner: ReadVelocity,
+    Min: LimitSwitch,
+    Max: LimitSwitch,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        self.inner
+            .current_velocity()
+            .map_err(LimitMonitorError::MotionControl)
+    }
+}
+
+fn direction(forward: bool) -> Direction {
+    if forward {
+        Direction::Forward
+    } else {
+        Direction::Backward
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use crate::{
+        limit_switch::{LimitSwitch, LimitSwitches, NoLimitSwitch},
+        mock::MockMotionControl,
+        traits::MotionControl,
+        Direction,
+    };
+
+    use super::{LimitMonitor, LimitMonitorError};
+
+    #[test]
+    fn limit_monitor_should_abort_motion_towards_triggered_switch() {
+        let axis = MockMotionControl::new();
+        let limits = LimitSwitches {
+            min: NoLimitSwitch,
+            max: Switch(axis.clone()),
+        };
+
+        let mut monitor = LimitMonitor::new(axis.clone(), limits);
+        monitor.move_to_position((), 100).unwrap();
+        let result = loop {
+            match monitor.update() {
+                Ok(true) => continue,
+                result => break result,
+            }
+        };
+
+        assert_eq!(
+            result,
+            Err(LimitMonitorError::LimitReached(Direction::Forward))
+        );
+        assert_eq!(axis.physical_position(), 50);
+
+        // Moving away from the switch is still possible.
+        monitor.move_to_position((), 0).unwrap();
+        while monitor.update().unwrap() {}
+        assert_eq!(axis.physical_position(), 0);
+    }
+
+    struct Switch(MockMotionControl);
+
+    impl LimitSwitch for Switch {
+        type Error = Infallible;
+
+        fn is_triggered(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.0.physical_position() >= 50)
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use ramp_maker::MotionProfile;
+
+/// The maximum number of delays that can be computed ahead of time
+pub const CAPACITY: usize = 8;
+
+/// Step delays that have been computed ahead of time
+///
+/// A ring buffer that is topped up from the motion profile while the step
+/// delay is waited out, so the next step doesn't have to wait for the profile.
+/// This smooths out the CPU load at high step rates.
+///
+/// The profile runs ahead of the motor by the number of buffered delays. The
+/// buffer must be cleared whenever the profile is re-planned, as the buffered
+/// delays belong to the old plan.
+pub struct Lookahead<Delay> {
+    entries: [Option<Delay>; CAPACITY],
+    start: usize,
+    len: usize,
+    depth: usize,
+}
+
+impl<Delay> Lookahead<Delay> {
+    /// Create an empty buffer
+    ///
+    /// A `depth` of `0` disables the lookahead.
+    pub fn new(depth: usize) -> Self {
+        Self {
+            entries: [(); CAPACITY].map(|_| None),
+            start: 0,
+            len: 0,
+            depth: depth.min(CAPACITY),
+        }
+    }
+
+    /// Access the number of delays that are computed ahead of time
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Change the number of delays that are computed ahead of time
+    ///
+    /// Delays that are already buffered are kept, even if there are more of
+    /// them than the new depth.
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth.min(CAPACITY);
+    }
+
+    /// Compute delays ahead of time, until the buffer is filled to its depth
+    pub fn fill<Profile>(&mut self, profile: &mut Profile)
+    where
+        Profile: MotionProfile<Delay = Delay>,
+    {
+        while self.len < self.depth {
+            let delay = match profile.next_delay() {
+                Some(delay) => delay,
+                None => break,
+            };
+
+            let i = (self.start + self.len) % CAPACITY;
+            self.entries[i] = Some(delay);
+            self.len += 1;
+        }
+    }
+
+    /// Take the next delay, from the buffer or straight from the profile
+    pub fn next_delay<Profile>(
+        &mut self,
+        profile: &mut Profile,
+    ) -> Option<Delay>
+    where
+        Profile: MotionProfile<Delay = Delay>,
+    {
+        if self.len == 0 {
+            return profile.next_delay();
+        }
+
+        let delay = self.entries[self.start].take();
+        self.start = (self.start + 1) % CAPACITY;
+        self.len -= 1;
+
+        delay
+    }
+
+    /// Drop all buffered delays
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.start = 0;
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ramp_maker::{MotionProfile as _, Trapezoidal};
+
+    use super::Lookahead;
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn lookahead_should_yield_the_same_delays_as_the_profile() {
+        let mut profile = Trapezoidal::new(Num::from_num(1000));
+        profile.enter_position_mode(Num::from_num(100), 20);
+        let expected: Vec<_> = profile.delays().collect();
+
+        let mut lookahead = Lookahead::new(3);
+        profile.enter_position_mode(Num::from_num(100), 20);
+
+        let mut delays = Vec::new();
+        while let Some(delay) = lookahead.next_delay(&mut profile) {
+            delays.push(delay);
+            lookahead.fill(&mut profile);
+        }
+
+        assert_eq!(delays, expected);
+    }
+}
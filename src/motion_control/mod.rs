@@ -2,32 +2,88 @@
 //!
 //! See [`SoftwareMotionControl`] for more information.
 
+mod acceleration;
+mod alignment;
+mod backlash;
+mod closed_loop;
 mod conversion;
+mod dither;
+mod dynamic;
 mod error;
+mod feedrate;
+mod gearing;
+mod jitter;
+mod limit_monitor;
+mod lookahead;
+mod pending_step_mode;
+mod pulse_train;
+mod queue;
+mod rotary;
+mod s_curve;
+mod short_delay;
+mod stall;
 mod state;
+mod status;
+mod timed;
+mod travel_limits;
 
 pub use self::{
-    conversion::DelayToTicks,
-    error::{BusyError, Error, TimeConversionError},
+    acceleration::SetAcceleration,
+    closed_loop::ClosedLoop,
+    conversion::{DelayInTicks, DelayOutOfRange, DelayToTicks},
+    dynamic::DynMotionControl,
+    error::{
+        BusyError, ClosedLoopError, DynError, Error, LimitMonitorError,
+        PulseTrainError, QueueError, TimeConversionError, TimedMoveError,
+        TravelLimitError,
+    },
+    limit_monitor::LimitMonitor,
+    pulse_train::PulseTrainMotionControl,
+    rotary::RotaryAxis,
+    s_curve::SCurve,
+    short_delay::ShortDelayMode,
+    status::{MotionPhase, MotionStatus},
+    travel_limits::{TravelLimitMode, TravelLimits},
 };
 
-use core::convert::Infallible;
+use core::{
+    convert::{Infallible, TryFrom},
+    ops,
+    task::Poll,
+};
 
 use embedded_hal::digital::ErrorType;
-use fugit::NanosDurationU32 as Nanoseconds;
+use fugit::{
+    NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+};
 use fugit_timer::Timer as TimerTrait;
+use num_traits::{FromPrimitive, Inv, Zero};
 use ramp_maker::MotionProfile;
 use replace_with::replace_with_and_return;
 
 use crate::{
+    step_mode::rescale_position,
     traits::{
-        EnableMotionControl, MotionControl, SetDirection, SetStepMode, Step,
+        DetectStall, EnableMotionControl, GetStepMode, MotionControl,
+        ReadFault, ReadVelocity, SetCurrent, SetDirection, SetOutputEnabled,
+        SetPowerMode, SetStepMode, Step,
     },
     util::ref_mut::RefMut,
-    Direction, SetDirectionFuture, SetStepModeFuture, StepFuture,
+    Direction, PowerMode, SetDirectionFuture, SetStepModeFuture, StepFuture,
 };
 
-use self::state::State;
+use self::{
+    backlash::Backlash,
+    dither::Dither,
+    feedrate::Feedrate,
+    gearing::Gearing,
+    jitter::Jitter,
+    lookahead::Lookahead,
+    pending_step_mode::{Phase, PollStepMode},
+    queue::{chain_end, MotionQueue},
+    state::State,
+    status::DelayHistory,
+};
 
 /// Software implementation of motion control capability
 ///
@@ -53,8 +109,48 @@ pub struct SoftwareMotionControl<
     current_step: i32,
     current_direction: Direction,
     convert: Convert,
+    target_alignment: Option<u16>,
+    feedrate: Feedrate,
+    dither: Dither,
+    jitter: Jitter,
+    backlash: Backlash,
+    gearing: Option<Gearing>,
+    step_mode: Option<u16>,
+    pending_step_mode: Option<(u16, PollStepMode<Driver, Timer>)>,
+    travel_limits: Option<TravelLimits>,
+    rotary_axis: Option<RotaryAxis>,
+    stall_check: Option<fn(&mut Driver) -> bool>,
+    delays: DelayHistory<Profile::Delay>,
+    short_delay_mode: ShortDelayMode,
+    rate_limited: bool,
+    lookahead: Lookahead<Profile::Delay>,
+    max_velocity: Option<Profile::Velocity>,
+    moving: bool,
+    pending_target: Option<i32>,
+    segment: Option<(Profile::Velocity, i32)>,
+    queue: MotionQueue<Profile::Velocity>,
+    jog: Option<Direction>,
 }
 
+/// [`SoftwareMotionControl`] using RampMaker's trapezoidal motion profile
+pub type TrapezoidalMotionControl<
+    Driver,
+    Timer,
+    Num,
+    Convert,
+    const TIMER_HZ: u32,
+> = SoftwareMotionControl<
+    Driver,
+    Timer,
+    ramp_maker::Trapezoidal<Num>,
+    Convert,
+    TIMER_HZ,
+>;
+
+/// [`SoftwareMotionControl`] using the jerk-limited [`SCurve`] motion profile
+pub type SCurveMotionControl<Driver, Timer, Num, Convert, const TIMER_HZ: u32> =
+    SoftwareMotionControl<Driver, Timer, SCurve<Num>, Convert, TIMER_HZ>;
+
 impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>
     SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
 where
@@ -85,6 +181,27 @@ where
             // that point.
             current_direction: Direction::Forward,
             convert,
+            target_alignment: None,
+            feedrate: Feedrate::new(100),
+            dither: Dither::new(),
+            jitter: Jitter::new(0),
+            backlash: Backlash::new(0),
+            gearing: None,
+            step_mode: None,
+            pending_step_mode: None,
+            travel_limits: None,
+            rotary_axis: None,
+            stall_check: None,
+            delays: DelayHistory::new(),
+            short_delay_mode: ShortDelayMode::Clamp,
+            rate_limited: false,
+            lookahead: Lookahead::new(0),
+            max_velocity: None,
+            moving: false,
+            pending_target: None,
+            segment: None,
+            queue: MotionQueue::new(),
+            jog: None,
         }
     }
 
@@ -110,6 +227,71 @@ where
         None
     }
 
+    /// Run a closure with access to the driver, even during a motion
+    ///
+    /// Unlike [`SoftwareMotionControl::driver_mut`], this also works while a
+    /// motion is ongoing, as long as the driver is at a safe point between two
+    /// steps: no step pulse is being generated, and no change of direction is
+    /// in progress. Returns `None`, if that is not the case right now. Since
+    /// most of the time is spent waiting out the step delays, trying again
+    /// after the next call to [`MotionControl::update`] usually succeeds.
+    ///
+    /// This is meant for harmless operations, like reading a fault flag. The
+    /// closure must not change the state of the STEP or DIR signals.
+    pub fn with_driver<R>(
+        &mut self,
+        f: impl FnOnce(&mut Driver) -> R,
+    ) -> Option<R> {
+        match &mut self.state {
+            State::Idle { driver, .. } | State::StepDelay { driver, .. } => {
+                Some(f(driver))
+            }
+            _ => None,
+        }
+    }
+
+    /// Indicates whether the driver and timer were lost
+    ///
+    /// This can only happen, if a panic occurred during a previous call, and
+    /// the application caught it. All further calls to
+    /// [`MotionControl::update`] and [`MotionControl::abort`] return
+    /// [`Error::InvalidState`], until [`SoftwareMotionControl::recover`] is
+    /// called.
+    pub fn is_invalid(&self) -> bool {
+        matches!(self.state, State::Invalid)
+    }
+
+    /// Recover from an invalid state, using a replacement driver and timer
+    ///
+    /// The driver and timer were moved out while the panic occurred, and can't
+    /// be recovered. This method takes their replacements, for example ones
+    /// that have been re-created from the same peripherals, and returns to an
+    /// idle state. Any ongoing or queued motions are dropped.
+    ///
+    /// The current position is kept, but the motor might have made a step that
+    /// wasn't counted, so re-homing is advisable.
+    ///
+    /// If the state is not invalid (see [`SoftwareMotionControl::is_invalid`]),
+    /// nothing changes, and the driver and timer are returned.
+    pub fn recover(
+        &mut self,
+        driver: Driver,
+        timer: Timer,
+    ) -> Result<(), (Driver, Timer)>
+    where
+        Profile::Velocity: Copy,
+    {
+        if !self.is_invalid() {
+            return Err((driver, timer));
+        }
+
+        self.state = State::Idle { driver, timer };
+        self.stop_profile();
+        self.normalize_position();
+
+        Ok(())
+    }
+
     /// Access a reference to the wrapped timer
     ///
     /// This is only possible if there is no ongoing movement.
@@ -138,7 +320,12 @@ where
     }
 
     /// Access a mutable reference to the wrapped motion profile
+    ///
+    /// Drops any delays that have been computed ahead of time, as they might
+    /// not match the profile anymore. See
+    /// [`SoftwareMotionControl::set_delay_lookahead`].
     pub fn profile_mut(&mut self) -> &mut Profile {
+        self.lookahead.clear();
         &mut self.profile
     }
 
@@ -152,6 +339,328 @@ where
         self.current_direction
     }
 
+    /// Indicates whether the step rate had to be limited
+    ///
+    /// Every step delay must leave enough time for the STEP pulse (see
+    /// [`Step::PULSE_LENGTH`]), and for the STEP signal to be low for as long
+    /// as it was high, and it can't be shorter than one timer tick. If the
+    /// motion profile produces a shorter delay, because the maximum velocity
+    /// is too high for the driver or the timer, the delay is lengthened
+    /// accordingly, and this flag is set.
+    ///
+    /// If the flag is set, the motor moved slower than planned, and motions
+    /// took longer. Starting a new motion resets it.
+    ///
+    /// This only applies with [`ShortDelayMode::Clamp`] (see
+    /// [`SoftwareMotionControl::set_short_delay_mode`]).
+    pub fn step_rate_limited(&self) -> bool {
+        self.rate_limited
+    }
+
+    /// Access the mode for handling step delays that are too short
+    ///
+    /// See [`SoftwareMotionControl::set_short_delay_mode`].
+    pub fn short_delay_mode(&self) -> ShortDelayMode {
+        self.short_delay_mode
+    }
+
+    /// Define what happens, if a step delay is too short for the driver
+    ///
+    /// With [`ShortDelayMode::Clamp`] (this is the default), the delay is
+    /// lengthened, and the motor moves slower than planned (see
+    /// [`SoftwareMotionControl::step_rate_limited`]).
+    ///
+    /// With [`ShortDelayMode::Reject`], [`MotionControl::update`] returns
+    /// [`Error::DelayTooShort`] instead, and the motion is stopped right away,
+    /// like when reaching a travel limit. This is the better choice, if the
+    /// timing of a motion matters more than its completion.
+    pub fn set_short_delay_mode(&mut self, mode: ShortDelayMode) {
+        self.short_delay_mode = mode;
+    }
+
+    /// Access the number of step delays that are computed ahead of time
+    ///
+    /// See [`SoftwareMotionControl::set_delay_lookahead`].
+    pub fn delay_lookahead(&self) -> usize {
+        self.lookahead.depth()
+    }
+
+    /// Compute step delays ahead of time
+    ///
+    /// Normally, the motion profile computes the delay for each step right
+    /// before that step is made, which puts it on the hot path. With a
+    /// lookahead, up to `depth` delays are computed while the previous step
+    /// delay is waited out instead, and the next step can start right away.
+    /// This smooths out the CPU load at high step rates.
+    ///
+    /// The profile runs ahead of the motor by the buffered delays, and those
+    /// are dropped whenever the motion changes. When a motion is stopped or
+    /// retargeted, the profile continues from a velocity that is slightly
+    /// ahead of the motor's, so keep the depth small. At most 8 delays can be
+    /// computed ahead of time, and a `depth` of `0` disables the lookahead
+    /// (this is the default).
+    pub fn set_delay_lookahead(&mut self, depth: usize) {
+        self.lookahead.set_depth(depth);
+    }
+
+    /// Returns the status of the ongoing motion
+    ///
+    /// Provides more information than [`MotionControl::update`], for user
+    /// interfaces and planners that want to introspect the motion. The phase
+    /// is derived from the delays of the two most recent steps.
+    pub fn status(&self) -> MotionStatus<Profile::Delay>
+    where
+        Profile::Delay: Copy + PartialOrd,
+    {
+        if !self.moving {
+            return MotionStatus {
+                phase: MotionPhase::Idle,
+                steps_remaining: 0,
+                current_delay: None,
+            };
+        }
+
+        let target = self
+            .pending_target
+            .or(self.segment.as_ref().map(|&(_, target)| target));
+        let steps_remaining = target
+            .map(|target| {
+                (i64::from(target) - i64::from(self.current_step))
+                    .unsigned_abs() as u32
+            })
+            .unwrap_or(0);
+
+        MotionStatus {
+            phase: self.delays.phase(),
+            steps_remaining,
+            current_delay: self.delays.current(),
+        }
+    }
+
+    /// Access the target alignment
+    ///
+    /// See [`SoftwareMotionControl::set_target_alignment`].
+    pub fn target_alignment(&self) -> Option<u16> {
+        self.target_alignment
+    }
+
+    /// Make all motions end on a step boundary
+    ///
+    /// If an alignment is set, every target passed to
+    /// [`MotionControl::move_to_position`] is rounded to the nearest multiple
+    /// of `alignment` microsteps. This means the target is adjusted by half the
+    /// alignment at most.
+    ///
+    /// This is useful for machines that power down the driver between moves,
+    /// as holding torque is highest on full step boundaries. For example, if
+    /// the driver is configured for 16 microsteps per step, an alignment of
+    /// `16` makes every motion end on a full step, while an alignment of `4`
+    /// makes it end on a quarter step.
+    ///
+    /// Boundaries are computed relative to position `0`, so this only works as
+    /// expected, if position `0` is itself located on a step boundary.
+    ///
+    /// Pass `None` to disable the alignment (this is the default). An
+    /// alignment of `0` is treated like `None`.
+    pub fn set_target_alignment(&mut self, alignment: Option<u16>) {
+        self.target_alignment = alignment.filter(|&alignment| alignment != 0);
+    }
+
+    /// Set the maximum acceleration and deceleration of the motion profile
+    ///
+    /// Forwards both values to the profile, which uses them for all following
+    /// motions. See [`SetAcceleration`] for profiles that don't support
+    /// separate values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing.
+    pub fn set_acceleration(
+        &mut self,
+        accel: Profile::Acceleration,
+        decel: Profile::Acceleration,
+    ) -> Result<(), BusyError<Infallible>>
+    where
+        Profile: SetAcceleration,
+    {
+        if !matches!(self.state, State::Idle { .. }) || self.moving {
+            return Err(BusyError::Busy);
+        }
+
+        self.profile.set_acceleration(accel, decel);
+        Ok(())
+    }
+
+    /// Access the feedrate override
+    ///
+    /// See [`SoftwareMotionControl::set_feedrate_override`].
+    pub fn feedrate_override(&self) -> u16 {
+        self.feedrate.percent()
+    }
+
+    /// Scale the velocity of all motions by a percentage
+    ///
+    /// Works like the feed override knob on a CNC machine: The delays produced
+    /// by the motion profile are scaled, so `50` moves at half the planned
+    /// velocity, and `200` at twice the planned velocity. Accelerations scale
+    /// with the square of the override. This can be changed at any time, and
+    /// takes effect with the next step, without re-planning the ongoing
+    /// motion.
+    ///
+    /// Since the motion profile is not aware of the override, an override
+    /// above `100` can exceed the limits the profile was configured with.
+    /// [`ReadVelocity::current_velocity`] keeps returning the planned
+    /// velocity.
+    ///
+    /// The default is `100`. A value of `0` is treated like `1`.
+    pub fn set_feedrate_override(&mut self, percent: u16) {
+        self.feedrate = Feedrate::new(percent);
+    }
+
+    /// Access the maximum step jitter
+    ///
+    /// See [`SoftwareMotionControl::set_step_jitter`].
+    pub fn step_jitter(&self) -> TimerDuration<TIMER_HZ> {
+        TimerDuration::<TIMER_HZ>::from_ticks(self.jitter.max())
+    }
+
+    /// Dither the timing of steps, to reduce electromagnetic interference
+    ///
+    /// If set to a non-zero value, the delay between steps is modified by a
+    /// pseudo-random offset of up to `max_deviation` in either direction. This
+    /// spreads out the spectral content of the step pulse train, which can help
+    /// products pass EMC testing.
+    ///
+    /// The offsets are chosen such that they cancel each other out over time,
+    /// so the aggregate step rate, and therefore the duration of a motion, is
+    /// not affected.
+    ///
+    /// `max_deviation` should be significantly smaller than the shortest delay
+    /// that the motion profile produces. Delays are never shortened below what
+    /// the driver's pulse length requires, so otherwise, some offsets are cut
+    /// short, and the average step rate will be lower than intended.
+    ///
+    /// Pass a zero duration to disable the jitter (this is the default).
+    pub fn set_step_jitter(&mut self, max_deviation: TimerDuration<TIMER_HZ>) {
+        self.jitter = Jitter::new(max_deviation.ticks());
+    }
+
+    /// Access the backlash compensation
+    ///
+    /// See [`SoftwareMotionControl::set_backlash`].
+    pub fn backlash(&self) -> u16 {
+        self.backlash.steps()
+    }
+
+    /// Compensate for backlash in the mechanism
+    ///
+    /// If set to a non-zero value, every motion that reverses the direction
+    /// of the previous one starts with `steps` additional steps, to take up
+    /// the play in the mechanism (for example, in a leadscrew). Those steps
+    /// don't change the position, so the position keeps referring to the
+    /// actual location of the axis.
+    ///
+    /// Compensation only starts after the first step has been made, as the
+    /// state of the mechanism is unknown before that.
+    ///
+    /// Pass `0` to disable the compensation (this is the default).
+    pub fn set_backlash(&mut self, steps: u16) {
+        self.backlash.set_steps(steps);
+    }
+
+    /// Access the travel limits
+    ///
+    /// See [`SoftwareMotionControl::set_travel_limits`].
+    pub fn travel_limits(&self) -> Option<TravelLimits> {
+        self.travel_limits
+    }
+
+    /// Restrict motions to a range of positions
+    ///
+    /// If travel limits are set, every target passed to
+    /// [`MotionControl::move_to_position`] that lies outside of the limits is
+    /// either clamped to the nearest limit, or rejected with
+    /// [`TravelLimitError::TargetOutOfRange`], depending on
+    /// [`TravelLimits::mode`]. The limits are applied after the target
+    /// alignment (see [`SoftwareMotionControl::set_target_alignment`]).
+    ///
+    /// In addition, [`MotionControl::update`] checks every step before making
+    /// it. If a step would leave the limits, which can happen if the limits are
+    /// changed during a motion or the position is reset to a value outside of
+    /// them, the motion is stopped immediately, and
+    /// [`TravelLimitError::LimitReached`] is returned. Motions that lead back
+    /// towards the limits are still possible after that.
+    ///
+    /// Pass `None` to remove the limits (this is the default).
+    pub fn set_travel_limits(&mut self, limits: Option<TravelLimits>) {
+        self.travel_limits = limits;
+    }
+
+    /// Access the rotary axis configuration
+    ///
+    /// See [`SoftwareMotionControl::set_rotary_axis`].
+    pub fn rotary_axis(&self) -> Option<RotaryAxis> {
+        self.rotary_axis
+    }
+
+    /// Treat the axis as rotary, with positions that wrap around
+    ///
+    /// If set, every target passed to [`MotionControl::move_to_position`] is
+    /// interpreted as a position on the circle, modulo
+    /// [`RotaryAxis::steps_per_revolution`]. Depending on
+    /// [`RotaryAxis::shortest_path`], the motor either takes the shorter way
+    /// around, or moves within the current revolution. This is applied after
+    /// the target alignment, and before the travel limits, which usually
+    /// shouldn't be combined with a rotary axis.
+    ///
+    /// The position is brought back into `0..steps_per_revolution` once the
+    /// motor comes to rest, so it doesn't accumulate towards overflow. The
+    /// same happens after every revolution of a jog (see
+    /// [`MotionControl::start_jog`]), which keeps going until it is stopped.
+    /// [`MotionControl::current_position`] always returns a position within
+    /// that range.
+    ///
+    /// Pass `None` to treat the axis as linear (this is the default).
+    pub fn set_rotary_axis(&mut self, rotary_axis: Option<RotaryAxis>) {
+        self.rotary_axis = rotary_axis;
+        self.normalize_position();
+    }
+
+    /// Abort motions, as soon as the driver signals a stall
+    ///
+    /// If enabled, [`MotionControl::update`] checks the driver for a stall
+    /// (see [`DetectStall`]) between steps. Once the motor stalls, the
+    /// motion is aborted, like with [`MotionControl::abort`], and
+    /// [`Error::Stalled`] is returned, so the application can react right
+    /// away, instead of grinding on.
+    ///
+    /// An error while checking for a stall is treated like a stall, to err on
+    /// the side of caution.
+    pub fn enable_stall_detection(&mut self)
+    where
+        Driver: DetectStall,
+    {
+        self.stall_check = Some(stall::is_stalled::<Driver>);
+    }
+
+    /// Stop checking the driver for a stall
+    ///
+    /// See [`SoftwareMotionControl::enable_stall_detection`]. Stall detection
+    /// is disabled by default.
+    pub fn disable_stall_detection(&mut self) {
+        self.stall_check = None;
+    }
+
+    /// Bring the position back onto the circle, if the axis is rotary
+    ///
+    /// Only does something while the motor is at rest, as the targets of
+    /// ongoing motions refer to the current position.
+    fn normalize_position(&mut self) {
+        if let (Some(rotary_axis), false) = (self.rotary_axis, self.moving) {
+            self.current_step = rotary_axis.normalize(self.current_step);
+        }
+    }
+
     /// Set step mode of the wrapped driver
     ///
     /// This method is a more convenient alternative to
@@ -162,30 +671,83 @@ where
     /// this method is only available, if you statically know that you're
     /// working with a driver wrapped by `SoftwareMotionControl`.
     ///
+    /// If a step mode has been set before, the current position and the
+    /// travel limits are converted to the new step mode (see
+    /// [`rescale_position`]), so they keep referring to the same physical
+    /// location. This also happens, if the step mode is set through
+    /// [`Stepper::set_step_mode`].
+    ///
     /// # Errors
     ///
     /// Returns [`BusyError::Busy`], if a motion is ongoing.
     ///
     /// [`Stepper::set_step_mode`]: crate::Stepper::set_step_mode
+    /// [`rescale_position`]: crate::step_mode::rescale_position
     pub fn set_step_mode(
         &mut self,
         step_mode: Driver::StepMode,
     ) -> Result<
-        SetStepModeFuture<RefMut<Driver>, RefMut<Timer>, TIMER_HZ>,
+        SetStepModeFuture<RefMut<'_, Driver>, RefMut<'_, Timer>, TIMER_HZ>,
         BusyError<Infallible>,
     >
     where
         Driver: SetStepMode,
         Timer: TimerTrait<TIMER_HZ>,
     {
-        let future = match &mut self.state {
-            State::Idle { driver, timer } => {
-                SetStepModeFuture::new(step_mode, RefMut(driver), RefMut(timer))
-            }
-            _ => return Err(BusyError::Busy),
-        };
+        if !matches!(self.state, State::Idle { .. }) {
+            return Err(BusyError::Busy);
+        }
+        self.pending_step_mode = None;
+        self.change_step_mode(step_mode.into());
 
-        Ok(future)
+        match &mut self.state {
+            State::Idle { driver, timer } => Ok(SetStepModeFuture::new(
+                step_mode,
+                RefMut(driver),
+                RefMut(timer),
+            )),
+            _ => Err(BusyError::Busy),
+        }
+    }
+
+    /// Set the step mode, once the current motion has finished
+    ///
+    /// Unlike [`SoftwareMotionControl::set_step_mode`], this doesn't fail
+    /// during a motion. Instead, the step mode is applied by
+    /// [`MotionControl::update`], once the motor has come to rest and no more
+    /// motions are queued, and the position is rescaled like with
+    /// [`SoftwareMotionControl::set_step_mode`]. If no motion is ongoing, it is
+    /// applied on the next call to [`MotionControl::update`].
+    ///
+    /// While the step mode is being applied, [`MotionControl::update`] doesn't
+    /// block, but keeps returning `true`, until the setup and hold times of the
+    /// driver have passed (see [`SetStepMode`]). A new motion starts after
+    /// that.
+    ///
+    /// If applying the step mode fails, [`Error::StepMode`] is returned, and
+    /// the step mode is no longer pending. If the driver rejected the mode
+    /// configuration, the step mode is left unchanged. If the configuration
+    /// was applied, but waiting for the setup and hold times failed, the new
+    /// step mode is kept, and the position is rescaled, as the driver already
+    /// uses it.
+    ///
+    /// Queueing another step mode replaces the one that is pending.
+    pub fn queue_step_mode(&mut self, step_mode: Driver::StepMode)
+    where
+        Driver: SetStepMode,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        self.pending_step_mode = Some((
+            step_mode.into(),
+            pending_step_mode::poll::<Driver, Timer, TIMER_HZ>,
+        ));
+    }
+
+    /// Access the step mode that is waiting for the current motion to finish
+    ///
+    /// See [`SoftwareMotionControl::queue_step_mode`].
+    pub fn pending_step_mode(&self) -> Option<u16> {
+        self.pending_step_mode.map(|(step_mode, _)| step_mode)
     }
 
     /// Set direction of the wrapped driver
@@ -207,7 +769,7 @@ where
         &mut self,
         direction: Direction,
     ) -> Result<
-        SetDirectionFuture<RefMut<Driver>, RefMut<Timer>, TIMER_HZ>,
+        SetDirectionFuture<RefMut<'_, Driver>, RefMut<'_, Timer>, TIMER_HZ>,
         BusyError<Infallible>,
     >
     where
@@ -244,7 +806,7 @@ where
     pub fn step(
         &mut self,
     ) -> Result<
-        StepFuture<RefMut<Driver>, RefMut<Timer>, TIMER_HZ>,
+        StepFuture<RefMut<'_, Driver>, RefMut<'_, Timer>, TIMER_HZ>,
         BusyError<Infallible>,
     >
     where
@@ -260,6 +822,397 @@ where
 
         Ok(future)
     }
+
+    /// Record a new step mode and rescale all positions accordingly
+    ///
+    /// The target alignment and the backlash compensation are configuration,
+    /// and are left untouched.
+    fn change_step_mode(&mut self, step_mode: u16) {
+        if let Some(previous) = self.step_mode {
+            let rescale =
+                |position| rescale_position(position, previous, step_mode);
+
+            self.current_step = rescale(self.current_step);
+            self.pending_target = self.pending_target.map(rescale);
+            if let Some((_, target)) = &mut self.segment {
+                *target = rescale(*target);
+            }
+            self.queue.map_targets(rescale);
+            self.travel_limits =
+                self.travel_limits.map(|limits| TravelLimits {
+                    min: rescale(limits.min),
+                    max: rescale(limits.max),
+                    ..limits
+                });
+            self.rotary_axis = self
+                .rotary_axis
+                .map(|rotary_axis| rotary_axis.rescale(previous, step_mode));
+        }
+
+        self.step_mode = Some(step_mode);
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>
+    SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Profile: MotionProfile,
+    Profile::Velocity: Copy,
+{
+    /// Bring the motion profile to a standstill, without making any steps
+    fn stop_profile(&mut self) {
+        // `MotionProfile` has no way to abort a motion. The best we can do is
+        // to tell it to stop right here, then drain the steps it still wants
+        // to make for slowing down, without actually making them.
+        if let Some(max_velocity) = self.max_velocity {
+            self.profile.enter_position_mode(max_velocity, 0);
+        }
+        while self.profile.next_delay().is_some() {}
+        self.lookahead.clear();
+
+        self.new_motion = None;
+        self.moving = false;
+        self.pending_target = None;
+        self.segment = None;
+        self.queue.clear();
+        self.delays.clear();
+        self.jog = None;
+        self.gearing = None;
+    }
+
+    /// Queue a motion to start after the current one
+    ///
+    /// If no motion is ongoing, the motion starts right away, like with
+    /// [`MotionControl::move_to_position`]. Otherwise, it starts once the
+    /// motions before it have finished. Up to 8 motions can be queued.
+    ///
+    /// Consecutive motions that continue in the same direction are planned as
+    /// one: The motor doesn't slow down between them, and only decelerates for
+    /// the last one in the chain, or before reversing direction. Each motion's
+    /// maximum velocity applies from where it starts. If it's lower than that
+    /// of the previous motion, the motor slows down after passing the
+    /// previous target.
+    ///
+    /// Target alignment and travel limits are applied when the motion is
+    /// queued. Starting a new motion with [`MotionControl::move_to_position`]
+    /// or [`MotionControl::retarget`], or stopping the motor, clears the queue.
+    pub fn queue_motion(
+        &mut self,
+        max_velocity: Profile::Velocity,
+        target_step: i32,
+    ) -> Result<(), QueueError> {
+        // Queued motions start where the motions before them end.
+        let from = self
+            .queue
+            .targets()
+            .last()
+            .or(self.segment.map(|(_, target)| target))
+            .unwrap_or(self.current_step);
+        let target_step = self
+            .adjust_target(target_step, from)
+            .map_err(QueueError::TravelLimit)?;
+
+        if !self.moving && self.queue.is_empty() {
+            self.start_motion(max_velocity, target_step);
+            return Ok(());
+        }
+
+        self.queue
+            .push(max_velocity, target_step)
+            .map_err(|_| QueueError::Full)?;
+
+        // The new motion might extend the chain of motions that the profile is
+        // currently planning for.
+        self.plan();
+
+        Ok(())
+    }
+
+    /// Returns the number of motions waiting in the queue
+    ///
+    /// See [`SoftwareMotionControl::queue_motion`].
+    pub fn queued_motions(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Remove all motions from the queue
+    ///
+    /// The current motion is not affected.
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+        self.plan();
+    }
+
+    /// Move to the given position, arriving after the given duration
+    ///
+    /// Computes the maximum velocity that makes the motion take `duration`,
+    /// then starts the motion like [`MotionControl::move_to_position`] would.
+    /// This is useful for camera sliders, or for synchronizing motions with
+    /// other devices. `duration` uses the same unit of time as the motion
+    /// profile. The motion finishes slightly early, rather than late.
+    ///
+    /// The velocity is found by running the motion through the profile
+    /// repeatedly, so the acceleration limits of any profile are respected.
+    /// The cost of this grows with the length of the motion. Steps made to
+    /// compensate backlash are not taken into account.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TimedMoveError::Busy`], if a motion is ongoing, and
+    /// [`TimedMoveError::TooShort`], if the profile can't reach the target in
+    /// time.
+    pub fn move_to_position_in(
+        &mut self,
+        target_step: i32,
+        duration: Profile::Delay,
+    ) -> Result<(), TimedMoveError>
+    where
+        Profile::Velocity: PartialOrd
+            + FromPrimitive
+            + ops::Add<Output = Profile::Velocity>
+            + ops::Mul<Output = Profile::Velocity>
+            + ops::Div<Output = Profile::Velocity>,
+        Profile::Delay: Copy
+            + PartialOrd
+            + Zero
+            + Inv<Output = Profile::Velocity>
+            + ops::Add<Output = Profile::Delay>,
+    {
+        if self.moving {
+            return Err(TimedMoveError::Busy);
+        }
+
+        let target_step = self
+            .adjust_target(target_step, self.current_step)
+            .map_err(TimedMoveError::TravelLimit)?;
+        let num_steps = (i64::from(target_step) - i64::from(self.current_step))
+            .unsigned_abs() as u32;
+        if num_steps == 0 {
+            return Ok(());
+        }
+
+        let max_velocity = timed::velocity_for_duration(
+            &mut self.profile,
+            num_steps,
+            duration,
+        )
+        .ok_or(TimedMoveError::TooShort)?;
+
+        self.jog = None;
+        self.queue.clear();
+        self.start_motion(max_velocity, target_step);
+
+        Ok(())
+    }
+
+    /// Start a motion to the given (already adjusted) target
+    fn start_motion(&mut self, max_velocity: Profile::Velocity, target: i32) {
+        // Can't overflow, and the magnitude always fits into a `u32`.
+        let steps_from_here = i64::from(target) - i64::from(self.current_step);
+
+        let direction = if steps_from_here > 0 {
+            Direction::Forward
+        } else {
+            Direction::Backward
+        };
+
+        if steps_from_here != 0 {
+            self.backlash.start_motion(direction);
+        }
+
+        self.max_velocity = Some(max_velocity);
+        self.moving = true;
+        self.rate_limited = false;
+        self.pending_target = None;
+        self.new_motion = Some(direction);
+        self.segment = Some((max_velocity, target));
+
+        self.plan();
+    }
+
+    /// Tell the profile how far the current chain of motions goes
+    fn plan(&mut self) {
+        self.gearing = None;
+
+        let (max_velocity, target) = match self.segment {
+            Some(segment) => segment,
+            None => return,
+        };
+
+        let direction =
+            (i64::from(target) - i64::from(self.current_step)).signum();
+        let end = chain_end(target, direction, self.queue.targets());
+
+        // Can't overflow, and the magnitude always fits into a `u32`.
+        let steps_from_here = i64::from(end) - i64::from(self.current_step);
+        let backlash_steps = if steps_from_here != 0 {
+            self.backlash.left()
+        } else {
+            0
+        };
+        let steps = (steps_from_here.unsigned_abs() as u32)
+            .saturating_add(backlash_steps);
+
+        self.lookahead.clear();
+        self.profile.enter_position_mode(max_velocity, steps);
+    }
+
+    /// The target of the current motion, if the next one is chained to it
+    fn segment_end(&self) -> Option<i32> {
+        let (_, target) = self.segment?;
+        let direction = self.current_direction as i64;
+
+        if chain_end(target, direction, self.queue.targets().take(1)) != target
+        {
+            return Some(target);
+        }
+
+        None
+    }
+
+    /// Apply target alignment, rotary axis, and travel limits to a target step
+    ///
+    /// `from` is the step the motion starts from.
+    fn adjust_target(
+        &self,
+        target_step: i32,
+        from: i32,
+    ) -> Result<i32, TravelLimitError> {
+        let target_step = match self.target_alignment {
+            Some(alignment) => alignment::align(target_step, alignment),
+            None => target_step,
+        };
+        let target_step = match self.rotary_axis {
+            Some(rotary_axis) => rotary_axis.target(from, target_step),
+            None => target_step,
+        };
+        let target_step = match self.travel_limits {
+            Some(limits) => limits.apply(target_step)?,
+            None => target_step,
+        };
+
+        Ok(target_step)
+    }
+
+    /// Re-plan the ongoing motion towards an (already adjusted) target
+    fn retarget_adjusted(
+        &mut self,
+        max_velocity: Profile::Velocity,
+        target_step: i32,
+    ) {
+        // If the motor isn't moving yet, there's nothing to re-plan. Same, if
+        // the direction of the ongoing motion hasn't been set yet.
+        if !self.moving || self.new_motion.is_some() {
+            self.queue.clear();
+            self.start_motion(max_velocity, target_step);
+            return;
+        }
+
+        // Can't overflow, and the magnitude always fits into a `u32`.
+        let steps_from_here =
+            i64::from(target_step) - i64::from(self.current_step);
+
+        let target_is_ahead = match self.current_direction {
+            Direction::Forward => steps_from_here > 0,
+            Direction::Backward => steps_from_here < 0,
+        };
+
+        // The profile continues from its current velocity. If the target is
+        // ahead, it ramps up or down as required. If it's too close, the motor
+        // overshoots. If it's behind, we let the motor come to a stop first.
+        // Either way, `update` starts another motion to the target, once the
+        // motor has stopped.
+        //
+        // If a step is in flight, the profile has already handed it out, but
+        // it hasn't been counted yet. It's part of the steps computed here.
+        let step_in_flight = matches!(self.state, State::Step { .. });
+        let steps_left = if target_is_ahead {
+            (steps_from_here.unsigned_abs() as u32)
+                .saturating_add(self.backlash.left())
+                .saturating_sub(step_in_flight as u32)
+        } else {
+            0
+        };
+        self.lookahead.clear();
+        self.profile.enter_position_mode(max_velocity, steps_left);
+
+        self.max_velocity = Some(max_velocity);
+        self.pending_target = Some(target_step);
+        self.segment = None;
+        self.queue.clear();
+        self.gearing = None;
+    }
+
+    /// Keep the target of a jog on a rotary axis ahead of the motor
+    ///
+    /// Once the motor has completed a revolution, its position is brought back
+    /// onto the circle, and the target is moved along with it.
+    fn extend_jog(&mut self) {
+        let (direction, rotary_axis, max_velocity) =
+            match (self.jog, self.rotary_axis, self.max_velocity) {
+                (Some(direction), Some(rotary_axis), Some(max_velocity)) => {
+                    (direction, rotary_axis, max_velocity)
+                }
+                _ => return,
+            };
+
+        let position = rotary_axis.normalize(self.current_step);
+        if position != self.current_step {
+            self.current_step = position;
+            let target_step = rotary_axis.jog_target(position, direction);
+            self.retarget_adjusted(max_velocity, target_step);
+        }
+    }
+
+    /// Start applying a queued step mode
+    ///
+    /// The mode configuration is applied right away. Once that has succeeded,
+    /// the driver is in the new step mode, so the new step mode is recorded,
+    /// even if waiting for the setup and hold times fails afterwards. The
+    /// waiting is left to [`MotionControl::update`].
+    fn start_step_mode_change(
+        &mut self,
+        step_mode: u16,
+        poll: PollStepMode<Driver, Timer>,
+    ) -> Poll<bool> {
+        let mut phase = Phase::Initial;
+
+        let result = replace_with_and_return(
+            &mut self.state,
+            || State::Invalid,
+            |state| match state {
+                State::Idle {
+                    mut driver,
+                    mut timer,
+                } => {
+                    let result =
+                        poll(&mut driver, &mut timer, &mut phase, step_mode);
+
+                    let state = if result.is_pending() {
+                        State::SetStepMode {
+                            driver,
+                            timer,
+                            step_mode,
+                            phase,
+                            poll,
+                        }
+                    } else {
+                        State::Idle { driver, timer }
+                    };
+
+                    (result, state)
+                }
+                // Only called once the motion has finished, so we're idle.
+                state => (Poll::Ready(false), state),
+            },
+        );
+
+        if phase != Phase::Initial {
+            self.change_step_mode(step_mode);
+        }
+
+        result
+    }
 }
 
 impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> MotionControl
@@ -269,6 +1222,7 @@ where
     Profile: MotionProfile,
     Timer: TimerTrait<TIMER_HZ>,
     Profile::Velocity: Copy,
+    Profile::Delay: Copy,
     Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
 {
     type Velocity = Profile::Velocity;
@@ -286,35 +1240,90 @@ where
         max_velocity: Self::Velocity,
         target_step: i32,
     ) -> Result<(), Self::Error> {
-        let steps_from_here = target_step - self.current_step;
+        let target_step = self
+            .adjust_target(target_step, self.current_step)
+            .map_err(Error::TravelLimit)?;
 
-        self.profile
-            .enter_position_mode(max_velocity, steps_from_here.abs() as u32);
+        self.jog = None;
+        self.queue.clear();
+        self.start_motion(max_velocity, target_step);
 
-        let direction = if steps_from_here > 0 {
-            Direction::Forward
-        } else {
-            Direction::Backward
-        };
-        self.new_motion = Some(direction);
+        Ok(())
+    }
+
+    fn move_to_position_synced(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+        num_steps: u32,
+    ) -> Result<bool, Self::Error> {
+        let target_step = self
+            .adjust_target(target_step, self.current_step)
+            .map_err(Error::TravelLimit)?;
+        // Can't overflow, and the magnitude always fits into a `u32`.
+        let steps = (i64::from(target_step) - i64::from(self.current_step))
+            .unsigned_abs() as u32;
+
+        self.jog = None;
+        self.queue.clear();
+        self.start_motion(max_velocity, target_step);
+
+        // Plan for the longer motion, then spread our steps over it.
+        if steps > 0 && num_steps > steps {
+            self.gearing = Some(Gearing::new(steps, num_steps));
+            self.lookahead.clear();
+            self.profile.enter_position_mode(
+                max_velocity,
+                num_steps.saturating_add(self.backlash.left()),
+            );
+        }
+
+        Ok(true)
+    }
+
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        let target_step = self
+            .adjust_target(target_step, self.current_step)
+            .map_err(Error::TravelLimit)?;
+
+        self.jog = None;
+        self.retarget_adjusted(max_velocity, target_step);
 
         Ok(())
     }
 
     fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
         self.current_step = step;
+        self.normalize_position();
         Ok(())
     }
 
     fn update(&mut self) -> Result<bool, Self::Error> {
+        let segment_end = self.segment_end();
+
         // Otherwise the closure will borrow all of `self`.
         let new_motion = &mut self.new_motion;
         let profile = &mut self.profile;
         let current_step = &mut self.current_step;
         let current_direction = &mut self.current_direction;
         let convert = &self.convert;
+        let feedrate = self.feedrate;
+        let dither = &mut self.dither;
+        let jitter = &mut self.jitter;
+        let backlash = &mut self.backlash;
+        let gearing = &mut self.gearing;
+        let travel_limits = self.travel_limits;
+        let stall_check = self.stall_check;
+        let delays = &mut self.delays;
+        let short_delay_mode = self.short_delay_mode;
+        let rate_limited = &mut self.rate_limited;
+        let lookahead = &mut self.lookahead;
 
-        replace_with_and_return(
+        let result = replace_with_and_return(
             &mut self.state,
             || State::Invalid,
             |state| {
@@ -325,9 +1334,213 @@ where
                     current_step,
                     current_direction,
                     convert,
+                    feedrate,
+                    dither,
+                    jitter,
+                    backlash,
+                    gearing,
+                    travel_limits,
+                    segment_end,
+                    stall_check,
+                    delays,
+                    short_delay_mode,
+                    rate_limited,
+                    lookahead,
                 )
             },
-        )
+        );
+
+        match result {
+            Ok(true) if self.jog.is_some() => {
+                self.extend_jog();
+            }
+            Ok(true) if segment_end == Some(self.current_step) => {
+                // We've reached the end of a motion that the next one is
+                // chained to. Continue with the next one, without stopping.
+                if let Some((max_velocity, target_step)) = self.queue.pop() {
+                    self.max_velocity = Some(max_velocity);
+                    self.segment = Some((max_velocity, target_step));
+                    self.plan();
+                    return self.update();
+                }
+            }
+            Ok(false) => {
+                self.moving = false;
+                self.segment = None;
+                self.gearing = None;
+
+                // If the motion was retargeted, the motor might not have ended
+                // up at the new target. Continue from here.
+                if let (Some(target_step), Some(max_velocity)) =
+                    (self.pending_target.take(), self.max_velocity)
+                {
+                    if target_step != self.current_step {
+                        self.start_motion(max_velocity, target_step);
+                        return self.update();
+                    }
+                }
+
+                if let Some((max_velocity, target_step)) = self.queue.pop() {
+                    self.start_motion(max_velocity, target_step);
+                    return self.update();
+                }
+
+                self.jog = None;
+                self.normalize_position();
+
+                if let Some((step_mode, poll)) = self.pending_step_mode.take() {
+                    return match self.start_step_mode_change(step_mode, poll) {
+                        Poll::Ready(true) => Ok(false),
+                        Poll::Ready(false) => Err(Error::StepMode),
+                        Poll::Pending => Ok(true),
+                    };
+                }
+            }
+            Err(Error::TravelLimit(TravelLimitError::LimitReached(_)))
+            | Err(Error::DelayTooShort) => {
+                self.stop_profile();
+            }
+            Err(Error::Stalled) => {
+                self.abort()?;
+            }
+            _ => {}
+        }
+
+        result
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        let position = match self.rotary_axis {
+            Some(rotary_axis) => rotary_axis.normalize(self.current_step),
+            None => self.current_step,
+        };
+
+        Ok(position)
+    }
+
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: Self::Velocity,
+    ) -> Result<(), Self::Error> {
+        // Targets on a rotary axis wrap around, so a jog towards a far-away
+        // one wouldn't get far. Jog towards a target that is unaffected by
+        // that, and keep it ahead of the motor, until the jog is stopped.
+        if let (Some(rotary_axis), None) =
+            (self.rotary_axis, self.travel_limits)
+        {
+            let target_step =
+                rotary_axis.jog_target(self.current_step, direction);
+            self.retarget_adjusted(velocity, target_step);
+            self.jog = Some(direction);
+            return Ok(());
+        }
+
+        // Jog towards the travel limit, if there is one. Otherwise, the target
+        // might be rejected.
+        let target_step = match (direction, self.travel_limits) {
+            (Direction::Forward, Some(limits)) => limits.max,
+            (Direction::Backward, Some(limits)) => limits.min,
+            (Direction::Forward, None) => i32::MAX,
+            (Direction::Backward, None) => i32::MIN,
+        };
+
+        self.retarget(velocity, target_step)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        // A motion that hasn't started yet doesn't need to be stopped.
+        self.new_motion = None;
+        self.pending_target = None;
+        self.jog = None;
+        self.segment = None;
+        self.queue.clear();
+
+        // Telling the profile that no steps are left makes it decelerate, if
+        // the motor is moving. If the motor isn't moving, there's nothing to
+        // do anyway.
+        self.lookahead.clear();
+        if let Some(max_velocity) = self.max_velocity {
+            self.profile.enter_position_mode(max_velocity, 0);
+        }
+
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        if self.is_invalid() {
+            self.stop_profile();
+            return Err(Error::InvalidState);
+        }
+
+        let current_step = &mut self.current_step;
+        let current_direction = self.current_direction;
+        let backlash = &mut self.backlash;
+
+        let result = replace_with_and_return(
+            &mut self.state,
+            || State::Invalid,
+            |state| {
+                state::abort(state, current_step, current_direction, backlash)
+            },
+        );
+
+        self.stop_profile();
+
+        result.map_err(Error::Step)
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> ReadVelocity
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetDirection + Step,
+    Profile: MotionProfile,
+    Timer: TimerTrait<TIMER_HZ>,
+    Profile::Velocity: Copy + Zero,
+    Profile::Delay: Copy + Inv<Output = Profile::Velocity>,
+    Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        // The delay between steps is the inverse of the velocity. This ignores
+        // any step jitter, which is the right thing to do, since it averages
+        // out anyway.
+        let velocity = match &self.state {
+            State::Step { delay, .. } | State::StepDelay { delay, .. } => {
+                delay.inv()
+            }
+            _ => Profile::Velocity::zero(),
+        };
+
+        Ok(velocity)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>
+    crate::traits::RegisterWaker
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetDirection + Step,
+    Timer: TimerTrait<TIMER_HZ> + crate::traits::RegisterWaker,
+    Profile: MotionProfile,
+{
+    fn register_waker(&mut self, waker: &core::task::Waker) {
+        // Whatever state we're in, it's always the timer that we're waiting
+        // for.
+        match &mut self.state {
+            State::Idle { timer, .. } | State::StepDelay { timer, .. } => {
+                timer.register_waker(waker)
+            }
+            State::SetDirection(future) => {
+                future.timer_mut().register_waker(waker)
+            }
+            State::Step { future, .. } => {
+                future.timer_mut().register_waker(waker)
+            }
+            State::SetStepMode { timer, .. } => timer.register_waker(waker),
+            State::Invalid => {}
+        }
     }
 }
 
@@ -355,16 +1568,18 @@ where
         match self.driver_mut() {
             Some(driver) => driver
                 .apply_mode_config(step_mode)
-                .map_err(|err| BusyError::Other(err)),
-            None => Err(BusyError::Busy),
+                .map_err(BusyError::Other)?,
+            None => return Err(BusyError::Busy),
         }
+
+        self.pending_step_mode = None;
+        self.change_step_mode(step_mode.into());
+        Ok(())
     }
 
     fn enable_driver(&mut self) -> Result<(), Self::Error> {
         match self.driver_mut() {
-            Some(driver) => {
-                driver.enable_driver().map_err(|err| BusyError::Other(err))
-            }
+            Some(driver) => driver.enable_driver().map_err(BusyError::Other),
             None => Err(BusyError::Busy),
         }
     }
@@ -383,7 +1598,7 @@ where
 
     fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
         match self.driver_mut() {
-            Some(driver) => driver.dir().map_err(|err| BusyError::Other(err)),
+            Some(driver) => driver.dir().map_err(BusyError::Other),
             None => Err(BusyError::Busy),
         }
     }
@@ -402,7 +1617,98 @@ where
 
     fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
         match self.driver_mut() {
-            Some(driver) => driver.step().map_err(|err| BusyError::Other(err)),
+            Some(driver) => driver.step().map_err(BusyError::Other),
+            None => Err(BusyError::Busy),
+        }
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> SetOutputEnabled
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetOutputEnabled,
+    Profile: MotionProfile,
+{
+    type Error = BusyError<Driver::Error>;
+
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        match self.driver_mut() {
+            Some(driver) => {
+                driver.set_output_enabled(enabled).map_err(BusyError::Other)
+            }
+            None => Err(BusyError::Busy),
+        }
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> GetStepMode
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetStepMode,
+    Profile: MotionProfile,
+{
+    type StepMode = Driver::StepMode;
+
+    fn step_mode(&self) -> Option<Self::StepMode> {
+        self.step_mode
+            .and_then(|mode| Driver::StepMode::try_from(mode).ok())
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> SetCurrent
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetCurrent,
+    Profile: MotionProfile,
+{
+    type Current = Driver::Current;
+    type Error = BusyError<Driver::Error>;
+
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error> {
+        match self.driver_mut() {
+            Some(driver) => {
+                driver.set_current(current).map_err(BusyError::Other)
+            }
+            None => Err(BusyError::Busy),
+        }
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> SetPowerMode
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetPowerMode,
+    Profile: MotionProfile,
+{
+    const WAKE_UP_TIME: Nanoseconds = Driver::WAKE_UP_TIME;
+
+    type Error = BusyError<Driver::Error>;
+
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error> {
+        match self.driver_mut() {
+            Some(driver) => {
+                driver.apply_power_mode(mode).map_err(BusyError::Other)
+            }
+            None => Err(BusyError::Busy),
+        }
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> ReadFault
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: ReadFault,
+    Profile: MotionProfile,
+{
+    type Fault = Driver::Fault;
+    type Error = BusyError<Driver::Error>;
+
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error> {
+        match self.driver_mut() {
+            Some(driver) => driver.read_fault().map_err(BusyError::Other),
             None => Err(BusyError::Busy),
         }
     }
@@ -417,6 +1723,7 @@ where
     Profile: MotionProfile,
     Timer: TimerTrait<TIMER_HZ>,
     Profile::Velocity: Copy,
+    Profile::Delay: Copy,
     Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
 {
     type WithMotionControl =
@@ -429,3 +1736,181 @@ where
         SoftwareMotionControl::new(self, timer, profile, convert)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+    use ramp_maker::MotionProfile;
+
+    use crate::{
+        mock::{MockDriver, MockTimer, Signal},
+        traits::{EnableMotionControl as _, MotionControl as _},
+        Direction,
+    };
+
+    use super::{DelayInTicks, DelayToTicks, RotaryAxis};
+
+    #[test]
+    fn custom_profiles_should_not_require_velocity_arithmetic() {
+        // Neither `()` nor `u32` support computing a velocity from a delay.
+        // That must not prevent them from being used for motion control.
+        let driver = MockDriver::new();
+        let timer = driver.timer::<1_000_000>();
+        let mut motion_control = driver.clone().enable_motion_control((
+            timer,
+            ConstantDelay(0),
+            DelayInTicks,
+        ));
+
+        motion_control.move_to_position((), 3).unwrap();
+        while motion_control.update().unwrap() {}
+
+        assert_eq!(steps(&driver), 3);
+    }
+
+    #[test]
+    fn retarget_should_account_for_step_in_flight() {
+        let driver = MockDriver::new();
+        let mut motion_control = trapezoidal(&driver);
+
+        motion_control
+            .move_to_position(Num::from_num(1000), 2000)
+            .unwrap();
+        for _ in 0..500 {
+            motion_control.update().unwrap();
+        }
+        motion_control.retarget(Num::from_num(1000), 4000).unwrap();
+        while motion_control.update().unwrap() {}
+
+        assert_eq!(motion_control.current_position().unwrap(), 4000);
+        assert_eq!(steps(&driver), 4000);
+        assert_eq!(directions(&driver), [true]);
+    }
+
+    #[test]
+    fn aborted_reversal_should_not_leave_backlash_compensation_behind() {
+        let driver = MockDriver::new();
+        let mut motion_control = trapezoidal(&driver);
+        motion_control.set_backlash(5);
+
+        motion_control
+            .move_to_position(Num::from_num(1000), 10)
+            .unwrap();
+        while motion_control.update().unwrap() {}
+
+        motion_control
+            .move_to_position(Num::from_num(1000), 0)
+            .unwrap();
+        motion_control.abort().unwrap();
+
+        driver.clear_transitions();
+        motion_control
+            .move_to_position(Num::from_num(1000), 20)
+            .unwrap();
+        while motion_control.update().unwrap() {}
+
+        assert_eq!(motion_control.current_position().unwrap(), 20);
+        assert_eq!(steps(&driver), 10);
+    }
+
+    #[test]
+    fn jog_on_rotary_axis_should_continue_until_stopped() {
+        let driver = MockDriver::new();
+        let mut motion_control = trapezoidal(&driver);
+        motion_control.set_rotary_axis(Some(RotaryAxis {
+            steps_per_revolution: 200,
+            shortest_path: true,
+        }));
+        motion_control.reset_position(100).unwrap();
+
+        motion_control
+            .start_jog(Direction::Forward, Num::from_num(1000))
+            .unwrap();
+        for _ in 0..1000 {
+            assert!(motion_control.update().unwrap());
+        }
+        motion_control.stop_jog().unwrap();
+        while motion_control.update().unwrap() {}
+
+        let steps = steps(&driver);
+        assert!(steps > 500);
+        assert_eq!(
+            motion_control.current_position().unwrap(),
+            ((100 + steps) % 200) as i32
+        );
+        assert_eq!(directions(&driver), [true]);
+    }
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    /// Create motion control with a trapezoidal profile
+    fn trapezoidal(
+        driver: &MockDriver,
+    ) -> super::SoftwareMotionControl<
+        MockDriver,
+        MockTimer<1_000_000>,
+        ramp_maker::Trapezoidal<Num>,
+        Convert,
+        1_000_000,
+    > {
+        driver.clone().enable_motion_control((
+            driver.timer(),
+            ramp_maker::Trapezoidal::new(Num::from_num(10_000)),
+            Convert,
+        ))
+    }
+
+    /// List the direction signals that were sent to the driver
+    fn directions(driver: &MockDriver) -> Vec<bool> {
+        driver
+            .transitions()
+            .into_iter()
+            .filter_map(|transition| match transition.signal {
+                Signal::Dir(forward) => Some(forward),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Count the step pulses that were sent to the driver
+    fn steps(driver: &MockDriver) -> usize {
+        driver
+            .transitions()
+            .into_iter()
+            .filter(|transition| transition.signal == Signal::Step(true))
+            .count()
+    }
+
+    /// Profile that moves at a constant rate of one step per 10 ticks
+    struct ConstantDelay(u32);
+
+    impl MotionProfile for ConstantDelay {
+        type Velocity = ();
+        type Delay = u32;
+
+        fn enter_position_mode(&mut self, _: Self::Velocity, num_steps: u32) {
+            self.0 = num_steps;
+        }
+
+        fn next_delay(&mut self) -> Option<Self::Delay> {
+            self.0 = self.0.checked_sub(1)?;
+            Some(10)
+        }
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<Num, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: Num,
+        ) -> Result<TimerDuration<1_000_000>, Self::Error> {
+            let ticks = delay * Num::from_num(1_000_000);
+            Ok(TimerDuration::<1_000_000>::from_ticks(ticks.to_num()))
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use core::{convert::TryFrom, task::Poll};
+
+use fugit::TimerDurationU32 as TimerDuration;
+use fugit_timer::Timer as TimerTrait;
+
+use crate::traits::SetStepMode;
+
+/// Polls a step mode change, as implemented by [`poll`]
+pub type PollStepMode<Driver, Timer> =
+    fn(&mut Driver, &mut Timer, &mut Phase, u16) -> Poll<bool>;
+
+/// How far a step mode change has progressed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Phase {
+    /// The mode configuration hasn't been applied yet
+    Initial,
+
+    /// The mode configuration has been applied, waiting for the setup time
+    Setup,
+
+    /// The driver has been enabled, waiting for the hold time
+    Hold,
+}
+
+/// Apply a step mode to the driver, without blocking
+///
+/// Used by [`SoftwareMotionControl`] as a function pointer, so it can apply a
+/// queued step mode without requiring [`SetStepMode`] from every driver. Works
+/// like [`SetStepModeFuture`], but keeps its progress in `phase`, as the
+/// driver and timer are owned by the caller.
+///
+/// Returns [`Poll::Pending`], while waiting for the setup and hold times of
+/// the driver, and `Poll::Ready(false)`, if an error occurred. Once `phase`
+/// has left [`Phase::Initial`], the mode configuration has been applied,
+/// even if an error occurs later.
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`SetStepModeFuture`]: crate::SetStepModeFuture
+pub fn poll<Driver, Timer, const TIMER_HZ: u32>(
+    driver: &mut Driver,
+    timer: &mut Timer,
+    phase: &mut Phase,
+    step_mode: u16,
+) -> Poll<bool>
+where
+    Driver: SetStepMode,
+    Timer: TimerTrait<TIMER_HZ>,
+{
+    match phase {
+        Phase::Initial => {
+            // The step mode was converted from `Driver::StepMode` when it was
+            // queued, so converting it back can't fail.
+            let step_mode = match Driver::StepMode::try_from(step_mode) {
+                Ok(step_mode) => step_mode,
+                Err(_) => return Poll::Ready(false),
+            };
+
+            if driver.apply_mode_config(step_mode).is_err() {
+                return Poll::Ready(false);
+            }
+            *phase = Phase::Setup;
+
+            let ticks: TimerDuration<TIMER_HZ> = Driver::SETUP_TIME.convert();
+            if timer.start(ticks).is_err() {
+                return Poll::Ready(false);
+            }
+
+            Poll::Pending
+        }
+        Phase::Setup => match timer.wait() {
+            Ok(()) => {
+                if driver.enable_driver().is_err() {
+                    return Poll::Ready(false);
+                }
+                *phase = Phase::Hold;
+
+                let ticks: TimerDuration<TIMER_HZ> =
+                    Driver::HOLD_TIME.convert();
+                if timer.start(ticks).is_err() {
+                    return Poll::Ready(false);
+                }
+
+                Poll::Pending
+            }
+            Err(nb::Error::WouldBlock) => Poll::Pending,
+            Err(nb::Error::Other(_)) => Poll::Ready(false),
+        },
+        Phase::Hold => match timer.wait() {
+            Ok(()) => Poll::Ready(true),
+            Err(nb::Error::WouldBlock) => Poll::Pending,
+            Err(nb::Error::Other(_)) => Poll::Ready(false),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+    use fugit_timer::Timer as _;
+
+    use crate::{
+        mock::{MockDriver, MockTimer, Signal},
+        motion_control::{DelayToTicks, Error, SoftwareMotionControl},
+        step_mode::StepMode256,
+        traits::{EnableMotionControl as _, GetStepMode as _, MotionControl},
+    };
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn queued_step_mode_should_apply_after_motion() {
+        let driver = MockDriver::new();
+        let mut motion_control = motion_control(&driver);
+        motion_control
+            .set_step_mode(StepMode256::Full)
+            .unwrap()
+            .wait()
+            .unwrap();
+
+        motion_control
+            .move_to_position(Num::from_num(1000), 100)
+            .unwrap();
+        motion_control.queue_step_mode(StepMode256::M4);
+        assert_eq!(driver.step_mode(), Some(StepMode256::Full));
+
+        while motion_control.update().unwrap() {}
+
+        assert_eq!(driver.step_mode(), Some(StepMode256::M4));
+        assert_eq!(motion_control.current_position().unwrap(), 400);
+    }
+
+    #[test]
+    fn queued_step_mode_should_not_block_update() {
+        let driver = MockDriver::new();
+        let mut motion_control = motion_control(&driver);
+        motion_control.queue_step_mode(StepMode256::M4);
+
+        // The mode configuration is applied right away, but waiting out the
+        // setup and hold times takes one call each.
+        assert_eq!(motion_control.update(), Ok(true));
+        assert_eq!(step_modes(&driver), [4]);
+        let applied = driver.timer::<1_000_000>().now();
+
+        assert_eq!(motion_control.update(), Ok(true));
+        assert_eq!(motion_control.update(), Ok(false));
+        let finished = driver.timer::<1_000_000>().now();
+
+        assert_eq!((finished - applied).ticks(), 2);
+    }
+
+    #[test]
+    fn failed_step_mode_should_leave_step_mode_unchanged() {
+        let driver = MockDriver::new();
+        let mut motion_control = motion_control(&driver);
+        motion_control
+            .set_step_mode(StepMode256::Full)
+            .unwrap()
+            .wait()
+            .unwrap();
+        motion_control.reset_position(100).unwrap();
+
+        driver.fail_after(0);
+        motion_control.queue_step_mode(StepMode256::M4);
+        assert!(matches!(motion_control.update(), Err(Error::StepMode)));
+
+        assert_eq!(driver.step_mode(), Some(StepMode256::Full));
+        assert_eq!(motion_control.pending_step_mode(), None);
+        assert_eq!(motion_control.current_position().unwrap(), 100);
+        assert_eq!(motion_control.update(), Ok(false));
+    }
+
+    fn motion_control(
+        driver: &MockDriver,
+    ) -> SoftwareMotionControl<
+        MockDriver,
+        MockTimer<1_000_000>,
+        ramp_maker::Trapezoidal<Num>,
+        Convert,
+        1_000_000,
+    > {
+        driver.clone().enable_motion_control((
+            driver.timer(),
+            ramp_maker::Trapezoidal::new(Num::from_num(2000)),
+            Convert,
+        ))
+    }
+
+    /// List the step modes that were applied to the driver
+    fn step_modes(driver: &MockDriver) -> Vec<u16> {
+        driver
+            .transitions()
+            .into_iter()
+            .filter_map(|transition| match transition.signal {
+                Signal::StepMode(step_mode) => Some(step_mode),
+                _ => None,
+            })
+            .collect()
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<Num, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: Num,
+        ) -> Result<TimerDuration<1_000_000>, Self::Error> {
+            let ticks = delay * Num::from_num(1_000_000);
+            Ok(TimerDuration::<1_000_000>::from_ticks(ticks.to_num()))
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use embedded_hal::digital::{ErrorType, OutputPin as _};
+use fugit::TimerDurationU32 as TimerDuration;
+use num_traits::{Inv, Zero};
+use ramp_maker::MotionProfile;
+
+use crate::{
+    traits::{
+        EnableMotionControl, GeneratePulseTrain, MotionControl, ReadVelocity,
+        SetDirection,
+    },
+    Direction, SignalError,
+};
+
+use super::{
+    dither::Dither,
+    error::{PulseTrainError, TimeConversionError},
+    DelayToTicks,
+};
+
+/// The number of pulses that are computed and queued ahead of time
+///
+/// Higher values make it less likely that the queue runs empty, if `update`
+/// isn't called in time. Lower values make the motion react faster to
+/// [`MotionControl::stop`] and new targets.
+const BLOCK_SIZE: usize = 16;
+
+/// Software motion control, with pulses generated in hardware
+///
+/// Like [`SoftwareMotionControl`], this computes the motion using a motion
+/// profile from RampMaker. But instead of generating every step pulse itself,
+/// it hands the delays between steps off to a driver that implements
+/// [`GeneratePulseTrain`], in blocks of a few pulses. This way, the CPU only
+/// needs to get involved once per block, not once per step.
+///
+/// [`MotionControl::update`] must still be called regularly, to keep the queue
+/// of the driver filled. Since some pulses are always queued ahead of time, a
+/// motion is only stopped or changed after those have been generated.
+///
+/// Unlike [`SoftwareMotionControl`], this doesn't support target alignment,
+/// step jitter, backlash compensation, or travel limits.
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+pub struct PulseTrainMotionControl<
+    Driver,
+    Profile: MotionProfile,
+    Convert,
+    const TIMER_HZ: u32,
+> {
+    driver: Driver,
+    profile: Profile,
+    convert: Convert,
+    dither: Dither,
+    current_step: i32,
+    current_direction: Direction,
+    direction_known: bool,
+    new_motion: Option<Direction>,
+    setup_pending: bool,
+    queued: u32,
+    buffer: [TimerDuration<TIMER_HZ>; BLOCK_SIZE],
+    buffered: usize,
+    last_delay: Option<Profile::Delay>,
+    max_velocity: Option<Profile::Velocity>,
+}
+
+impl<Driver, Profile, Convert, const TIMER_HZ: u32>
+    PulseTrainMotionControl<Driver, Profile, Convert, TIMER_HZ>
+where
+    Profile: MotionProfile,
+{
+    /// Construct a new instance of `PulseTrainMotionControl`
+    ///
+    /// Instead of using this constructor directly, you can instead use
+    /// [`Stepper::enable_motion_control`] with any driver that implements
+    /// [`SetDirection`] and [`GeneratePulseTrain`], providing a motion profile
+    /// and a [`DelayToTicks`] implementation.
+    ///
+    /// [`Stepper::enable_motion_control`]: crate::Stepper::enable_motion_control
+    pub fn new(driver: Driver, profile: Profile, convert: Convert) -> Self {
+        Self {
+            driver,
+            profile,
+            convert,
+            dither: Dither::new(),
+            current_step: 0,
+            current_direction: Direction::Forward,
+            direction_known: false,
+            new_motion: None,
+            setup_pending: false,
+            queued: 0,
+            buffer: [TimerDuration::<TIMER_HZ>::from_ticks(0); BLOCK_SIZE],
+            buffered: 0,
+            last_delay: None,
+            max_velocity: None,
+        }
+    }
+
+    /// Access a reference to the wrapped driver
+    pub fn driver(&self) -> &Driver {
+        &self.driver
+    }
+
+    /// Access a mutable reference to the wrapped driver
+    pub fn driver_mut(&mut self) -> &mut Driver {
+        &mut self.driver
+    }
+
+    /// Access a reference to the wrapped motion profile
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Access a mutable reference to the wrapped motion profile
+    pub fn profile_mut(&mut self) -> &mut Profile {
+        &mut self.profile
+    }
+
+    /// Release the wrapped driver, motion profile, and conversion
+    pub fn release(self) -> (Driver, Profile, Convert) {
+        (self.driver, self.profile, self.convert)
+    }
+}
+
+impl<Driver, Profile, Convert, const TIMER_HZ: u32>
+    PulseTrainMotionControl<Driver, Profile, Convert, TIMER_HZ>
+where
+    Driver: GeneratePulseTrain<TIMER_HZ>,
+    Profile: MotionProfile,
+{
+    /// Count the pulses that have been generated since the last call
+    ///
+    /// Returns the number of pulses that are still pending.
+    fn account(&mut self) -> Result<u32, Driver::Error> {
+        let pending = self.driver.pending_pulses()?.min(self.queued);
+        let generated = self.queued - pending;
+
+        self.current_step = self.current_step.wrapping_add(
+            (generated as i32).wrapping_mul(self.current_direction as i32),
+        );
+        self.queued = pending;
+
+        Ok(pending)
+    }
+}
+
+impl<Driver, Profile, Convert, const TIMER_HZ: u32> MotionControl
+    for PulseTrainMotionControl<Driver, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetDirection + GeneratePulseTrain<TIMER_HZ>,
+    Profile: MotionProfile,
+    Profile::Velocity: Copy,
+    Profile::Delay: Copy,
+    Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
+{
+    type Velocity = Profile::Velocity;
+    type Error = PulseTrainError<
+        <Driver as SetDirection>::Error,
+        <<Driver as SetDirection>::Dir as ErrorType>::Error,
+        <Driver as GeneratePulseTrain<TIMER_HZ>>::Error,
+        Convert::Error,
+    >;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        // Pulses that have already been handed to the driver will still be
+        // generated, so the motion starts from where they end.
+        let queued =
+            (self.queued as i32).wrapping_mul(self.current_direction as i32);
+        let start_step = self.current_step.wrapping_add(queued);
+
+        // Can't overflow, and the magnitude always fits into a `u32`.
+        let steps_from_here = i64::from(target_step) - i64::from(start_step);
+
+        let direction = if steps_from_here > 0 {
+            Direction::Forward
+        } else {
+            Direction::Backward
+        };
+
+        self.profile.enter_position_mode(
+            max_velocity,
+            steps_from_here.unsigned_abs() as u32,
+        );
+
+        self.buffered = 0;
+        self.max_velocity = Some(max_velocity);
+        self.new_motion = Some(direction);
+
+        Ok(())
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        self.account().map_err(PulseTrainError::PulseTrain)?;
+        self.current_step = step;
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        let pending = self.account().map_err(PulseTrainError::PulseTrain)?;
+
+        if let Some(direction) = self.new_motion {
+            if !self.direction_known || direction != self.current_direction {
+                // The direction can only change, once all pulses in the old
+                // direction have been generated.
+                if pending > 0 {
+                    return Ok(true);
+                }
+
+                let dir = self.driver.dir().map_err(|err| {
+                    PulseTrainError::SetDirection(SignalError::PinUnavailable(
+                        err,
+                    ))
+                })?;
+                match direction {
+                    Direction::Forward => dir.set_high(),
+                    Direction::Backward => dir.set_low(),
+                }
+                .map_err(|err| {
+                    PulseTrainError::SetDirection(SignalError::Pin(err))
+                })?;
+
+                self.current_direction = direction;
+                self.direction_known = true;
+                self.setup_pending = true;
+            }
+
+            self.new_motion = None;
+        }
+
+        while self.buffered < BLOCK_SIZE {
+            let delay = match self.profile.next_delay() {
+                Some(delay) => delay,
+                None => break,
+            };
+
+            let (ticks, fraction) = self
+                .convert
+                .delay_to_fractional_ticks(delay)
+                .map_err(|err| {
+                    PulseTrainError::TimeConversion(
+                        TimeConversionError::DelayToTicks(err),
+                    )
+                })?;
+            let mut ticks = TimerDuration::<TIMER_HZ>::from_ticks(
+                self.dither.apply(ticks.ticks(), fraction),
+            );
+
+            // The queue is empty after a change of direction, so the delay of
+            // the first pulse starts counting right away. Make sure it covers
+            // the setup time of the DIR signal.
+            if self.setup_pending {
+                let setup_time: TimerDuration<TIMER_HZ> =
+                    <Driver as SetDirection>::SETUP_TIME.convert();
+                ticks = ticks.max(setup_time);
+                self.setup_pending = false;
+            }
+
+            self.buffer[self.buffered] = ticks;
+            self.buffered += 1;
+            self.last_delay = Some(delay);
+        }
+
+        // Don't queue more than one block, to keep the motion responsive.
+        let room = BLOCK_SIZE.saturating_sub(self.queued as usize);
+        let count = self.buffered.min(room);
+        if count > 0 {
+            let accepted = self
+                .driver
+                .queue_pulses(&self.buffer[..count])
+                .map_err(PulseTrainError::PulseTrain)?
+                .min(count);
+
+            self.buffer.copy_within(accepted..self.buffered, 0);
+            self.buffered -= accepted;
+            self.queued += accepted as u32;
+        }
+
+        let still_moving = self.queued > 0 || self.buffered > 0;
+        if !still_moving {
+            self.last_delay = None;
+        }
+
+        Ok(still_moving)
+    }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        self.account().map_err(PulseTrainError::PulseTrain)?;
+        Ok(self.current_step)
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        // A motion that hasn't started yet doesn't need to be stopped.
+        self.new_motion = None;
+
+        // Telling the profile that no steps are left makes it decelerate, if
+        // the motor is moving. The pulses that have already been computed are
+        // still generated first.
+        if let Some(max_velocity) = self.max_velocity {
+            self.profile.enter_position_mode(max_velocity, 0);
+        }
+
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        let removed = self
+            .driver
+            .clear_pulses()
+            .map_err(PulseTrainError::PulseTrain)?;
+        self.queued = self.queued.saturating_sub(removed);
+        self.account().map_err(PulseTrainError::PulseTrain)?;
+
+        // `MotionProfile` has no way to abort a motion. See
+        // `SoftwareMotionControl::stop_profile`.
+        if let Some(max_velocity) = self.max_velocity {
+            self.profile.enter_position_mode(max_velocity, 0);
+        }
+        while self.profile.next_delay().is_some() {}
+
+        self.new_motion = None;
+        self.buffered = 0;
+        self.last_delay = None;
+
+        Ok(())
+    }
+}
+
+impl<Driver, Profile, Convert, const TIMER_HZ: u32> ReadVelocity
+    for PulseTrainMotionControl<Driver, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetDirection + GeneratePulseTrain<TIMER_HZ>,
+    Profile: MotionProfile,
+    Profile::Velocity: Copy + Zero,
+    Profile::Delay: Copy + Inv<Output = Profile::Velocity>,
+    Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        // This is the velocity of the most recently computed step, which is
+        // a few steps ahead of the motor. Close enough.
+        let velocity = match self.last_delay {
+            Some(delay) => delay.inv(),
+            None => Profile::Velocity::zero(),
+        };
+
+        Ok(velocity)
+    }
+}
+
+// Blanket implementation of `EnableMotionControl` for all drivers that can
+// generate pulse trains.
+impl<Driver, Profile, Convert, const TIMER_HZ: u32>
+    EnableMotionControl<(Profile, Convert), TIMER_HZ> for Driver
+where
+    Driver: SetDirection + GeneratePulseTrain<TIMER_HZ>,
+    Profile: MotionProfile,
+    Profile::Velocity: Copy,
+    Profile::Delay: Copy,
+    Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
+{
+    type WithMotionControl =
+        PulseTrainMotionControl<Driver, Profile, Convert, TIMER_HZ>;
+
+    fn enable_motion_control(
+        self,
+        (profile, convert): (Profile, Convert),
+    ) -> Self::WithMotionControl {
+        PulseTrainMotionControl::new(self, profile, convert)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+
+    use crate::{
+        mock::{MockDriver, Signal},
+        motion_control::DelayToTicks,
+        traits::MotionControl,
+    };
+
+    use super::PulseTrainMotionControl;
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn pulse_train_should_reach_target() {
+        let driver = MockDriver::new();
+        let profile = ramp_maker::Trapezoidal::new(Num::from_num(0.001));
+        let mut motion_control =
+            PulseTrainMotionControl::new(driver.clone(), profile, Convert);
+
+        motion_control
+            .move_to_position(Num::from_num(0.01), -100)
+            .unwrap();
+        while motion_control.update().unwrap() {}
+        assert_eq!(motion_control.current_position().unwrap(), -100);
+        assert_eq!(pulses(&driver), 100);
+
+        motion_control
+            .move_to_position(Num::from_num(0.01), 50)
+            .unwrap();
+        while motion_control.update().unwrap() {}
+        assert_eq!(motion_control.current_position().unwrap(), 50);
+        assert_eq!(pulses(&driver), 250);
+    }
+
+    /// Count the pulses that the driver generated
+    fn pulses(driver: &MockDriver) -> usize {
+        driver
+            .transitions()
+            .into_iter()
+            .filter(|transition| transition.signal == Signal::Step(true))
+            .count()
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<Num, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: Num,
+        ) -> Result<TimerDuration<1_000_000>, Self::Error> {
+            Ok(TimerDuration::<1_000_000>::from_ticks(delay.to_num()))
+        }
+    }
+}
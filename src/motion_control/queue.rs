@@ -0,0 +1,204 @@
+/// The number of motions that can be queued
+pub const CAPACITY: usize = 8;
+
+/// Motions that are queued up after the current one
+///
+/// A ring buffer of `(max_velocity, target_step)` pairs.
+pub struct MotionQueue<Velocity> {
+    entries: [Option<(Velocity, i32)>; CAPACITY],
+    start: usize,
+    len: usize,
+}
+
+impl<Velocity> MotionQueue<Velocity> {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self {
+            entries: [(); CAPACITY].map(|_| None),
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of queued motions
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Indicates whether no motions are queued
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add a motion to the end of the queue
+    ///
+    /// Returns the motion, if the queue is full.
+    pub fn push(
+        &mut self,
+        max_velocity: Velocity,
+        target_step: i32,
+    ) -> Result<(), (Velocity, i32)> {
+        if self.len == CAPACITY {
+            return Err((max_velocity, target_step));
+        }
+
+        let i = (self.start + self.len) % CAPACITY;
+        self.entries[i] = Some((max_velocity, target_step));
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Remove the motion at the front of the queue
+    pub fn pop(&mut self) -> Option<(Velocity, i32)> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let entry = self.entries[self.start].take();
+        self.start = (self.start + 1) % CAPACITY;
+        self.len -= 1;
+
+        entry
+    }
+
+    /// Remove all motions
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Iterate over the target steps of the queued motions, front to back
+    pub fn targets(&self) -> impl Iterator<Item = i32> + '_ {
+        (0..self.len).filter_map(move |i| {
+            self.entries[(self.start + i) % CAPACITY]
+                .as_ref()
+                .map(|&(_, target_step)| target_step)
+        })
+    }
+
+    /// Apply `f` to the target steps of all queued motions
+    pub fn map_targets(&mut self, mut f: impl FnMut(i32) -> i32) {
+        for (_, target_step) in self.entries.iter_mut().flatten() {
+            *target_step = f(*target_step);
+        }
+    }
+}
+
+/// Returns the target at which a chain of motions ends
+///
+/// Consecutive motions that continue in `direction` don't require the motor
+/// to stop in between, so they're chained together. The chain starts with the
+/// current motion, which ends at `target_step`.
+pub fn chain_end(
+    target_step: i32,
+    direction: i64,
+    queued: impl Iterator<Item = i32>,
+) -> i32 {
+    let mut end = target_step;
+
+    for next in queued {
+        if (i64::from(next) - i64::from(end)).signum() != direction {
+            break;
+        }
+        end = next;
+    }
+
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+
+    use crate::{
+        mock::{MockDriver, MockTimer, Signal},
+        motion_control::{DelayToTicks, SoftwareMotionControl},
+        traits::{EnableMotionControl as _, MotionControl},
+    };
+
+    use super::{chain_end, MotionQueue, CAPACITY};
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn queue_should_chain_motions_in_same_direction() {
+        let mut queue = MotionQueue::new();
+        for target in [10, 20, 15, 30] {
+            queue.push((), target).unwrap();
+        }
+
+        assert_eq!(chain_end(5, 1, queue.targets()), 20);
+        assert_eq!(chain_end(5, -1, queue.targets()), 5);
+
+        assert_eq!(queue.pop(), Some(((), 10)));
+        assert_eq!(queue.len(), 3);
+
+        queue.clear();
+        for i in 0..CAPACITY {
+            queue.push((), i as i32).unwrap();
+        }
+        assert_eq!(queue.push((), 0), Err(((), 0)));
+    }
+
+    #[test]
+    fn queued_motions_should_not_stop_in_between() {
+        let driver = MockDriver::new();
+        let mut motion_control: SoftwareMotionControl<
+            MockDriver,
+            MockTimer<1_000_000>,
+            _,
+            _,
+            1_000_000,
+        > = driver.clone().enable_motion_control((
+            driver.timer(),
+            ramp_maker::Trapezoidal::new(Num::from_num(2000)),
+            Convert,
+        ));
+
+        let max_velocity = Num::from_num(1000);
+        motion_control.queue_motion(max_velocity, 200).unwrap();
+        motion_control.queue_motion(max_velocity, 1000).unwrap();
+        motion_control.queue_motion(max_velocity, 900).unwrap();
+        assert_eq!(motion_control.queued_motions(), 2);
+
+        while motion_control.update().unwrap() {}
+
+        assert_eq!(motion_control.current_position().unwrap(), 900);
+        assert_eq!(motion_control.queued_motions(), 0);
+
+        // The motor keeps accelerating past step 200, but stops at step 1000
+        // to reverse.
+        let periods = step_periods(&driver);
+        assert_eq!(periods.len(), 1099);
+        assert!(periods[200] < periods[199]);
+        assert!(periods[999] > 10_000);
+    }
+
+    /// Returns the time between each two step pulses, in microseconds
+    fn step_periods(driver: &MockDriver) -> Vec<u64> {
+        let steps: Vec<_> = driver
+            .transitions()
+            .into_iter()
+            .filter(|transition| transition.signal == Signal::Step(true))
+            .map(|transition| transition.time_ns / 1000)
+            .collect();
+
+        steps.windows(2).map(|steps| steps[1] - steps[0]).collect()
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<Num, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: Num,
+        ) -> Result<TimerDuration<1_000_000>, Self::Error> {
+            let ticks = delay * Num::from_num(1_000_000);
+            Ok(TimerDuration::<1_000_000>::from_ticks(ticks.to_num()))
+        }
+    }
+}
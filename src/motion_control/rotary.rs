@@ -0,0 +1,105 @@
+use crate::{step_mode::rescale_position, Direction};
+
+/// Configuration of an axis whose positions wrap around
+///
+/// See [`SoftwareMotionControl::set_rotary_axis`].
+///
+/// [`SoftwareMotionControl::set_rotary_axis`]: super::SoftwareMotionControl::set_rotary_axis
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RotaryAxis {
+    /// The number of steps that make up one revolution
+    ///
+    /// Positions are kept within `0..steps_per_revolution`. A value of `0` is
+    /// treated like `1`.
+    pub steps_per_revolution: u32,
+
+    /// Whether to move to a target along the shorter way around the circle
+    ///
+    /// If this is `false`, the motor moves to the target within the current
+    /// revolution, without crossing position `0`.
+    pub shortest_path: bool,
+}
+
+impl RotaryAxis {
+    /// Map a position onto the circle
+    ///
+    /// Returns a position within `0..steps_per_revolution`.
+    pub fn normalize(&self, step: i32) -> i32 {
+        (i64::from(step).rem_euclid(self.revolution())) as i32
+    }
+
+    /// Compute the unwrapped target step for a motion starting at `from`
+    pub(crate) fn target(&self, from: i32, target_step: i32) -> i32 {
+        let revolution = self.revolution();
+        let from = i64::from(from);
+        let from_normalized = from.rem_euclid(revolution);
+        let target_normalized = i64::from(target_step).rem_euclid(revolution);
+
+        let mut delta = target_normalized - from_normalized;
+        if self.shortest_path {
+            delta = delta.rem_euclid(revolution);
+            if delta > revolution / 2 {
+                delta -= revolution;
+            }
+        }
+
+        // `from` is within `i32`, and `delta` is smaller than a revolution in
+        // magnitude, so this can only leave `i32` close to its limits.
+        (from + delta).clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+    }
+
+    /// Compute the target of a jog starting at `from`
+    ///
+    /// The target is far enough away to be unaffected by the wrapping. It is
+    /// not adjusted like other targets, and needs to be moved along with the
+    /// motor to keep it out of reach.
+    pub(crate) fn jog_target(&self, from: i32, direction: Direction) -> i32 {
+        const JOG_DISTANCE: i32 = i32::MAX / 2;
+
+        match direction {
+            Direction::Forward => from.saturating_add(JOG_DISTANCE),
+            Direction::Backward => from.saturating_sub(JOG_DISTANCE),
+        }
+    }
+
+    /// Convert the configuration from one step mode to another
+    ///
+    /// See [`rescale_position`].
+    pub(crate) fn rescale(&self, from: u16, to: u16) -> Self {
+        let steps = self.steps_per_revolution.min(i32::MAX as u32) as i32;
+
+        Self {
+            steps_per_revolution: rescale_position(steps, from, to) as u32,
+            ..*self
+        }
+    }
+
+    fn revolution(&self) -> i64 {
+        i64::from(self.steps_per_revolution.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RotaryAxis;
+
+    #[test]
+    fn target_should_wrap_around() {
+        let mut axis = RotaryAxis {
+            steps_per_revolution: 200,
+            shortest_path: true,
+        };
+
+        assert_eq!(axis.normalize(-10), 190);
+        assert_eq!(axis.normalize(410), 10);
+
+        assert_eq!(axis.target(190, 10), 210);
+        assert_eq!(axis.target(10, 190), -10);
+        assert_eq!(axis.target(10, 50), 50);
+        assert_eq!(axis.target(10, 250), 50);
+
+        axis.shortest_path = false;
+        assert_eq!(axis.target(190, 10), 10);
+        assert_eq!(axis.target(10, 190), 190);
+    }
+}
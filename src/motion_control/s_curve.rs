@@ -0,0 +1,521 @@
+use core::ops;
+
+use num_traits::{clamp_max, clamp_min, FromPrimitive, Inv, One, Zero};
+use ramp_maker::{util::traits::Sqrt, MotionProfile};
+
+use super::SetAcceleration;
+
+/// Jerk-limited ("S-curve") motion profile
+///
+/// RampMaker only provides a trapezoidal profile, which changes acceleration
+/// instantly at the start and end of each ramp. This profile limits the rate
+/// of change of the acceleration (the jerk) instead, which results in smoother
+/// motion and less excitation of mechanical resonances. It implements
+/// [`MotionProfile`], so it can be used with [`SoftwareMotionControl`], like
+/// any profile from RampMaker.
+///
+/// The profile is computed step by step, so it is an approximation. The
+/// velocity and acceleration never exceed their configured maximums. If a
+/// motion is too short to reach them, the profile stays below. If the target
+/// is changed during a motion, and is too close to stop in time, the profile
+/// overshoots it, like [`ramp_maker::Trapezoidal`] does.
+///
+/// Like [`ramp_maker::Trapezoidal`], the motion starts at the velocity that a
+/// trapezoidal profile would reach after its first step, and ends at the same
+/// velocity.
+///
+/// # Unit of Time
+///
+/// Like the profiles from RampMaker, this profile is agnostic over the unit of
+/// time. Jerk values are very small when expressed in timer ticks, which is a
+/// problem for fixed-point types. Using seconds as the unit of time, and
+/// converting the delays to timer ticks in [`DelayToTicks`], is recommended.
+///
+/// # Type Parameter
+///
+/// `Num` is the numeric type used to represent acceleration, jerk, velocity,
+/// and delays. It must be a signed type, like `fixed::FixedI64<typenum::U32>`,
+/// as the acceleration becomes negative while slowing down. Floating-point
+/// types require the square root implementation from RampMaker, which needs
+/// its `std` or `libm` feature.
+///
+/// # Example
+///
+/// ``` rust
+/// # fn main()
+/// #     -> Result<
+/// #         (),
+/// #         stepper::Error<
+/// #             core::convert::Infallible,
+/// #             core::convert::Infallible,
+/// #             core::convert::Infallible,
+/// #             core::convert::Infallible,
+/// #         >
+/// #     > {
+/// #
+/// use stepper::{
+///     fugit::TimerDurationU32 as TimerDuration,
+///     motion_control::{self, SCurve},
+///     Direction, Stepper,
+/// };
+///
+/// # type MyDriver = stepper::drivers::drv8825::DRV8825<
+/// #     (), (), (), (), (), (), (), (), ()
+/// # >;
+/// #
+/// # struct Pin;
+/// # impl embedded_hal::digital::ErrorType for Pin {
+/// #     type Error = core::convert::Infallible;
+/// # }
+/// # impl stepper::embedded_hal::digital::OutputPin for Pin {
+/// #     fn set_low(&mut self) -> Result<(), Self::Error> { Ok(()) }
+/// #     fn set_high(&mut self) -> Result<(), Self::Error> { Ok(()) }
+/// # }
+/// #
+/// # struct Timer;
+/// # impl fugit_timer::Timer<1_000_000> for Timer {
+/// #     type Error = core::convert::Infallible;
+/// #     fn now(&mut self) -> fugit::TimerInstantU32<1_000_000> {
+/// #         todo!()
+/// #     }
+/// #     fn start(&mut self, _: TimerDuration<1_000_000>)
+/// #         -> Result<(), Self::Error>
+/// #     {
+/// #         Ok(())
+/// #     }
+/// #     fn cancel(&mut self) -> Result<(), Self::Error> {
+/// #         Ok(())
+/// #     }
+/// #     fn wait(&mut self) -> nb::Result<(), Self::Error> {
+/// #         Ok(())
+/// #     }
+/// # }
+/// #
+/// # let (step, dir, mut timer) = (Pin, Pin, Timer);
+/// #
+/// type Num = fixed::FixedI64<typenum::U32>;
+///
+/// // All values use seconds as the unit of time. The delays are converted to
+/// // timer ticks below.
+/// let max_accel = Num::from_num(2000); // steps / s^2
+/// let jerk = Num::from_num(40000); // steps / s^3
+/// let max_velocity = Num::from_num(1000); // steps / s
+///
+/// let profile = SCurve::new(max_accel, jerk);
+///
+/// let mut stepper = Stepper::from_driver(MyDriver::new())
+///     .enable_direction_control(dir, Direction::Forward, &mut timer)?
+///     .enable_step_control(step)
+///     .enable_motion_control((timer, profile, DelayToTicks));
+///
+/// stepper.move_to_position(max_velocity, 2000).wait()?;
+///
+/// // The profile produces delays in seconds, which need to be converted to
+/// // ticks of the 1 MHz timer.
+/// struct DelayToTicks;
+/// impl motion_control::DelayToTicks<Num, 1_000_000> for DelayToTicks {
+///     type Error = core::convert::Infallible;
+///
+///     fn delay_to_ticks(&self, delay: Num)
+///         -> Result<TimerDuration<1_000_000>, Self::Error>
+///     {
+///         let ticks = delay * Num::from_num(1_000_000);
+///         Ok(TimerDuration::<1_000_000>::from_ticks(ticks.to_num()))
+///     }
+/// }
+/// #
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`DelayToTicks`]: super::DelayToTicks
+pub struct SCurve<Num> {
+    max_accel: Num,
+    max_decel: Num,
+    jerk: Num,
+    min_velocity: Num,
+    max_velocity: Option<Num>,
+
+    velocity: Num,
+    accel: Num,
+    steps_left: u32,
+    decelerating: bool,
+}
+
+impl<Num> SCurve<Num>
+where
+    Num: Copy
+        + Zero
+        + One
+        + ops::Add<Output = Num>
+        + ops::Mul<Output = Num>
+        + Sqrt,
+{
+    /// Create a new instance of `SCurve`
+    ///
+    /// Accepts the maximum acceleration in steps per (unit of time)^2, and the
+    /// jerk in steps per (unit of time)^3. Both must be positive. See the
+    /// struct documentation for information about units of time.
+    pub fn new(max_accel: Num, jerk: Num) -> Self {
+        let two = Num::one() + Num::one();
+
+        Self {
+            max_accel,
+            max_decel: max_accel,
+            jerk,
+            min_velocity: (two * max_accel).sqrt(),
+            max_velocity: None,
+
+            velocity: Num::zero(),
+            accel: Num::zero(),
+            steps_left: 0,
+            decelerating: false,
+        }
+    }
+
+    /// Use a different maximum deceleration
+    ///
+    /// By default, the profile slows down as hard as it speeds up. The
+    /// deceleration is given in steps per (unit of time)^2 and must be
+    /// positive.
+    pub fn with_deceleration(mut self, max_decel: Num) -> Self {
+        self.max_decel = max_decel;
+        self
+    }
+}
+
+impl<Num> SCurve<Num>
+where
+    Num: Copy
+        + PartialOrd
+        + Zero
+        + One
+        + FromPrimitive
+        + Inv<Output = Num>
+        + ops::Add<Output = Num>
+        + ops::Sub<Output = Num>
+        + ops::Mul<Output = Num>
+        + ops::Div<Output = Num>
+        + Sqrt,
+{
+    /// The number of steps needed to come to a stop from the current state
+    ///
+    /// This is an upper bound. It assumes the maximum acceleration is reached
+    /// while slowing down, which overestimates the distance for slow motions.
+    fn steps_to_stop(&self) -> Num {
+        let two = Num::one() + Num::one();
+
+        // If we're still accelerating, the acceleration has to be brought back
+        // to zero first, which increases the velocity some more.
+        let (velocity, ramp_down) = if self.accel > Num::zero() {
+            let t = self.accel / self.jerk;
+            let velocity = self.velocity + self.accel * t / two;
+            (velocity, velocity * t)
+        } else {
+            (self.velocity, Num::zero())
+        };
+
+        let floor = self.floor_velocity();
+        if velocity <= floor {
+            return ramp_down;
+        }
+
+        // Slowing down from `velocity` to `floor`, ramping the deceleration up
+        // to the maximum, and back down again.
+        ramp_down
+            + (velocity * velocity - floor * floor) / (two * self.max_decel)
+            + (velocity + floor) * self.max_decel / (two * self.jerk)
+    }
+
+    /// The lowest velocity of the motion, at the start and the end
+    fn floor_velocity(&self) -> Num {
+        match self.max_velocity {
+            Some(max_velocity) => clamp_max(self.min_velocity, max_velocity),
+            None => self.min_velocity,
+        }
+    }
+
+    fn target_accel(&self) -> Num {
+        let two = Num::one() + Num::one();
+
+        if self.decelerating {
+            if self.steps_left == 0 {
+                return Num::zero() - self.max_decel;
+            }
+
+            // The deceleration that lands us on the target step. This is
+            // usually a bit below the maximum, as `steps_to_stop` starts the
+            // deceleration early.
+            let v = self.velocity;
+            let v_min = self.floor_velocity();
+            let steps = Num::from_u32(self.steps_left).unwrap_or(Num::one());
+            let required = (v * v - v_min * v_min) / (two * steps);
+
+            return Num::zero() - clamp_max(required, self.max_decel);
+        }
+
+        let max_velocity = match self.max_velocity {
+            Some(max_velocity) => max_velocity,
+            None => return Num::zero() - self.max_decel,
+        };
+
+        if self.velocity > max_velocity {
+            return Num::zero() - self.max_decel;
+        }
+
+        // Reducing the acceleration to zero takes time, during which the
+        // velocity keeps increasing. Start early, to not overshoot.
+        let accel = self.accel;
+        let overshoot = if accel > Num::zero() {
+            accel * accel / (two * self.jerk)
+        } else {
+            Num::zero()
+        };
+
+        if self.velocity + overshoot >= max_velocity {
+            Num::zero()
+        } else {
+            self.max_accel
+        }
+    }
+}
+
+impl<Num> MotionProfile for SCurve<Num>
+where
+    Num: Copy
+        + PartialOrd
+        + Zero
+        + One
+        + FromPrimitive
+        + Inv<Output = Num>
+        + ops::Add<Output = Num>
+        + ops::Sub<Output = Num>
+        + ops::Mul<Output = Num>
+        + ops::Div<Output = Num>
+        + Sqrt,
+{
+    type Velocity = Num;
+    type Delay = Num;
+
+    fn enter_position_mode(
+        &mut self,
+        max_velocity: Self::Velocity,
+        num_steps: u32,
+    ) {
+        self.max_velocity = if max_velocity.is_zero() {
+            None
+        } else {
+            Some(max_velocity)
+        };
+
+        self.steps_left = num_steps;
+
+        // Whether we need to slow down has to be re-evaluated for the new
+        // target.
+        self.decelerating = false;
+    }
+
+    fn next_delay(&mut self) -> Option<Self::Delay> {
+        let floor_velocity = self.floor_velocity();
+        let moving = self.velocity > floor_velocity;
+        let no_steps_left = self.steps_left == 0;
+
+        if !moving && (no_steps_left || self.max_velocity.is_none()) {
+            self.velocity = Num::zero();
+            self.accel = Num::zero();
+            return None;
+        }
+
+        if self.velocity.is_zero() {
+            // Starting a new motion.
+            self.velocity = floor_velocity;
+        } else {
+            let two = Num::one() + Num::one();
+
+            if !self.decelerating {
+                let steps_left = Num::from_u32(self.steps_left);
+                self.decelerating = match steps_left {
+                    Some(steps_left) => steps_left <= self.steps_to_stop(),
+                    // Too far away to be representable. No need to slow down.
+                    None => false,
+                };
+            }
+
+            // The time the last step took, which limits how much the
+            // acceleration can change.
+            let dt = self.velocity.inv();
+            let max_change = self.jerk * dt;
+
+            let target = self.target_accel();
+            self.accel = if target > self.accel {
+                clamp_max(self.accel + max_change, target)
+            } else {
+                clamp_min(self.accel - max_change, target)
+            };
+
+            let v_squared = self.velocity * self.velocity + two * self.accel;
+            let mut velocity = if v_squared > Num::zero() {
+                v_squared.sqrt()
+            } else {
+                Num::zero()
+            };
+
+            if let (Some(max_velocity), false) =
+                (self.max_velocity, self.decelerating)
+            {
+                // Don't overshoot the maximum velocity when accelerating, and
+                // don't undershoot it when slowing down to it.
+                let was_below = self.velocity <= max_velocity;
+                if was_below && velocity >= max_velocity
+                    || !was_below && velocity <= max_velocity
+                {
+                    velocity = max_velocity;
+                    self.accel = Num::zero();
+                }
+            }
+
+            self.velocity = clamp_min(velocity, floor_velocity);
+        }
+
+        self.steps_left = self.steps_left.saturating_sub(1);
+
+        Some(self.velocity.inv())
+    }
+}
+
+impl<Num> SetAcceleration for SCurve<Num>
+where
+    Self: MotionProfile,
+    Num: Copy
+        + Zero
+        + One
+        + ops::Add<Output = Num>
+        + ops::Mul<Output = Num>
+        + Sqrt,
+{
+    type Acceleration = Num;
+
+    fn set_acceleration(&mut self, accel: Num, decel: Num) {
+        *self = Self::new(accel, self.jerk).with_deceleration(decel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+    use ramp_maker::MotionProfile as _;
+
+    use crate::{
+        mock::{MockDriver, Signal},
+        motion_control::{DelayToTicks, SCurveMotionControl},
+        traits::MotionControl,
+    };
+
+    use super::{SCurve, SetAcceleration as _};
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn s_curve_should_limit_jerk_and_reach_target() {
+        let max_accel = 2000.0;
+        let max_velocity = 1000.0;
+
+        let mut profile =
+            SCurve::new(Num::from_num(max_accel), Num::from_num(40000));
+        profile.enter_position_mode(Num::from_num(max_velocity), 2000);
+
+        let velocities: Vec<f32> = profile
+            .delays()
+            .map(|delay| delay.recip().to_num::<f32>())
+            .collect();
+        let accels: Vec<f32> = velocities
+            .windows(2)
+            .map(|v| (v[1] * v[1] - v[0] * v[0]) / 2.0)
+            .collect();
+
+        assert_eq!(velocities.len(), 2000);
+        assert!(velocities.iter().all(|&v| v <= max_velocity + 0.01));
+        assert!((velocities[1000] - max_velocity).abs() < 0.01);
+        assert!(velocities[1999] < velocities[0] * 1.1);
+
+        // A trapezoidal profile would start with the maximum acceleration.
+        assert!(accels[0] < max_accel * 0.5);
+        assert!(accels.iter().all(|a| a.abs() <= max_accel * 1.01));
+    }
+
+    #[test]
+    fn s_curve_should_decelerate_slower_than_it_accelerates() {
+        let mut profile = SCurve::new(Num::from_num(1), Num::from_num(40000));
+        profile.set_acceleration(Num::from_num(2000), Num::from_num(500));
+        profile.enter_position_mode(Num::from_num(1000), 2000);
+
+        let velocities: Vec<f32> = profile
+            .delays()
+            .map(|delay| delay.recip().to_num::<f32>())
+            .collect();
+        let accels: Vec<f32> = velocities
+            .windows(2)
+            .map(|v| (v[1] * v[1] - v[0] * v[0]) / 2.0)
+            .collect();
+
+        assert_eq!(velocities.len(), 2000);
+        assert!(accels.iter().any(|&a| a > 1900.0));
+        assert!(accels.iter().all(|&a| a >= -500.0 * 1.01));
+    }
+
+    #[test]
+    fn s_curve_should_work_with_software_motion_control() {
+        let driver = MockDriver::new();
+        let profile = SCurve::new(Num::from_num(2000), Num::from_num(40000));
+        let mut motion_control: SCurveMotionControl<_, _, _, _, 1_000_000> =
+            SCurveMotionControl::new(
+                driver.clone(),
+                driver.timer(),
+                profile,
+                Convert,
+            );
+
+        motion_control
+            .move_to_position(Num::from_num(1000), 500)
+            .unwrap();
+        while motion_control.update().unwrap() {}
+
+        assert_eq!(motion_control.current_position().unwrap(), 500);
+
+        // The delays are in seconds. If they weren't converted, all of them
+        // would be zero ticks.
+        let periods = step_periods(&driver);
+        assert_eq!(periods.len(), 499);
+        assert!(periods.iter().all(|&period| period >= 1000));
+        assert!(periods.iter().any(|&period| period > 10_000));
+    }
+
+    /// Returns the time between each two step pulses, in microseconds
+    fn step_periods(driver: &MockDriver) -> Vec<u64> {
+        let steps: Vec<_> = driver
+            .transitions()
+            .into_iter()
+            .filter(|transition| transition.signal == Signal::Step(true))
+            .map(|transition| transition.time_ns / 1000)
+            .collect();
+
+        steps.windows(2).map(|steps| steps[1] - steps[0]).collect()
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<Num, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: Num,
+        ) -> Result<TimerDuration<1_000_000>, Self::Error> {
+            let ticks = delay * Num::from_num(1_000_000);
+            Ok(TimerDuration::<1_000_000>::from_ticks(ticks.to_num()))
+        }
+    }
+}
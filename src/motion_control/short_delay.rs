@@ -0,0 +1,16 @@
+/// Defines how [`SoftwareMotionControl`] handles step delays that are too short
+///
+/// A step delay is too short, if it doesn't leave enough time for the STEP
+/// pulse, and for the STEP signal to be low for as long as it was high. See
+/// [`SoftwareMotionControl::set_short_delay_mode`].
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`SoftwareMotionControl::set_short_delay_mode`]: super::SoftwareMotionControl::set_short_delay_mode
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShortDelayMode {
+    /// Lengthen the delay to the shortest one the driver can handle
+    Clamp,
+
+    /// Stop the motion, return an error instead
+    Reject,
+}
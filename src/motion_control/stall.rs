@@ -0,0 +1,87 @@
+use crate::traits::DetectStall;
+
+/// Check the driver for a stall, treating errors like a stall
+///
+/// Used by [`SoftwareMotionControl`] as a function pointer, so it can check
+/// for a stall without requiring [`DetectStall`] from every driver.
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+pub fn is_stalled<Driver>(driver: &mut Driver) -> bool
+where
+    Driver: DetectStall,
+{
+    driver.is_stalled().unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+
+    use crate::{
+        mock::{MockDriver, MockTimer, Signal},
+        motion_control::{DelayToTicks, Error, SoftwareMotionControl},
+        traits::{EnableMotionControl as _, MotionControl},
+    };
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn stall_should_abort_motion() {
+        let driver = MockDriver::new();
+        let mut motion_control: SoftwareMotionControl<
+            MockDriver,
+            MockTimer<1_000_000>,
+            _,
+            _,
+            1_000_000,
+        > = driver.clone().enable_motion_control((
+            driver.timer(),
+            ramp_maker::Trapezoidal::new(Num::from_num(2000)),
+            Convert,
+        ));
+        motion_control.enable_stall_detection();
+
+        motion_control
+            .move_to_position(Num::from_num(1000), 100)
+            .unwrap();
+        let result = loop {
+            if steps(&driver) >= 10 {
+                driver.set_stalled(true);
+            }
+
+            match motion_control.update() {
+                Ok(true) => continue,
+                result => break result,
+            }
+        };
+
+        assert!(matches!(result, Err(Error::Stalled)));
+        assert_eq!(motion_control.current_position().unwrap(), 10);
+        assert!(!motion_control.update().unwrap());
+    }
+
+    /// Count the step pulses that were sent to the driver
+    fn steps(driver: &MockDriver) -> usize {
+        driver
+            .transitions()
+            .into_iter()
+            .filter(|transition| transition.signal == Signal::Step(true))
+            .count()
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<Num, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: Num,
+        ) -> Result<TimerDuration<1_000_000>, Self::Error> {
+            let ticks = delay * Num::from_num(1_000_000);
+            Ok(TimerDuration::<1_000_000>::from_ticks(ticks.to_num()))
+        }
+    }
+}
@@ -9,12 +9,21 @@ use ramp_maker::MotionProfile;
 
 use crate::{
     traits::{SetDirection, Step},
-    Direction, SetDirectionFuture, StepFuture,
+    Direction, SetDirectionFuture, SignalError, StepFuture,
 };
 
 use super::{
-    error::{Error, TimeConversionError},
-    DelayToTicks,
+    backlash::Backlash,
+    dither::Dither,
+    error::{Error, TimeConversionError, TravelLimitError},
+    feedrate::Feedrate,
+    gearing::Gearing,
+    jitter::Jitter,
+    lookahead::Lookahead,
+    pending_step_mode::{Phase, PollStepMode},
+    short_delay::ShortDelayMode,
+    status::DelayHistory,
+    DelayToTicks, TravelLimits,
 };
 
 pub enum State<Driver, Timer, Profile: MotionProfile, const TIMER_HZ: u32> {
@@ -30,10 +39,19 @@ pub enum State<Driver, Timer, Profile: MotionProfile, const TIMER_HZ: u32> {
     StepDelay {
         driver: Driver,
         timer: Timer,
+        delay: Profile::Delay,
+    },
+    SetStepMode {
+        driver: Driver,
+        timer: Timer,
+        step_mode: u16,
+        phase: Phase,
+        poll: PollStepMode<Driver, Timer>,
     },
     Invalid,
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>(
     mut state: State<Driver, Timer, Profile, TIMER_HZ>,
     new_motion: &mut Option<Direction>,
@@ -41,6 +59,18 @@ pub fn update<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>(
     current_step: &mut i32,
     current_direction: &mut Direction,
     convert: &Convert,
+    feedrate: Feedrate,
+    dither: &mut Dither,
+    jitter: &mut Jitter,
+    backlash: &mut Backlash,
+    gearing: &mut Option<Gearing>,
+    travel_limits: Option<TravelLimits>,
+    segment_end: Option<i32>,
+    stall_check: Option<fn(&mut Driver) -> bool>,
+    delays: &mut DelayHistory<Profile::Delay>,
+    short_delay_mode: ShortDelayMode,
+    rate_limited: &mut bool,
+    lookahead: &mut Lookahead<Profile::Delay>,
 ) -> (
     Result<
         bool,
@@ -59,11 +89,12 @@ where
     Driver: SetDirection + Step,
     Timer: TimerTrait<TIMER_HZ>,
     Profile: MotionProfile,
+    Profile::Delay: Copy,
     Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
 {
     loop {
         match state {
-            State::Idle { driver, timer } => {
+            State::Idle { driver, mut timer } => {
                 // Being idle can mean that there's actually nothing to do, or
                 // it might just be a short breather before more work comes in.
 
@@ -80,11 +111,78 @@ where
                     continue;
                 }
 
+                // If the next motion is chained to this one, the caller needs
+                // to switch over once we've reached the end of this one.
+                if segment_end == Some(*current_step) && !backlash.pending() {
+                    return (Ok(true), State::Idle { driver, timer });
+                }
+
                 // No new motion has been started, but we might still have an
                 // ongoing one. Let's ask the motion profile.
-                if let Some(delay) = profile.next_delay() {
+                if let Some(delay) = lookahead.next_delay(profile) {
+                    // A synchronized motion doesn't make all the steps the
+                    // profile plans for. For those it skips, the delay still
+                    // needs to be waited out. Steps that take up backlash are
+                    // always made.
+                    let skip_step = !backlash.pending()
+                        && gearing.as_mut().map(Gearing::advance)
+                            == Some(false);
+                    if skip_step {
+                        let delay_left = match delay_left(
+                            delay,
+                            Nanoseconds::from_ticks(0),
+                            convert,
+                            feedrate,
+                            dither,
+                        ) {
+                            Ok((delay_left, _)) => delay_left,
+                            Err(err) => {
+                                return (
+                                    Err(Error::TimeConversion(err)),
+                                    State::Idle { driver, timer },
+                                )
+                            }
+                        };
+                        if let Err(err) = timer.start(delay_left) {
+                            return (
+                                Err(Error::StepDelay(err)),
+                                State::Idle { driver, timer },
+                            );
+                        }
+
+                        state = State::StepDelay {
+                            driver,
+                            timer,
+                            delay,
+                        };
+                        continue;
+                    }
+
+                    // Make sure the step we're about to make doesn't leave the
+                    // travel limits. If it would, stop right here. The caller
+                    // takes care of stopping the motion profile.
+                    // Steps that take up backlash don't change the position.
+                    let next_step = if backlash.pending() {
+                        *current_step
+                    } else {
+                        *current_step + *current_direction as i32
+                    };
+                    if let Some(limits) = travel_limits {
+                        if !limits.contains(next_step) {
+                            return (
+                                Err(Error::TravelLimit(
+                                    TravelLimitError::LimitReached(
+                                        *current_step,
+                                    ),
+                                )),
+                                State::Idle { driver, timer },
+                            );
+                        }
+                    }
+
                     // There's a motion ongoing. Let's start the next step, but
                     // again, don't return yet. The future needs to be polled.
+                    delays.push(delay);
                     state = State::Step {
                         future: StepFuture::new(driver, timer),
                         delay,
@@ -94,6 +192,7 @@ where
 
                 // Now we know that there's truly nothing to do. Return to the
                 // caller and stay idle.
+                delays.clear();
                 return (Ok(false), State::Idle { driver, timer });
             }
             State::SetDirection(mut future) => {
@@ -129,7 +228,9 @@ where
                         // A step was made. Now we need to wait out the rest of
                         // the step delay before we can do something else.
 
-                        *current_step += *current_direction as i32;
+                        if backlash.step_made(*current_direction) {
+                            *current_step += *current_direction as i32;
+                        }
 
                         let (driver, mut timer) = future.release();
                         let delay_left: TimerDuration<TIMER_HZ> =
@@ -137,8 +238,24 @@ where
                                 delay,
                                 Driver::PULSE_LENGTH,
                                 convert,
+                                feedrate,
+                                dither,
                             ) {
-                                Ok(delay_left) => delay_left,
+                                Ok((_, true))
+                                    if short_delay_mode
+                                        == ShortDelayMode::Reject =>
+                                {
+                                    // The step has been made. The caller takes
+                                    // care of stopping the motion profile.
+                                    return (
+                                        Err(Error::DelayTooShort),
+                                        State::Idle { driver, timer },
+                                    );
+                                }
+                                Ok((delay_left, limited)) => {
+                                    *rate_limited |= limited;
+                                    delay_left
+                                }
                                 Err(err) => {
                                     return (
                                         Err(Error::TimeConversion(err)),
@@ -146,6 +263,8 @@ where
                                     )
                                 }
                             };
+                        let delay_left =
+                            jittered(delay_left, Driver::PULSE_LENGTH, jitter);
 
                         if let Err(err) = timer.start(delay_left) {
                             return (
@@ -154,7 +273,11 @@ where
                             );
                         }
 
-                        state = State::StepDelay { driver, timer };
+                        state = State::StepDelay {
+                            driver,
+                            timer,
+                            delay,
+                        };
                         continue;
                     }
                     Poll::Ready(Err(err)) => {
@@ -174,7 +297,25 @@ where
                     }
                 }
             }
-            State::StepDelay { driver, mut timer } => {
+            State::StepDelay {
+                mut driver,
+                mut timer,
+                delay,
+            } => {
+                if let Some(stall_check) = stall_check {
+                    if stall_check(&mut driver) {
+                        // The caller is responsible for aborting the motion.
+                        return (
+                            Err(Error::Stalled),
+                            State::StepDelay {
+                                driver,
+                                timer,
+                                delay,
+                            },
+                        );
+                    }
+                }
+
                 match timer.wait() {
                     Ok(()) => {
                         // We've waited out the step delay. Return to idle
@@ -183,14 +324,60 @@ where
                         continue;
                     }
                     Err(nb::Error::WouldBlock) => {
-                        // The timer is still running. Let the user know.
-                        return (Ok(true), State::StepDelay { driver, timer });
+                        // The timer is still running. Use the time to compute
+                        // upcoming delays, then let the user know.
+                        lookahead.fill(profile);
+                        return (
+                            Ok(true),
+                            State::StepDelay {
+                                driver,
+                                timer,
+                                delay,
+                            },
+                        );
                     }
                     Err(nb::Error::Other(err)) => {
                         // Error while trying to wait. Need to tell the caller.
                         return (
                             Err(Error::StepDelay(err)),
-                            State::StepDelay { driver, timer },
+                            State::StepDelay {
+                                driver,
+                                timer,
+                                delay,
+                            },
+                        );
+                    }
+                }
+            }
+            State::SetStepMode {
+                mut driver,
+                mut timer,
+                step_mode,
+                mut phase,
+                poll,
+            } => {
+                // A queued step mode is being applied, now that the previous
+                // motion has finished. Any new motion has to wait for that.
+                match poll(&mut driver, &mut timer, &mut phase, step_mode) {
+                    Poll::Ready(true) => {
+                        state = State::Idle { driver, timer };
+                    }
+                    Poll::Ready(false) => {
+                        return (
+                            Err(Error::StepMode),
+                            State::Idle { driver, timer },
+                        );
+                    }
+                    Poll::Pending => {
+                        return (
+                            Ok(true),
+                            State::SetStepMode {
+                                driver,
+                                timer,
+                                step_mode,
+                                phase,
+                                poll,
+                            },
                         );
                     }
                 }
@@ -201,10 +388,72 @@ where
                 // continue.
                 //
                 // A panic in this closure is always going to be a
-                // bug, and once that happened, we're in an invalid
-                // state. Not a lot we can do about it.
-                panic!("Invalid internal state, caused by a previous panic.")
+                // bug, and once that happened, the driver and timer are
+                // gone. Only the user can provide replacements.
+                return (Err(Error::InvalidState), State::Invalid);
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn abort<Driver, Timer, Profile, const TIMER_HZ: u32>(
+    state: State<Driver, Timer, Profile, TIMER_HZ>,
+    current_step: &mut i32,
+    current_direction: Direction,
+    backlash: &mut Backlash,
+) -> (
+    Result<
+        (),
+        SignalError<
+            <Driver as Step>::Error,
+            <<Driver as Step>::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    >,
+    State<Driver, Timer, Profile, TIMER_HZ>,
+)
+where
+    Driver: SetDirection + Step,
+    Timer: TimerTrait<TIMER_HZ>,
+    Profile: MotionProfile,
+{
+    match state {
+        State::Idle { driver, timer }
+        | State::StepDelay { driver, timer, .. } => {
+            // If we're waiting out a step delay, the timer is still running.
+            // That's fine. It will be restarted before it's used again.
+            (Ok(()), State::Idle { driver, timer })
+        }
+        State::SetDirection(future) => {
+            // No step has been made since the direction was changed, so it
+            // doesn't matter whether the new direction has been fully set.
+            let (driver, timer) = future.release();
+            (Ok(()), State::Idle { driver, timer })
+        }
+        State::Step { mut future, .. } => {
+            // A step pulse is ongoing. The driver already saw the rising edge,
+            // so we count the step as made and end the pulse early.
+            //
+            // If this fails, the step pin might still be high. The next step
+            // pulse will take care of that.
+            let result = future.cancel();
+            let (driver, timer) = future.release();
+            if backlash.step_made(current_direction) {
+                *current_step += current_direction as i32;
             }
+
+            (result, State::Idle { driver, timer })
+        }
+        state @ State::SetStepMode { .. } => {
+            // No motion is ongoing. The step mode change needs to finish, to
+            // respect the timing requirements of the driver.
+            (Ok(()), state)
+        }
+        State::Invalid => {
+            // See comment in `update`. There's nothing to abort, and the
+            // caller reports the invalid state.
+            (Ok(()), State::Invalid)
         }
     }
 }
@@ -213,15 +462,169 @@ fn delay_left<Delay, Convert, const TIMER_HZ: u32>(
     delay: Delay,
     pulse_length: Nanoseconds,
     convert: &Convert,
-) -> Result<TimerDuration<TIMER_HZ>, TimeConversionError<Convert::Error>>
+    feedrate: Feedrate,
+    dither: &mut Dither,
+) -> Result<(TimerDuration<TIMER_HZ>, bool), TimeConversionError<Convert::Error>>
 where
     Convert: DelayToTicks<Delay, TIMER_HZ>,
 {
-    let delay: TimerDuration<TIMER_HZ> = convert
-        .delay_to_ticks(delay)
-        .map_err(|err| TimeConversionError::DelayToTicks(err))?;
+    let (delay, fraction) = convert
+        .delay_to_fractional_ticks(delay)
+        .map_err(TimeConversionError::DelayToTicks)?;
+    // Scale before dithering, so the fraction survives the feedrate override.
+    let (ticks, fraction) = feedrate.apply(delay.ticks(), fraction);
+    let delay =
+        TimerDuration::<TIMER_HZ>::from_ticks(dither.apply(ticks, fraction));
     let pulse_length: TimerDuration<TIMER_HZ> = pulse_length.convert();
 
-    let delay_left = delay - pulse_length;
-    Ok(delay_left)
+    // If the delay is too short, the velocity is higher than the driver can
+    // handle.
+    let min_delay_left = min_delay_left::<TIMER_HZ>(pulse_length);
+    let delay_left = delay.ticks().saturating_sub(pulse_length.ticks());
+    if delay_left < min_delay_left {
+        return Ok((
+            TimerDuration::<TIMER_HZ>::from_ticks(min_delay_left),
+            true,
+        ));
+    }
+
+    Ok((TimerDuration::<TIMER_HZ>::from_ticks(delay_left), false))
+}
+
+/// Apply jitter to the delay that is left after a step pulse
+///
+/// Jitter averages out over time, so it doesn't count towards the velocity
+/// limit. Still, it must not shorten a delay below the minimum either.
+fn jittered<const TIMER_HZ: u32>(
+    delay_left: TimerDuration<TIMER_HZ>,
+    pulse_length: Nanoseconds,
+    jitter: &mut Jitter,
+) -> TimerDuration<TIMER_HZ> {
+    let min_delay_left = min_delay_left::<TIMER_HZ>(pulse_length.convert());
+    let ticks = jitter.apply(delay_left.ticks()).max(min_delay_left);
+
+    TimerDuration::<TIMER_HZ>::from_ticks(ticks)
+}
+
+fn min_delay_left<const TIMER_HZ: u32>(
+    pulse_length: TimerDuration<TIMER_HZ>,
+) -> u32 {
+    // The STEP signal needs to stay low for at least as long as it was high,
+    // and the timer can't wait for less than one tick.
+    pulse_length.ticks().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::{NanosDurationU32 as Nanoseconds, TimerDurationU32};
+
+    use crate::motion_control::{
+        dither::Dither, feedrate::Feedrate, jitter::Jitter, DelayToTicks,
+    };
+
+    use super::{delay_left, jittered};
+
+    #[test]
+    fn delay_left_should_be_limited_by_pulse_length() {
+        let pulse_length = Nanoseconds::from_ticks(5_000);
+        let feedrate = Feedrate::new(100);
+        let mut dither = Dither::new();
+
+        let (delay, limited) =
+            delay_left(20u32, pulse_length, &Convert, feedrate, &mut dither)
+                .unwrap();
+        assert_eq!(delay.ticks(), 15);
+        assert!(!limited);
+
+        let (delay, limited) =
+            delay_left(8u32, pulse_length, &Convert, feedrate, &mut dither)
+                .unwrap();
+        assert_eq!(delay.ticks(), 5);
+        assert!(limited);
+
+        let (delay, limited) = delay_left(
+            0u32,
+            Nanoseconds::from_ticks(0),
+            &Convert,
+            feedrate,
+            &mut dither,
+        )
+        .unwrap();
+        assert_eq!(delay.ticks(), 1);
+        assert!(limited);
+    }
+
+    #[test]
+    fn delay_left_should_keep_fraction_at_any_feedrate() {
+        let feedrate = Feedrate::new(200);
+        let mut dither = Dither::new();
+
+        // 10.25 ticks per delay, at 200% that's 5.125 ticks.
+        let total: u32 = (0..800)
+            .map(|_| {
+                let (delay, _) = delay_left(
+                    41u32,
+                    Nanoseconds::from_ticks(0),
+                    &QuarterTicks,
+                    feedrate,
+                    &mut dither,
+                )
+                .unwrap();
+                delay.ticks()
+            })
+            .sum();
+
+        assert_eq!(total, 4100);
+    }
+
+    #[test]
+    fn jitter_should_not_undercut_minimum_delay() {
+        let pulse_length = Nanoseconds::from_ticks(5_000);
+        let mut jitter = Jitter::new(3);
+
+        for _ in 0..100 {
+            let delay = jittered(
+                TimerDurationU32::<1_000_000>::from_ticks(5),
+                pulse_length,
+                &mut jitter,
+            );
+            assert!((5..=8).contains(&delay.ticks()));
+        }
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<u32, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: u32,
+        ) -> Result<TimerDurationU32<1_000_000>, Self::Error> {
+            Ok(TimerDurationU32::from_ticks(delay))
+        }
+    }
+
+    /// Converts delays given in quarter ticks
+    struct QuarterTicks;
+
+    impl DelayToTicks<u32, 1_000_000> for QuarterTicks {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: u32,
+        ) -> Result<TimerDurationU32<1_000_000>, Self::Error> {
+            Ok(TimerDurationU32::from_ticks(delay / 4))
+        }
+
+        fn delay_to_fractional_ticks(
+            &self,
+            delay: u32,
+        ) -> Result<(TimerDurationU32<1_000_000>, u32), Self::Error> {
+            Ok((self.delay_to_ticks(delay)?, (delay % 4) << 30))
+        }
+    }
 }
@@ -0,0 +1,117 @@
+/// A snapshot of the motion that [`SoftwareMotionControl`] is executing
+///
+/// Returned by [`SoftwareMotionControl::status`].
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`SoftwareMotionControl::status`]: super::SoftwareMotionControl::status
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MotionStatus<Delay> {
+    /// The phase of the motion
+    pub phase: MotionPhase,
+
+    /// The number of steps left until the target is reached
+    ///
+    /// Doesn't include queued motions, or steps that take up backlash. Zero,
+    /// if no motion is ongoing.
+    pub steps_remaining: u32,
+
+    /// The delay of the current step, as produced by the motion profile
+    ///
+    /// `None`, if no step has been made yet.
+    pub current_delay: Option<Delay>,
+}
+
+/// The phase of a motion
+///
+/// See [`MotionStatus`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MotionPhase {
+    /// No motion is ongoing
+    Idle,
+
+    /// The motor is speeding up
+    Accelerating,
+
+    /// The motor is moving at a constant velocity
+    Cruising,
+
+    /// The motor is slowing down
+    Decelerating,
+}
+
+/// The delays of the two most recent steps
+pub struct DelayHistory<Delay> {
+    current: Option<Delay>,
+    previous: Option<Delay>,
+}
+
+impl<Delay> DelayHistory<Delay> {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self {
+            current: None,
+            previous: None,
+        }
+    }
+
+    /// Record the delay of a new step
+    pub fn push(&mut self, delay: Delay) {
+        self.previous = self.current.replace(delay);
+    }
+
+    /// Forget all delays, once a motion has ended
+    pub fn clear(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+}
+
+impl<Delay> DelayHistory<Delay>
+where
+    Delay: Copy + PartialOrd,
+{
+    /// Access the delay of the most recent step
+    pub fn current(&self) -> Option<Delay> {
+        self.current
+    }
+
+    /// Derive the phase of an ongoing motion from the recent delays
+    ///
+    /// Shorter delays mean higher velocity.
+    pub fn phase(&self) -> MotionPhase {
+        match (self.previous, self.current) {
+            (Some(previous), Some(current)) if current < previous => {
+                MotionPhase::Accelerating
+            }
+            (Some(previous), Some(current)) if current > previous => {
+                MotionPhase::Decelerating
+            }
+            (Some(_), Some(_)) => MotionPhase::Cruising,
+            // Every motion starts by speeding up.
+            _ => MotionPhase::Accelerating,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DelayHistory, MotionPhase};
+
+    #[test]
+    fn phase_should_follow_delays() {
+        let mut history = DelayHistory::new();
+        assert_eq!(history.phase(), MotionPhase::Accelerating);
+
+        history.push(10);
+        history.push(8);
+        assert_eq!(history.phase(), MotionPhase::Accelerating);
+        history.push(8);
+        assert_eq!(history.phase(), MotionPhase::Cruising);
+        history.push(9);
+        assert_eq!(history.phase(), MotionPhase::Decelerating);
+        assert_eq!(history.current(), Some(9));
+
+        history.clear();
+        assert_eq!(history.current(), None);
+    }
+}
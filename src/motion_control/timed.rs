@@ -0,0 +1,107 @@
+use core::ops;
+
+use num_traits::{FromPrimitive, Inv, Zero};
+use ramp_maker::MotionProfile;
+
+/// The number of times the velocity is doubled, looking for an upper bound
+const MAX_DOUBLINGS: u32 = 16;
+
+/// The number of bisection steps, narrowing down the velocity
+const BISECTIONS: u32 = 16;
+
+/// Find the maximum velocity that completes a motion within `duration`
+///
+/// Runs the motion through the profile repeatedly, which means the result
+/// respects all of the profile's limits, but also that the profile must be
+/// idle. Returns `None`, if the profile can't complete the motion in time.
+pub fn velocity_for_duration<Profile>(
+    profile: &mut Profile,
+    num_steps: u32,
+    duration: Profile::Delay,
+) -> Option<Profile::Velocity>
+where
+    Profile: MotionProfile,
+    Profile::Velocity: Copy
+        + PartialOrd
+        + FromPrimitive
+        + ops::Add<Output = Profile::Velocity>
+        + ops::Mul<Output = Profile::Velocity>
+        + ops::Div<Output = Profile::Velocity>,
+    Profile::Delay: Copy
+        + PartialOrd
+        + Zero
+        + Inv<Output = Profile::Velocity>
+        + ops::Add<Output = Profile::Delay>,
+{
+    let two = Profile::Velocity::from_u32(2)?;
+
+    // The average velocity is a lower bound for the maximum velocity, as the
+    // motor moves slower than that while accelerating and decelerating.
+    let mut low = Profile::Velocity::from_u32(num_steps)? * duration.inv();
+    let mut high = low;
+
+    let mut doublings = 0;
+    while travel_time(profile, high, num_steps) > duration {
+        if doublings == MAX_DOUBLINGS {
+            return None;
+        }
+
+        low = high;
+        high = high * two;
+        doublings += 1;
+    }
+
+    for _ in 0..BISECTIONS {
+        let middle = (low + high) / two;
+        if travel_time(profile, middle, num_steps) > duration {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    // `high` is the lowest velocity we know to be fast enough.
+    Some(high)
+}
+
+fn travel_time<Profile>(
+    profile: &mut Profile,
+    max_velocity: Profile::Velocity,
+    num_steps: u32,
+) -> Profile::Delay
+where
+    Profile: MotionProfile,
+    Profile::Delay: Zero + ops::Add<Output = Profile::Delay>,
+{
+    profile.enter_position_mode(max_velocity, num_steps);
+    profile
+        .delays()
+        .fold(Profile::Delay::zero(), |total, delay| total + delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use ramp_maker::Trapezoidal;
+
+    use super::{travel_time, velocity_for_duration};
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn velocity_for_duration_should_arrive_in_time() {
+        let mut profile = Trapezoidal::new(Num::from_num(2000));
+        let duration = Num::from_num(2);
+
+        let velocity =
+            velocity_for_duration(&mut profile, 1000, duration).unwrap();
+        let time = travel_time(&mut profile, velocity, 1000);
+        assert!(time <= duration);
+        assert!(time > duration - Num::from_num(0.01));
+
+        // Too short, even if accelerating all the way to the middle.
+        assert_eq!(
+            velocity_for_duration(&mut profile, 1000, Num::from_num(0.9)),
+            None
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use super::error::TravelLimitError;
+
+/// The range of positions that [`SoftwareMotionControl`] may move within
+///
+/// See [`SoftwareMotionControl::set_travel_limits`].
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`SoftwareMotionControl::set_travel_limits`]: super::SoftwareMotionControl::set_travel_limits
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TravelLimits {
+    /// The minimum position (inclusive)
+    pub min: i32,
+
+    /// The maximum position (inclusive)
+    pub max: i32,
+
+    /// Defines what happens to targets outside of the limits
+    pub mode: TravelLimitMode,
+}
+
+impl TravelLimits {
+    /// Indicates whether the given step is within the limits
+    pub fn contains(&self, step: i32) -> bool {
+        self.min <= step && step <= self.max
+    }
+
+    /// Apply the limits to a target step, according to the mode
+    pub(crate) fn apply(
+        &self,
+        target_step: i32,
+    ) -> Result<i32, TravelLimitError> {
+        if self.contains(target_step) {
+            return Ok(target_step);
+        }
+
+        match self.mode {
+            TravelLimitMode::Clamp => {
+                // Don't use `Ord::clamp`, as that panics, if `min > max`.
+                Ok(target_step.min(self.max).max(self.min))
+            }
+            TravelLimitMode::Reject => {
+                Err(TravelLimitError::TargetOutOfRange(target_step))
+            }
+        }
+    }
+}
+
+/// Defines how [`TravelLimits`] handle targets outside of the limits
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TravelLimitMode {
+    /// Move to the nearest limit instead
+    Clamp,
+
+    /// Don't start the motion, return an error instead
+    Reject,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TravelLimitError, TravelLimitMode, TravelLimits};
+
+    #[test]
+    fn apply_should_clamp_or_reject_targets() {
+        let mut limits = TravelLimits {
+            min: -10,
+            max: 100,
+            mode: TravelLimitMode::Clamp,
+        };
+
+        assert_eq!(limits.apply(50), Ok(50));
+        assert_eq!(limits.apply(-20), Ok(-10));
+        assert_eq!(limits.apply(200), Ok(100));
+
+        limits.mode = TravelLimitMode::Reject;
+
+        assert_eq!(limits.apply(100), Ok(100));
+        assert_eq!(
+            limits.apply(101),
+            Err(TravelLimitError::TargetOutOfRange(101))
+        );
+    }
+}
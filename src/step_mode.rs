@@ -1,6 +1,6 @@
 //! Types related to working with a driver's microstepping mode
 
-use core::convert::TryFrom;
+use core::{convert::TryFrom, fmt, str::FromStr};
 
 use paste::paste;
 
@@ -16,6 +16,42 @@ pub trait StepMode:
     /// Starts at the mode for configuring full steps and ends at the highest
     /// supported number of microsteps per step.
     fn iter() -> Self::Iter;
+
+    /// Returns the number of microsteps per full step
+    ///
+    /// This is the same value that the `Into<u16>` implementation returns.
+    fn microsteps_per_step(self) -> u16 {
+        self.into()
+    }
+
+    /// Returns the number of microsteps per full step, as a `u32`
+    ///
+    /// All step mode enums in this crate also implement `Into<u32>`. Generic
+    /// code can use this method instead, without requiring that bound.
+    fn to_u32(self) -> u32 {
+        u32::from(self.microsteps_per_step())
+    }
+
+    /// Convert a number of full steps into microsteps
+    ///
+    /// Returns `None`, if the result overflows an `i32`.
+    fn checked_mul(self, full_steps: i32) -> Option<i32> {
+        full_steps.checked_mul(i32::from(self.microsteps_per_step()))
+    }
+
+    /// Convert a number of microsteps into full steps
+    ///
+    /// Returns `None`, if `microsteps` is not a whole number of full steps.
+    /// Use [`rescale_position`] to round to the nearest step instead.
+    fn checked_div(self, microsteps: i32) -> Option<i32> {
+        let microsteps_per_step = i32::from(self.microsteps_per_step());
+
+        if microsteps % microsteps_per_step != 0 {
+            return None;
+        }
+
+        Some(microsteps / microsteps_per_step)
+    }
 }
 
 macro_rules! generate_step_mode_enums {
@@ -122,6 +158,7 @@ macro_rules! generate_step_mode_enums {
                 of up to " $max " microsteps"
             ]
             #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+            #[cfg_attr(feature = "defmt", derive(defmt::Format))]
             pub enum [<StepMode $max>] {
                 $($variant_output)*
             }
@@ -132,6 +169,12 @@ macro_rules! generate_step_mode_enums {
                 }
             }
 
+            impl From<[<StepMode $max>]> for u32 {
+                fn from(step_mode: [<StepMode $max>]) -> Self {
+                    step_mode as Self
+                }
+            }
+
             impl TryFrom<u16> for [<StepMode $max>] {
                 type Error = InvalidStepModeError;
 
@@ -144,6 +187,23 @@ macro_rules! generate_step_mode_enums {
                 }
             }
 
+            impl FromStr for [<StepMode $max>] {
+                type Err = InvalidStepModeError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    Self::try_from(parse_microsteps_per_step(s)?)
+                }
+            }
+
+            impl fmt::Display for [<StepMode $max>] {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    match self {
+                        Self::Full => write!(f, "full"),
+                        mode => write!(f, "1/{}", *mode as u16),
+                    }
+                }
+            }
+
             impl StepMode for [<StepMode $max>] {
                 // It would be nice to avoid the custom iterator and use
                 // `iter::from_fn` instead. That would require `impl Iterator`
@@ -200,8 +260,62 @@ generate_step_mode_enums! {
 ///
 /// Returned by the `TryFrom` implementations of the various step mode enums.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct InvalidStepModeError;
 
+/// Parse the number of microsteps per full step from a string
+///
+/// Used by the `FromStr` implementations of the step mode enums. Accepts the
+/// format produced by their `Display` implementations ("full", "1/16"), as
+/// well as plain numbers ("16").
+fn parse_microsteps_per_step(s: &str) -> Result<u16, InvalidStepModeError> {
+    let s = s.trim();
+
+    if s.eq_ignore_ascii_case("full") {
+        return Ok(1);
+    }
+
+    let microsteps = s.strip_prefix("1/").unwrap_or(s);
+    microsteps.parse().map_err(|_| InvalidStepModeError)
+}
+
+/// Convert a position from one step mode to another
+///
+/// `from` and `to` are the number of microsteps per full step of the old and
+/// the new step mode respectively, as returned by the `Into<u16>`
+/// implementation of the step mode enums. When converting to a coarser step
+/// mode, the position is rounded to the nearest step. The result saturates, if
+/// it is not representable as an `i32`.
+///
+/// [`Stepper`] and [`SoftwareMotionControl`] use this to keep their positions
+/// consistent, when the step mode changes.
+///
+/// [`Stepper`]: crate::Stepper
+/// [`SoftwareMotionControl`]: crate::motion_control::SoftwareMotionControl
+pub fn rescale_position(position: i32, from: u16, to: u16) -> i32 {
+    if from == to || from == 0 {
+        return position;
+    }
+
+    let from = i64::from(from);
+    let scaled = i64::from(position) * i64::from(to);
+
+    // Round half away from zero.
+    let rounded = if scaled < 0 {
+        (scaled - from / 2) / from
+    } else {
+        (scaled + from / 2) / from
+    };
+
+    rounded.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
+
+impl fmt::Display for InvalidStepModeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid step mode")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     // Only tests `StepMode256`. This should be fine, since all other step mode
@@ -209,7 +323,9 @@ mod tests {
 
     use core::convert::TryFrom;
 
-    use super::{StepMode as _, StepMode256};
+    use super::{
+        rescale_position, InvalidStepModeError, StepMode as _, StepMode256,
+    };
 
     #[test]
     fn step_mode_should_convert_into_microsteps_per_step() {
@@ -241,6 +357,20 @@ mod tests {
         assert_eq!(<StepMode256 as TryFrom<u16>>::try_from(256), Ok(M256));
     }
 
+    #[test]
+    fn step_mode_should_parse_and_display() {
+        use StepMode256::*;
+
+        assert_eq!("full".parse(), Ok(Full));
+        assert_eq!("1/16".parse(), Ok(M16));
+        assert_eq!(" 256 ".parse(), Ok(M256));
+        assert_eq!("1/3".parse::<StepMode256>(), Err(InvalidStepModeError));
+        assert_eq!("half".parse::<StepMode256>(), Err(InvalidStepModeError));
+
+        assert_eq!(Full.to_string(), "full");
+        assert_eq!(M16.to_string(), "1/16");
+    }
+
     #[test]
     fn step_mode_should_provide_iterator_over_modes() {
         use StepMode256::*;
@@ -248,4 +378,28 @@ mod tests {
         let modes: Vec<_> = StepMode256::iter().collect();
         assert_eq!(modes, [Full, M2, M4, M8, M16, M32, M64, M128, M256]);
     }
+
+    #[test]
+    fn step_mode_should_convert_between_full_steps_and_microsteps() {
+        use StepMode256::*;
+
+        assert_eq!(M16.microsteps_per_step(), 16);
+        assert_eq!(M256.to_u32(), 256);
+        assert_eq!(u32::from(M256), 256);
+
+        assert_eq!(M16.checked_mul(-100), Some(-1600));
+        assert_eq!(M256.checked_mul(i32::MAX / 2), None);
+
+        assert_eq!(M16.checked_div(-1600), Some(-100));
+        assert_eq!(M16.checked_div(1601), None);
+    }
+
+    #[test]
+    fn rescale_position_should_convert_between_step_modes() {
+        assert_eq!(rescale_position(100, 16, 256), 1600);
+        assert_eq!(rescale_position(-1600, 256, 16), -100);
+        assert_eq!(rescale_position(24, 16, 1), 2);
+        assert_eq!(rescale_position(-23, 16, 1), -1);
+        assert_eq!(rescale_position(i32::MAX, 1, 256), i32::MAX);
+    }
 }
@@ -0,0 +1,169 @@
+use core::{
+    sync::atomic::{AtomicBool, Ordering},
+    task::Poll,
+};
+
+use crate::traits::MotionControl;
+
+use super::MoveToFuture;
+
+/// Starts multiple motions at the same time
+///
+/// Motions that are started through [`MoveToFuture::start_with`] don't start
+/// when they are first polled. Instead, they wait until
+/// [`StartBarrier::release`] has been called. This makes it possible to arm
+/// the motions of several steppers one by one, then release all of them at
+/// once.
+///
+/// Since only an atomic flag is used, [`StartBarrier::release`] can be called
+/// from an interrupt handler, for example to start the motions on an external
+/// trigger. The barrier can be stored in a `static`.
+///
+/// Each motion still starts when its future is polled after the release, so
+/// the futures should be polled right after, in quick succession.
+pub struct StartBarrier {
+    released: AtomicBool,
+}
+
+impl StartBarrier {
+    /// Create a new instance of `StartBarrier`
+    pub const fn new() -> Self {
+        Self {
+            released: AtomicBool::new(false),
+        }
+    }
+
+    /// Release all motions that are waiting for this barrier
+    pub fn release(&self) {
+        self.released.store(true, Ordering::Release);
+    }
+
+    /// Indicates whether the barrier has been released
+    pub fn is_released(&self) -> bool {
+        self.released.load(Ordering::Acquire)
+    }
+
+    /// Reset the barrier, so it can be used for the next set of motions
+    ///
+    /// Motions that haven't started yet will wait for the next release.
+    pub fn reset(&self) {
+        self.released.store(false, Ordering::Release);
+    }
+}
+
+impl Default for StartBarrier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The "future" returned by [`MoveToFuture::start_with`]
+///
+/// Behaves like [`MoveToFuture`], except that the motion doesn't start before
+/// the [`StartBarrier`] has been released.
+///
+/// If the `async` feature is enabled, this type also implements
+/// [`core::future::Future`]. The barrier has no way to wake the task, so while
+/// waiting, the task is woken right away, causing the executor to keep
+/// polling.
+#[must_use]
+pub struct GatedMoveToFuture<'b, Driver: MotionControl> {
+    future: MoveToFuture<Driver>,
+    barrier: &'b StartBarrier,
+}
+
+impl<'b, Driver> GatedMoveToFuture<'b, Driver>
+where
+    Driver: MotionControl,
+{
+    pub(crate) fn new(
+        future: MoveToFuture<Driver>,
+        barrier: &'b StartBarrier,
+    ) -> Self {
+        Self { future, barrier }
+    }
+
+    /// Poll the future
+    ///
+    /// Returns [`Poll::Pending`] without doing anything, until the barrier has
+    /// been released. See [`MoveToFuture::poll`] for everything else.
+    pub fn poll(&mut self) -> Poll<Result<(), Driver::Error>> {
+        if !self.barrier.is_released() {
+            return Poll::Pending;
+        }
+
+        self.future.poll()
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the barrier
+    /// has been released and the operation has finished.
+    pub fn wait(&mut self) -> Result<(), Driver::Error> {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the barrier and return the wrapped future
+    ///
+    /// The motion starts the next time the returned future is polled, whether
+    /// the barrier has been released or not.
+    pub fn release(self) -> MoveToFuture<Driver> {
+        self.future
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'b, Driver> core::future::Future for GatedMoveToFuture<'b, Driver>
+where
+    Driver: MotionControl + crate::traits::RegisterWaker,
+    MoveToFuture<Driver>: Unpin,
+{
+    type Output = Result<(), Driver::Error>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        if !self_.barrier.is_released() {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        core::future::Future::poll(core::pin::Pin::new(&mut self_.future), cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::task::Poll;
+
+    use crate::{mock::MockMotionControl, MoveToFuture};
+
+    use super::StartBarrier;
+
+    #[test]
+    fn motions_should_wait_for_barrier() {
+        let barrier = StartBarrier::new();
+
+        let mut a = MoveToFuture::new(MockMotionControl::new(), (), 1)
+            .start_with(&barrier);
+        let mut b = MoveToFuture::new(MockMotionControl::new(), (), 2)
+            .start_with(&barrier);
+
+        assert_eq!(a.poll(), Poll::Pending);
+        assert_eq!(b.poll(), Poll::Pending);
+
+        barrier.release();
+        a.wait().unwrap();
+        b.wait().unwrap();
+
+        assert_eq!(a.release().release().physical_position(), 1);
+        assert_eq!(b.release().release().physical_position(), 2);
+    }
+}
@@ -0,0 +1,210 @@
+use core::convert::Infallible;
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{ErrorType, OutputPin},
+};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    traits::{SetDirection, SetStepMode, Step},
+    Direction,
+};
+
+use super::{SignalError, Stepper};
+
+impl<Driver, Limits> Stepper<Driver, Limits> {
+    /// Sets the microstepping mode, blocking until it has been applied
+    ///
+    /// This is a blocking alternative to [`Stepper::set_step_mode`] that uses
+    /// a [`DelayNs`] implementation to wait, instead of a timer.
+    ///
+    /// You might need to call [`Stepper::enable_step_mode_control`] to make
+    /// this method available.
+    pub fn set_step_mode_blocking(
+        &mut self,
+        step_mode: Driver::StepMode,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), SignalError<Infallible, Driver::Error, Infallible>>
+    where
+        Driver: SetStepMode,
+    {
+        self.record_step_mode(step_mode.into());
+
+        self.driver
+            .apply_mode_config(step_mode)
+            .map_err(SignalError::Pin)?;
+        delay.delay_ns(Driver::SETUP_TIME.ticks());
+
+        self.driver.enable_driver().map_err(SignalError::Pin)?;
+        delay.delay_ns(Driver::HOLD_TIME.ticks());
+
+        Ok(())
+    }
+
+    /// Set direction for future movements, blocking until it has been set
+    ///
+    /// This is a blocking alternative to [`Stepper::set_direction`] that uses
+    /// a [`DelayNs`] implementation to wait, instead of a timer.
+    ///
+    /// You might need to call [`Stepper::enable_direction_control`] to make
+    /// this method available.
+    #[allow(clippy::type_complexity)]
+    pub fn set_direction_blocking(
+        &mut self,
+        direction: Direction,
+        delay: &mut impl DelayNs,
+    ) -> Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Dir as ErrorType>::Error,
+            Infallible,
+        >,
+    >
+    where
+        Driver: SetDirection,
+    {
+        self.direction = direction;
+
+        let dir = self.driver.dir().map_err(SignalError::PinUnavailable)?;
+        match direction {
+            Direction::Forward => dir.set_high().map_err(SignalError::Pin)?,
+            Direction::Backward => dir.set_low().map_err(SignalError::Pin)?,
+        }
+        delay.delay_ns(Driver::SETUP_TIME.ticks());
+
+        Ok(())
+    }
+
+    /// Rotates the motor one (micro-)step, blocking until the pulse has ended
+    ///
+    /// This is a blocking alternative to [`Stepper::step`] that uses a
+    /// [`DelayNs`] implementation to wait, instead of a timer. If position
+    /// tracking is enabled, the step is counted.
+    ///
+    /// You might need to call [`Stepper::enable_step_control`] to make this
+    /// method available.
+    #[allow(clippy::type_complexity)]
+    pub fn step_blocking(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Infallible,
+        >,
+    >
+    where
+        Driver: Step,
+    {
+        let step = self.driver.step().map_err(SignalError::PinUnavailable)?;
+
+        step.set_high().map_err(SignalError::Pin)?;
+        delay.delay_ns(Driver::PULSE_LENGTH.ticks());
+        step.set_low().map_err(SignalError::Pin)?;
+
+        if let Some(position) = &mut self.position {
+            *position = position.wrapping_add(self.direction as i32);
+        }
+
+        Ok(())
+    }
+
+    /// Rotates the motor a number of (micro-)steps, blocking until done
+    ///
+    /// This is a blocking alternative to [`Stepper::step_n`] that uses a
+    /// [`DelayNs`] implementation to wait, instead of a timer. A new step
+    /// pulse is started every `interval`, or right after the previous one, if
+    /// `interval` is shorter than the driver's pulse length.
+    ///
+    /// You might need to call [`Stepper::enable_step_control`] to make this
+    /// method available.
+    #[allow(clippy::type_complexity)]
+    pub fn step_n_blocking(
+        &mut self,
+        count: u32,
+        interval: Nanoseconds,
+        delay: &mut impl DelayNs,
+    ) -> Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Infallible,
+        >,
+    >
+    where
+        Driver: Step,
+    {
+        let pause = interval
+            .checked_sub(Driver::PULSE_LENGTH)
+            .unwrap_or(Nanoseconds::from_ticks(0));
+
+        for i in 0..count {
+            if i > 0 {
+                delay.delay_ns(pause.ticks());
+            }
+
+            self.step_blocking(delay)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::delay::DelayNs;
+    use fugit::NanosDurationU32 as Nanoseconds;
+
+    use crate::{
+        mock::{MockDriver, Signal, MOCK_DELAY},
+        Direction, Stepper,
+    };
+
+    #[test]
+    fn step_n_blocking_should_step_and_wait() {
+        let driver = MockDriver::new();
+        let mut stepper = Stepper::from_driver(driver.clone())
+            .enable_direction_control(
+                (),
+                Direction::Forward,
+                &mut driver.timer::<1_000_000>(),
+            )
+            .unwrap()
+            .enable_step_control(());
+        stepper.enable_position_tracking(0);
+
+        let mut delay = Delay(0);
+        stepper
+            .set_direction_blocking(Direction::Backward, &mut delay)
+            .unwrap();
+        stepper
+            .step_n_blocking(3, Nanoseconds::from_ticks(10_000), &mut delay)
+            .unwrap();
+
+        let steps = driver
+            .transitions()
+            .iter()
+            .filter(|transition| transition.signal == Signal::Step(true))
+            .count();
+
+        assert_eq!(steps, 3);
+        assert_eq!(stepper.current_position(), Some(-3));
+        assert_eq!(
+            delay.0,
+            MOCK_DELAY.ticks() + 10_000 * 2 + MOCK_DELAY.ticks()
+        );
+    }
+
+    struct Delay(u32);
+
+    impl DelayNs for Delay {
+        fn delay_ns(&mut self, ns: u32) {
+            self.0 += ns;
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use core::convert::Infallible;
+
+use embedded_hal::digital::ErrorType;
+use fugit_timer::Timer as TimerTrait;
+
+use crate::{
+    traits::{
+        EnableDirectionControl, EnableMotionControl, EnableStepControl,
+        EnableStepModeControl, SetDirection, SetStepMode,
+    },
+    Direction,
+};
+
+use super::{SignalError, Stepper};
+
+type WithStepModeControl<Driver, Resources> =
+    <Driver as EnableStepModeControl<Resources>>::WithStepModeControl;
+type WithDirectionControl<Driver, Resources> =
+    <Driver as EnableDirectionControl<Resources>>::WithDirectionControl;
+type WithStepControl<Driver, Resources> =
+    <Driver as EnableStepControl<Resources>>::WithStepControl;
+type WithMotionControl<Driver, Resources, const TIMER_HZ: u32> =
+    <Driver as EnableMotionControl<Resources, TIMER_HZ>>::WithMotionControl;
+
+type DirectionError<Driver, Timer, const TIMER_HZ: u32> = SignalError<
+    <Driver as SetDirection>::Error,
+    <<Driver as SetDirection>::Dir as ErrorType>::Error,
+    <Timer as TimerTrait<TIMER_HZ>>::Error,
+>;
+
+/// Sets up a [`Stepper`] with software motion control in one go
+///
+/// Enabling step mode control, direction control, step control, and motion
+/// control one after the other requires threading a timer through the calls.
+/// `StepperBuilder` collects the resources for all of them instead, and
+/// enables everything at once in [`StepperBuilder::build`].
+///
+/// Direction and step control are required, step mode control is optional.
+pub struct StepperBuilder<Driver, StepMode = (), Dir = (), Step = ()> {
+    driver: Driver,
+    step_mode: StepMode,
+    dir: Dir,
+    step: Step,
+}
+
+impl<Driver> StepperBuilder<Driver> {
+    /// Start building a `Stepper` from the given driver
+    pub fn new(driver: Driver) -> Self {
+        Self {
+            driver,
+            step_mode: (),
+            dir: (),
+            step: (),
+        }
+    }
+}
+
+impl<Driver, Dir, Step> StepperBuilder<Driver, (), Dir, Step> {
+    /// Provide the resources for step mode control, and the initial step mode
+    ///
+    /// See [`Stepper::enable_step_mode_control`].
+    #[allow(clippy::type_complexity)]
+    pub fn step_mode<Resources>(
+        self,
+        res: Resources,
+        initial: <WithStepModeControl<Driver, Resources> as SetStepMode>::StepMode,
+    ) -> StepperBuilder<
+        Driver,
+        (
+            Resources,
+            <WithStepModeControl<Driver, Resources> as SetStepMode>::StepMode,
+        ),
+        Dir,
+        Step,
+    >
+    where
+        Driver: EnableStepModeControl<Resources>,
+    {
+        StepperBuilder {
+            driver: self.driver,
+            step_mode: (res, initial),
+            dir: self.dir,
+            step: self.step,
+        }
+    }
+}
+
+impl<Driver, StepMode, Step> StepperBuilder<Driver, StepMode, (), Step> {
+    /// Provide the resources for direction control, and the initial direction
+    ///
+    /// See [`Stepper::enable_direction_control`].
+    pub fn direction<Resources>(
+        self,
+        res: Resources,
+        initial: Direction,
+    ) -> StepperBuilder<Driver, StepMode, (Resources, Direction), Step> {
+        StepperBuilder {
+            driver: self.driver,
+            step_mode: self.step_mode,
+            dir: (res, initial),
+            step: self.step,
+        }
+    }
+}
+
+impl<Driver, StepMode, Dir> StepperBuilder<Driver, StepMode, Dir, ()> {
+    /// Provide the resources for step control
+    ///
+    /// See [`Stepper::enable_step_control`].
+    pub fn step<Resources>(
+        self,
+        res: Resources,
+    ) -> StepperBuilder<Driver, StepMode, Dir, (Resources,)> {
+        StepperBuilder {
+            driver: self.driver,
+            step_mode: self.step_mode,
+            dir: self.dir,
+            step: (res,),
+        }
+    }
+}
+
+impl<Driver, DirRes, StepRes>
+    StepperBuilder<Driver, (), (DirRes, Direction), (StepRes,)>
+where
+    Driver: EnableDirectionControl<DirRes>,
+    WithDirectionControl<Driver, DirRes>: EnableStepControl<StepRes>,
+{
+    /// Enable all capabilities, and return the resulting `Stepper`
+    ///
+    /// Uses the timer to set the initial direction, then passes it on to the
+    /// software motion control, together with the motion profile and the
+    /// conversion (see [`Stepper::enable_motion_control`]).
+    #[allow(clippy::type_complexity)]
+    pub fn build<Timer, Profile, Convert, const TIMER_HZ: u32>(
+        self,
+        mut timer: Timer,
+        profile: Profile,
+        convert: Convert,
+    ) -> Result<
+        Stepper<
+            WithMotionControl<
+                WithStepControl<WithDirectionControl<Driver, DirRes>, StepRes>,
+                (Timer, Profile, Convert),
+                TIMER_HZ,
+            >,
+        >,
+        BuildError<
+            Infallible,
+            DirectionError<
+                WithDirectionControl<Driver, DirRes>,
+                Timer,
+                TIMER_HZ,
+            >,
+        >,
+    >
+    where
+        Timer: TimerTrait<TIMER_HZ>,
+        WithStepControl<WithDirectionControl<Driver, DirRes>, StepRes>:
+            EnableMotionControl<(Timer, Profile, Convert), TIMER_HZ>,
+    {
+        let (dir_res, direction) = self.dir;
+        let (step_res,) = self.step;
+
+        let stepper = Stepper::from_driver(self.driver)
+            .enable_direction_control(dir_res, direction, &mut timer)
+            .map_err(BuildError::Direction)?
+            .enable_step_control(step_res)
+            .enable_motion_control((timer, profile, convert));
+
+        Ok(stepper)
+    }
+}
+
+impl<Driver, ModeRes, DirRes, StepRes>
+    StepperBuilder<
+        Driver,
+        (
+            ModeRes,
+            <WithStepModeControl<Driver, ModeRes> as SetStepMode>::StepMode,
+        ),
+        (DirRes, Direction),
+        (StepRes,),
+    >
+where
+    Driver: EnableStepModeControl<ModeRes>,
+    WithStepModeControl<Driver, ModeRes>: EnableDirectionControl<DirRes>,
+    WithDirectionControl<WithStepModeControl<Driver, ModeRes>, DirRes>:
+        EnableStepControl<StepRes>,
+{
+    /// Enable all capabilities, and return the resulting `Stepper`
+    ///
+    /// Uses the timer to set the initial step mode and direction, then passes
+    /// it on to the software motion control, together with the motion profile
+    /// and the conversion (see [`Stepper::enable_motion_control`]).
+    #[allow(clippy::type_complexity)]
+    pub fn build<Timer, Profile, Convert, const TIMER_HZ: u32>(
+        self,
+        mut timer: Timer,
+        profile: Profile,
+        convert: Convert,
+    ) -> Result<
+        Stepper<
+            WithMotionControl<
+                WithStepControl<
+                    WithDirectionControl<
+                        WithStepModeControl<Driver, ModeRes>,
+                        DirRes,
+                    >,
+                    StepRes,
+                >,
+                (Timer, Profile, Convert),
+                TIMER_HZ,
+            >,
+        >,
+        BuildError<
+            SignalError<
+                Infallible, // only applies to `SetDirection`, `Step`
+                <WithStepModeControl<Driver, ModeRes> as SetStepMode>::Error,
+                Timer::Error,
+            >,
+            DirectionError<
+                WithDirectionControl<
+                    WithStepModeControl<Driver, ModeRes>,
+                    DirRes,
+                >,
+                Timer,
+                TIMER_HZ,
+            >,
+        >,
+    >
+    where
+        Timer: TimerTrait<TIMER_HZ>,
+        WithStepControl<
+            WithDirectionControl<WithStepModeControl<Driver, ModeRes>, DirRes>,
+            StepRes,
+        >: EnableMotionControl<(Timer, Profile, Convert), TIMER_HZ>,
+    {
+        let (mode_res, step_mode) = self.step_mode;
+        let (dir_res, direction) = self.dir;
+        let (step_res,) = self.step;
+
+        let stepper = Stepper::from_driver(self.driver)
+            .enable_step_mode_control(mode_res, step_mode, &mut timer)
+            .map_err(BuildError::StepMode)?
+            .enable_direction_control(dir_res, direction, &mut timer)
+            .map_err(BuildError::Direction)?
+            .enable_step_control(step_res)
+            .enable_motion_control((timer, profile, convert));
+
+        Ok(stepper)
+    }
+}
+
+/// An error that can occur while building a [`Stepper`]
+///
+/// Identifies the stage of [`StepperBuilder::build`] that failed.
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BuildError<StepModeError, DirectionError> {
+    /// Error while setting the initial step mode
+    StepMode(StepModeError),
+
+    /// Error while setting the initial direction
+    Direction(DirectionError),
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+
+    use crate::{
+        mock::{MockDriver, Signal},
+        motion_control::DelayToTicks,
+        step_mode::StepMode256,
+        Direction,
+    };
+
+    use super::StepperBuilder;
+
+    type Num = fixed::FixedI64<typenum::U32>;
+
+    #[test]
+    fn builder_should_enable_all_capabilities() {
+        let driver = MockDriver::new();
+        let mut stepper = StepperBuilder::new(driver.clone())
+            .step_mode((), StepMode256::M4)
+            .direction((), Direction::Backward)
+            .step(())
+            .build(
+                driver.timer(),
+                ramp_maker::Trapezoidal::new(Num::from_num(2000)),
+                Convert,
+            )
+            .unwrap();
+
+        assert_eq!(stepper.step_mode(), Some(StepMode256::M4));
+        stepper
+            .move_to_position(Num::from_num(1000), 10)
+            .wait()
+            .unwrap();
+
+        let signals: Vec<_> = driver
+            .transitions()
+            .into_iter()
+            .map(|transition| transition.signal)
+            .collect();
+        assert_eq!(signals[..2], [Signal::StepMode(4), Signal::Dir(false)]);
+        assert_eq!(
+            signals
+                .iter()
+                .filter(|&&signal| signal == Signal::Step(true))
+                .count(),
+            10
+        );
+    }
+
+    struct Convert;
+
+    impl DelayToTicks<Num, 1_000_000> for Convert {
+        type Error = Infallible;
+
+        fn delay_to_ticks(
+            &self,
+            delay: Num,
+        ) -> Result<TimerDuration<1_000_000>, Self::Error> {
+            let ticks = delay * Num::from_num(1_000_000);
+            Ok(TimerDuration::<1_000_000>::from_ticks(ticks.to_num()))
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use core::task::Poll;
+
+use fugit::TimerDurationU32 as TimerDuration;
+use fugit_timer::Timer as TimerTrait;
+
+/// The "future" returned by [`Stepper::dwell`]
+///
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the timer to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// [`traits`]: crate::traits
+///
+/// [`Stepper::dwell`]: crate::Stepper::dwell
+#[must_use]
+pub struct DwellFuture<Timer, const TIMER_HZ: u32> {
+    duration: TimerDuration<TIMER_HZ>,
+    timer: Timer,
+    state: State,
+}
+
+impl<Timer, const TIMER_HZ: u32> DwellFuture<Timer, TIMER_HZ>
+where
+    Timer: TimerTrait<TIMER_HZ>,
+{
+    /// Create new instance of `DwellFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::dwell`] instead.
+    ///
+    /// [`Stepper::dwell`]: crate::Stepper::dwell
+    pub fn new(duration: TimerDuration<TIMER_HZ>, timer: Timer) -> Self {
+        Self {
+            duration,
+            timer,
+            state: State::Initial,
+        }
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the timer finishes
+    /// counting down, and call this method again once it does.
+    pub fn poll(&mut self) -> Poll<Result<(), Timer::Error>> {
+        match self.state {
+            State::Initial => {
+                self.timer.start(self.duration)?;
+
+                self.state = State::Waiting;
+                Poll::Pending
+            }
+            State::Waiting => match self.timer.wait() {
+                Ok(()) => {
+                    self.state = State::Finished;
+                    Poll::Ready(Ok(()))
+                }
+                Err(nb::Error::Other(err)) => {
+                    self.state = State::Finished;
+                    Poll::Ready(Err(err))
+                }
+                Err(nb::Error::WouldBlock) => Poll::Pending,
+            },
+            State::Finished => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    pub fn wait(&mut self) -> Result<(), Timer::Error> {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    pub fn release(self) -> Timer {
+        self.timer
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Timer, const TIMER_HZ: u32> core::future::Future
+    for DwellFuture<Timer, TIMER_HZ>
+where
+    Timer: TimerTrait<TIMER_HZ> + crate::traits::RegisterWaker,
+    Self: Unpin,
+{
+    type Output = Result<(), Timer::Error>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the timer might expire between
+        // polling and registering, and we'd miss the wake-up.
+        self_.timer.register_waker(cx.waker());
+        DwellFuture::poll(self_)
+    }
+}
+
+enum State {
+    Initial,
+    Waiting,
+    Finished,
+}
@@ -1,3 +1,5 @@
+use core::fmt;
+
 use crate::motion_control;
 
 /// Unified error type
@@ -7,6 +9,7 @@ use crate::motion_control;
 ///
 /// [`Stepper`]: crate::Stepper
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error<PinUnavailableError, PinError, DelayToTicksError, TimerError> {
     /// A signal error
     Signal(SignalError<PinUnavailableError, PinError, TimerError>),
@@ -24,6 +27,22 @@ pub enum Error<PinUnavailableError, PinError, DelayToTicksError, TimerError> {
     ),
 }
 
+impl<PinUnavailableError, PinError, DelayToTicksError, TimerError> fmt::Display
+    for Error<PinUnavailableError, PinError, DelayToTicksError, TimerError>
+where
+    PinUnavailableError: fmt::Debug,
+    PinError: fmt::Debug,
+    DelayToTicksError: fmt::Debug,
+    TimerError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Signal(err) => write!(f, "{}", err),
+            Self::MotionControl(err) => write!(f, "{}", err),
+        }
+    }
+}
+
 impl<PinUnavailableError, PinError, DelayToTicksError, TimerError>
     From<SignalError<PinUnavailableError, PinError, TimerError>>
     for Error<PinUnavailableError, PinError, DelayToTicksError, TimerError>
@@ -63,6 +82,7 @@ impl<PinUnavailableError, PinError, DelayToTicksError, TimerError>
 
 /// An error that can occur while using this API
 #[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SignalError<PinUnavailableError, PinError, TimerError> {
     /// A pin was not accessible
     PinUnavailable(PinUnavailableError),
@@ -75,3 +95,21 @@ pub enum SignalError<PinUnavailableError, PinError, TimerError> {
     /// An error originated from working with a timer
     Timer(TimerError),
 }
+
+impl<PinUnavailableError, PinError, TimerError> fmt::Display
+    for SignalError<PinUnavailableError, PinError, TimerError>
+where
+    PinUnavailableError: fmt::Debug,
+    PinError: fmt::Debug,
+    TimerError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PinUnavailable(err) => {
+                write!(f, "pin unavailable: {:?}", err)
+            }
+            Self::Pin(err) => write!(f, "pin error: {:?}", err),
+            Self::Timer(err) => write!(f, "timer error: {:?}", err),
+        }
+    }
+}
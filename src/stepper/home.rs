@@ -0,0 +1,408 @@
+use core::{fmt, task::Poll};
+
+use crate::{
+    limit_switch::{self, LimitSwitch, LimitSwitches},
+    traits::MotionControl,
+    Direction,
+};
+
+/// Configuration for a homing operation
+///
+/// Passed to [`Stepper::home`].
+///
+/// [`Stepper::home`]: crate::Stepper::home
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HomingConfig<Velocity> {
+    /// The direction in which the limit switch is located
+    ///
+    /// [`Direction::Forward`] homes against the limit switch at the maximum
+    /// position, [`Direction::Backward`] against the one at the minimum
+    /// position.
+    pub direction: Direction,
+
+    /// The velocity used while searching for the limit switch
+    pub fast_velocity: Velocity,
+
+    /// The velocity used while re-approaching the limit switch
+    ///
+    /// This should be low enough for the motor to stop at the position where
+    /// the switch triggers, without overshooting it by much.
+    pub slow_velocity: Velocity,
+
+    /// The maximum number of steps to move while searching for the switch
+    ///
+    /// If the switch hasn't triggered after moving this far, homing fails with
+    /// [`HomingError::SwitchNotFound`]. This should be at least the length of
+    /// the axis.
+    pub max_travel: u32,
+
+    /// The number of steps to back off after the switch has triggered
+    ///
+    /// This must be enough to release the switch again. The slow re-approach
+    /// moves at most twice this distance.
+    pub backoff: u32,
+
+    /// The position that the switch location is reset to
+    pub home_position: i32,
+}
+
+/// The "future" returned by [`Stepper::home`]
+///
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the driver to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// Homing happens in three phases:
+///
+/// 1. Move towards the limit switch using the fast velocity, until it triggers.
+///    If it is already triggered, this phase is skipped.
+/// 2. Back off from the switch, until it is released.
+/// 3. Re-approach the switch using the slow velocity. Once it triggers, the
+///    position is reset to the home position.
+///
+/// [`traits`]: crate::traits
+///
+/// [`Stepper::home`]: crate::Stepper::home
+#[must_use]
+pub struct HomeFuture<'r, Driver: MotionControl, Min, Max> {
+    driver: Driver,
+    limits: &'r mut LimitSwitches<Min, Max>,
+    config: HomingConfig<Driver::Velocity>,
+    state: State,
+}
+
+impl<'r, Driver, Min, Max> HomeFuture<'r, Driver, Min, Max>
+where
+    Driver: MotionControl,
+    Min: LimitSwitch,
+    Max: LimitSwitch,
+{
+    /// Create new instance of `HomeFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::home`] instead.
+    ///
+    /// [`Stepper::home`]: crate::Stepper::home
+    pub fn new(
+        driver: Driver,
+        limits: &'r mut LimitSwitches<Min, Max>,
+        config: HomingConfig<Driver::Velocity>,
+    ) -> Self {
+        Self {
+            driver,
+            limits,
+            config,
+            state: State::Initial,
+        }
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the driver needs
+    /// attention, and call this method again once it does.
+    #[allow(clippy::type_complexity)]
+    pub fn poll(
+        &mut self,
+    ) -> Poll<Result<(), HomingError<Driver::Error, Min::Error, Max::Error>>>
+    {
+        match self.poll_inner() {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(())) => {
+                self.state = State::Finished;
+                Poll::Ready(Ok(()))
+            }
+            Err(err) => {
+                self.state = State::Finished;
+                Poll::Ready(Err(err))
+            }
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn poll_inner(
+        &mut self,
+    ) -> Result<Poll<()>, HomingError<Driver::Error, Min::Error, Max::Error>>
+    {
+        let direction = self.config.direction;
+
+        match self.state {
+            State::Initial => {
+                if self.switch_triggered()? {
+                    self.back_off()?;
+                } else {
+                    self.move_relative(
+                        self.config.fast_velocity,
+                        self.config.max_travel,
+                        direction,
+                    )?;
+                    self.state = State::Approaching { slow: false };
+                }
+
+                Ok(Poll::Pending)
+            }
+            State::Approaching { slow } => {
+                if self.switch_triggered()? {
+                    if slow {
+                        // We've found the home position. Reset the position
+                        // and move to it, which stops the ongoing motion.
+                        let home = self.config.home_position;
+                        self.driver
+                            .reset_position(home)
+                            .map_err(HomingError::MotionControl)?;
+                        self.driver
+                            .move_to_position(self.config.slow_velocity, home)
+                            .map_err(HomingError::MotionControl)?;
+                        self.state = State::Stopping;
+                    } else {
+                        self.back_off()?;
+                    }
+
+                    return Ok(Poll::Pending);
+                }
+
+                let still_moving =
+                    self.driver.update().map_err(HomingError::MotionControl)?;
+                if !still_moving {
+                    return Err(HomingError::SwitchNotFound);
+                }
+
+                Ok(Poll::Pending)
+            }
+            State::BackingOff => {
+                let still_moving =
+                    self.driver.update().map_err(HomingError::MotionControl)?;
+                if still_moving {
+                    return Ok(Poll::Pending);
+                }
+
+                if self.switch_triggered()? {
+                    return Err(HomingError::SwitchStillTriggered);
+                }
+
+                self.move_relative(
+                    self.config.slow_velocity,
+                    self.config.backoff.saturating_mul(2),
+                    direction,
+                )?;
+                self.state = State::Approaching { slow: true };
+
+                Ok(Poll::Pending)
+            }
+            State::Stopping => {
+                let still_moving =
+                    self.driver.update().map_err(HomingError::MotionControl)?;
+                if still_moving {
+                    Ok(Poll::Pending)
+                } else {
+                    Ok(Poll::Ready(()))
+                }
+            }
+            State::Finished => Ok(Poll::Ready(())),
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    #[allow(clippy::type_complexity)]
+    pub fn wait(
+        &mut self,
+    ) -> Result<(), HomingError<Driver::Error, Min::Error, Max::Error>> {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    pub fn release(self) -> (Driver, &'r mut LimitSwitches<Min, Max>) {
+        (self.driver, self.limits)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn switch_triggered(
+        &mut self,
+    ) -> Result<bool, HomingError<Driver::Error, Min::Error, Max::Error>> {
+        self.limits
+            .is_triggered(self.config.direction)
+            .map_err(HomingError::LimitSwitch)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn back_off(
+        &mut self,
+    ) -> Result<(), HomingError<Driver::Error, Min::Error, Max::Error>> {
+        let direction = self.config.direction.reverse();
+
+        self.move_relative(
+            self.config.fast_velocity,
+            self.config.backoff,
+            direction,
+        )?;
+        self.state = State::BackingOff;
+
+        Ok(())
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn move_relative(
+        &mut self,
+        velocity: Driver::Velocity,
+        steps: u32,
+        direction: Direction,
+    ) -> Result<(), HomingError<Driver::Error, Min::Error, Max::Error>> {
+        // `MotionControl` doesn't provide a way to query the current position.
+        // We don't need the current position anyway, until homing is finished,
+        // so we just reset it, to have a known reference.
+        let steps = steps.min(i32::MAX as u32) as i32;
+
+        self.driver
+            .reset_position(0)
+            .map_err(HomingError::MotionControl)?;
+        self.driver
+            .move_to_position(velocity, steps * direction as i32)
+            .map_err(HomingError::MotionControl)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'r, Driver, Min, Max> core::future::Future
+    for HomeFuture<'r, Driver, Min, Max>
+where
+    Driver: MotionControl + crate::traits::RegisterWaker,
+    Min: LimitSwitch,
+    Max: LimitSwitch,
+    Self: Unpin,
+{
+    type Output =
+        Result<(), HomingError<Driver::Error, Min::Error, Max::Error>>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the driver might need attention
+        // between polling and registering, and we'd miss the wake-up.
+        self_.driver.register_waker(cx.waker());
+        HomeFuture::poll(self_)
+    }
+}
+
+/// An error that can occur while homing
+///
+/// Returned by [`HomeFuture`].
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HomingError<MotionControlError, MinError, MaxError> {
+    /// An error originated from the motion controller
+    MotionControl(MotionControlError),
+
+    /// An error occurred while reading a limit switch
+    LimitSwitch(limit_switch::Error<MinError, MaxError>),
+
+    /// The limit switch didn't trigger within the maximum travel distance
+    SwitchNotFound,
+
+    /// The limit switch was still triggered after backing off
+    ///
+    /// Increase [`HomingConfig::backoff`] to fix this.
+    SwitchStillTriggered,
+}
+
+impl<MotionControlError, MinError, MaxError> fmt::Display
+    for HomingError<MotionControlError, MinError, MaxError>
+where
+    MotionControlError: fmt::Debug,
+    MinError: fmt::Debug,
+    MaxError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MotionControl(err) => {
+                write!(f, "motion control error: {:?}", err)
+            }
+            Self::LimitSwitch(err) => write!(f, "{}", err),
+            Self::SwitchNotFound => {
+                write!(f, "limit switch not found within maximum travel")
+            }
+            Self::SwitchStillTriggered => {
+                write!(f, "limit switch still triggered after backing off")
+            }
+        }
+    }
+}
+
+enum State {
+    Initial,
+    Approaching { slow: bool },
+    BackingOff,
+    Stopping,
+    Finished,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use crate::{
+        limit_switch::{LimitSwitch, LimitSwitches, NoLimitSwitch},
+        mock::MockMotionControl,
+        traits::MotionControl,
+        Direction,
+    };
+
+    use super::{HomeFuture, HomingConfig};
+
+    #[test]
+    fn home_should_reset_position_at_switch() {
+        // Resetting the position doesn't move the motor, so its physical
+        // position is still 0. The switch at -35 is at -10 after the reset.
+        let mut driver = MockMotionControl::new();
+        driver.reset_position(25).unwrap();
+
+        let mut limits = LimitSwitches {
+            min: Switch(driver.clone()),
+            max: NoLimitSwitch,
+        };
+
+        let config = HomingConfig {
+            direction: Direction::Backward,
+            fast_velocity: (),
+            slow_velocity: (),
+            max_travel: 100,
+            backoff: 5,
+            home_position: 3,
+        };
+        HomeFuture::new(driver.clone(), &mut limits, config)
+            .wait()
+            .unwrap();
+
+        assert_eq!(driver.physical_position(), -35);
+        assert_eq!(driver.current_position(), Ok(3));
+    }
+
+    struct Switch(MockMotionControl);
+
+    impl LimitSwitch for Switch {
+        type Error = Infallible;
+
+        fn is_triggered(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.0.physical_position() <= -35)
+        }
+    }
+}
@@ -1,32 +1,65 @@
+mod barrier;
+mod blocking;
+mod builder;
+mod dwell;
 mod error;
+mod home;
 mod move_to;
+mod nonblocking;
+mod position;
+mod probe;
 mod set_direction;
+mod set_power_mode;
 mod set_step_mode;
+mod split;
 mod step;
+mod step_n;
+mod stop;
 
 pub use self::{
+    barrier::{GatedMoveToFuture, StartBarrier},
+    builder::{BuildError, StepperBuilder},
+    dwell::DwellFuture,
     error::{Error, SignalError},
+    home::{HomeFuture, HomingConfig, HomingError},
     move_to::MoveToFuture,
+    position::{PositionTracker, TrackPosition},
+    probe::{ProbeError, ProbeFuture},
     set_direction::SetDirectionFuture,
+    set_power_mode::SetPowerModeFuture,
     set_step_mode::SetStepModeFuture,
+    split::{CommandPendingError, ControlHandle, Shared, TickHandle},
     step::StepFuture,
+    step_n::StepNFuture,
+    stop::StopFuture,
 };
 
-use core::convert::Infallible;
+use core::convert::{Infallible, TryFrom};
 
 use embedded_hal::digital::ErrorType;
-use fugit::NanosDurationU32 as Nanoseconds;
+use fugit::{
+    NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+};
 use fugit_timer::Timer as TimerTrait;
 
 use crate::{
+    limit_switch::{self, LimitSwitch, LimitSwitches},
+    step_mode::rescale_position,
     traits::{
-        EnableDirectionControl, EnableMotionControl, EnableStepControl,
-        EnableStepModeControl, MotionControl, SetDirection, SetStepMode, Step,
+        DisableDirectionControl, DisableStepControl, DisableStepModeControl,
+        EnableCurrentControl, EnableDirectionControl, EnableFaultDetection,
+        EnableMotionControl, EnableOutputControl, EnableSleepControl,
+        EnableStepControl, EnableStepModeControl, MotionControl, ReadFault,
+        ReadVelocity, SetCurrent, SetDirection, SetOutputEnabled, SetPowerMode,
+        SetStepMode, Step,
     },
+    units::{Linear, Rotary},
     util::ref_mut::RefMut,
-    Direction,
+    Direction, PowerMode,
 };
 
+use self::nonblocking::NbState;
+
 /// Unified stepper motor interface
 ///
 /// Wraps a driver that interfaces with the motor-controlling hardware and
@@ -75,7 +108,8 @@ use crate::{
 ///
 /// Enable direction control with [`Stepper::enable_direction_control`] and use
 /// it with [`Stepper::set_direction`]. Enable step control with
-/// [`Stepper::enable_step_control`] and use ith with [`Stepper::step`].
+/// [`Stepper::enable_step_control`] and use ith with [`Stepper::step`] (or
+/// [`Stepper::step_n`], to make multiple steps at a constant rate).
 ///
 /// These capabilities are supported by virtually all stepper drivers, but might
 /// not be available for motion controllers. Where they are available, they are
@@ -83,32 +117,232 @@ use crate::{
 /// to not make any assumptions. If you want to generate steps from software,
 /// for example, but control direction via some other means, then you can.
 ///
+/// ## Output control
+///
+/// Enable this capability with [`Stepper::enable_output_control`] and use it
+/// with [`Stepper::enable_output`] and [`Stepper::disable_output`]. Most
+/// drivers have an input that enables their motor outputs, and support this
+/// capability.
+///
+/// ## Sleep control
+///
+/// Enable this capability with [`Stepper::enable_sleep_control`] and use it
+/// with [`Stepper::set_power_mode`]. Drivers that have a SLEEP or STANDBY
+/// input support this capability.
+///
+/// ## Fault detection
+///
+/// Enable this capability with [`Stepper::enable_fault_detection`] and use it
+/// with [`Stepper::check_fault`]. Drivers that have a fault output, or that
+/// report faults through their status registers, support this capability.
+///
+/// ## Current control
+///
+/// Enable this capability with [`Stepper::enable_current_control`] and use it
+/// with [`Stepper::set_current`]. Only drivers that allow setting the motor
+/// current from software support this capability.
+///
 /// ## Motion control
 ///
 /// Enable motion control with [`Stepper::enable_motion_control`] and use it
-/// with [`Stepper::move_to_position`] and [`Stepper::reset_position`].
+/// with [`Stepper::move_to_position`], [`Stepper::move_by`], and
+/// [`Stepper::reset_position`].
 ///
 /// Motion control capability is directly supported by motion control chips, but
 /// a software implementation based on direction and step control exists in the
 /// [`motion_control`] module, to make the capability available for all drivers.
 ///
+/// Positions are specified in steps. To work with millimetres or degrees
+/// instead, describe the axis using the types in the [`units`] module and use
+/// [`Stepper::move_to_mm`] or [`Stepper::move_to_degrees`].
+///
 /// [`motion_control`]: crate::motion_control
+/// [`units`]: crate::units
+///
+/// ## Position tracking
+///
+/// When using step control directly, without motion control, the position of
+/// the motor can be tracked in software. Enable this with
+/// [`Stepper::enable_position_tracking`] and query the position with
+/// [`Stepper::current_position`].
+///
+/// ## Limit switches
+///
+/// Limit switches are not a capability of the driver, but can be attached to
+/// any `Stepper` using [`Stepper::attach_limit_switches`]. Check them with
+/// [`Stepper::limit_reached`]. See the [`limit_switch`] module for more
+/// information.
+///
+/// [`limit_switch`]: crate::limit_switch
 ///
 /// # Notes on timer use
 ///
 /// Some of this struct's methods take a timer argument. This is expected to be
 /// an implementation of [`fugit_timer::Timer`].
 ///
-pub struct Stepper<Driver> {
+/// Applications that don't need the timer and future machinery, for example
+/// to make a few steps in a setup routine, can use the blocking methods
+/// instead, like [`Stepper::step_blocking`]. Those take an implementation of
+/// [`DelayNs`] and only return, once the operation has finished.
+///
+/// [`DelayNs`]: embedded_hal::delay::DelayNs
+///
+pub struct Stepper<Driver, Limits = ()> {
     driver: Driver,
+    position: Option<i32>,
+    direction: Direction,
+    step_mode: Option<u16>,
+    limits: Limits,
+    nb: NbState,
 }
 
 impl<Driver> Stepper<Driver> {
     /// Create a new `Stepper` instance from a driver
     pub fn from_driver(driver: Driver) -> Self {
-        Self { driver }
+        Self {
+            driver,
+            position: None,
+            // Doesn't matter what we initialize it with. It's only used for
+            // position tracking, which requires direction control, and
+            // enabling direction control overwrites it.
+            direction: Direction::Forward,
+            step_mode: None,
+            limits: (),
+            nb: NbState::new(),
+        }
+    }
+
+    /// Attach limit switches
+    ///
+    /// Consumes this instance of `Stepper` and returns a new instance that
+    /// has access to the limit switches at both ends of the axis. Once this
+    /// method has been called, the [`Stepper::limit_reached`] method becomes
+    /// available.
+    ///
+    /// If the axis only has a limit switch on one end, pass
+    /// [`NoLimitSwitch`] for the other one.
+    ///
+    /// [`NoLimitSwitch`]: crate::limit_switch::NoLimitSwitch
+    pub fn attach_limit_switches<Min, Max>(
+        self,
+        min: Min,
+        max: Max,
+    ) -> Stepper<Driver, LimitSwitches<Min, Max>>
+    where
+        Min: LimitSwitch,
+        Max: LimitSwitch,
+    {
+        Stepper {
+            driver: self.driver,
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: LimitSwitches { min, max },
+            nb: self.nb,
+        }
+    }
+
+    /// Split into a tick handle and a control handle
+    ///
+    /// Consumes this instance of `Stepper` and returns two handles that
+    /// communicate through `shared`: A [`TickHandle`], which owns the driver
+    /// and needs to be ticked regularly, typically from a timer interrupt, and
+    /// a [`ControlHandle`], which can start motions and query the position
+    /// from another context, without needing a lock. This is useful with
+    /// frameworks like RTIC, where the motion can then be updated
+    /// deterministically from a high-priority task.
+    ///
+    /// Any state left in `shared` from previous use is discarded. Use
+    /// [`TickHandle::release`] to get the driver back.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn split<'r>(
+        self,
+        shared: &'r mut Shared<Driver::Velocity>,
+    ) -> (TickHandle<'r, Driver>, ControlHandle<'r, Driver::Velocity>)
+    where
+        Driver: MotionControl,
+    {
+        split::split(self.driver, shared)
+    }
+}
+
+impl<Driver, Min, Max> Stepper<Driver, LimitSwitches<Min, Max>>
+where
+    Min: LimitSwitch,
+    Max: LimitSwitch,
+{
+    /// Access the limit switches
+    pub fn limit_switches(&self) -> &LimitSwitches<Min, Max> {
+        &self.limits
+    }
+
+    /// Access the limit switches mutably
+    pub fn limit_switches_mut(&mut self) -> &mut LimitSwitches<Min, Max> {
+        &mut self.limits
+    }
+
+    /// Indicates whether the limit in the given direction has been reached
+    ///
+    /// Reads the limit switch at the maximum position for
+    /// [`Direction::Forward`], and the one at the minimum position for
+    /// [`Direction::Backward`]. If the limit switches are debounced, this
+    /// method needs to be called regularly.
+    ///
+    /// You might need to call [`Stepper::attach_limit_switches`] to make this
+    /// method available.
+    pub fn limit_reached(
+        &mut self,
+        direction: Direction,
+    ) -> Result<bool, limit_switch::Error<Min::Error, Max::Error>> {
+        self.limits.is_triggered(direction)
+    }
+
+    /// Home the motor using the limit switches
+    ///
+    /// Moves towards the limit switch in the direction given by `config`,
+    /// backs off once it triggers, then slowly re-approaches it. Once the
+    /// switch triggers again, the position is reset to
+    /// [`HomingConfig::home_position`]. See [`HomeFuture`] for details.
+    ///
+    /// If position tracking is enabled, it is not updated by this method. Call
+    /// [`Stepper::enable_position_tracking`] again after homing, if required.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] and
+    /// [`Stepper::attach_limit_switches`] to make this method available.
+    pub fn home<'r>(
+        &'r mut self,
+        config: HomingConfig<Driver::Velocity>,
+    ) -> HomeFuture<'r, RefMut<'r, Driver>, Min, Max>
+    where
+        Driver: MotionControl,
+    {
+        HomeFuture::new(RefMut(&mut self.driver), &mut self.limits, config)
     }
 
+    /// Detach the limit switches
+    ///
+    /// This is the reverse of [`Stepper::attach_limit_switches`]. Returns the
+    /// limit switches, together with an instance of `Stepper` that no longer
+    /// has access to them.
+    pub fn detach_limit_switches(
+        self,
+    ) -> (Stepper<Driver>, LimitSwitches<Min, Max>) {
+        let self_ = Stepper {
+            driver: self.driver,
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: (),
+            nb: self.nb,
+        };
+
+        (self_, self.limits)
+    }
+}
+
+impl<Driver, Limits> Stepper<Driver, Limits> {
     /// Access a reference to the wrapped driver
     ///
     /// Can be used to access driver-specific functionality that can't be
@@ -146,13 +380,14 @@ impl<Driver> Stepper<Driver> {
     /// This method is only available, if the driver supports enabling step mode
     /// control. It might no longer be available, once step mode control has
     /// been enabled.
+    #[allow(clippy::type_complexity)]
     pub fn enable_step_mode_control<Resources, Timer, const TIMER_HZ: u32>(
         self,
         res: Resources,
         initial: <Driver::WithStepModeControl as SetStepMode>::StepMode,
         timer: &mut Timer,
     ) -> Result<
-        Stepper<Driver::WithStepModeControl>,
+        Stepper<Driver::WithStepModeControl, Limits>,
         SignalError<
             Infallible, // only applies to `SetDirection`, `Step`
             <Driver::WithStepModeControl as SetStepMode>::Error,
@@ -165,6 +400,11 @@ impl<Driver> Stepper<Driver> {
     {
         let mut self_ = Stepper {
             driver: self.driver.enable_step_mode_control(res),
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
         };
         self_.set_step_mode(initial, timer).wait()?;
 
@@ -178,8 +418,16 @@ impl<Driver> Stepper<Driver> {
     /// hardware might not support microstepping at all, or only allow setting
     /// the step mode by changing physical switches.
     ///
+    /// If a step mode has been set before, the positions that `Stepper` keeps
+    /// track of (the tracked position and the target of the last motion) are
+    /// converted to the new step mode, so they keep referring to the same
+    /// physical location. [`SoftwareMotionControl`] does the same for its own
+    /// position.
+    ///
     /// You might need to call [`Stepper::enable_step_mode_control`] to make
     /// this method available.
+    ///
+    /// [`SoftwareMotionControl`]: crate::motion_control::SoftwareMotionControl
     pub fn set_step_mode<'r, Timer, const TIMER_HZ: u32>(
         &'r mut self,
         step_mode: Driver::StepMode,
@@ -189,6 +437,8 @@ impl<Driver> Stepper<Driver> {
         Driver: SetStepMode,
         Timer: TimerTrait<TIMER_HZ>,
     {
+        self.record_step_mode(step_mode.into());
+
         SetStepModeFuture::new(
             step_mode,
             RefMut(&mut self.driver),
@@ -196,6 +446,93 @@ impl<Driver> Stepper<Driver> {
         )
     }
 
+    /// Sets the microstepping mode, without returning a future
+    ///
+    /// This is a non-blocking alternative to [`Stepper::set_step_mode`]. See
+    /// [`Stepper::try_step`] for details. Must be called with the same step
+    /// mode, until the operation has finished.
+    ///
+    /// You might need to call [`Stepper::enable_step_mode_control`] to make
+    /// this method available.
+    pub fn try_set_step_mode<Timer, const TIMER_HZ: u32>(
+        &mut self,
+        step_mode: Driver::StepMode,
+        timer: &mut Timer,
+    ) -> nb::Result<(), SignalError<Infallible, Driver::Error, Timer::Error>>
+    where
+        Driver: SetStepMode,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        if let set_step_mode::State::Initial = self.nb.step_mode {
+            self.record_step_mode(step_mode.into());
+        }
+
+        let mut future = SetStepModeFuture::new(
+            step_mode,
+            RefMut(&mut self.driver),
+            RefMut(timer),
+        )
+        .with_state(self.nb.step_mode);
+
+        let poll = future.poll();
+        self.nb.step_mode = future.state();
+
+        nonblocking::finish(
+            poll,
+            &mut self.nb.step_mode,
+            set_step_mode::State::Initial,
+        )
+    }
+
+    /// Returns the current microstepping mode
+    ///
+    /// Returns the step mode that was most recently passed to
+    /// [`Stepper::set_step_mode`] (or [`Stepper::enable_step_mode_control`]),
+    /// or `None`, if the step mode has never been set through this API.
+    ///
+    /// This can be used to convert between microsteps and full steps, for
+    /// example when interpreting the values passed to
+    /// [`Stepper::move_to_position`].
+    pub fn step_mode(&self) -> Option<Driver::StepMode>
+    where
+        Driver: SetStepMode,
+    {
+        self.step_mode
+            .and_then(|mode| Driver::StepMode::try_from(mode).ok())
+    }
+
+    /// Disable microstepping mode control
+    ///
+    /// This is the reverse of [`Stepper::enable_step_mode_control`]. Consumes
+    /// this instance of `Stepper` and returns a new instance without
+    /// microstepping mode control, together with the hardware resources that
+    /// were required for it, so they can be used for something else.
+    ///
+    /// The driver's step mode is no longer under software control after this,
+    /// so [`Stepper::step_mode`] no longer returns it.
+    pub fn disable_step_mode_control(
+        self,
+    ) -> (
+        Stepper<Driver::WithoutStepModeControl, Limits>,
+        Driver::Resources,
+    )
+    where
+        Driver: DisableStepModeControl,
+    {
+        let (driver, res) = self.driver.disable_step_mode_control();
+
+        let self_ = Stepper {
+            driver,
+            position: self.position,
+            direction: self.direction,
+            step_mode: None,
+            limits: self.limits,
+            nb: self.nb,
+        };
+
+        (self_, res)
+    }
+
     /// Enable direction control
     ///
     /// Consumes this instance of `Stepper` and returns a new instance that
@@ -210,13 +547,14 @@ impl<Driver> Stepper<Driver> {
     /// This method is only available, if the driver supports enabling direction
     /// control. It might no longer be available, once direction control has
     /// been enabled.
+    #[allow(clippy::type_complexity)]
     pub fn enable_direction_control<Resources, Timer, const TIMER_HZ: u32>(
         self,
         res: Resources,
         initial: Direction,
         timer: &mut Timer,
     ) -> Result<
-        Stepper<Driver::WithDirectionControl>,
+        Stepper<Driver::WithDirectionControl, Limits>,
         SignalError<
             <Driver::WithDirectionControl as SetDirection>::Error,
             <<Driver::WithDirectionControl as SetDirection>::Dir as ErrorType>::Error,
@@ -229,6 +567,11 @@ impl<Driver> Stepper<Driver> {
     {
         let mut self_ = Stepper {
             driver: self.driver.enable_direction_control(res),
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
         };
         self_.set_direction(initial, timer).wait()?;
 
@@ -248,6 +591,8 @@ impl<Driver> Stepper<Driver> {
         Driver: SetDirection,
         Timer: TimerTrait<TIMER_HZ>,
     {
+        self.direction = direction;
+
         SetDirectionFuture::new(
             direction,
             RefMut(&mut self.driver),
@@ -255,6 +600,79 @@ impl<Driver> Stepper<Driver> {
         )
     }
 
+    /// Set direction for future movements, without returning a future
+    ///
+    /// This is a non-blocking alternative to [`Stepper::set_direction`]. See
+    /// [`Stepper::try_step`] for details. Must be called with the same
+    /// direction, until the operation has finished.
+    ///
+    /// You might need to call [`Stepper::enable_direction_control`] to make
+    /// this method available.
+    #[allow(clippy::type_complexity)]
+    pub fn try_set_direction<Timer, const TIMER_HZ: u32>(
+        &mut self,
+        direction: Direction,
+        timer: &mut Timer,
+    ) -> nb::Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Dir as ErrorType>::Error,
+            Timer::Error,
+        >,
+    >
+    where
+        Driver: SetDirection,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        self.direction = direction;
+
+        let mut future = SetDirectionFuture::new(
+            direction,
+            RefMut(&mut self.driver),
+            RefMut(timer),
+        )
+        .with_state(self.nb.direction);
+
+        let poll = future.poll();
+        self.nb.direction = future.state();
+
+        nonblocking::finish(
+            poll,
+            &mut self.nb.direction,
+            set_direction::State::Initial,
+        )
+    }
+
+    /// Disable direction control
+    ///
+    /// This is the reverse of [`Stepper::enable_direction_control`]. Consumes
+    /// this instance of `Stepper` and returns a new instance without direction
+    /// control, together with the hardware resources that were required for
+    /// it, so they can be used for something else.
+    pub fn disable_direction_control(
+        self,
+    ) -> (
+        Stepper<Driver::WithoutDirectionControl, Limits>,
+        Driver::Resources,
+    )
+    where
+        Driver: DisableDirectionControl,
+    {
+        let (driver, res) = self.driver.disable_direction_control();
+
+        let self_ = Stepper {
+            driver,
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
+        };
+
+        (self_, res)
+    }
+
     /// Enable step control
     ///
     /// Consumes this instance of `Stepper` and returns a new instance that
@@ -272,12 +690,17 @@ impl<Driver> Stepper<Driver> {
     pub fn enable_step_control<Resources>(
         self,
         res: Resources,
-    ) -> Stepper<Driver::WithStepControl>
+    ) -> Stepper<Driver::WithStepControl, Limits>
     where
         Driver: EnableStepControl<Resources>,
     {
         Stepper {
             driver: self.driver.enable_step_control(res),
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
         }
     }
 
@@ -287,17 +710,140 @@ impl<Driver> Stepper<Driver> {
     /// according to current microstepping configuration. To achieve a specific
     /// speed, the user must call this method at an appropriate frequency.
     ///
+    /// If position tracking is enabled, the step is counted once the returned
+    /// future has finished. See [`Stepper::enable_position_tracking`].
+    ///
     /// You might need to call [`Stepper::enable_step_control`] to make this
     /// method available.
     pub fn step<'r, Timer, const TIMER_HZ: u32>(
         &'r mut self,
         timer: &'r mut Timer,
-    ) -> StepFuture<RefMut<'r, Driver>, RefMut<'r, Timer>, TIMER_HZ>
+    ) -> StepFuture<
+        RefMut<'r, Driver>,
+        RefMut<'r, Timer>,
+        TIMER_HZ,
+        PositionTracker<'r>,
+    >
+    where
+        Driver: Step,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        StepFuture::with_position(
+            RefMut(&mut self.driver),
+            RefMut(timer),
+            PositionTracker::new(&mut self.position, self.direction),
+        )
+    }
+
+    /// Rotates the motor one (micro-)step, without returning a future
+    ///
+    /// This is a non-blocking alternative to [`Stepper::step`], for
+    /// applications that use [`nb`]-style superloops and don't want to keep a
+    /// future around between iterations. The first call starts the step
+    /// pulse. This and any further calls return [`nb::Error::WouldBlock`],
+    /// until the pulse has finished.
+    ///
+    /// Once started, this method must be called until it returns something
+    /// other than [`nb::Error::WouldBlock`], before the timer is used for
+    /// anything else.
+    ///
+    /// You might need to call [`Stepper::enable_step_control`] to make this
+    /// method available.
+    #[allow(clippy::type_complexity)]
+    pub fn try_step<Timer, const TIMER_HZ: u32>(
+        &mut self,
+        timer: &mut Timer,
+    ) -> nb::Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    >
+    where
+        Driver: Step,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        let mut future = StepFuture::with_position(
+            RefMut(&mut self.driver),
+            RefMut(timer),
+            PositionTracker::new(&mut self.position, self.direction),
+        )
+        .with_state(self.nb.step);
+
+        let poll = future.poll();
+        self.nb.step = future.state();
+
+        nonblocking::finish(poll, &mut self.nb.step, step::State::Initial)
+    }
+
+    /// Rotates the motor a number of (micro-)steps at a constant rate
+    ///
+    /// Makes `count` steps in the direction that was previously set, starting
+    /// a new step pulse every `interval`. There is no acceleration or
+    /// deceleration, so the rate must be low enough for the motor to start
+    /// and stop at it. Use motion control, if that is not the case.
+    ///
+    /// If position tracking is enabled, every step is counted once it has been
+    /// made. See [`Stepper::enable_position_tracking`].
+    ///
+    /// You might need to call [`Stepper::enable_step_control`] to make this
+    /// method available.
+    pub fn step_n<'r, Timer, const TIMER_HZ: u32>(
+        &'r mut self,
+        count: u32,
+        interval: TimerDuration<TIMER_HZ>,
+        timer: &'r mut Timer,
+    ) -> StepNFuture<
+        RefMut<'r, Driver>,
+        RefMut<'r, Timer>,
+        TIMER_HZ,
+        PositionTracker<'r>,
+    >
     where
         Driver: Step,
         Timer: TimerTrait<TIMER_HZ>,
     {
-        StepFuture::new(RefMut(&mut self.driver), RefMut(timer))
+        StepNFuture::with_position(
+            RefMut(&mut self.driver),
+            RefMut(timer),
+            count,
+            interval,
+            PositionTracker::new(&mut self.position, self.direction),
+        )
+    }
+
+    /// Disable step control
+    ///
+    /// This is the reverse of [`Stepper::enable_step_control`]. Consumes this
+    /// instance of `Stepper` and returns a new instance without step control,
+    /// together with the hardware resources that were required for it, so
+    /// they can be used for something else.
+    ///
+    /// If position tracking is enabled, the tracked position is kept, but
+    /// steps made while step control is disabled are not tracked.
+    pub fn disable_step_control(
+        self,
+    ) -> (
+        Stepper<Driver::WithoutStepControl, Limits>,
+        Driver::Resources,
+    )
+    where
+        Driver: DisableStepControl,
+    {
+        let (driver, res) = self.driver.disable_step_control();
+
+        let self_ = Stepper {
+            driver,
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
+        };
+
+        (self_, res)
     }
 
     /// Returns the step pulse length of the wrapped driver/controller
@@ -314,6 +860,234 @@ impl<Driver> Stepper<Driver> {
         Driver::PULSE_LENGTH
     }
 
+    /// Enable output control
+    ///
+    /// Consumes this instance of `Stepper` and returns a new instance that
+    /// provides control over the motor outputs. Once this method has been
+    /// called, the [`Stepper::enable_output`] and [`Stepper::disable_output`]
+    /// methods become available.
+    ///
+    /// Takes the hardware resources that are required for controlling the
+    /// motor outputs as an argument. What exactly those are depends on the
+    /// specific driver. Typically it's going to be the output pin that is
+    /// connected to the hardware's ENABLE pin.
+    ///
+    /// This method doesn't change the state of the motor outputs. Call
+    /// [`Stepper::enable_output`] or [`Stepper::disable_output`] to do that.
+    ///
+    /// This method is only available, if the driver supports enabling output
+    /// control. It might no longer be available, once output control has been
+    /// enabled.
+    pub fn enable_output_control<Resources>(
+        self,
+        res: Resources,
+    ) -> Stepper<Driver::WithOutputControl, Limits>
+    where
+        Driver: EnableOutputControl<Resources>,
+    {
+        Stepper {
+            driver: self.driver.enable_output_control(res),
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
+        }
+    }
+
+    /// Enable the motor outputs
+    ///
+    /// Energizes the motor, so it holds its position and can be moved.
+    ///
+    /// You might need to call [`Stepper::enable_output_control`] to make this
+    /// method available.
+    pub fn enable_output(&mut self) -> Result<(), Driver::Error>
+    where
+        Driver: SetOutputEnabled,
+    {
+        self.driver.set_output_enabled(true)
+    }
+
+    /// Disable the motor outputs
+    ///
+    /// De-energizes the motor, to save power and allow moving it by hand. Steps
+    /// made while the outputs are disabled are lost.
+    ///
+    /// You might need to call [`Stepper::enable_output_control`] to make this
+    /// method available.
+    pub fn disable_output(&mut self) -> Result<(), Driver::Error>
+    where
+        Driver: SetOutputEnabled,
+    {
+        self.driver.set_output_enabled(false)
+    }
+
+    /// Enable sleep control
+    ///
+    /// Consumes this instance of `Stepper` and returns a new instance that
+    /// can put the driver to sleep. Once this method has been called, the
+    /// [`Stepper::set_power_mode`] method becomes available.
+    ///
+    /// Takes the hardware resources that are required for controlling the
+    /// power mode as an argument. What exactly those are depends on the
+    /// specific driver. Typically it's going to be the output pin that is
+    /// connected to the hardware's SLEEP pin.
+    ///
+    /// This method doesn't change the power mode of the driver. Call
+    /// [`Stepper::set_power_mode`] to do that.
+    ///
+    /// This method is only available, if the driver supports enabling sleep
+    /// control. It might no longer be available, once sleep control has been
+    /// enabled.
+    pub fn enable_sleep_control<Resources>(
+        self,
+        res: Resources,
+    ) -> Stepper<Driver::WithSleepControl, Limits>
+    where
+        Driver: EnableSleepControl<Resources>,
+    {
+        Stepper {
+            driver: self.driver.enable_sleep_control(res),
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
+        }
+    }
+
+    /// Sets the power mode
+    ///
+    /// Putting the driver to sleep minimizes its power consumption, but the
+    /// motor is not held in place while the driver is asleep. When waking the
+    /// driver up, the returned future waits until the driver is ready to
+    /// accept STEP pulses again.
+    ///
+    /// You might need to call [`Stepper::enable_sleep_control`] to make this
+    /// method available.
+    pub fn set_power_mode<'r, Timer, const TIMER_HZ: u32>(
+        &'r mut self,
+        power_mode: PowerMode,
+        timer: &'r mut Timer,
+    ) -> SetPowerModeFuture<RefMut<'r, Driver>, RefMut<'r, Timer>, TIMER_HZ>
+    where
+        Driver: SetPowerMode,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        SetPowerModeFuture::new(
+            power_mode,
+            RefMut(&mut self.driver),
+            RefMut(timer),
+        )
+    }
+
+    /// Enable fault detection
+    ///
+    /// Consumes this instance of `Stepper` and returns a new instance that
+    /// can detect driver faults. Once this method has been called, the
+    /// [`Stepper::check_fault`] method becomes available.
+    ///
+    /// Takes the hardware resources that are required for detecting faults as
+    /// an argument. What exactly those are depends on the specific driver.
+    /// Typically it's going to be the input pin that is connected to the
+    /// driver's FAULT pin.
+    ///
+    /// This method is only available, if the driver supports enabling fault
+    /// detection. It might no longer be available, once fault detection has
+    /// been enabled.
+    pub fn enable_fault_detection<Resources>(
+        self,
+        res: Resources,
+    ) -> Stepper<Driver::WithFaultDetection, Limits>
+    where
+        Driver: EnableFaultDetection<Resources>,
+    {
+        Stepper {
+            driver: self.driver.enable_fault_detection(res),
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
+        }
+    }
+
+    /// Check whether the driver is signaling a fault
+    ///
+    /// Returns `None`, if no fault is present. Drivers typically disable their
+    /// outputs while a fault is present, which means any steps made during
+    /// that time are lost. Applications that check for faults regularly can
+    /// react to this, for example by homing the motor again.
+    ///
+    /// You might need to call [`Stepper::enable_fault_detection`] to make this
+    /// method available.
+    pub fn check_fault(
+        &mut self,
+    ) -> Result<Option<Driver::Fault>, Driver::Error>
+    where
+        Driver: ReadFault,
+    {
+        self.driver.read_fault()
+    }
+
+    /// Enable current control
+    ///
+    /// Consumes this instance of `Stepper` and returns a new instance that
+    /// provides control over the motor current. Once this method has been
+    /// called, the [`Stepper::set_current`] method becomes available.
+    ///
+    /// Takes the hardware resources that are required for controlling the
+    /// motor current as an argument. What exactly those are depends on the
+    /// specific driver.
+    ///
+    /// This method is only available, if the driver supports enabling current
+    /// control. It might no longer be available, once current control has
+    /// been enabled.
+    pub fn enable_current_control<Resources>(
+        self,
+        res: Resources,
+        initial: <Driver::WithCurrentControl as SetCurrent>::Current,
+    ) -> Result<
+        Stepper<Driver::WithCurrentControl, Limits>,
+        <Driver::WithCurrentControl as SetCurrent>::Error,
+    >
+    where
+        Driver: EnableCurrentControl<Resources>,
+    {
+        let mut self_ = Stepper {
+            driver: self.driver.enable_current_control(res),
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
+        };
+        self_.set_current(initial)?;
+
+        Ok(self_)
+    }
+
+    /// Set the motor current
+    ///
+    /// This method is only available, if the wrapped driver supports setting
+    /// the motor current through software. What exactly the current value
+    /// means depends on the specific driver.
+    ///
+    /// This can be used to reduce the current while the motor is at
+    /// standstill, to save power and reduce heat.
+    ///
+    /// You might need to call [`Stepper::enable_current_control`] to make this
+    /// method available.
+    pub fn set_current(
+        &mut self,
+        current: Driver::Current,
+    ) -> Result<(), Driver::Error>
+    where
+        Driver: SetCurrent,
+    {
+        self.driver.set_current(current)
+    }
+
     /// Enable motion control
     ///
     /// Consumes this instance of `Stepper` and returns a new instance that
@@ -336,12 +1110,17 @@ impl<Driver> Stepper<Driver> {
     pub fn enable_motion_control<Resources, const TIMER_HZ: u32>(
         self,
         res: Resources,
-    ) -> Stepper<Driver::WithMotionControl>
+    ) -> Stepper<Driver::WithMotionControl, Limits>
     where
         Driver: EnableMotionControl<Resources, TIMER_HZ>,
     {
         Stepper {
             driver: self.driver.enable_motion_control(res),
+            position: self.position,
+            direction: self.direction,
+            step_mode: self.step_mode,
+            limits: self.limits,
+            nb: self.nb,
         }
     }
 
@@ -372,18 +1151,372 @@ impl<Driver> Stepper<Driver> {
         MoveToFuture::new(RefMut(&mut self.driver), max_velocity, target_step)
     }
 
+    /// Move towards the given position, until a probe triggers
+    ///
+    /// Starts a motion to `target_step`, like [`Stepper::move_to_position`],
+    /// and reads `probe` every time the returned future is polled. Once the
+    /// probe triggers, the future latches the position at which that happened,
+    /// brings the motor to a stop, and resolves to the latched position. This
+    /// is needed for Z-probing and touch-off workflows. See [`ProbeFuture`]
+    /// for details.
+    ///
+    /// The probe can be anything that implements [`LimitSwitch`], like an
+    /// [`Endstop`].
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    ///
+    /// [`Endstop`]: crate::limit_switch::Endstop
+    pub fn probe<'r, Probe>(
+        &'r mut self,
+        probe: &'r mut Probe,
+        max_velocity: Driver::Velocity,
+        target_step: i32,
+    ) -> ProbeFuture<'r, RefMut<'r, Driver>, Probe>
+    where
+        Driver: MotionControl,
+        Probe: LimitSwitch,
+    {
+        ProbeFuture::new(
+            RefMut(&mut self.driver),
+            probe,
+            max_velocity,
+            target_step,
+        )
+    }
+
+    /// Move the motor by the given number of steps
+    ///
+    /// The motion is relative to the position reported by the motion
+    /// controller (see [`Stepper::position`]), at the time the returned future
+    /// is first polled. This way, the motion starts from where the motor
+    /// actually is, even if the previous motion was interrupted (for example
+    /// using [`Stepper::stop`]) or failed.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn move_by<'r>(
+        &'r mut self,
+        max_velocity: Driver::Velocity,
+        delta_steps: i32,
+    ) -> MoveToFuture<RefMut<'r, Driver>>
+    where
+        Driver: MotionControl,
+    {
+        MoveToFuture::new_relative(
+            RefMut(&mut self.driver),
+            max_velocity,
+            delta_steps,
+        )
+    }
+
+    /// Returns the current position of the motor, as reported by the driver
+    ///
+    /// Unlike [`Stepper::current_position`], which returns the position that
+    /// is tracked while using step control directly, this queries the motion
+    /// controller, which keeps track of the position by itself.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn position(&mut self) -> Result<i32, Driver::Error>
+    where
+        Driver: MotionControl,
+    {
+        self.driver.current_position()
+    }
+
+    /// Move the motor to the given position of a linear axis, in millimetres
+    ///
+    /// Converts the position to steps using `axis`, then works like
+    /// [`Stepper::move_to_position`].
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn move_to_mm<'r>(
+        &'r mut self,
+        axis: &Linear,
+        max_velocity: Driver::Velocity,
+        mm: f32,
+    ) -> MoveToFuture<RefMut<'r, Driver>>
+    where
+        Driver: MotionControl,
+    {
+        self.move_to_position(max_velocity, axis.to_steps(mm))
+    }
+
+    /// Move the motor to the given angle of a rotary axis, in degrees
+    ///
+    /// Converts the angle to steps using `axis`, then works like
+    /// [`Stepper::move_to_position`].
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn move_to_degrees<'r>(
+        &'r mut self,
+        axis: &Rotary,
+        max_velocity: Driver::Velocity,
+        degrees: f32,
+    ) -> MoveToFuture<RefMut<'r, Driver>>
+    where
+        Driver: MotionControl,
+    {
+        self.move_to_position(max_velocity, axis.to_steps(degrees))
+    }
+
+    /// Returns the current position of a linear axis, in millimetres
+    ///
+    /// See [`Stepper::position`].
+    pub fn position_mm(&mut self, axis: &Linear) -> Result<f32, Driver::Error>
+    where
+        Driver: MotionControl,
+    {
+        self.position().map(|steps| axis.to_mm(steps))
+    }
+
+    /// Returns the current angle of a rotary axis, in degrees
+    ///
+    /// See [`Stepper::position`].
+    pub fn position_degrees(
+        &mut self,
+        axis: &Rotary,
+    ) -> Result<f32, Driver::Error>
+    where
+        Driver: MotionControl,
+    {
+        self.position().map(|steps| axis.to_degrees(steps))
+    }
+
+    /// Returns the current velocity of the motor
+    ///
+    /// The velocity is returned as a magnitude, in the same unit that is used
+    /// for the maximum velocity in [`Stepper::move_to_position`].
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available. Requires the driver to implement [`ReadVelocity`],
+    /// which [`SoftwareMotionControl`] only does, if the velocity type of its
+    /// motion profile supports the required arithmetic.
+    ///
+    /// [`SoftwareMotionControl`]: crate::motion_control::SoftwareMotionControl
+    pub fn current_velocity(
+        &mut self,
+    ) -> Result<Driver::Velocity, Driver::Error>
+    where
+        Driver: ReadVelocity,
+    {
+        self.driver.current_velocity()
+    }
+
+    /// Decelerate the motor to a standstill
+    ///
+    /// Instead of finishing the ongoing motion, the motor slows down using the
+    /// same deceleration that it would use at the end of the motion. Returns a
+    /// future that finishes once the motor has stopped.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn stop<'r>(&'r mut self) -> StopFuture<RefMut<'r, Driver>>
+    where
+        Driver: MotionControl,
+    {
+        StopFuture::new(RefMut(&mut self.driver))
+    }
+
+    /// Start moving continuously in the given direction
+    ///
+    /// The motor accelerates to `velocity` and keeps moving, until
+    /// [`Stepper::stop_jog`] is called. Calling this method again while the
+    /// motor is jogging changes velocity and direction smoothly.
+    ///
+    /// Unlike the other motion control methods, this doesn't return a future,
+    /// as a jog doesn't end by itself. If the driver requires it (like
+    /// [`SoftwareMotionControl`] does), keep calling
+    /// [`MotionControl::update`] on the driver (via [`Stepper::driver_mut`])
+    /// while jogging.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    ///
+    /// [`SoftwareMotionControl`]: crate::motion_control::SoftwareMotionControl
+    pub fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: Driver::Velocity,
+    ) -> Result<(), Driver::Error>
+    where
+        Driver: MotionControl,
+    {
+        self.driver.start_jog(direction, velocity)
+    }
+
+    /// Stop jogging
+    ///
+    /// Decelerates the motor to a standstill. Returns a future that finishes
+    /// once the motor has stopped. See [`Stepper::start_jog`].
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn stop_jog<'r>(&'r mut self) -> StopFuture<RefMut<'r, Driver>>
+    where
+        Driver: MotionControl,
+    {
+        StopFuture::for_jog(RefMut(&mut self.driver))
+    }
+
+    /// Wait for the given duration
+    ///
+    /// Doesn't do anything with the motor. This is useful for sequences of
+    /// operations that require a pause in between (for example, move, dwell,
+    /// move), as it uses the same polling model as the other operations.
+    ///
+    /// Since this method doesn't require anything from the driver, it is
+    /// always available.
+    pub fn dwell<'r, Timer, const TIMER_HZ: u32>(
+        &self,
+        duration: TimerDuration<TIMER_HZ>,
+        timer: &'r mut Timer,
+    ) -> DwellFuture<RefMut<'r, Timer>, TIMER_HZ>
+    where
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        DwellFuture::new(duration, RefMut(timer))
+    }
+
+    /// Stop the motor immediately
+    ///
+    /// Stops generating steps right away, without decelerating, and discards
+    /// the rest of the ongoing motion. This is meant for emergencies, for
+    /// example from a fault or panic handler. Depending on the velocity, the
+    /// motor might lose steps, so the position should be considered unreliable
+    /// afterwards, until the motor has been homed again.
+    ///
+    /// The position reported by the driver still reflects the steps that have
+    /// actually been made.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn emergency_stop(&mut self) -> Result<(), Driver::Error>
+    where
+        Driver: MotionControl,
+    {
+        self.driver.abort()
+    }
+
     /// Reset the position to the given value
     ///
     /// This should never result in a movement, as this method only overwrites
     /// the internal position counter of the driver. However, it might influence
     /// an already ongoing movement.
     ///
+    /// If position tracking is enabled, the tracked position is reset too (see
+    /// [`Stepper::enable_position_tracking`]).
+    ///
     /// You might need to call [`Stepper::enable_motion_control`] to make this
     /// method available.
     pub fn reset_position(&mut self, step: i32) -> Result<(), Driver::Error>
     where
         Driver: MotionControl,
     {
-        self.driver.reset_position(step)
+        self.driver.reset_position(step)?;
+
+        if let Some(position) = &mut self.position {
+            *position = step;
+        }
+
+        Ok(())
+    }
+
+    /// Enable position tracking
+    ///
+    /// Once enabled, every step made using [`Stepper::step`] or
+    /// [`Stepper::step_n`] moves the tracked position by one, in the direction
+    /// last set using [`Stepper::set_direction`] (or
+    /// [`Stepper::enable_direction_control`]).
+    /// The tracked position starts out at `initial`. Calling this method again
+    /// resets the tracked position.
+    ///
+    /// Position tracking is meant for cases where step control is used
+    /// directly. Motion controllers (including the software fallback in the
+    /// [`motion_control`] module) keep track of the position themselves, and
+    /// steps they make are not seen by the tracking. Resetting the position
+    /// using [`Stepper::reset_position`] resets both, to keep them in sync.
+    ///
+    /// [`motion_control`]: crate::motion_control
+    pub fn enable_position_tracking(&mut self, initial: i32) {
+        self.position = Some(initial);
+    }
+
+    /// Disable position tracking
+    ///
+    /// See [`Stepper::enable_position_tracking`].
+    pub fn disable_position_tracking(&mut self) {
+        self.position = None;
+    }
+
+    /// Returns the tracked position
+    ///
+    /// Returns `None`, if position tracking has not been enabled. See
+    /// [`Stepper::enable_position_tracking`]. If motion control is enabled, use
+    /// [`Stepper::position`] instead.
+    pub fn current_position(&self) -> Option<i32> {
+        self.position
+    }
+
+    /// Record a new step mode and rescale the position accordingly
+    fn record_step_mode(&mut self, step_mode: u16) {
+        if let Some(previous) = self.step_mode {
+            self.position = self.position.map(|position| {
+                rescale_position(position, previous, step_mode)
+            });
+        }
+
+        self.step_mode = Some(step_mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::task::Poll;
+
+    use crate::{
+        limit_switch::NoLimitSwitch, mock::MockMotionControl, Direction,
+        HomingConfig,
+    };
+
+    use super::Stepper;
+
+    #[test]
+    fn move_by_should_start_from_position_after_stop() {
+        let mut stepper = Stepper::from_driver(MockMotionControl::<()>::new());
+        stepper.driver().set_stop_distance(2);
+
+        let mut future = stepper.move_to_position((), 10);
+        for _ in 0..4 {
+            assert_eq!(future.poll(), Poll::Pending);
+        }
+        stepper.stop().wait().unwrap();
+        assert_eq!(stepper.position(), Ok(5));
+
+        stepper.move_by((), 5).wait().unwrap();
+        assert_eq!(stepper.position(), Ok(10));
+    }
+
+    #[test]
+    fn move_by_should_start_from_position_after_failed_home() {
+        let mut stepper = Stepper::from_driver(MockMotionControl::<()>::new())
+            .attach_limit_switches(NoLimitSwitch, NoLimitSwitch);
+
+        let config = HomingConfig {
+            direction: Direction::Backward,
+            fast_velocity: (),
+            slow_velocity: (),
+            max_travel: 20,
+            backoff: 5,
+            home_position: 3,
+        };
+        assert!(stepper.home(config).wait().is_err());
+        assert_eq!(stepper.position(), Ok(-20));
+
+        stepper.move_by((), 5).wait().unwrap();
+        assert_eq!(stepper.position(), Ok(-15));
     }
 }
@@ -2,11 +2,16 @@ use core::task::Poll;
 
 use crate::traits::MotionControl;
 
+use super::{GatedMoveToFuture, StartBarrier};
+
 /// The "future" returned by [`Stepper::move_to_position`]
 ///
-/// Please note that this type provides a custom API and does not implement
-/// [`core::future::Future`]. This might change, when using futures for embedded
-/// development becomes more practical.
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the driver to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// [`traits`]: crate::traits
 ///
 /// [`Stepper::move_to_position`]: crate::Stepper::move_to_position
 #[must_use]
@@ -40,6 +45,30 @@ where
         }
     }
 
+    /// Create new instance of `MoveToFuture` for a relative motion
+    ///
+    /// The target is `delta_steps` away from the position the motor is at
+    /// when the future is first polled.
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::move_by`] instead.
+    ///
+    /// [`Stepper::move_by`]: crate::Stepper::move_by
+    pub fn new_relative(
+        driver: Driver,
+        max_velocity: Driver::Velocity,
+        delta_steps: i32,
+    ) -> Self {
+        Self {
+            driver,
+            state: State::InitialRelative {
+                max_velocity,
+                delta_steps,
+            },
+        }
+    }
+
     /// Poll the future
     ///
     /// The future must be polled for the operation to make progress. The
@@ -61,6 +90,16 @@ where
                 self.state = State::Moving;
                 Poll::Pending
             }
+            State::InitialRelative {
+                max_velocity,
+                delta_steps,
+            } => {
+                let target_step =
+                    self.driver.current_position()?.saturating_add(delta_steps);
+                self.driver.move_to_position(max_velocity, target_step)?;
+                self.state = State::Moving;
+                Poll::Pending
+            }
             State::Moving => {
                 let still_moving = self.driver.update()?;
                 if still_moving {
@@ -74,6 +113,43 @@ where
         }
     }
 
+    /// Change the target of the motion
+    ///
+    /// If the motion is ongoing, it is re-planned from the current velocity,
+    /// using [`MotionControl::retarget`]. Otherwise, the motion to the new
+    /// target starts the next time the future is polled.
+    pub fn set_target(
+        &mut self,
+        max_velocity: Driver::Velocity,
+        target_step: i32,
+    ) -> Result<(), Driver::Error> {
+        match self.state {
+            State::Moving => {
+                self.driver.retarget(max_velocity, target_step)?;
+            }
+            State::Initial { .. }
+            | State::InitialRelative { .. }
+            | State::Finished => {
+                self.state = State::Initial {
+                    max_velocity,
+                    target_step,
+                };
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Don't start the motion, before the barrier has been released
+    ///
+    /// See [`StartBarrier`].
+    pub fn start_with(
+        self,
+        barrier: &StartBarrier,
+    ) -> GatedMoveToFuture<'_, Driver> {
+        GatedMoveToFuture::new(self, barrier)
+    }
+
     /// Wait until the operation completes
     ///
     /// This method will call [`Self::poll`] in a busy loop until the operation
@@ -86,17 +162,89 @@ where
         }
     }
 
+    /// Abort the motion, and release the resources that were moved into it
+    ///
+    /// Stops the motor right away, using [`MotionControl::abort`]. If a step
+    /// pulse is in progress, the driver ends it early and counts the step, so
+    /// its position reflects the steps that have actually been made. Call this
+    /// after [`Self::poll`] returned an error, to get the driver back in a
+    /// consistent state, instead of just dropping the future.
+    ///
+    /// Returns the driver, together with the position the motor stopped at.
+    /// The driver is returned, even if aborting the motion failed.
+    pub fn abort(mut self) -> (Driver, Result<i32, Driver::Error>) {
+        let result = self.abort_motion();
+        (self.driver, result)
+    }
+
+    fn abort_motion(&mut self) -> Result<i32, Driver::Error> {
+        if let State::Moving = self.state {
+            self.driver.abort()?;
+        }
+        self.state = State::Finished;
+
+        self.driver.current_position()
+    }
+
     /// Drop the future and release the resources that were moved into it
     pub fn release(self) -> Driver {
         self.driver
     }
 }
 
+#[cfg(feature = "async")]
+impl<Driver> core::future::Future for MoveToFuture<Driver>
+where
+    Driver: MotionControl + crate::traits::RegisterWaker,
+    Self: Unpin,
+{
+    type Output = Result<(), Driver::Error>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the driver might need attention
+        // between polling and registering, and we'd miss the wake-up.
+        self_.driver.register_waker(cx.waker());
+        MoveToFuture::poll(self_)
+    }
+}
+
 enum State<Velocity> {
     Initial {
         max_velocity: Velocity,
         target_step: i32,
     },
+    InitialRelative {
+        max_velocity: Velocity,
+        delta_steps: i32,
+    },
     Moving,
     Finished,
 }
+
+#[cfg(test)]
+mod tests {
+    use core::task::Poll;
+
+    use crate::{mock::MockMotionControl, traits::MotionControl};
+
+    use super::MoveToFuture;
+
+    #[test]
+    fn abort_should_stop_motion_and_return_position() {
+        let mut future = MoveToFuture::new(MockMotionControl::new(), (), 10);
+
+        assert_eq!(future.poll(), Poll::Pending);
+        assert_eq!(future.poll(), Poll::Pending);
+        assert_eq!(future.poll(), Poll::Pending);
+
+        let (mut driver, position) = future.abort();
+        assert_eq!(position, Ok(2));
+        assert_eq!(driver.update(), Ok(false));
+        assert_eq!(driver.physical_position(), 2);
+    }
+}
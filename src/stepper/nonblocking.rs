@@ -0,0 +1,42 @@
+use core::task::Poll;
+
+use super::{set_direction, set_step_mode, step};
+
+/// The state of the operations started through the non-blocking methods
+///
+/// Those methods can't keep a future around between calls, so they store the
+/// future's state here instead.
+#[derive(Clone, Copy)]
+pub(crate) struct NbState {
+    pub step: step::State,
+    pub direction: set_direction::State,
+    pub step_mode: set_step_mode::State,
+}
+
+impl NbState {
+    pub fn new() -> Self {
+        Self {
+            step: step::State::Initial,
+            direction: set_direction::State::Initial,
+            step_mode: set_step_mode::State::Initial,
+        }
+    }
+}
+
+/// Convert the result of polling a future into an `nb::Result`
+///
+/// Resets `state` to `initial`, once the future has finished, so the next call
+/// starts a new operation.
+pub(crate) fn finish<State, E>(
+    poll: Poll<Result<(), E>>,
+    state: &mut State,
+    initial: State,
+) -> nb::Result<(), E> {
+    match poll {
+        Poll::Pending => Err(nb::Error::WouldBlock),
+        Poll::Ready(result) => {
+            *state = initial;
+            result.map_err(nb::Error::Other)
+        }
+    }
+}
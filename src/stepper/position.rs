@@ -0,0 +1,53 @@
+use crate::Direction;
+
+/// Keeps track of the position, as steps are made
+///
+/// Used by [`StepFuture`] to report the steps it makes. Implemented for `()`,
+/// which doesn't track anything, and for [`PositionTracker`], which is used by
+/// [`Stepper`] to implement position tracking.
+///
+/// [`StepFuture`]: crate::StepFuture
+/// [`Stepper`]: crate::Stepper
+pub trait TrackPosition {
+    /// Called once a step has been made
+    fn step_made(&mut self);
+}
+
+impl TrackPosition for () {
+    fn step_made(&mut self) {}
+}
+
+/// Tracks the position of a [`Stepper`]
+///
+/// Returned as part of the [`StepFuture`] from [`Stepper::step`]. See
+/// [`Stepper::enable_position_tracking`] for more information.
+///
+/// [`Stepper`]: crate::Stepper
+/// [`StepFuture`]: crate::StepFuture
+/// [`Stepper::step`]: crate::Stepper::step
+/// [`Stepper::enable_position_tracking`]: crate::Stepper::enable_position_tracking
+pub struct PositionTracker<'r> {
+    position: &'r mut Option<i32>,
+    direction: Direction,
+}
+
+impl<'r> PositionTracker<'r> {
+    /// Create a new instance of `PositionTracker`
+    ///
+    /// If `position` is `None`, no position is tracked. Otherwise, every step
+    /// moves the position by one in the given direction.
+    pub fn new(position: &'r mut Option<i32>, direction: Direction) -> Self {
+        Self {
+            position,
+            direction,
+        }
+    }
+}
+
+impl TrackPosition for PositionTracker<'_> {
+    fn step_made(&mut self) {
+        if let Some(position) = self.position {
+            *position = position.wrapping_add(self.direction as i32);
+        }
+    }
+}
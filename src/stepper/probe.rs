@@ -0,0 +1,257 @@
+use core::{fmt, task::Poll};
+
+use crate::{limit_switch::LimitSwitch, traits::MotionControl};
+
+/// The "future" returned by [`Stepper::probe`]
+///
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the driver to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// Moves towards the target, reading the probe every time the future is
+/// polled. Once the probe triggers, the current position is latched, and the
+/// motor decelerates to a standstill. The future then resolves to the latched
+/// position. Moving slowly keeps the distance the motor travels after the
+/// trigger short, and makes the latched position more accurate.
+///
+/// [`traits`]: crate::traits
+///
+/// [`Stepper::probe`]: crate::Stepper::probe
+#[must_use]
+pub struct ProbeFuture<'r, Driver: MotionControl, Probe> {
+    driver: Driver,
+    probe: &'r mut Probe,
+    max_velocity: Driver::Velocity,
+    target_step: i32,
+    state: State,
+}
+
+impl<'r, Driver, Probe> ProbeFuture<'r, Driver, Probe>
+where
+    Driver: MotionControl,
+    Probe: LimitSwitch,
+{
+    /// Create new instance of `ProbeFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::probe`] instead.
+    ///
+    /// [`Stepper::probe`]: crate::Stepper::probe
+    pub fn new(
+        driver: Driver,
+        probe: &'r mut Probe,
+        max_velocity: Driver::Velocity,
+        target_step: i32,
+    ) -> Self {
+        Self {
+            driver,
+            probe,
+            max_velocity,
+            target_step,
+            state: State::Initial,
+        }
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the driver needs
+    /// attention, and call this method again once it does.
+    #[allow(clippy::type_complexity)]
+    pub fn poll(
+        &mut self,
+    ) -> Poll<Result<i32, ProbeError<Driver::Error, Probe::Error>>> {
+        match self.poll_inner() {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(position)) => {
+                self.state = State::Finished(Some(position));
+                Poll::Ready(Ok(position))
+            }
+            Err(err) => {
+                self.state = State::Finished(None);
+                Poll::Ready(Err(err))
+            }
+        }
+    }
+
+    fn poll_inner(
+        &mut self,
+    ) -> Result<Poll<i32>, ProbeError<Driver::Error, Probe::Error>> {
+        match self.state {
+            State::Initial => {
+                self.driver
+                    .move_to_position(self.max_velocity, self.target_step)
+                    .map_err(ProbeError::MotionControl)?;
+                self.state = State::Probing;
+
+                Ok(Poll::Pending)
+            }
+            State::Probing => {
+                if self.probe.is_triggered().map_err(ProbeError::Probe)? {
+                    let position = self
+                        .driver
+                        .current_position()
+                        .map_err(ProbeError::MotionControl)?;
+                    self.driver.stop().map_err(ProbeError::MotionControl)?;
+                    self.state = State::Stopping(position);
+
+                    return Ok(Poll::Pending);
+                }
+
+                let still_moving =
+                    self.driver.update().map_err(ProbeError::MotionControl)?;
+                if !still_moving {
+                    return Err(ProbeError::NotTriggered);
+                }
+
+                Ok(Poll::Pending)
+            }
+            State::Stopping(position) => {
+                let still_moving =
+                    self.driver.update().map_err(ProbeError::MotionControl)?;
+                if still_moving {
+                    Ok(Poll::Pending)
+                } else {
+                    Ok(Poll::Ready(position))
+                }
+            }
+            State::Finished(Some(position)) => Ok(Poll::Ready(position)),
+            // The operation has failed before. There's no position to return.
+            State::Finished(None) => Err(ProbeError::NotTriggered),
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    pub fn wait(
+        &mut self,
+    ) -> Result<i32, ProbeError<Driver::Error, Probe::Error>> {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    pub fn release(self) -> (Driver, &'r mut Probe) {
+        (self.driver, self.probe)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'r, Driver, Probe> core::future::Future for ProbeFuture<'r, Driver, Probe>
+where
+    Driver: MotionControl + crate::traits::RegisterWaker,
+    Probe: LimitSwitch,
+    Self: Unpin,
+{
+    type Output = Result<i32, ProbeError<Driver::Error, Probe::Error>>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the driver might need attention
+        // between polling and registering, and we'd miss the wake-up.
+        self_.driver.register_waker(cx.waker());
+        ProbeFuture::poll(self_)
+    }
+}
+
+/// An error that can occur while probing
+///
+/// Returned by [`ProbeFuture`].
+#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProbeError<MotionControlError, InputError> {
+    /// An error originated from the motion controller
+    MotionControl(MotionControlError),
+
+    /// An error occurred while reading the probe
+    Probe(InputError),
+
+    /// The target was reached without the probe triggering
+    NotTriggered,
+}
+
+impl<MotionControlError, InputError> fmt::Display
+    for ProbeError<MotionControlError, InputError>
+where
+    MotionControlError: fmt::Debug,
+    InputError: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MotionControl(err) => {
+                write!(f, "motion control error: {:?}", err)
+            }
+            Self::Probe(err) => write!(f, "error reading probe: {:?}", err),
+            Self::NotTriggered => {
+                write!(f, "target reached without probe triggering")
+            }
+        }
+    }
+}
+
+enum State {
+    Initial,
+    Probing,
+    Stopping(i32),
+    Finished(Option<i32>),
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use crate::{limit_switch::LimitSwitch, mock::MockMotionControl};
+
+    use super::{ProbeError, ProbeFuture};
+
+    #[test]
+    fn probe_should_latch_position_at_trigger() {
+        let driver = MockMotionControl::new();
+        // Overshoot a bit, like a real motor that decelerates.
+        driver.set_stop_distance(3);
+
+        let mut probe = Probe {
+            axis: driver.clone(),
+            trigger: 42,
+        };
+
+        let position = ProbeFuture::new(driver.clone(), &mut probe, (), 100)
+            .wait()
+            .unwrap();
+        assert_eq!(position, 42);
+
+        probe.trigger = 200;
+        let result = ProbeFuture::new(driver, &mut probe, (), 100).wait();
+        assert_eq!(result, Err(ProbeError::NotTriggered));
+    }
+
+    struct Probe {
+        axis: MockMotionControl,
+        trigger: i32,
+    }
+
+    impl LimitSwitch for Probe {
+        type Error = Infallible;
+
+        fn is_triggered(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.axis.physical_position() >= self.trigger)
+        }
+    }
+}
@@ -11,9 +11,12 @@ use super::SignalError;
 
 /// The "future" returned by [`Stepper::set_direction`]
 ///
-/// Please note that this type provides a custom API and does not implement
-/// [`core::future::Future`]. This might change, when using futures for embedded
-/// development becomes more practical.
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the timer to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// [`traits`]: crate::traits
 ///
 /// [`Stepper::set_direction`]: crate::Stepper::set_direction
 #[must_use]
@@ -57,6 +60,7 @@ where
     /// calling it at a high frequency (see [`Self::wait`]) until the operation
     /// completes, or set up an interrupt that fires once the timer finishes
     /// counting down, and call this method again once it does.
+    #[allow(clippy::type_complexity)]
     pub fn poll(
         &mut self,
     ) -> Poll<
@@ -75,22 +79,20 @@ where
                     Direction::Forward => self
                         .driver
                         .dir()
-                        .map_err(|err| SignalError::PinUnavailable(err))?
+                        .map_err(SignalError::PinUnavailable)?
                         .set_high()
-                        .map_err(|err| SignalError::Pin(err))?,
+                        .map_err(SignalError::Pin)?,
                     Direction::Backward => self
                         .driver
                         .dir()
-                        .map_err(|err| SignalError::PinUnavailable(err))?
+                        .map_err(SignalError::PinUnavailable)?
                         .set_low()
-                        .map_err(|err| SignalError::Pin(err))?,
+                        .map_err(SignalError::Pin)?,
                 }
 
                 let ticks: TimerDuration<TIMER_HZ> =
                     Driver::SETUP_TIME.convert();
-                self.timer
-                    .start(ticks)
-                    .map_err(|err| SignalError::Timer(err))?;
+                self.timer.start(ticks).map_err(SignalError::Timer)?;
 
                 self.state = State::DirectionSet;
                 Poll::Pending
@@ -114,6 +116,7 @@ where
     ///
     /// This method will call [`Self::poll`] in a busy loop until the operation
     /// has finished.
+    #[allow(clippy::type_complexity)]
     pub fn wait(
         &mut self,
     ) -> Result<
@@ -131,13 +134,62 @@ where
         }
     }
 
+    /// Continue from a state saved using [`Self::state`]
+    ///
+    /// Used by the non-blocking methods of `Stepper`, which can't keep the
+    /// future around between calls.
+    pub(crate) fn with_state(mut self, state: State) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub(crate) fn state(&self) -> State {
+        self.state
+    }
+
     /// Drop the future and release the resources that were moved into it
     pub fn release(self) -> (Driver, Timer) {
         (self.driver, self.timer)
     }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Driver, Timer, const TIMER_HZ: u32> core::future::Future
+    for SetDirectionFuture<Driver, Timer, TIMER_HZ>
+where
+    Driver: SetDirection,
+    Timer: TimerTrait<TIMER_HZ> + crate::traits::RegisterWaker,
+    Self: Unpin,
+{
+    type Output = Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Dir as ErrorType>::Error,
+            Timer::Error,
+        >,
+    >;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the timer might expire between
+        // polling and registering, and we'd miss the wake-up.
+        self_.timer.register_waker(cx.waker());
+        SetDirectionFuture::poll(self_)
+    }
 }
 
-enum State {
+#[derive(Clone, Copy)]
+pub(crate) enum State {
     Initial,
     DirectionSet,
     Finished,
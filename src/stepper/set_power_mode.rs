@@ -0,0 +1,171 @@
+use core::{convert::Infallible, task::Poll};
+
+use fugit::TimerDurationU32 as TimerDuration;
+use fugit_timer::Timer as TimerTrait;
+
+use crate::{traits::SetPowerMode, PowerMode};
+
+use super::SignalError;
+
+/// The "future" returned by [`Stepper::set_power_mode`]
+///
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the timer to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// [`traits`]: crate::traits
+///
+/// [`Stepper::set_power_mode`]: crate::Stepper::set_power_mode
+#[must_use]
+pub struct SetPowerModeFuture<Driver, Timer, const TIMER_HZ: u32> {
+    power_mode: PowerMode,
+    driver: Driver,
+    timer: Timer,
+    state: State,
+}
+
+impl<Driver, Timer, const TIMER_HZ: u32>
+    SetPowerModeFuture<Driver, Timer, TIMER_HZ>
+where
+    Driver: SetPowerMode,
+    Timer: TimerTrait<TIMER_HZ>,
+{
+    /// Create new instance of `SetPowerModeFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::set_power_mode`] instead.
+    ///
+    /// [`Stepper::set_power_mode`]: crate::Stepper::set_power_mode
+    pub fn new(power_mode: PowerMode, driver: Driver, timer: Timer) -> Self {
+        Self {
+            power_mode,
+            driver,
+            timer,
+            state: State::Initial,
+        }
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the timer finishes
+    /// counting down, and call this method again once it does.
+    #[allow(clippy::type_complexity)]
+    pub fn poll(
+        &mut self,
+    ) -> Poll<
+        Result<
+            (),
+            SignalError<
+                Infallible, // only applies to `SetDirection`, `Step`
+                Driver::Error,
+                Timer::Error,
+            >,
+        >,
+    > {
+        match self.state {
+            State::Initial => {
+                self.driver
+                    .apply_power_mode(self.power_mode)
+                    .map_err(SignalError::Pin)?;
+
+                // Only waking up requires waiting. The driver is ready to go
+                // to sleep right away.
+                if self.power_mode == PowerMode::Sleep {
+                    self.state = State::Finished;
+                    return Poll::Ready(Ok(()));
+                }
+
+                let ticks: TimerDuration<TIMER_HZ> =
+                    Driver::WAKE_UP_TIME.convert();
+
+                self.timer.start(ticks).map_err(SignalError::Timer)?;
+
+                self.state = State::WakingUp;
+                Poll::Pending
+            }
+            State::WakingUp => match self.timer.wait() {
+                Ok(()) => {
+                    self.state = State::Finished;
+                    Poll::Ready(Ok(()))
+                }
+                Err(nb::Error::Other(err)) => {
+                    self.state = State::Finished;
+                    Poll::Ready(Err(SignalError::Timer(err)))
+                }
+                Err(nb::Error::WouldBlock) => Poll::Pending,
+            },
+            State::Finished => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    pub fn wait(
+        &mut self,
+    ) -> Result<
+        (),
+        SignalError<
+            Infallible, // only applies to `SetDirection`, `Step`
+            Driver::Error,
+            Timer::Error,
+        >,
+    > {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    pub fn release(self) -> (Driver, Timer) {
+        (self.driver, self.timer)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Driver, Timer, const TIMER_HZ: u32> core::future::Future
+    for SetPowerModeFuture<Driver, Timer, TIMER_HZ>
+where
+    Driver: SetPowerMode,
+    Timer: TimerTrait<TIMER_HZ> + crate::traits::RegisterWaker,
+    Self: Unpin,
+{
+    type Output = Result<
+        (),
+        SignalError<
+            Infallible, // only applies to `SetDirection`, `Step`
+            Driver::Error,
+            Timer::Error,
+        >,
+    >;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the timer might expire between
+        // polling and registering, and we'd miss the wake-up.
+        self_.timer.register_waker(cx.waker());
+        SetPowerModeFuture::poll(self_)
+    }
+}
+
+enum State {
+    Initial,
+    WakingUp,
+    Finished,
+}
@@ -9,9 +9,12 @@ use super::SignalError;
 
 /// The "future" returned by [`Stepper::set_step_mode`]
 ///
-/// Please note that this type provides a custom API and does not implement
-/// [`core::future::Future`]. This might change, when using futures for embedded
-/// development becomes more practical.
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the timer to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// [`traits`]: crate::traits
 ///
 /// [`Stepper::set_step_mode`]: crate::Stepper::set_step_mode
 #[must_use]
@@ -59,6 +62,7 @@ where
     /// calling it at a high frequency (see [`Self::wait`]) until the operation
     /// completes, or set up an interrupt that fires once the timer finishes
     /// counting down, and call this method again once it does.
+    #[allow(clippy::type_complexity)]
     pub fn poll(
         &mut self,
     ) -> Poll<
@@ -75,30 +79,24 @@ where
             State::Initial => {
                 self.driver
                     .apply_mode_config(self.step_mode)
-                    .map_err(|err| SignalError::Pin(err))?;
+                    .map_err(SignalError::Pin)?;
 
                 let ticks: TimerDuration<TIMER_HZ> =
                     Driver::SETUP_TIME.convert();
 
-                self.timer
-                    .start(ticks)
-                    .map_err(|err| SignalError::Timer(err))?;
+                self.timer.start(ticks).map_err(SignalError::Timer)?;
 
                 self.state = State::ApplyingConfig;
                 Poll::Pending
             }
             State::ApplyingConfig => match self.timer.wait() {
                 Ok(()) => {
-                    self.driver
-                        .enable_driver()
-                        .map_err(|err| SignalError::Pin(err))?;
+                    self.driver.enable_driver().map_err(SignalError::Pin)?;
 
                     let ticks: TimerDuration<TIMER_HZ> =
                         Driver::HOLD_TIME.convert();
 
-                    self.timer
-                        .start(ticks)
-                        .map_err(|err| SignalError::Timer(err))?;
+                    self.timer.start(ticks).map_err(SignalError::Timer)?;
 
                     self.state = State::EnablingDriver;
                     Poll::Ready(Ok(()))
@@ -146,13 +144,57 @@ where
         }
     }
 
+    /// Continue from a state saved using [`Self::state`]
+    ///
+    /// Used by the non-blocking methods of `Stepper`, which can't keep the
+    /// future around between calls.
+    pub(crate) fn with_state(mut self, state: State) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub(crate) fn state(&self) -> State {
+        self.state
+    }
+
     /// Drop the future and release the resources that were moved into it
     pub fn release(self) -> (Driver, Timer) {
         (self.driver, self.timer)
     }
 }
 
-enum State {
+#[cfg(feature = "async")]
+impl<Driver, Timer, const TIMER_HZ: u32> core::future::Future
+    for SetStepModeFuture<Driver, Timer, TIMER_HZ>
+where
+    Driver: SetStepMode,
+    Timer: TimerTrait<TIMER_HZ> + crate::traits::RegisterWaker,
+    Self: Unpin,
+{
+    type Output = Result<
+        (),
+        SignalError<
+            Infallible, // only applies to `SetDirection`, `Step`
+            Driver::Error,
+            Timer::Error,
+        >,
+    >;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the timer might expire between
+        // polling and registering, and we'd miss the wake-up.
+        self_.timer.register_waker(cx.waker());
+        SetStepModeFuture::poll(self_)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum State {
     Initial,
     ApplyingConfig,
     EnablingDriver,
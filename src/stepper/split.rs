@@ -0,0 +1,339 @@
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    sync::atomic::{AtomicBool, AtomicI32, Ordering},
+};
+
+use crate::traits::MotionControl;
+
+/// The state shared between a [`TickHandle`] and a [`ControlHandle`]
+///
+/// Created by the user and passed to [`Stepper::split`]. Both handles borrow
+/// it, so it must outlive them. With RTIC, it can be placed in a local
+/// resource of the `init` task, which provides a `&'static mut` reference.
+///
+/// Commands are passed from the control handle to the tick handle through a
+/// single slot, and the status is passed back using atomic variables. No
+/// locks or critical sections are required, and only atomic loads and stores
+/// are used, so this works on targets without compare-and-swap instructions.
+/// Aborting doesn't go through the command slot, so it's always possible, even
+/// while another command is pending.
+///
+/// [`Stepper::split`]: crate::Stepper::split
+pub struct Shared<Velocity> {
+    command: UnsafeCell<Option<Command<Velocity>>>,
+    command_pending: AtomicBool,
+    abort_pending: AtomicBool,
+    position: AtomicI32,
+    moving: AtomicBool,
+}
+
+impl<Velocity> Shared<Velocity> {
+    /// Create a new instance of `Shared`
+    pub const fn new() -> Self {
+        Self {
+            command: UnsafeCell::new(None),
+            command_pending: AtomicBool::new(false),
+            abort_pending: AtomicBool::new(false),
+            position: AtomicI32::new(0),
+            moving: AtomicBool::new(false),
+        }
+    }
+
+    fn send(
+        &self,
+        command: Command<Velocity>,
+    ) -> Result<(), CommandPendingError> {
+        if self.command_pending.load(Ordering::Acquire) {
+            return Err(CommandPendingError);
+        }
+
+        // SAFETY: There is only one `ControlHandle` per `Shared`, so we're the
+        // only writer. The `TickHandle` only accesses the command while it is
+        // pending, and we've just checked that it isn't.
+        unsafe { *self.command.get() = Some(command) };
+        self.command_pending.store(true, Ordering::Release);
+
+        Ok(())
+    }
+
+    fn receive(&self) -> Option<Command<Velocity>> {
+        if !self.command_pending.load(Ordering::Acquire) {
+            return None;
+        }
+
+        // SAFETY: There is only one `TickHandle` per `Shared`, so we're the
+        // only reader. The `ControlHandle` doesn't access the command while
+        // it is pending.
+        unsafe { (*self.command.get()).take() }
+    }
+
+    /// Mark the command taken by `receive` as applied
+    ///
+    /// This is separate from `receive`, so the command is still considered
+    /// pending, until its effects on the status are visible.
+    fn command_applied(&self) {
+        self.command_pending.store(false, Ordering::Release);
+    }
+}
+
+impl<Velocity> Default for Shared<Velocity> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SAFETY: Access to the command slot is synchronized through
+// `command_pending`, as documented in `send` and `receive`. The command is
+// moved between contexts, hence the `Send` bound.
+unsafe impl<Velocity: Send> Sync for Shared<Velocity> {}
+
+/// The part of a split [`Stepper`] that drives the motion
+///
+/// Created by [`Stepper::split`]. [`TickHandle::tick`] must be called
+/// regularly, typically from a timer interrupt.
+///
+/// [`Stepper`]: crate::Stepper
+/// [`Stepper::split`]: crate::Stepper::split
+pub struct TickHandle<'r, Driver: MotionControl> {
+    driver: Driver,
+    shared: &'r Shared<Driver::Velocity>,
+}
+
+impl<'r, Driver> TickHandle<'r, Driver>
+where
+    Driver: MotionControl,
+{
+    /// Apply pending commands and update the motion
+    ///
+    /// Applies the command that was last sent through the [`ControlHandle`],
+    /// if any, then calls [`MotionControl::update`] and publishes the current
+    /// position. Returns whether the motion is still ongoing, like
+    /// [`MotionControl::update`].
+    ///
+    /// If [`ControlHandle::abort`] has been called, the motion is aborted
+    /// instead, and a pending command is discarded.
+    ///
+    /// If this returns an error, the status seen by the [`ControlHandle`] is
+    /// not updated.
+    pub fn tick(&mut self) -> Result<bool, Driver::Error> {
+        let abort = self.shared.abort_pending.load(Ordering::Acquire);
+        let command = self.shared.receive();
+        let received = command.is_some();
+
+        let result = if abort {
+            self.driver.abort().and_then(|()| self.update())
+        } else {
+            self.apply_and_update(command)
+        };
+
+        if received {
+            self.shared.command_applied();
+        }
+        if abort {
+            self.shared.abort_pending.store(false, Ordering::Release);
+        }
+
+        result
+    }
+
+    fn apply_and_update(
+        &mut self,
+        command: Option<Command<Driver::Velocity>>,
+    ) -> Result<bool, Driver::Error> {
+        if let Some(command) = command {
+            match command {
+                Command::MoveTo {
+                    max_velocity,
+                    target_step,
+                } => self.driver.move_to_position(max_velocity, target_step)?,
+                Command::ResetPosition(step) => {
+                    self.driver.reset_position(step)?
+                }
+                Command::Stop => self.driver.stop()?,
+            }
+        }
+
+        self.update()
+    }
+
+    fn update(&mut self) -> Result<bool, Driver::Error> {
+        let moving = self.driver.update()?;
+        let position = self.driver.current_position()?;
+
+        self.shared.position.store(position, Ordering::Release);
+        self.shared.moving.store(moving, Ordering::Release);
+
+        Ok(moving)
+    }
+
+    /// Access a mutable reference to the driver
+    pub fn driver_mut(&mut self) -> &mut Driver {
+        &mut self.driver
+    }
+
+    /// Release the driver
+    ///
+    /// The driver can be wrapped into a new [`Stepper`] using
+    /// [`Stepper::from_driver`].
+    ///
+    /// [`Stepper`]: crate::Stepper
+    /// [`Stepper::from_driver`]: crate::Stepper::from_driver
+    pub fn release(self) -> Driver {
+        self.driver
+    }
+}
+
+/// The part of a split [`Stepper`] that commands the motion
+///
+/// Created by [`Stepper::split`]. Commands sent through this handle are
+/// applied by the next call to [`TickHandle::tick`]. Only one command can be
+/// pending at a time. Sending another one before that fails with
+/// [`CommandPendingError`]. The exception is [`ControlHandle::abort`], which
+/// always succeeds.
+///
+/// [`Stepper`]: crate::Stepper
+/// [`Stepper::split`]: crate::Stepper::split
+pub struct ControlHandle<'r, Velocity> {
+    shared: &'r Shared<Velocity>,
+}
+
+impl<'r, Velocity> ControlHandle<'r, Velocity> {
+    /// Move the motor to the given position
+    ///
+    /// See [`MotionControl::move_to_position`].
+    pub fn move_to_position(
+        &mut self,
+        max_velocity: Velocity,
+        target_step: i32,
+    ) -> Result<(), CommandPendingError> {
+        self.shared.send(Command::MoveTo {
+            max_velocity,
+            target_step,
+        })
+    }
+
+    /// Reset the position to the given value
+    ///
+    /// See [`MotionControl::reset_position`].
+    pub fn reset_position(
+        &mut self,
+        step: i32,
+    ) -> Result<(), CommandPendingError> {
+        self.shared.send(Command::ResetPosition(step))
+    }
+
+    /// Decelerate the motor to a standstill
+    ///
+    /// See [`MotionControl::stop`].
+    pub fn stop(&mut self) -> Result<(), CommandPendingError> {
+        self.shared.send(Command::Stop)
+    }
+
+    /// Stop the motor immediately
+    ///
+    /// Takes precedence over any pending command, which is discarded. See
+    /// [`MotionControl::abort`].
+    pub fn abort(&mut self) {
+        self.shared.abort_pending.store(true, Ordering::Release);
+    }
+
+    /// Returns the position as of the last call to [`TickHandle::tick`]
+    pub fn position(&self) -> i32 {
+        self.shared.position.load(Ordering::Acquire)
+    }
+
+    /// Indicates whether a motion is ongoing
+    ///
+    /// Also returns `true`, if a command has been sent, but not applied yet.
+    pub fn is_moving(&self) -> bool {
+        self.shared.command_pending.load(Ordering::Acquire)
+            || self.shared.abort_pending.load(Ordering::Acquire)
+            || self.shared.moving.load(Ordering::Acquire)
+    }
+}
+
+/// The previous command has not been applied yet
+///
+/// Returned by the methods of [`ControlHandle`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommandPendingError;
+
+enum Command<Velocity> {
+    MoveTo {
+        max_velocity: Velocity,
+        target_step: i32,
+    },
+    ResetPosition(i32),
+    Stop,
+}
+
+impl fmt::Display for CommandPendingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "previous command not applied yet")
+    }
+}
+
+pub(crate) fn split<'r, Driver>(
+    driver: Driver,
+    shared: &'r mut Shared<Driver::Velocity>,
+) -> (TickHandle<'r, Driver>, ControlHandle<'r, Driver::Velocity>)
+where
+    Driver: MotionControl,
+{
+    // Discard any state from previous use.
+    *shared = Shared::new();
+    let shared = &*shared;
+
+    (TickHandle { driver, shared }, ControlHandle { shared })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::MockMotionControl;
+
+    use super::{split, CommandPendingError, Shared};
+
+    #[test]
+    fn commands_should_be_applied_on_tick() {
+        let mut shared = Shared::new();
+        let (mut tick, mut control) =
+            split(MockMotionControl::new(), &mut shared);
+
+        control.move_to_position((), 2).unwrap();
+        assert_eq!(control.stop(), Err(CommandPendingError));
+        assert!(control.is_moving());
+
+        assert!(tick.tick().unwrap());
+        assert_eq!(control.position(), 1);
+        assert!(!tick.tick().unwrap());
+        assert_eq!(control.position(), 2);
+        assert!(!control.is_moving());
+
+        control.reset_position(-5).unwrap();
+        tick.tick().unwrap();
+        assert_eq!(control.position(), -5);
+    }
+
+    #[test]
+    fn abort_should_override_pending_command() {
+        let mut shared = Shared::new();
+        let (mut tick, mut control) =
+            split(MockMotionControl::new(), &mut shared);
+
+        control.move_to_position((), 2).unwrap();
+        assert!(tick.tick().unwrap());
+
+        control.move_to_position((), 5).unwrap();
+        control.abort();
+        assert!(control.is_moving());
+
+        assert!(!tick.tick().unwrap());
+        assert_eq!(control.position(), 1);
+        assert!(!control.is_moving());
+
+        // The command slot is free again.
+        control.move_to_position((), 3).unwrap();
+    }
+}
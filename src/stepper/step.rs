@@ -7,19 +7,23 @@ use fugit_timer::Timer as TimerTrait;
 
 use crate::traits::Step;
 
-use super::SignalError;
+use super::{SignalError, TrackPosition};
 
 /// The "future" returned by [`Stepper::step`]
 ///
-/// Please note that this type provides a custom API and does not implement
-/// [`core::future::Future`]. This might change, when using futures for embedded
-/// development becomes more practical.
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the timer to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// [`traits`]: crate::traits
 ///
 /// [`Stepper::step`]: crate::Stepper::step
 #[must_use]
-pub struct StepFuture<Driver, Timer, const TIMER_HZ: u32> {
+pub struct StepFuture<Driver, Timer, const TIMER_HZ: u32, Position = ()> {
     driver: Driver,
     timer: Timer,
+    position: Position,
     state: State,
 }
 
@@ -36,9 +40,30 @@ where
     ///
     /// [`Stepper::step`]: crate::Stepper::step
     pub fn new(driver: Driver, timer: Timer) -> Self {
+        Self::with_position(driver, timer, ())
+    }
+}
+
+impl<Driver, Timer, Position, const TIMER_HZ: u32>
+    StepFuture<Driver, Timer, TIMER_HZ, Position>
+where
+    Driver: Step,
+    Timer: TimerTrait<TIMER_HZ>,
+    Position: TrackPosition,
+{
+    /// Create new instance of `StepFuture` that tracks the position
+    ///
+    /// Like [`StepFuture::new`], but the step is reported to `position`, once
+    /// it has been made.
+    pub fn with_position(
+        driver: Driver,
+        timer: Timer,
+        position: Position,
+    ) -> Self {
         Self {
             driver,
             timer,
+            position,
             state: State::Initial,
         }
     }
@@ -54,6 +79,7 @@ where
     /// calling it at a high frequency (see [`Self::wait`]) until the operation
     /// completes, or set up an interrupt that fires once the timer finishes
     /// counting down, and call this method again once it does.
+    #[allow(clippy::type_complexity)]
     pub fn poll(
         &mut self,
     ) -> Poll<
@@ -71,16 +97,14 @@ where
                 // Start step pulse
                 self.driver
                     .step()
-                    .map_err(|err| SignalError::PinUnavailable(err))?
+                    .map_err(SignalError::PinUnavailable)?
                     .set_high()
-                    .map_err(|err| SignalError::Pin(err))?;
+                    .map_err(SignalError::Pin)?;
 
                 let ticks: TimerDuration<TIMER_HZ> =
                     Driver::PULSE_LENGTH.convert();
 
-                self.timer
-                    .start(ticks)
-                    .map_err(|err| SignalError::Timer(err))?;
+                self.timer.start(ticks).map_err(SignalError::Timer)?;
 
                 self.state = State::PulseStarted;
                 Poll::Pending
@@ -91,9 +115,11 @@ where
                         // End step pulse
                         self.driver
                             .step()
-                            .map_err(|err| SignalError::PinUnavailable(err))?
+                            .map_err(SignalError::PinUnavailable)?
                             .set_low()
-                            .map_err(|err| SignalError::Pin(err))?;
+                            .map_err(SignalError::Pin)?;
+
+                        self.position.step_made();
 
                         self.state = State::Finished;
                         Poll::Ready(Ok(()))
@@ -113,6 +139,7 @@ where
     ///
     /// This method will call [`Self::poll`] in a busy loop until the operation
     /// has finished.
+    #[allow(clippy::type_complexity)]
     pub fn wait(
         &mut self,
     ) -> Result<
@@ -130,13 +157,108 @@ where
         }
     }
 
+    /// Cancel the operation
+    ///
+    /// If a step pulse is ongoing, it is ended early, leaving the STEP signal
+    /// low. The driver has already seen the start of the pulse at that point,
+    /// so the step is reported to the position tracking, as if it had
+    /// finished normally. Afterwards, the future is finished, and polling it
+    /// returns [`Poll::Ready`].
+    ///
+    /// Dropping the future while a pulse is ongoing leaves the STEP signal
+    /// high, until the next step is made. Call this method (or
+    /// [`Self::release`], which calls it) before dropping the future, if that
+    /// is a problem.
+    #[allow(clippy::type_complexity)]
+    pub fn cancel(
+        &mut self,
+    ) -> Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    > {
+        if let State::PulseStarted = self.state {
+            self.state = State::Finished;
+
+            // The timer has either expired already, or we don't need it to
+            // anymore. Either way, there's nothing to do about an error here.
+            let _ = self.timer.cancel();
+
+            self.driver
+                .step()
+                .map_err(SignalError::PinUnavailable)?
+                .set_low()
+                .map_err(SignalError::Pin)?;
+
+            self.position.step_made();
+        }
+
+        Ok(())
+    }
+
+    /// Continue from a state saved using [`Self::state`]
+    ///
+    /// Used by the non-blocking methods of `Stepper`, which can't keep the
+    /// future around between calls.
+    pub(crate) fn with_state(mut self, state: State) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub(crate) fn state(&self) -> State {
+        self.state
+    }
+
     /// Drop the future and release the resources that were moved into it
-    pub fn release(self) -> (Driver, Timer) {
+    ///
+    /// If a step pulse is ongoing, it is ended early. See [`Self::cancel`].
+    pub fn release(mut self) -> (Driver, Timer) {
+        let _ = self.cancel();
         (self.driver, self.timer)
     }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Driver, Timer, Position, const TIMER_HZ: u32> core::future::Future
+    for StepFuture<Driver, Timer, TIMER_HZ, Position>
+where
+    Driver: Step,
+    Timer: TimerTrait<TIMER_HZ> + crate::traits::RegisterWaker,
+    Position: TrackPosition,
+    Self: Unpin,
+{
+    type Output = Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    >;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the timer might expire between
+        // polling and registering, and we'd miss the wake-up.
+        self_.timer.register_waker(cx.waker());
+        StepFuture::poll(self_)
+    }
 }
 
-enum State {
+#[derive(Clone, Copy)]
+pub(crate) enum State {
     Initial,
     PulseStarted,
     Finished,
@@ -0,0 +1,339 @@
+use core::task::Poll;
+
+use embedded_hal::digital::ErrorType;
+use embedded_hal::digital::OutputPin;
+use fugit::TimerDurationU32 as TimerDuration;
+use fugit_timer::Timer as TimerTrait;
+
+use crate::traits::Step;
+
+use super::{SignalError, TrackPosition};
+
+/// The "future" returned by [`Stepper::step_n`]
+///
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the timer to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// [`traits`]: crate::traits
+///
+/// [`Stepper::step_n`]: crate::Stepper::step_n
+#[must_use]
+pub struct StepNFuture<Driver, Timer, const TIMER_HZ: u32, Position = ()> {
+    driver: Driver,
+    timer: Timer,
+    position: Position,
+    remaining: u32,
+    interval: TimerDuration<TIMER_HZ>,
+    state: State,
+}
+
+impl<Driver, Timer, const TIMER_HZ: u32> StepNFuture<Driver, Timer, TIMER_HZ>
+where
+    Driver: Step,
+    Timer: TimerTrait<TIMER_HZ>,
+{
+    /// Create new instance of `StepNFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::step_n`] instead.
+    ///
+    /// [`Stepper::step_n`]: crate::Stepper::step_n
+    pub fn new(
+        driver: Driver,
+        timer: Timer,
+        count: u32,
+        interval: TimerDuration<TIMER_HZ>,
+    ) -> Self {
+        Self::with_position(driver, timer, count, interval, ())
+    }
+}
+
+impl<Driver, Timer, Position, const TIMER_HZ: u32>
+    StepNFuture<Driver, Timer, TIMER_HZ, Position>
+where
+    Driver: Step,
+    Timer: TimerTrait<TIMER_HZ>,
+    Position: TrackPosition,
+{
+    /// Create new instance of `StepNFuture` that tracks the position
+    ///
+    /// Like [`StepNFuture::new`], but every step is reported to `position`,
+    /// once it has been made.
+    pub fn with_position(
+        driver: Driver,
+        timer: Timer,
+        count: u32,
+        interval: TimerDuration<TIMER_HZ>,
+        position: Position,
+    ) -> Self {
+        Self {
+            driver,
+            timer,
+            position,
+            remaining: count,
+            interval,
+            state: State::Initial,
+        }
+    }
+
+    /// Access the number of steps that are still to be made
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the timer finishes
+    /// counting down, and call this method again once it does.
+    #[allow(clippy::type_complexity)]
+    pub fn poll(
+        &mut self,
+    ) -> Poll<
+        Result<
+            (),
+            SignalError<
+                Driver::Error,
+                <Driver::Step as ErrorType>::Error,
+                Timer::Error,
+            >,
+        >,
+    > {
+        match self.poll_inner() {
+            Ok(Poll::Pending) => Poll::Pending,
+            Ok(Poll::Ready(())) => {
+                self.state = State::Finished;
+                Poll::Ready(Ok(()))
+            }
+            Err(err) => {
+                self.state = State::Finished;
+                Poll::Ready(Err(err))
+            }
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn poll_inner(
+        &mut self,
+    ) -> Result<
+        Poll<()>,
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    > {
+        match self.state {
+            State::Initial => {
+                if self.remaining == 0 {
+                    return Ok(Poll::Ready(()));
+                }
+
+                self.start_pulse()?;
+                Ok(Poll::Pending)
+            }
+            State::PulseStarted => {
+                match self.timer.wait() {
+                    Ok(()) => {}
+                    Err(nb::Error::Other(err)) => {
+                        return Err(SignalError::Timer(err))
+                    }
+                    Err(nb::Error::WouldBlock) => return Ok(Poll::Pending),
+                }
+
+                // End step pulse
+                self.driver
+                    .step()
+                    .map_err(SignalError::PinUnavailable)?
+                    .set_low()
+                    .map_err(SignalError::Pin)?;
+
+                self.position.step_made();
+                self.remaining -= 1;
+
+                if self.remaining == 0 {
+                    return Ok(Poll::Ready(()));
+                }
+
+                // The interval is measured from the start of one pulse to the
+                // start of the next, so the pulse itself counts towards it.
+                let pulse: TimerDuration<TIMER_HZ> =
+                    Driver::PULSE_LENGTH.convert();
+                let delay = TimerDuration::<TIMER_HZ>::from_ticks(
+                    self.interval.ticks().saturating_sub(pulse.ticks()),
+                );
+
+                if delay.ticks() == 0 {
+                    self.start_pulse()?;
+                } else {
+                    self.timer.start(delay).map_err(SignalError::Timer)?;
+                    self.state = State::Waiting;
+                }
+
+                Ok(Poll::Pending)
+            }
+            State::Waiting => match self.timer.wait() {
+                Ok(()) => {
+                    self.start_pulse()?;
+                    Ok(Poll::Pending)
+                }
+                Err(nb::Error::Other(err)) => Err(SignalError::Timer(err)),
+                Err(nb::Error::WouldBlock) => Ok(Poll::Pending),
+            },
+            State::Finished => Ok(Poll::Ready(())),
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    #[allow(clippy::type_complexity)]
+    pub fn wait(
+        &mut self,
+    ) -> Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    > {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Cancel the operation
+    ///
+    /// No more steps are made. If a step pulse is ongoing, it is ended early,
+    /// leaving the STEP signal low. The driver has already seen the start of
+    /// the pulse at that point, so the step is reported to the position
+    /// tracking, as if it had finished normally. Afterwards, the future is
+    /// finished, and polling it returns [`Poll::Ready`].
+    ///
+    /// Dropping the future while a pulse is ongoing leaves the STEP signal
+    /// high, until the next step is made. Call this method (or
+    /// [`Self::release`], which calls it) before dropping the future, if that
+    /// is a problem.
+    #[allow(clippy::type_complexity)]
+    pub fn cancel(
+        &mut self,
+    ) -> Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    > {
+        match self.state {
+            State::PulseStarted => {
+                self.state = State::Finished;
+
+                // The timer has either expired already, or we don't need it
+                // anymore. Either way, there's nothing to do about an error.
+                let _ = self.timer.cancel();
+
+                self.driver
+                    .step()
+                    .map_err(SignalError::PinUnavailable)?
+                    .set_low()
+                    .map_err(SignalError::Pin)?;
+
+                self.position.step_made();
+                self.remaining -= 1;
+            }
+            State::Waiting => {
+                self.state = State::Finished;
+                let _ = self.timer.cancel();
+            }
+            State::Initial | State::Finished => {
+                self.state = State::Finished;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    ///
+    /// If a step pulse is ongoing, it is ended early. See [`Self::cancel`].
+    pub fn release(mut self) -> (Driver, Timer) {
+        let _ = self.cancel();
+        (self.driver, self.timer)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn start_pulse(
+        &mut self,
+    ) -> Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    > {
+        self.driver
+            .step()
+            .map_err(SignalError::PinUnavailable)?
+            .set_high()
+            .map_err(SignalError::Pin)?;
+
+        let ticks: TimerDuration<TIMER_HZ> = Driver::PULSE_LENGTH.convert();
+        self.timer.start(ticks).map_err(SignalError::Timer)?;
+
+        self.state = State::PulseStarted;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Driver, Timer, Position, const TIMER_HZ: u32> core::future::Future
+    for StepNFuture<Driver, Timer, TIMER_HZ, Position>
+where
+    Driver: Step,
+    Timer: TimerTrait<TIMER_HZ> + crate::traits::RegisterWaker,
+    Position: TrackPosition,
+    Self: Unpin,
+{
+    type Output = Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Timer::Error,
+        >,
+    >;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the timer might expire between
+        // polling and registering, and we'd miss the wake-up.
+        self_.timer.register_waker(cx.waker());
+        StepNFuture::poll(self_)
+    }
+}
+
+enum State {
+    Initial,
+    PulseStarted,
+    Waiting,
+    Finished,
+}
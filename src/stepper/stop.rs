@@ -0,0 +1,131 @@
+use core::task::Poll;
+
+use crate::traits::MotionControl;
+
+/// The "future" returned by [`Stepper::stop`]
+///
+/// This type provides a custom API that doesn't require an async runtime. If
+/// the `async` feature is enabled, it also implements [`core::future::Future`].
+/// This requires the driver to implement `RegisterWaker` from the [`traits`]
+/// module.
+///
+/// [`traits`]: crate::traits
+///
+/// [`Stepper::stop`]: crate::Stepper::stop
+#[must_use]
+pub struct StopFuture<Driver> {
+    driver: Driver,
+    jog: bool,
+    state: State,
+}
+
+impl<Driver> StopFuture<Driver>
+where
+    Driver: MotionControl,
+{
+    /// Create new instance of `StopFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::stop`] instead.
+    ///
+    /// [`Stepper::stop`]: crate::Stepper::stop
+    pub fn new(driver: Driver) -> Self {
+        Self {
+            driver,
+            jog: false,
+            state: State::Initial,
+        }
+    }
+
+    /// Create new instance of `StopFuture` that stops a jog
+    ///
+    /// Like [`StopFuture::new`], but uses [`MotionControl::stop_jog`] instead
+    /// of [`MotionControl::stop`].
+    pub fn for_jog(driver: Driver) -> Self {
+        Self {
+            driver,
+            jog: true,
+            state: State::Initial,
+        }
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the timer finishes
+    /// counting down, and call this method again once it does.
+    pub fn poll(&mut self) -> Poll<Result<(), Driver::Error>> {
+        match self.state {
+            State::Initial => {
+                if self.jog {
+                    self.driver.stop_jog()?;
+                } else {
+                    self.driver.stop()?;
+                }
+                self.state = State::Stopping;
+                Poll::Pending
+            }
+            State::Stopping => {
+                let still_moving = self.driver.update()?;
+                if still_moving {
+                    Poll::Pending
+                } else {
+                    self.state = State::Finished;
+                    Poll::Ready(Ok(()))
+                }
+            }
+            State::Finished => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    pub fn wait(&mut self) -> Result<(), Driver::Error> {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    pub fn release(self) -> Driver {
+        self.driver
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Driver> core::future::Future for StopFuture<Driver>
+where
+    Driver: MotionControl + crate::traits::RegisterWaker,
+    Self: Unpin,
+{
+    type Output = Result<(), Driver::Error>;
+
+    fn poll(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context,
+    ) -> Poll<Self::Output> {
+        let self_ = self.get_mut();
+
+        // Register the waker first. Otherwise the driver might need attention
+        // between polling and registering, and we'd miss the wake-up.
+        self_.driver.register_waker(cx.waker());
+        StopFuture::poll(self_)
+    }
+}
+
+enum State {
+    Initial,
+    Stopping,
+    Finished,
+}
@@ -21,9 +21,11 @@
 //! [`Stepper`]: crate::Stepper
 
 use embedded_hal::digital::OutputPin;
-use fugit::NanosDurationU32 as Nanoseconds;
+use fugit::{
+    NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+};
 
-use crate::step_mode::StepMode;
+use crate::{step_mode::StepMode, Direction, PowerMode};
 
 /// Enable microstepping mode control for a driver
 ///
@@ -40,6 +42,25 @@ pub trait EnableStepModeControl<Resources> {
     ) -> Self::WithStepModeControl;
 }
 
+/// Disable microstepping mode control for a driver
+///
+/// This is the reverse of [`EnableStepModeControl`]. It returns the hardware
+/// resources that were required for controlling the microstepping mode, so
+/// they can be used for something else.
+pub trait DisableStepModeControl {
+    /// The type of the driver after microstepping mode control has been
+    /// disabled
+    type WithoutStepModeControl;
+
+    /// The hardware resources that are returned
+    type Resources;
+
+    /// Disable microstepping mode control
+    fn disable_step_mode_control(
+        self,
+    ) -> (Self::WithoutStepModeControl, Self::Resources);
+}
+
 /// Implemented by drivers that support controlling the microstepping mode
 pub trait SetStepMode {
     /// The time the mode signals need to be held before re-enabling the driver
@@ -69,6 +90,18 @@ pub trait SetStepMode {
     fn enable_driver(&mut self) -> Result<(), Self::Error>;
 }
 
+/// Implemented by drivers that keep track of their microstepping mode
+pub trait GetStepMode {
+    /// The type that defines the microstepping mode
+    type StepMode: StepMode;
+
+    /// Returns the current microstepping mode
+    ///
+    /// Returns `None`, if the step mode is not known, for example because it
+    /// hasn't been set since the driver was created.
+    fn step_mode(&self) -> Option<Self::StepMode>;
+}
+
 /// Enable direction control for a driver
 ///
 /// The `Resources` type parameter defines the hardware resources required for
@@ -84,6 +117,24 @@ pub trait EnableDirectionControl<Resources> {
     ) -> Self::WithDirectionControl;
 }
 
+/// Disable direction control for a driver
+///
+/// This is the reverse of [`EnableDirectionControl`]. It returns the hardware
+/// resources that were required for direction control, so they can be used
+/// for something else.
+pub trait DisableDirectionControl {
+    /// The type of the driver after direction control has been disabled
+    type WithoutDirectionControl;
+
+    /// The hardware resources that are returned
+    type Resources;
+
+    /// Disable direction control
+    fn disable_direction_control(
+        self,
+    ) -> (Self::WithoutDirectionControl, Self::Resources);
+}
+
 /// Implemented by drivers that support controlling the DIR signal
 pub trait SetDirection {
     /// The time that the DIR signal must be held for a change to apply
@@ -111,6 +162,24 @@ pub trait EnableStepControl<Resources> {
     fn enable_step_control(self, res: Resources) -> Self::WithStepControl;
 }
 
+/// Disable step control for a driver
+///
+/// This is the reverse of [`EnableStepControl`]. It returns the hardware
+/// resources that were required for step control, so they can be used for
+/// something else.
+pub trait DisableStepControl {
+    /// The type of the driver after step control has been disabled
+    type WithoutStepControl;
+
+    /// The hardware resources that are returned
+    type Resources;
+
+    /// Disable step control
+    fn disable_step_control(
+        self,
+    ) -> (Self::WithoutStepControl, Self::Resources);
+}
+
 /// Implemented by drivers that support controlling the STEP signal
 pub trait Step {
     /// The minimum length of a STEP pulse
@@ -126,6 +195,191 @@ pub trait Step {
     fn step(&mut self) -> Result<&mut Self::Step, Self::Error>;
 }
 
+/// Implemented by drivers that can generate a train of STEP pulses in hardware
+///
+/// This is typically implemented on top of a HAL, using a timer that generates
+/// the pulses, with DMA or a second, chained timer loading the period of each
+/// pulse. This way, the CPU isn't involved in every single step, which allows
+/// for much higher step rates than [`Step`].
+///
+/// Used by [`PulseTrainMotionControl`].
+///
+/// [`PulseTrainMotionControl`]: crate::motion_control::PulseTrainMotionControl
+pub trait GeneratePulseTrain<const TIMER_HZ: u32> {
+    /// The error that can occur while generating pulses
+    type Error;
+
+    /// Add pulses to the end of the queue
+    ///
+    /// Every delay in `delays` defines one pulse. The delay is the time between
+    /// the start of the previous pulse (or, if no pulse has been generated
+    /// since the queue ran empty, the moment the pulse is queued) and the start
+    /// of this pulse. Generation starts right away, if the queue was empty.
+    ///
+    /// The queue might not be able to hold all delays. Returns the number of
+    /// delays that have been added, starting from the beginning of `delays`.
+    fn queue_pulses(
+        &mut self,
+        delays: &[TimerDuration<TIMER_HZ>],
+    ) -> Result<usize, Self::Error>;
+
+    /// Returns the number of queued pulses that have not been generated yet
+    ///
+    /// A pulse counts as generated, once it has started.
+    fn pending_pulses(&mut self) -> Result<u32, Self::Error>;
+
+    /// Remove all pulses from the queue that have not been generated yet
+    ///
+    /// Returns the number of pulses that have been removed.
+    fn clear_pulses(&mut self) -> Result<u32, Self::Error>;
+}
+
+/// Enable output control for a driver
+///
+/// The `Resources` type parameter defines the hardware resources required for
+/// enabling and disabling the motor outputs.
+pub trait EnableOutputControl<Resources> {
+    /// The type of the driver after output control has been enabled
+    type WithOutputControl: SetOutputEnabled;
+
+    /// Enable output control
+    fn enable_output_control(self, res: Resources) -> Self::WithOutputControl;
+}
+
+/// Implemented by drivers that support enabling and disabling the motor outputs
+pub trait SetOutputEnabled {
+    /// The error that can occur while using this trait
+    type Error;
+
+    /// Enable or disable the motor outputs
+    ///
+    /// While the outputs are disabled, the motor is de-energized. It doesn't
+    /// draw any current and can be moved by hand.
+    ///
+    /// Implementations are responsible for applying the polarity of the
+    /// driver's enable input, which differs between drivers.
+    fn set_output_enabled(&mut self, enabled: bool) -> Result<(), Self::Error>;
+}
+
+/// Enable sleep control for a driver
+///
+/// The `Resources` type parameter defines the hardware resources required for
+/// putting the driver to sleep, typically the output pin connected to its
+/// SLEEP or STANDBY input.
+pub trait EnableSleepControl<Resources> {
+    /// The type of the driver after sleep control has been enabled
+    type WithSleepControl: SetPowerMode;
+
+    /// Enable sleep control
+    fn enable_sleep_control(self, res: Resources) -> Self::WithSleepControl;
+}
+
+/// Implemented by drivers that support a low-power sleep mode
+pub trait SetPowerMode {
+    /// The time the driver needs to wake up from sleep mode
+    ///
+    /// No STEP pulses must be sent during that time.
+    const WAKE_UP_TIME: Nanoseconds;
+
+    /// The error that can occur while using this trait
+    type Error;
+
+    /// Put the driver into the given power mode
+    ///
+    /// Implementations must not wait for the driver to wake up. That is the
+    /// responsibility of the caller, using [`SetPowerMode::WAKE_UP_TIME`].
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error>;
+}
+
+/// Enable fault detection for a driver
+///
+/// The `Resources` type parameter defines the hardware resources required for
+/// detecting faults, typically the input pin connected to the driver's fault
+/// output.
+pub trait EnableFaultDetection<Resources> {
+    /// The type of the driver after fault detection has been enabled
+    type WithFaultDetection: ReadFault;
+
+    /// Enable fault detection
+    fn enable_fault_detection(self, res: Resources)
+        -> Self::WithFaultDetection;
+}
+
+/// Implemented by drivers that can report faults
+///
+/// Faults are conditions like overcurrent or overtemperature, that cause the
+/// driver to disable its outputs. Any steps made while the driver is in that
+/// state are lost.
+pub trait ReadFault {
+    /// The type that describes a fault
+    ///
+    /// Drivers that can only signal that a fault occurred, without providing
+    /// any details, use [`UnspecifiedFault`].
+    ///
+    /// [`UnspecifiedFault`]: crate::drivers::UnspecifiedFault
+    type Fault;
+
+    /// The error that can occur while using this trait
+    type Error;
+
+    /// Check whether the driver is signaling a fault
+    ///
+    /// Returns `None`, if no fault is present.
+    fn read_fault(&mut self) -> Result<Option<Self::Fault>, Self::Error>;
+}
+
+/// Implemented by drivers that can detect a stalled motor
+///
+/// Some drivers measure the load on the motor (like StallGuard on Trinamic
+/// drivers), and signal when the motor has stalled, usually through a
+/// dedicated output like DIAG. Any steps made while the motor is stalled are
+/// lost.
+///
+/// [`SoftwareMotionControl::enable_stall_detection`] uses this to abort
+/// motions as soon as the motor stalls.
+///
+/// [`SoftwareMotionControl::enable_stall_detection`]: crate::motion_control::SoftwareMotionControl::enable_stall_detection
+pub trait DetectStall {
+    /// The error that can occur while using this trait
+    type Error;
+
+    /// Check whether the driver is signaling a stall
+    fn is_stalled(&mut self) -> Result<bool, Self::Error>;
+}
+
+/// Enable current control for a driver
+///
+/// The `Resources` type parameter defines the hardware resources required for
+/// controlling the motor current.
+pub trait EnableCurrentControl<Resources> {
+    /// The type of the driver after current control has been enabled
+    type WithCurrentControl: SetCurrent;
+
+    /// Enable current control
+    fn enable_current_control(self, res: Resources)
+        -> Self::WithCurrentControl;
+}
+
+/// Implemented by drivers that support controlling the motor current
+pub trait SetCurrent {
+    /// The type that defines the motor current
+    ///
+    /// How the current can be configured differs a lot between drivers, so
+    /// each driver defines its own type.
+    type Current;
+
+    /// The error that can occur while using this trait
+    type Error;
+
+    /// Set the motor current
+    ///
+    /// The new current is expected to take effect right away.
+    fn set_current(
+        &mut self,
+        current: Self::Current,
+    ) -> Result<(), Self::Error>;
+}
+
 /// Enable motion control for a driver
 ///
 /// The `Resources` type parameter defines the hardware resources required for
@@ -143,6 +397,9 @@ pub trait EnableMotionControl<Resources, const TIMER_HZ: u32> {
 /// A software-based fallback implementation exists in the [`motion_control`]
 /// module, for drivers that implement [SetDirection] and [Step].
 ///
+/// Reading the current velocity is not part of this trait, as not every
+/// implementation can support it. See [`ReadVelocity`].
+///
 /// [`motion_control`]: crate::motion_control
 pub trait MotionControl {
     /// The type used by the driver to represent velocity
@@ -162,6 +419,51 @@ pub trait MotionControl {
         target_step: i32,
     ) -> Result<(), Self::Error>;
 
+    /// Change the target of an ongoing motion
+    ///
+    /// Unlike starting a new motion using [`MotionControl::move_to_position`],
+    /// this must continue from the current velocity. If the new target lies
+    /// behind the motor, or is too close to stop in time, the motor must
+    /// decelerate smoothly before turning around.
+    ///
+    /// If no motion is ongoing, this must behave like
+    /// [`MotionControl::move_to_position`], which is what the default
+    /// implementation does. This is correct for motion controllers whose
+    /// ramp generator re-plans from the current velocity by itself.
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.move_to_position(max_velocity, target_step)
+    }
+
+    /// Move to the given position, in lockstep with a motion of `num_steps`
+    ///
+    /// Like [`MotionControl::move_to_position`], but the motion is planned as
+    /// if it had `num_steps` steps, with `max_velocity` applying to those. Only
+    /// the steps required to reach `target_step` are made, spread evenly over
+    /// the whole motion. Motors whose motions are started like this with the
+    /// same `max_velocity` and `num_steps`, and which accelerate the same way,
+    /// stay in proportion to each other throughout. This is used for
+    /// coordinated motion (see [`MultiStepper`]).
+    ///
+    /// If `num_steps` is not larger than the distance to the target, this must
+    /// behave like [`MotionControl::move_to_position`].
+    ///
+    /// Returns `false` without starting a motion, if this is not supported.
+    /// That is what the default implementation does.
+    ///
+    /// [`MultiStepper`]: crate::coordinated::MultiStepper
+    fn move_to_position_synced(
+        &mut self,
+        _max_velocity: Self::Velocity,
+        _target_step: i32,
+        _num_steps: u32,
+    ) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
     /// Reset internal position to the given value
     ///
     /// This method must not start a motion. Its only purpose is to change the
@@ -177,4 +479,124 @@ pub trait MotionControl {
     /// returned, the caller may assume that this method doesn't need to be
     /// called again, until starting another motion.
     fn update(&mut self) -> Result<bool, Self::Error>;
+
+    /// Returns the current position of the motor
+    ///
+    /// This is the driver's internal position, as changed by motions and by
+    /// [`MotionControl::reset_position`]. During a motion, it reflects the
+    /// steps that have been made so far.
+    fn current_position(&mut self) -> Result<i32, Self::Error>;
+
+    /// Decelerate the motor to a standstill
+    ///
+    /// This method must arrange for the motor to slow down and stop, using
+    /// the deceleration that would be used at the end of a regular motion,
+    /// instead of finishing the ongoing motion. Like
+    /// [`MotionControl::move_to_position`], it must not block until the motor
+    /// has stopped. [`MotionControl::update`] returns `false`, once it has.
+    ///
+    /// Does nothing, if no motion is ongoing.
+    fn stop(&mut self) -> Result<(), Self::Error>;
+
+    /// Start moving continuously in the given direction
+    ///
+    /// The motor accelerates to the given velocity and keeps moving, until
+    /// [`MotionControl::stop_jog`] (or [`MotionControl::stop`]) is called. If
+    /// the motor is already moving, it changes to the new velocity and
+    /// direction smoothly. This is intended for UI buttons and setup
+    /// procedures, where no target position exists.
+    ///
+    /// The default implementation uses [`MotionControl::retarget`] to move to
+    /// the most distant position in the given direction. Drivers whose
+    /// positions wrap around before that need to override it.
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: Self::Velocity,
+    ) -> Result<(), Self::Error> {
+        let target_step = match direction {
+            Direction::Forward => i32::MAX,
+            Direction::Backward => i32::MIN,
+        };
+
+        self.retarget(velocity, target_step)
+    }
+
+    /// Decelerate the motor to a standstill, after it has been jogging
+    ///
+    /// See [`MotionControl::start_jog`]. The default implementation calls
+    /// [`MotionControl::stop`].
+    fn stop_jog(&mut self) -> Result<(), Self::Error> {
+        self.stop()
+    }
+
+    /// Stop the motor immediately
+    ///
+    /// This method must stop generating steps right away, without
+    /// decelerating, and discard whatever remains of the ongoing motion. It is
+    /// meant for emergencies, like from a fault or panic handler, where
+    /// stopping the motor is more important than not losing steps.
+    ///
+    /// The driver's internal position must still reflect the steps that have
+    /// actually been made. After this method returns, [`MotionControl::update`]
+    /// must eventually return `false`, and a new motion can be started.
+    fn abort(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Implemented by motion controllers that can report the current velocity
+///
+/// This is a separate trait, as [`SoftwareMotionControl`] can only derive the
+/// velocity from the motion profile, if its number types support that.
+///
+/// [`SoftwareMotionControl`]: crate::motion_control::SoftwareMotionControl
+pub trait ReadVelocity: MotionControl {
+    /// Returns the current velocity of the motor
+    ///
+    /// The velocity is returned as a magnitude, in the same unit as the
+    /// maximum velocity passed to [`MotionControl::move_to_position`]. It is
+    /// zero, if the motor is not moving.
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error>;
+}
+
+/// Implemented by sensors that measure the position of the motor
+///
+/// This can be a quadrature encoder, which counts relative to where it started,
+/// or an absolute encoder. Either way, implementations must convert their
+/// readings into steps, in the same unit as [`MotionControl::current_position`].
+/// The offset between both doesn't matter.
+///
+/// Used by [`ClosedLoop`] to correct for lost steps.
+///
+/// [`ClosedLoop`]: crate::motion_control::ClosedLoop
+pub trait PositionFeedback {
+    /// The error that can happen while reading the position
+    type Error;
+
+    /// Read the measured position, in steps
+    fn read_position(&mut self) -> Result<i32, Self::Error>;
+}
+
+/// Implemented by types that can wake an async task, once it needs attention
+///
+/// Required by the [`core::future::Future`] implementations of the future types
+/// in this crate. Those are only available, if the `async` feature is enabled.
+///
+/// This trait is typically implemented by timers, which wake the registered
+/// waker from their interrupt handler, once they have expired. It is also
+/// implemented by drivers that implement [`MotionControl`], which need to wake
+/// the task whenever [`MotionControl::update`] needs to be called.
+///
+/// If a timer has no support for interrupts, it can be wrapped in
+/// [`WakeImmediately`], which wakes the task right away, causing the executor to
+/// keep polling.
+///
+/// [`WakeImmediately`]: crate::util::waker::WakeImmediately
+#[cfg(feature = "async")]
+pub trait RegisterWaker {
+    /// Register the waker of the current task
+    ///
+    /// The waker must be woken, once there's something to do. For a timer, this
+    /// is the case when it has expired. Only the waker that was registered
+    /// most recently needs to be woken.
+    fn register_waker(&mut self, waker: &core::task::Waker);
 }
@@ -0,0 +1,221 @@
+//! Conversion between steps and physical units
+//!
+//! The motion control API works with positions in (micro)steps. The types in
+//! this module describe the mechanics of an axis, to convert between steps and
+//! millimetres (for a [`Linear`] axis) or degrees (for a [`Rotary`] axis).
+//!
+//! Those types can be used on their own, or passed to methods like
+//! [`Stepper::move_to_mm`] and [`Stepper::move_to_degrees`].
+//!
+//! The functions in this module convert velocities and accelerations into the
+//! units expected by [`SoftwareMotionControl`], if the motion profile uses
+//! timer ticks as its unit of time. This is the setup recommended in the
+//! crate-level example, where `DelayToTicks` doesn't need to convert anything.
+//!
+//! [`Stepper::move_to_mm`]: crate::Stepper::move_to_mm
+//! [`Stepper::move_to_degrees`]: crate::Stepper::move_to_degrees
+//! [`SoftwareMotionControl`]: crate::motion_control::SoftwareMotionControl
+
+use num_traits::FromPrimitive;
+
+/// A linear axis, for example driven by a leadscrew or a belt
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Linear {
+    steps_per_mm: f32,
+}
+
+impl Linear {
+    /// Create a new instance of `Linear`
+    ///
+    /// - `full_steps_per_revolution` is the number of full steps per revolution
+    ///   of the motor. This is `200` for the typical 1.8° motor.
+    /// - `microsteps` is the number of microsteps per full step, as configured
+    ///   using the step mode.
+    /// - `gear_ratio` is the number of motor revolutions per revolution of the
+    ///   leadscrew or pulley. Pass `1.0`, if the motor drives it directly.
+    /// - `lead` is the distance the axis travels per revolution of the
+    ///   leadscrew or pulley, in millimetres.
+    pub fn new(
+        full_steps_per_revolution: u16,
+        microsteps: u16,
+        gear_ratio: f32,
+        lead: f32,
+    ) -> Self {
+        let steps_per_revolution = f32::from(full_steps_per_revolution)
+            * f32::from(microsteps)
+            * gear_ratio;
+
+        Self::from_steps_per_mm(steps_per_revolution / lead)
+    }
+
+    /// Create a new instance of `Linear` from a known number of steps per mm
+    pub fn from_steps_per_mm(steps_per_mm: f32) -> Self {
+        Self { steps_per_mm }
+    }
+
+    /// Access the number of steps per millimetre
+    pub fn steps_per_mm(&self) -> f32 {
+        self.steps_per_mm
+    }
+
+    /// Convert a distance or position in millimetres to steps
+    ///
+    /// The result is rounded to the nearest step. It saturates, if it is not
+    /// representable as an `i32`.
+    pub fn to_steps(&self, mm: f32) -> i32 {
+        round(mm * self.steps_per_mm)
+    }
+
+    /// Convert a number of steps to millimetres
+    pub fn to_mm(&self, steps: i32) -> f32 {
+        steps as f32 / self.steps_per_mm
+    }
+}
+
+/// A rotary axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotary {
+    steps_per_degree: f32,
+}
+
+impl Rotary {
+    /// Create a new instance of `Rotary`
+    ///
+    /// - `full_steps_per_revolution` is the number of full steps per revolution
+    ///   of the motor. This is `200` for the typical 1.8° motor.
+    /// - `microsteps` is the number of microsteps per full step, as configured
+    ///   using the step mode.
+    /// - `gear_ratio` is the number of motor revolutions per revolution of the
+    ///   axis. Pass `1.0`, if the motor drives the axis directly.
+    pub fn new(
+        full_steps_per_revolution: u16,
+        microsteps: u16,
+        gear_ratio: f32,
+    ) -> Self {
+        let steps_per_revolution = f32::from(full_steps_per_revolution)
+            * f32::from(microsteps)
+            * gear_ratio;
+
+        Self {
+            steps_per_degree: steps_per_revolution / 360.0,
+        }
+    }
+
+    /// Access the number of steps per degree
+    pub fn steps_per_degree(&self) -> f32 {
+        self.steps_per_degree
+    }
+
+    /// Convert an angle in degrees to steps
+    ///
+    /// The result is rounded to the nearest step. It saturates, if it is not
+    /// representable as an `i32`.
+    pub fn to_steps(&self, degrees: f32) -> i32 {
+        round(degrees * self.steps_per_degree)
+    }
+
+    /// Convert a number of steps to degrees
+    pub fn to_degrees(&self, steps: i32) -> f32 {
+        steps as f32 / self.steps_per_degree
+    }
+}
+
+/// Convert a velocity in steps per second to steps per timer tick
+///
+/// `Num` is the numeric type used by the motion profile. Returns `None`, if the
+/// result can't be represented by `Num`.
+///
+/// ``` rust
+/// type Num = fixed::FixedI64<typenum::U32>;
+///
+/// // 1000 steps per second, using a 1 MHz timer
+/// let velocity: Num =
+///     stepper::units::steps_per_second::<_, 1_000_000>(1000.0).unwrap();
+/// assert_eq!(velocity, Num::from_num(0.001));
+/// ```
+pub fn steps_per_second<Num, const TIMER_HZ: u32>(
+    steps_per_second: f32,
+) -> Option<Num>
+where
+    Num: FromPrimitive,
+{
+    Num::from_f64(f64::from(steps_per_second) / f64::from(TIMER_HZ))
+}
+
+/// Convert a velocity in revolutions per minute to steps per timer tick
+///
+/// `full_steps_per_revolution` is the number of full steps per revolution of
+/// the motor (`200` for the typical 1.8° motor), `microsteps` the number of
+/// microsteps per full step, as configured using the step mode.
+///
+/// `Num` is the numeric type used by the motion profile. Returns `None`, if the
+/// result can't be represented by `Num`.
+pub fn rpm<Num, const TIMER_HZ: u32>(
+    rpm: f32,
+    full_steps_per_revolution: u16,
+    microsteps: u16,
+) -> Option<Num>
+where
+    Num: FromPrimitive,
+{
+    let steps_per_minute = f64::from(rpm)
+        * f64::from(full_steps_per_revolution)
+        * f64::from(microsteps);
+
+    Num::from_f64(steps_per_minute / 60.0 / f64::from(TIMER_HZ))
+}
+
+/// Convert an acceleration in steps per second² to steps per timer tick²
+///
+/// `Num` is the numeric type used by the motion profile. Returns `None`, if the
+/// result can't be represented by `Num`.
+pub fn steps_per_second_squared<Num, const TIMER_HZ: u32>(
+    steps_per_second_squared: f32,
+) -> Option<Num>
+where
+    Num: FromPrimitive,
+{
+    let timer_hz = f64::from(TIMER_HZ);
+    Num::from_f64(f64::from(steps_per_second_squared) / timer_hz / timer_hz)
+}
+
+// `f32::round` is not available in `core`. Rounds half away from zero, like
+// `f32::round` does. Float to integer casts saturate.
+fn round(value: f32) -> i32 {
+    if value < 0.0 {
+        (value - 0.5) as i32
+    } else {
+        (value + 0.5) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Linear, Rotary};
+
+    #[test]
+    fn units_should_convert_to_and_from_steps() {
+        // 200 full steps, 16 microsteps, 8 mm lead
+        let linear = Linear::new(200, 16, 1.0, 8.0);
+        assert_eq!(linear.steps_per_mm(), 400.0);
+        assert_eq!(linear.to_steps(2.5), 1000);
+        assert_eq!(linear.to_steps(-0.001), 0);
+        assert_eq!(linear.to_mm(-200), -0.5);
+
+        // 200 full steps, 8 microsteps, 1:3 reduction
+        let rotary = Rotary::new(200, 8, 3.0);
+        assert_eq!(rotary.to_steps(90.0), 1200);
+        assert_eq!(rotary.to_degrees(4800), 360.0);
+    }
+
+    #[test]
+    fn velocity_helpers_should_convert_to_timer_ticks() {
+        assert_eq!(super::steps_per_second::<f64, 1_000>(500.0), Some(0.5));
+        assert_eq!(super::rpm::<f64, 1_000>(60.0, 200, 4), Some(0.8));
+        assert_eq!(
+            super::steps_per_second_squared::<f64, 1_000>(4000.0),
+            Some(0.004)
+        );
+        assert_eq!(super::steps_per_second::<u8, 1>(1000.0), None);
+    }
+}
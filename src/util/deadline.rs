@@ -0,0 +1,122 @@
+//! Utilities for driving futures from a timer interrupt
+//!
+//! See [`DeadlineTimer`] for more information.
+
+use fugit::{
+    TimerDurationU32 as TimerDuration, TimerInstantU32 as TimerInstant,
+};
+use fugit_timer::Timer;
+
+/// Wrapper around a timer that exposes when a future needs to be polled again
+///
+/// All futures in this crate that need to wait for something, do so by
+/// starting a timer. `DeadlineTimer` records the instant at which the timer
+/// expires, which is exactly when the future needs to be polled again. It can
+/// be queried using [`DeadlineTimer::deadline`], and is passed to an optional
+/// callback whenever the timer is started.
+///
+/// This allows users to arm a hardware timer interrupt (or schedule a task)
+/// for the deadline and poll the future from there, instead of calling `wait`,
+/// which keeps the CPU busy.
+///
+/// Implements [`Timer`] by forwarding to the wrapped timer.
+pub struct DeadlineTimer<T, Callback, const TIMER_HZ: u32> {
+    timer: T,
+    callback: Callback,
+    deadline: Option<TimerInstant<TIMER_HZ>>,
+}
+
+impl<T, const TIMER_HZ: u32>
+    DeadlineTimer<T, fn(TimerInstant<TIMER_HZ>), TIMER_HZ>
+{
+    /// Create a new instance of `DeadlineTimer`, without a callback
+    pub fn new(timer: T) -> Self {
+        Self {
+            timer,
+            callback: |_| {},
+            deadline: None,
+        }
+    }
+}
+
+impl<T, Callback, const TIMER_HZ: u32> DeadlineTimer<T, Callback, TIMER_HZ> {
+    /// Call the provided callback, whenever the timer is started
+    ///
+    /// The callback receives the deadline of the timer. It is called from
+    /// within the `poll` method of the future that started the timer.
+    pub fn with_callback<C>(self, callback: C) -> DeadlineTimer<T, C, TIMER_HZ>
+    where
+        C: FnMut(TimerInstant<TIMER_HZ>),
+    {
+        DeadlineTimer {
+            timer: self.timer,
+            callback,
+            deadline: self.deadline,
+        }
+    }
+
+    /// Returns the instant at which the running timer expires
+    ///
+    /// Returns `None`, if the timer is not running, or if it has been observed
+    /// to be expired. In that case, the future that is using the timer is not
+    /// waiting for it.
+    pub fn deadline(&self) -> Option<TimerInstant<TIMER_HZ>> {
+        self.deadline
+    }
+
+    /// Release the wrapped timer
+    pub fn release(self) -> T {
+        self.timer
+    }
+}
+
+impl<T, Callback, const TIMER_HZ: u32> Timer<TIMER_HZ>
+    for DeadlineTimer<T, Callback, TIMER_HZ>
+where
+    T: Timer<TIMER_HZ>,
+    Callback: FnMut(TimerInstant<TIMER_HZ>),
+{
+    type Error = T::Error;
+
+    fn now(&mut self) -> TimerInstant<TIMER_HZ> {
+        self.timer.now()
+    }
+
+    fn start(
+        &mut self,
+        duration: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        let deadline = self.timer.now() + duration;
+
+        self.timer.start(duration)?;
+        self.deadline = Some(deadline);
+        (self.callback)(deadline);
+
+        Ok(())
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.deadline = None;
+        self.timer.cancel()
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        let result = self.timer.wait();
+        if !matches!(result, Err(nb::Error::WouldBlock)) {
+            self.deadline = None;
+        }
+
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T, Callback, const TIMER_HZ: u32> crate::traits::RegisterWaker
+    for DeadlineTimer<T, Callback, TIMER_HZ>
+where
+    T: crate::traits::RegisterWaker,
+{
+    fn register_waker(&mut self, waker: &core::task::Waker) {
+        self.timer.register_waker(waker)
+    }
+}
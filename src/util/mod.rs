@@ -1,3 +1,8 @@
 //! Utility module for miscellaneous stuff that the rest of the crate needs
 
+pub mod deadline;
+pub mod monotonic;
 pub mod ref_mut;
+
+#[cfg(feature = "async")]
+pub mod waker;
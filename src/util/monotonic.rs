@@ -0,0 +1,114 @@
+//! Software timer based on a monotonic clock
+//!
+//! See [`MonotonicTimer`] for more information.
+
+use fugit::{
+    TimerDurationU32 as TimerDuration, TimerInstantU32 as TimerInstant,
+};
+use fugit_timer::Timer;
+
+use crate::compat::NotRunningError;
+
+/// A software timer that is based on a free-running counter
+///
+/// Stepper requires one timer per [`Stepper`] instance. Many platforms have a
+/// free-running counter, but not enough one-shot timers for all steppers.
+/// `MonotonicTimer` implements [`Timer`] in software, based on a closure that
+/// returns the current value of such a counter. Since any number of closures
+/// can read the same counter, several steppers can share it.
+///
+/// The counter is expected to wrap around when it overflows. Durations must
+/// not exceed half of its range, which is the same limitation that applies to
+/// comparing [`TimerInstant`]s.
+///
+/// [`Stepper`]: crate::Stepper
+pub struct MonotonicTimer<Now, const TIMER_HZ: u32> {
+    now: Now,
+    deadline: Option<TimerInstant<TIMER_HZ>>,
+}
+
+impl<Now, const TIMER_HZ: u32> MonotonicTimer<Now, TIMER_HZ>
+where
+    Now: FnMut() -> TimerInstant<TIMER_HZ>,
+{
+    /// Create a new instance of `MonotonicTimer`
+    ///
+    /// The `now` closure must return the current value of the counter.
+    pub fn new(now: Now) -> Self {
+        Self {
+            now,
+            deadline: None,
+        }
+    }
+
+    /// Release the closure that returns the current value of the counter
+    pub fn release(self) -> Now {
+        self.now
+    }
+}
+
+impl<Now, const TIMER_HZ: u32> Timer<TIMER_HZ> for MonotonicTimer<Now, TIMER_HZ>
+where
+    Now: FnMut() -> TimerInstant<TIMER_HZ>,
+{
+    type Error = NotRunningError;
+
+    fn now(&mut self) -> TimerInstant<TIMER_HZ> {
+        (self.now)()
+    }
+
+    fn start(
+        &mut self,
+        duration: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        self.deadline = Some((self.now)() + duration);
+        Ok(())
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.deadline.take().map(|_| ()).ok_or(NotRunningError)
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        let deadline = self.deadline.ok_or(NotRunningError)?;
+
+        if (self.now)() < deadline {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        self.deadline = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use fugit::{
+        TimerDurationU32 as TimerDuration, TimerInstantU32 as TimerInstant,
+    };
+    use fugit_timer::Timer as _;
+
+    use super::MonotonicTimer;
+
+    #[test]
+    fn timers_should_share_counter_and_handle_overflow() {
+        let counter = Cell::new(u32::MAX - 5);
+        let now = || TimerInstant::<1_000_000>::from_ticks(counter.get());
+
+        let mut a = MonotonicTimer::new(now);
+        let mut b = MonotonicTimer::new(now);
+
+        a.start(TimerDuration::from_ticks(10)).unwrap();
+        b.start(TimerDuration::from_ticks(20)).unwrap();
+
+        counter.set(counter.get().wrapping_add(10));
+        assert_eq!(a.wait(), Ok(()));
+        assert_eq!(b.wait(), Err(nb::Error::WouldBlock));
+
+        counter.set(counter.get().wrapping_add(10));
+        assert_eq!(b.wait(), Ok(()));
+        assert!(a.cancel().is_err());
+    }
+}
@@ -8,7 +8,13 @@ use fugit::{
 };
 use fugit_timer::Timer;
 
-use crate::traits::{MotionControl, SetDirection, SetStepMode, Step};
+use crate::{
+    traits::{
+        MotionControl, ReadVelocity, SetDirection, SetPowerMode, SetStepMode,
+        Step,
+    },
+    Direction, PowerMode,
+};
 
 /// Generic wrapper around a mutable reference
 ///
@@ -62,6 +68,24 @@ where
         self.0.move_to_position(max_velocity, target_step)
     }
 
+    fn move_to_position_synced(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+        num_steps: u32,
+    ) -> Result<bool, Self::Error> {
+        self.0
+            .move_to_position_synced(max_velocity, target_step, num_steps)
+    }
+
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.0.retarget(max_velocity, target_step)
+    }
+
     fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
         self.0.reset_position(step)
     }
@@ -69,6 +93,49 @@ where
     fn update(&mut self) -> Result<bool, Self::Error> {
         self.0.update()
     }
+
+    fn current_position(&mut self) -> Result<i32, Self::Error> {
+        self.0.current_position()
+    }
+
+    fn start_jog(
+        &mut self,
+        direction: Direction,
+        velocity: Self::Velocity,
+    ) -> Result<(), Self::Error> {
+        self.0.start_jog(direction, velocity)
+    }
+
+    fn stop_jog(&mut self) -> Result<(), Self::Error> {
+        self.0.stop_jog()
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        self.0.stop()
+    }
+
+    fn abort(&mut self) -> Result<(), Self::Error> {
+        self.0.abort()
+    }
+}
+
+impl<'r, T> ReadVelocity for RefMut<'r, T>
+where
+    T: ReadVelocity,
+{
+    fn current_velocity(&mut self) -> Result<Self::Velocity, Self::Error> {
+        self.0.current_velocity()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'r, T> crate::traits::RegisterWaker for RefMut<'r, T>
+where
+    T: crate::traits::RegisterWaker,
+{
+    fn register_waker(&mut self, waker: &core::task::Waker) {
+        self.0.register_waker(waker)
+    }
 }
 
 impl<'r, T> SetDirection for RefMut<'r, T>
@@ -107,6 +174,19 @@ where
     }
 }
 
+impl<'r, T> SetPowerMode for RefMut<'r, T>
+where
+    T: SetPowerMode,
+{
+    const WAKE_UP_TIME: Nanoseconds = T::WAKE_UP_TIME;
+
+    type Error = T::Error;
+
+    fn apply_power_mode(&mut self, mode: PowerMode) -> Result<(), Self::Error> {
+        self.0.apply_power_mode(mode)
+    }
+}
+
 impl<'r, T> Step for RefMut<'r, T>
 where
     T: Step,
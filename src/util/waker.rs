@@ -0,0 +1,54 @@
+//! Utilities for using Stepper with async/await
+//!
+//! See [`WakeImmediately`] for more information.
+
+use core::task::Waker;
+
+use fugit::{
+    TimerDurationU32 as TimerDuration, TimerInstantU32 as TimerInstant,
+};
+use fugit_timer::Timer;
+
+use crate::traits::RegisterWaker;
+
+/// Wrapper around a timer that has no support for waking a task
+///
+/// Implements [`RegisterWaker`] by waking the task right away, which causes the
+/// executor to poll the future again as soon as possible. This doesn't require
+/// any support for interrupts, but keeps the CPU busy, similar to calling
+/// `wait` on the future types.
+///
+/// Implements all other traits by forwarding to the wrapped timer.
+pub struct WakeImmediately<T>(pub T);
+
+impl<T> RegisterWaker for WakeImmediately<T> {
+    fn register_waker(&mut self, waker: &Waker) {
+        waker.wake_by_ref();
+    }
+}
+
+impl<T, const TIMER_HZ: u32> Timer<TIMER_HZ> for WakeImmediately<T>
+where
+    T: Timer<TIMER_HZ>,
+{
+    type Error = T::Error;
+
+    fn now(&mut self) -> TimerInstant<TIMER_HZ> {
+        self.0.now()
+    }
+
+    fn start(
+        &mut self,
+        duration: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        self.0.start(duration)
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.0.cancel()
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        self.0.wait()
+    }
+}